@@ -0,0 +1,46 @@
+// Criterion harness over the same write/read kernel as the command-line
+// tool, for proper outlier detection and comparison against saved baselines
+// (`cargo bench -- --save-baseline main`, then `--baseline main`).
+// Run with `cargo bench --bench alignment`; set ALIGNMENT_N to resize.
+
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use data_alignment_perf::kernel::{
+    BenchElement, UnalignedBuffer, read_pass_big_endian, write_pass_big_endian, write_read_pass,
+};
+
+fn elements() -> usize {
+    std::env::var("ALIGNMENT_N")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(1 << 20)
+}
+
+fn bench_type<T: BenchElement>(c: &mut Criterion, name: &str) {
+    let n = elements();
+    let mut group = c.benchmark_group(name);
+    group.throughput(Throughput::Bytes((n * std::mem::size_of::<T>()) as u64));
+    for offset in 0..std::mem::size_of::<T>() {
+        let mut buffer = UnalignedBuffer::<T>::new(n, offset);
+        group.bench_with_input(BenchmarkId::new("offset", offset), &offset, |b, _| {
+            b.iter(|| write_read_pass(&mut buffer))
+        });
+        group.bench_with_input(BenchmarkId::new("big-endian", offset), &offset, |b, _| {
+            b.iter(|| {
+                write_pass_big_endian(&mut buffer);
+                read_pass_big_endian(&buffer)
+            })
+        });
+    }
+    group.finish();
+}
+
+fn alignment(c: &mut Criterion) {
+    bench_type::<i32>(c, "i32");
+    bench_type::<i64>(c, "i64");
+    bench_type::<i128>(c, "i128");
+    bench_type::<f32>(c, "f32");
+    bench_type::<f64>(c, "f64");
+}
+
+criterion_group!(benches, alignment);
+criterion_main!(benches);
@@ -0,0 +1,14 @@
+// Records the compiler version for the machine section of reports.
+
+use std::process::Command;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    let rustc = std::env::var("RUSTC").unwrap_or_else(|_| "rustc".to_string());
+    if let Ok(output) = Command::new(rustc).arg("--version").output()
+        && output.status.success()
+    {
+        let version = String::from_utf8_lossy(&output.stdout);
+        println!("cargo:rustc-env=RUSTC_VERSION={}", version.trim());
+    }
+}
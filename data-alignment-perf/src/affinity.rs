@@ -0,0 +1,88 @@
+//! Pinning threads to CPUs.
+//!
+//! Left alone, the scheduler may move the benchmark between cores mid-run;
+//! on hybrid CPUs a move between a performance and an efficiency core
+//! shows up as a second cluster of timings. Pinning is implemented with
+//! `sched_setaffinity` on Linux and reported as unsupported elsewhere.
+
+use std::io;
+
+/// Parses a CPU list such as `0-3,8,10-11`, the format of both `--pin` and
+/// the kernel's `cpulist` files.
+pub fn parse_cpu_list(list: &str) -> Result<Vec<usize>, String> {
+    let mut cpus = Vec::new();
+    for part in list.trim().split(',').filter(|part| !part.is_empty()) {
+        let parse = |s: &str| s.trim().parse::<usize>().map_err(|e| format!("bad CPU `{s}`: {e}"));
+        match part.split_once('-') {
+            Some((first, last)) => {
+                let (first, last) = (parse(first)?, parse(last)?);
+                if first > last {
+                    return Err(format!("empty CPU range `{part}`"));
+                }
+                cpus.extend(first..=last);
+            }
+            None => cpus.push(parse(part)?),
+        }
+    }
+    Ok(cpus)
+}
+
+/// Restricts the calling thread to `cpus`.
+#[cfg(target_os = "linux")]
+pub fn pin_current_thread(cpus: &[usize]) -> io::Result<()> {
+    unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &cpu in cpus {
+            if cpu >= libc::CPU_SETSIZE as usize {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("CPU {cpu} out of range")));
+            }
+            libc::CPU_SET(cpu, &mut set);
+        }
+        if libc::sched_setaffinity(0, size_of::<libc::cpu_set_t>(), &set) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// Restricts the calling thread to `cpus`.
+#[cfg(not(target_os = "linux"))]
+pub fn pin_current_thread(_cpus: &[usize]) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "CPU pinning is only implemented on Linux"))
+}
+
+/// The CPU thread `index` of a run is pinned to, round robin over `pin`;
+/// `None` when nothing is pinned.
+pub fn cpu_for(pin: &[usize], index: usize) -> Option<usize> {
+    (!pin.is_empty()).then(|| pin[index % pin.len()])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_cpu_list() {
+        assert_eq!(parse_cpu_list("0-3,8,10-11\n").unwrap(), [0, 1, 2, 3, 8, 10, 11]);
+        assert_eq!(parse_cpu_list("").unwrap(), Vec::<usize>::new());
+        assert!(parse_cpu_list("3-1").is_err());
+        assert!(parse_cpu_list("a").is_err());
+    }
+
+    #[test]
+    fn test_cpu_for() {
+        assert_eq!(cpu_for(&[], 3), None);
+        assert_eq!(cpu_for(&[0, 2], 3), Some(2));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_pin_current_thread() {
+        std::thread::spawn(|| {
+            pin_current_thread(&[0]).unwrap();
+            assert!(pin_current_thread(&[100_000]).is_err());
+        })
+        .join()
+        .unwrap();
+    }
+}
@@ -0,0 +1,260 @@
+//! Buffers with a guaranteed alignment, and typed views at a chosen byte
+//! offset into them: the two halves of every experiment in this crate, for
+//! reproducing one in other code.
+//!
+//! ```
+//! use data_alignment_perf::aligned::{AlignedVec, MisalignedView};
+//!
+//! // 1024 u64s starting 3 bytes past a cache-line boundary.
+//! let mut bytes = AlignedVec::<u8, 64>::from_elem(0, 3 + 1024 * 8);
+//! let mut view = MisalignedView::<u64>::new(&mut bytes, 3);
+//! assert_eq!(view.as_ptr() as usize % 64, 3);
+//! view.write(0, 42);
+//! assert_eq!(view.read(0), 42);
+//! ```
+
+use std::alloc::{alloc, dealloc, handle_alloc_error, realloc, Layout};
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::ptr::{self, NonNull};
+
+/// A growable vector of `T` whose first element is always `A`-aligned (or
+/// `align_of::<T>()`-aligned, if that is larger), even when empty. `A` must
+/// be a power of two and `T` must not be zero-sized.
+pub struct AlignedVec<T: Copy, const A: usize> {
+    ptr: NonNull<T>,
+    len: usize,
+    capacity: usize,
+}
+
+impl<T: Copy, const A: usize> AlignedVec<T, A> {
+    /// The alignment of the first element.
+    pub const ALIGN: usize = {
+        assert!(A.is_power_of_two(), "alignment must be a power of two");
+        assert!(size_of::<T>() > 0, "zero-sized types are not supported");
+        if A > align_of::<T>() { A } else { align_of::<T>() }
+    };
+
+    pub fn new() -> Self {
+        Self {
+            // Never read through; aligned so `as_ptr` keeps its promise.
+            ptr: NonNull::new(ptr::without_provenance_mut(Self::ALIGN)).unwrap(),
+            len: 0,
+            capacity: 0,
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        let mut vec = Self::new();
+        vec.reserve(capacity);
+        vec
+    }
+
+    /// `len` copies of `value`.
+    pub fn from_elem(value: T, len: usize) -> Self {
+        let mut vec = Self::with_capacity(len);
+        for i in 0..len {
+            unsafe { vec.ptr.as_ptr().add(i).write(value) };
+        }
+        vec.len = len;
+        vec
+    }
+
+    pub fn from_slice(values: &[T]) -> Self {
+        let mut vec = Self::new();
+        vec.extend_from_slice(values);
+        vec
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn layout(capacity: usize) -> Layout {
+        Layout::array::<T>(capacity)
+            .and_then(|layout| layout.align_to(Self::ALIGN))
+            .expect("Invalid layout")
+    }
+
+    /// Makes room for at least `additional` more elements.
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self.len.checked_add(additional).expect("capacity overflow");
+        if needed <= self.capacity {
+            return;
+        }
+        let capacity = needed.max(self.capacity * 2).max(4);
+        let layout = Self::layout(capacity);
+        let raw = unsafe {
+            if self.capacity == 0 {
+                alloc(layout)
+            } else {
+                // realloc keeps the alignment of the old layout, which is
+                // the same.
+                realloc(self.ptr.as_ptr() as *mut u8, Self::layout(self.capacity), layout.size())
+            }
+        };
+        let Some(ptr) = NonNull::new(raw as *mut T) else {
+            handle_alloc_error(layout);
+        };
+        self.ptr = ptr;
+        self.capacity = capacity;
+    }
+
+    pub fn push(&mut self, value: T) {
+        self.reserve(1);
+        unsafe { self.ptr.as_ptr().add(self.len).write(value) };
+        self.len += 1;
+    }
+
+    pub fn extend_from_slice(&mut self, values: &[T]) {
+        self.reserve(values.len());
+        unsafe { ptr::copy_nonoverlapping(values.as_ptr(), self.ptr.as_ptr().add(self.len), values.len()) };
+        self.len += values.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+    }
+}
+
+impl<T: Copy, const A: usize> Default for AlignedVec<T, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Copy, const A: usize> Deref for AlignedVec<T, A> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T: Copy, const A: usize> DerefMut for AlignedVec<T, A> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+    }
+}
+
+impl<T: Copy, const A: usize> Clone for AlignedVec<T, A> {
+    fn clone(&self) -> Self {
+        Self::from_slice(self)
+    }
+}
+
+impl<T: Copy + std::fmt::Debug, const A: usize> std::fmt::Debug for AlignedVec<T, A> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Copy, const A: usize> Drop for AlignedVec<T, A> {
+    fn drop(&mut self) {
+        if self.capacity > 0 {
+            unsafe { dealloc(self.ptr.as_ptr() as *mut u8, Self::layout(self.capacity)) };
+        }
+    }
+}
+
+// Owns its elements like a `Vec<T>`.
+unsafe impl<T: Copy + Send, const A: usize> Send for AlignedVec<T, A> {}
+unsafe impl<T: Copy + Sync, const A: usize> Sync for AlignedVec<T, A> {}
+
+/// As many values of `T` as fit in a byte slice, starting `offset` bytes
+/// in, read and written with `ptr::read_unaligned`/`ptr::write_unaligned`
+/// so any offset is allowed. Every bit pattern the bytes hold must be a
+/// valid `T` (true of the integer and float types).
+pub struct MisalignedView<'a, T> {
+    data: *mut T,
+    len: usize,
+    _bytes: PhantomData<&'a mut [u8]>,
+}
+
+impl<'a, T: Copy> MisalignedView<'a, T> {
+    /// Panics if `offset` is past the end of `bytes`.
+    pub fn new(bytes: &'a mut [u8], offset: usize) -> Self {
+        assert!(offset <= bytes.len(), "offset {} past the end of {} bytes", offset, bytes.len());
+        Self {
+            data: unsafe { bytes.as_mut_ptr().add(offset) } as *mut T,
+            len: (bytes.len() - offset) / size_of::<T>(),
+            _bytes: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Pointer to the first element; only valid for unaligned accesses.
+    pub fn as_ptr(&self) -> *mut T {
+        self.data
+    }
+
+    /// Whether the elements are naturally aligned for `T`.
+    pub fn is_aligned(&self) -> bool {
+        (self.data as usize).is_multiple_of(align_of::<T>())
+    }
+
+    /// Writes element `i`; panics if `i >= len`.
+    #[inline(always)]
+    pub fn write(&mut self, i: usize, value: T) {
+        assert!(i < self.len, "index {} out of bounds for length {}", i, self.len);
+        unsafe { ptr::write_unaligned(self.data.add(i), value) }
+    }
+
+    /// Reads element `i`; panics if `i >= len`.
+    #[inline(always)]
+    pub fn read(&self, i: usize) -> T {
+        assert!(i < self.len, "index {} out of bounds for length {}", i, self.len);
+        unsafe { ptr::read_unaligned(self.data.add(i)) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aligned_vec_stays_aligned() {
+        let mut vec = AlignedVec::<u8, 4096>::new();
+        assert_eq!(vec.as_ptr() as usize % 4096, 0);
+        for i in 0..10_000 {
+            vec.push(i as u8);
+            assert_eq!(vec.as_ptr() as usize % 4096, 0);
+        }
+        assert_eq!(vec.len(), 10_000);
+        assert_eq!(vec[9_999], (9_999 % 256) as u8);
+        assert_eq!(vec.clone()[..], vec[..]);
+
+        // The element's own alignment wins over a smaller `A`.
+        assert_eq!(AlignedVec::<u64, 1>::ALIGN, 8);
+        let words = AlignedVec::<u64, 1>::from_slice(&[1, 2, 3]);
+        assert_eq!(words.as_ptr() as usize % 8, 0);
+        assert_eq!(*words, [1, 2, 3]);
+    }
+
+    #[test]
+    fn test_misaligned_view() {
+        let mut bytes = AlignedVec::<u8, 64>::from_elem(0, 3 + 4 * 4 + 2);
+        let mut view = MisalignedView::<u32>::new(&mut bytes, 3);
+        assert_eq!(view.len(), 4);
+        assert!(!view.is_aligned());
+        view.write(0, 0x0403_0201);
+        view.write(3, u32::MAX);
+        assert_eq!(view.read(0), 0x0403_0201);
+        assert_eq!(bytes[3..7], 0x0403_0201u32.to_ne_bytes());
+        assert_eq!(bytes[15..19], [0xff; 4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_misaligned_view_bounds() {
+        let mut bytes = [0u8; 9];
+        MisalignedView::<u64>::new(&mut bytes, 2).read(1);
+    }
+}
@@ -0,0 +1,239 @@
+//! Where the benchmark buffers come from.
+//!
+//! The allocator decides where a buffer lands relative to pages, cache sets
+//! and other allocations, and whether its pages are already mapped; two
+//! offsets that differ only in that can time differently. Pick the backend
+//! per run to hold placement fixed, or to see how much it matters.
+//!
+//! Besides allocators proper, the buffer can be a `Vec<u8>` or `Box<[u8]>`
+//! of the global allocator, as ordinary code would hold it, or (on Linux) an
+//! anonymous or file-backed `mmap`, which starts page aligned and takes a
+//! page fault on the first touch of every page, through the page cache for
+//! the file.
+//!
+//! Every backend hands out a zeroed block whose first byte is
+//! [`BASE_ALIGN`]-aligned, so offset k still means k bytes past a cache-line
+//! boundary.
+
+use std::alloc::{alloc_zeroed, dealloc, handle_alloc_error, GlobalAlloc, Layout, System};
+#[cfg(target_os = "linux")]
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::kernel::BASE_ALIGN;
+
+/// Bytes written per page by [`Allocator::Pretouched`].
+const PAGE: usize = 4096;
+
+/// An allocation strategy for the benchmark buffers.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Allocator {
+    /// The global allocator asked for `BASE_ALIGN` directly.
+    #[default]
+    Aligned,
+    /// `malloc` with no alignment request, rounded up to `BASE_ALIGN` by
+    /// hand, as a `Vec<u8>` would be placed.
+    System,
+    /// Like `aligned`, with every page written before the buffer is used, so
+    /// no page faults land in the first iterations.
+    Pretouched,
+    /// jemalloc, asked for `BASE_ALIGN`.
+    #[cfg(feature = "jemalloc")]
+    Jemalloc,
+    /// mimalloc, asked for `BASE_ALIGN`.
+    #[cfg(feature = "mimalloc")]
+    Mimalloc,
+    /// A zeroed `Vec<u8>`, rounded up to `BASE_ALIGN` by hand.
+    Vec,
+    /// A `Vec<u8>` turned into a `Box<[u8]>`, rounded up by hand.
+    BoxedSlice,
+    /// An anonymous private mapping.
+    #[cfg(target_os = "linux")]
+    Mmap,
+    /// A shared mapping of a sparse temporary file, unlinked once mapped.
+    #[cfg(target_os = "linux")]
+    FileMmap,
+}
+
+// What an `Allocation` owns, and how to give it back. The `Vec` and `Box`
+// are never read, only held until the allocation is dropped.
+#[allow(dead_code)]
+enum Owner {
+    /// Memory from an allocator, freed with this layout.
+    Layout(Layout),
+    Vec(Vec<u8>),
+    Boxed(Box<[u8]>),
+    /// A mapping of this many bytes.
+    #[cfg(target_os = "linux")]
+    Mapped(usize),
+}
+
+impl std::fmt::Display for Allocator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+/// A zeroed block of at least `size` bytes from one [`Allocator`], freed on
+/// drop.
+pub struct Allocation {
+    allocator: Allocator,
+    /// What the backend returned, and what holds it.
+    raw: *mut u8,
+    owner: Owner,
+    /// `raw` rounded up to `BASE_ALIGN`.
+    base: *mut u8,
+}
+
+impl Allocation {
+    pub fn new(size: usize, allocator: Allocator) -> Self {
+        let size = size.max(1);
+        // Room to round the start up by hand.
+        let padded = size + BASE_ALIGN - 1;
+        let (raw, owner) = match allocator {
+            Allocator::Vec => {
+                let mut bytes = vec![0u8; padded];
+                (bytes.as_mut_ptr(), Owner::Vec(bytes))
+            }
+            Allocator::BoxedSlice => {
+                let mut bytes = vec![0u8; padded].into_boxed_slice();
+                (bytes.as_mut_ptr(), Owner::Boxed(bytes))
+            }
+            #[cfg(target_os = "linux")]
+            Allocator::Mmap => (map(size, None), Owner::Mapped(size)),
+            #[cfg(target_os = "linux")]
+            Allocator::FileMmap => (map_file(size), Owner::Mapped(size)),
+            _ => {
+                let layout = match allocator {
+                    Allocator::System => Layout::from_size_align(padded, 1),
+                    _ => Layout::from_size_align(size, BASE_ALIGN),
+                }
+                .expect("Invalid layout");
+                let raw = unsafe {
+                    match allocator {
+                        Allocator::System => System.alloc_zeroed(layout),
+                        #[cfg(feature = "jemalloc")]
+                        Allocator::Jemalloc => tikv_jemallocator::Jemalloc.alloc_zeroed(layout),
+                        #[cfg(feature = "mimalloc")]
+                        Allocator::Mimalloc => mimalloc::MiMalloc.alloc_zeroed(layout),
+                        _ => alloc_zeroed(layout),
+                    }
+                };
+                if raw.is_null() {
+                    handle_alloc_error(layout);
+                }
+                (raw, Owner::Layout(layout))
+            }
+        };
+        let base = unsafe { raw.add(raw.align_offset(BASE_ALIGN)) };
+        if allocator == Allocator::Pretouched {
+            // Zeroed memory from a fresh mapping is not faulted in until
+            // written; write the zeros again, one byte a page.
+            for page in (0..size).step_by(PAGE) {
+                unsafe { base.add(page).write_volatile(0) };
+            }
+        }
+        Self {
+            allocator,
+            raw,
+            owner,
+            base,
+        }
+    }
+
+    /// The first byte, `BASE_ALIGN`-aligned.
+    pub fn as_ptr(&self) -> *mut u8 {
+        self.base
+    }
+}
+
+/// Maps `size` zeroed bytes: anonymous memory, or `fd` shared.
+#[cfg(target_os = "linux")]
+fn map(size: usize, fd: Option<libc::c_int>) -> *mut u8 {
+    let flags = match fd {
+        Some(_) => libc::MAP_SHARED,
+        None => libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+    };
+    let data = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            size,
+            libc::PROT_READ | libc::PROT_WRITE,
+            flags,
+            fd.unwrap_or(-1),
+            0,
+        )
+    };
+    if data == libc::MAP_FAILED {
+        panic!("mmap of {} bytes failed: {}", size, std::io::Error::last_os_error());
+    }
+    data as *mut u8
+}
+
+/// Maps a new sparse file of `size` bytes in the temporary directory. The
+/// file is removed straight away; the mapping keeps it alive.
+#[cfg(target_os = "linux")]
+fn map_file(size: usize) -> *mut u8 {
+    use std::os::fd::AsRawFd;
+
+    static FILES: AtomicUsize = AtomicUsize::new(0);
+    let path = std::env::temp_dir().join(format!(
+        "alignment-mmap-{}-{}",
+        std::process::id(),
+        FILES.fetch_add(1, Ordering::Relaxed)
+    ));
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create_new(true)
+        .open(&path)
+        .and_then(|file| file.set_len(size as u64).map(|()| file))
+        .unwrap_or_else(|e| panic!("creating {}: {}", path.display(), e));
+    let data = map(size, Some(file.as_raw_fd()));
+    let _ = std::fs::remove_file(&path);
+    data
+}
+
+impl Drop for Allocation {
+    fn drop(&mut self) {
+        unsafe {
+            match (&self.owner, self.allocator) {
+                (Owner::Layout(layout), Allocator::System) => System.dealloc(self.raw, *layout),
+                #[cfg(feature = "jemalloc")]
+                (Owner::Layout(layout), Allocator::Jemalloc) => tikv_jemallocator::Jemalloc.dealloc(self.raw, *layout),
+                #[cfg(feature = "mimalloc")]
+                (Owner::Layout(layout), Allocator::Mimalloc) => mimalloc::MiMalloc.dealloc(self.raw, *layout),
+                (Owner::Layout(layout), _) => dealloc(self.raw, *layout),
+                #[cfg(target_os = "linux")]
+                (Owner::Mapped(size), _) => {
+                    libc::munmap(self.raw as *mut libc::c_void, *size);
+                }
+                // Freed when the owner is dropped.
+                (Owner::Vec(_) | Owner::Boxed(_), _) => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_allocator() {
+        for &allocator in Allocator::value_variants() {
+            for size in [0, 1, 100, 1 << 20] {
+                let allocation = Allocation::new(size, allocator);
+                let base = allocation.as_ptr();
+                assert!((base as usize).is_multiple_of(BASE_ALIGN), "{allocator}");
+                let bytes = unsafe { std::slice::from_raw_parts(base, size) };
+                assert!(bytes.iter().all(|&b| b == 0), "{allocator}");
+            }
+        }
+        assert_eq!(Allocator::Pretouched.to_string(), "pretouched");
+        assert_eq!(Allocator::BoxedSlice.to_string(), "boxed-slice");
+    }
+}
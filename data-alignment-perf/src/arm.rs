@@ -0,0 +1,236 @@
+//! Unaligned accesses the way aarch64 code makes them: paired loads and
+//! stores (`ldp`/`stp`, what the compiler emits for 16-byte copies and
+//! for spilling register pairs) and 128-bit NEON loads and stores, at
+//! positions across the 16-byte and 64-byte boundaries.
+//!
+//!   granule        |0      7|8     15|16    23|
+//!   aligned        [pppppppp|pppppppp]           one 16-byte block
+//!   8-aligned      ·········[pppppppp|pppppppp]  crosses 16, each half aligned
+//!   misaligned     ·[pppppppp|pppppppp]          crosses 16, neither aligned
+//!   line-split     the pair straddles a 64-byte line
+//!
+//! Cores differ widely here: some split any access that crosses 16 bytes,
+//! others only those that cross a line, and Apple cores barely notice
+//! either. What is the same everywhere is that on Device memory, or with
+//! strict alignment checking (`SCTLR_EL1.A`) enabled, every unaligned
+//! placement faults; the results say which ones would.
+
+use std::arch::aarch64::{uint64x2_t, vdupq_n_u64, vgetq_lane_u64};
+use std::arch::asm;
+use std::hint::black_box;
+
+use serde::{Deserialize, Serialize};
+
+use crate::crossing::CACHE_LINE;
+use crate::kernel::UnalignedBuffer;
+use crate::stats::Stats;
+use crate::{summarize, Config};
+
+/// Bytes the accesses cycle through: half of the smallest L1 data cache
+/// in use, so the loads and stores never leave it.
+pub const FOOTPRINT: usize = 16 << 10;
+
+/// Accesses per iteration.
+pub const ACCESSES: usize = 1 << 20;
+
+/// The two access shapes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Access {
+    /// `ldp`/`stp` of two 64-bit registers.
+    Pair,
+    /// `ldr`/`str` of one 128-bit q register.
+    Vector,
+}
+
+impl Access {
+    pub fn name(self) -> &'static str {
+        match self {
+            Access::Pair => "ldp/stp",
+            Access::Vector => "ldr/str q",
+        }
+    }
+
+    /// The alignment strict checking requires: that of one element, so 8
+    /// for a pair of x registers and 16 for a q register.
+    pub fn required_alignment(self) -> usize {
+        match self {
+            Access::Pair => 8,
+            Access::Vector => 16,
+        }
+    }
+}
+
+/// Named positions within a cache line for a 16-byte access.
+pub fn placements() -> [(&'static str, usize); 4] {
+    [("aligned", 0), ("8-aligned", 8), ("misaligned", 1), ("line-split", CACHE_LINE - 8)]
+}
+
+/// Timings for one access shape and placement.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArmResult {
+    pub access: Access,
+    pub placement: String,
+    /// Byte position of the access within its cache line.
+    pub position: usize,
+    /// Whether this placement faults on Device memory or with strict
+    /// alignment checking.
+    pub strict_alignment_fault: bool,
+    /// Milliseconds per iteration, in run order.
+    pub samples: Vec<f64>,
+    pub stats: Stats,
+    /// Nanoseconds per access at the median.
+    pub ns_per_access: f64,
+}
+
+impl ArmResult {
+    /// A warning for placements that would fault under strict alignment.
+    pub fn warning(&self) -> Option<String> {
+        self.strict_alignment_fault.then(|| {
+            format!(
+                "warning: {} at position {} is not {}-byte aligned: it would fault (SIGBUS) on Device \
+                 memory or with strict alignment checking (SCTLR_EL1.A) enabled",
+                self.access.name(),
+                self.position,
+                self.access.required_alignment()
+            )
+        })
+    }
+}
+
+/// Stores then loads 16 bytes at `position` in each line of `bytes`,
+/// cycling through the lines until `accesses` of each are done; returns
+/// the sum of the loaded 64-bit halves.
+pub fn arm_pass(bytes: &mut [u8], access: Access, position: usize, accesses: usize) -> u64 {
+    let lines = bytes.len() / CACHE_LINE - 1;
+    assert!(lines > 0 && position + 16 <= 2 * CACHE_LINE, "no room for 16 bytes at {position}");
+    let base = bytes.as_mut_ptr();
+    let at = |i: usize| unsafe { base.add(i % lines * CACHE_LINE + position) };
+
+    for i in 0..accesses {
+        let value = (i % 100) as u64;
+        unsafe {
+            match access {
+                Access::Pair => asm!(
+                    "stp {a}, {b}, [{p}]",
+                    p = in(reg) at(i),
+                    a = in(reg) value,
+                    b = in(reg) value,
+                    options(nostack, preserves_flags),
+                ),
+                Access::Vector => asm!(
+                    "str {v:q}, [{p}]",
+                    p = in(reg) at(i),
+                    v = in(vreg) vdupq_n_u64(value),
+                    options(nostack, preserves_flags),
+                ),
+            }
+        }
+    }
+
+    let mut sum = 0u64;
+    match access {
+        Access::Pair => {
+            for i in 0..accesses {
+                let (a, b): (u64, u64);
+                unsafe {
+                    asm!(
+                        "ldp {a}, {b}, [{p}]",
+                        p = in(reg) at(i),
+                        a = out(reg) a,
+                        b = out(reg) b,
+                        options(nostack, preserves_flags, readonly),
+                    )
+                };
+                sum = sum.wrapping_add(a).wrapping_add(b);
+            }
+        }
+        Access::Vector => {
+            let mut total: uint64x2_t = unsafe { vdupq_n_u64(0) };
+            for i in 0..accesses {
+                unsafe {
+                    asm!(
+                        "ldr {v:q}, [{p}]",
+                        "add {t:v}.2d, {t:v}.2d, {v:v}.2d",
+                        p = in(reg) at(i),
+                        v = out(vreg) _,
+                        t = inout(vreg) total,
+                        options(nostack, preserves_flags, readonly),
+                    )
+                };
+            }
+            sum = unsafe { vgetq_lane_u64::<0>(total).wrapping_add(vgetq_lane_u64::<1>(total)) };
+        }
+    }
+    black_box(sum)
+}
+
+/// Times every access shape at every placement, with the warmup, repeat,
+/// clock and outlier settings of `config` (`config.n` is not used: each
+/// iteration is [`ACCESSES`] stores and loads over [`FOOTPRINT`] bytes).
+pub fn run_arm(config: &Config) -> Vec<ArmResult> {
+    // One extra line for the part of the last split access past the end.
+    let mut buffer = UnalignedBuffer::<u8>::new(FOOTPRINT + CACHE_LINE, 0);
+    let bytes = buffer.as_bytes_mut();
+    let mut results = Vec::new();
+    for access in [Access::Pair, Access::Vector] {
+        for (name, position) in placements() {
+            for _ in 0..config.warmup {
+                arm_pass(bytes, access, position, ACCESSES);
+            }
+            let samples: Vec<f64> = (0..config.repeat)
+                .map(|_| {
+                    config.clock.time(|| {
+                        arm_pass(bytes, access, position, ACCESSES);
+                    }) / 1_000_000.0
+                })
+                .collect();
+            let stats = summarize(&samples, config);
+            results.push(ArmResult {
+                access,
+                placement: name.to_string(),
+                position,
+                strict_alignment_fault: !position.is_multiple_of(access.required_alignment()),
+                // A store and a load per access.
+                ns_per_access: stats.median * 1_000_000.0 / (2 * ACCESSES) as f64,
+                stats,
+                samples,
+            });
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arm_pass_sums_every_access() {
+        let mut bytes = vec![0u8; 11 * CACHE_LINE];
+        let expected: u64 = (0..10).map(|i| 2 * i).sum();
+        for access in [Access::Pair, Access::Vector] {
+            for (name, position) in placements() {
+                assert_eq!(arm_pass(&mut bytes, access, position, 10), expected, "{name}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_strict_alignment_warnings() {
+        let config = Config {
+            repeat: 1,
+            warmup: 0,
+            ..Config::default()
+        };
+        let faulting: Vec<(Access, usize)> = run_arm(&config)
+            .iter()
+            .filter(|r| r.warning().is_some())
+            .map(|r| (r.access, r.position))
+            .collect();
+        assert_eq!(
+            faulting,
+            [(Access::Pair, 1), (Access::Vector, 8), (Access::Vector, 1), (Access::Vector, 56)]
+        );
+    }
+}
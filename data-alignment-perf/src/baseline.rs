@@ -0,0 +1,109 @@
+//! Saved runs and regression checks against them.
+//!
+//! A baseline is the JSON report of a run, stored as `<dir>/<name>.json`.
+//! Comparing matches the offsets of the current run to the baseline's by
+//! type and offset and reports the change in median time; an offset whose
+//! median grew by more than the tolerance is a regression. Offsets only
+//! one of the runs has are skipped.
+
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use crate::report::{write_json, BenchmarkReport};
+
+/// Where baselines go unless told otherwise.
+pub const DEFAULT_DIR: &str = "target/baselines";
+
+/// The file baseline `name` is stored in under `dir`.
+pub fn path(dir: &Path, name: &str) -> PathBuf {
+    dir.join(format!("{name}.json"))
+}
+
+/// Writes `report` as baseline `name`, replacing any older one.
+pub fn save(dir: &Path, name: &str, report: &BenchmarkReport) -> io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let mut out = BufWriter::new(File::create(path(dir, name))?);
+    write_json(&mut out, report)?;
+    out.flush()
+}
+
+/// Reads baseline `name`.
+pub fn load(dir: &Path, name: &str) -> io::Result<BenchmarkReport> {
+    let path = path(dir, name);
+    let file = File::open(&path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+    Ok(serde_json::from_reader(BufReader::new(file))?)
+}
+
+/// One offset in both runs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delta {
+    pub type_name: String,
+    pub offset: usize,
+    /// Median times in milliseconds.
+    pub baseline: f64,
+    pub current: f64,
+    /// `current / baseline - 1`: 0.1 is 10% slower.
+    pub change: f64,
+    /// Whether `change` exceeds the tolerance.
+    pub regressed: bool,
+}
+
+/// Compares every offset `current` shares with `baseline`; `tolerance` is
+/// the allowed slowdown as a fraction (0.05 for 5%).
+pub fn compare(baseline: &BenchmarkReport, current: &BenchmarkReport, tolerance: f64) -> Vec<Delta> {
+    let mut deltas = Vec::new();
+    for types in &current.results {
+        let Some(old) = baseline.results.iter().find(|old| old.type_name == types.type_name) else {
+            continue;
+        };
+        for result in &types.offsets {
+            let Some(old) = old.offsets.iter().find(|old| old.offset == result.offset) else {
+                continue;
+            };
+            let change = result.stats.median / old.stats.median - 1.0;
+            deltas.push(Delta {
+                type_name: types.type_name.clone(),
+                offset: result.offset,
+                baseline: old.stats.median,
+                current: result.stats.median,
+                change,
+                regressed: change > tolerance,
+            });
+        }
+    }
+    deltas
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::sample_report;
+
+    fn report(medians: &[(usize, f64)]) -> BenchmarkReport {
+        let offsets: Vec<(usize, &[f64])> =
+            medians.iter().map(|(offset, median)| (*offset, std::slice::from_ref(median))).collect();
+        sample_report("i32", 4, &offsets)
+    }
+
+    #[test]
+    fn test_compare() {
+        let baseline = report(&[(0, 1.0), (1, 2.0), (2, 1.0)]);
+        let current = report(&[(0, 1.04), (1, 2.5), (3, 9.0)]);
+        let deltas = compare(&baseline, &current, 0.05);
+        assert_eq!(deltas.len(), 2);
+        assert_eq!((deltas[0].offset, deltas[0].regressed), (0, false));
+        assert_eq!((deltas[1].offset, deltas[1].regressed), (1, true));
+        assert!((deltas[1].change - 0.25).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let dir = std::env::temp_dir().join(format!("baselines-{}", std::process::id()));
+        let saved = report(&[(0, 1.5)]);
+        save(&dir, "main", &saved).unwrap();
+        assert_eq!(load(&dir, "main").unwrap(), saved);
+        assert!(load(&dir, "missing").is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
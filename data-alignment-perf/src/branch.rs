@@ -0,0 +1,151 @@
+//! Branch prediction: summing the bytes at or above 128, with a branch and
+//! without one, over the same values sorted and shuffled.
+//!
+//! Over sorted values the branch goes one way for the first half and the
+//! other for the second, so the predictor is almost always right; over
+//! shuffled values it is wrong about half the time and every miss flushes
+//! the pipeline. The branchless kernel turns the comparison into a mask and
+//! runs at the same speed either way.
+//!
+//! The branchy kernel passes each taken value through `black_box`, which
+//! the compiler cannot execute speculatively, so it has to keep the branch
+//! instead of turning it into a conditional move.
+
+use std::hint::black_box;
+
+use serde::{Deserialize, Serialize};
+
+use crate::pattern::SplitMix64;
+use crate::stats::Stats;
+use crate::{summarize, Config};
+
+/// Values at or above this are summed.
+const THRESHOLD: u8 = 128;
+
+/// The two kernels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BranchKernel {
+    Branchy,
+    Branchless,
+}
+
+impl BranchKernel {
+    pub const ALL: [BranchKernel; 2] = [BranchKernel::Branchy, BranchKernel::Branchless];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BranchKernel::Branchy => "branchy",
+            BranchKernel::Branchless => "branchless",
+        }
+    }
+}
+
+/// The sum of the `values` at or above [`THRESHOLD`], with a branch per
+/// value.
+pub fn branchy_sum(values: &[u8]) -> u64 {
+    let mut sum = 0u64;
+    for &value in values {
+        if value >= THRESHOLD {
+            sum = sum.wrapping_add(black_box(value) as u64);
+        }
+    }
+    black_box(sum)
+}
+
+/// [`branchy_sum`] without the branch: the comparison becomes an all-ones or
+/// all-zeros mask.
+pub fn branchless_sum(values: &[u8]) -> u64 {
+    let mut sum = 0u64;
+    for &value in values {
+        let mask = ((value >= THRESHOLD) as u64).wrapping_neg();
+        sum = sum.wrapping_add(value as u64 & mask);
+    }
+    black_box(sum)
+}
+
+/// `len` bytes from a fixed-seed generator.
+pub fn random_bytes(len: usize) -> Vec<u8> {
+    let mut rng = SplitMix64::new();
+    (0..len).map(|_| rng.next_u64() as u8).collect()
+}
+
+/// Timings of one kernel over one arrangement of the values.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BranchResult {
+    pub kernel: BranchKernel,
+    pub sorted: bool,
+    /// Milliseconds per iteration, in run order.
+    pub samples: Vec<f64>,
+    pub stats: Stats,
+    /// Nanoseconds per value at the median.
+    pub ns_per_element: f64,
+}
+
+/// Times both kernels over `config.n` random bytes, shuffled and then
+/// sorted, with the warmup, repeat, clock and outlier settings of `config`.
+pub fn run_branch(config: &Config) -> Vec<BranchResult> {
+    let shuffled = random_bytes(config.n);
+    let mut sorted = shuffled.clone();
+    sorted.sort_unstable();
+
+    let mut results = Vec::new();
+    for (values, is_sorted) in [(&shuffled, false), (&sorted, true)] {
+        for kernel in BranchKernel::ALL {
+            let pass = || match kernel {
+                BranchKernel::Branchy => branchy_sum(values),
+                BranchKernel::Branchless => branchless_sum(values),
+            };
+            for _ in 0..config.warmup {
+                pass();
+            }
+            let samples: Vec<f64> = (0..config.repeat)
+                .map(|_| {
+                    config.clock.time(|| {
+                        pass();
+                    }) / 1_000_000.0
+                })
+                .collect();
+            let stats = summarize(&samples, config);
+            results.push(BranchResult {
+                kernel,
+                sorted: is_sorted,
+                ns_per_element: stats.median * 1_000_000.0 / config.n as f64,
+                stats,
+                samples,
+            });
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kernels_agree() {
+        let values = random_bytes(10_000);
+        let expected: u64 = values.iter().filter(|&&v| v >= THRESHOLD).map(|&v| v as u64).sum();
+        assert_eq!(branchy_sum(&values), expected);
+        assert_eq!(branchless_sum(&values), expected);
+        assert_eq!(branchless_sum(&[127, 128, 255]), 383);
+    }
+
+    #[test]
+    fn test_run_branch() {
+        let config = Config {
+            n: 1000,
+            repeat: 2,
+            warmup: 0,
+            ..Config::default()
+        };
+        let results = run_branch(&config);
+        let variants: Vec<(&str, bool)> = results.iter().map(|r| (r.kernel.name(), r.sorted)).collect();
+        assert_eq!(
+            variants,
+            [("branchy", false), ("branchless", false), ("branchy", true), ("branchless", true)]
+        );
+        assert!(results.iter().all(|r| r.samples.len() == 2));
+    }
+}
@@ -0,0 +1,131 @@
+//! Sizing the workload to the machine instead of fixing `n` and `repeat`.
+//!
+//! A fixed `n` that takes 100 ms on one machine takes 10 ms on a faster one
+//! and is then mostly timer and scheduler noise; a fixed `repeat` is either
+//! too few samples for a noisy machine or wasted time on a quiet one. With
+//! a [`Calibration`], each element type first grows `n` until one iteration
+//! of the aligned kernel takes about the target time, and each offset keeps
+//! sampling past `repeat` until the confidence interval of its mean is
+//! within the tolerance (or `max_repeat` is reached).
+
+use serde::{Deserialize, Serialize};
+
+use crate::kernel::{write_read_pass, BenchElement, UnalignedBuffer};
+use crate::stats::relative_ci_half_width;
+use crate::Config;
+
+/// Confidence level of the stopping rule.
+pub const CONFIDENCE: f64 = 0.95;
+
+/// Largest buffer calibration will size up to.
+const MAX_BYTES: usize = 1 << 30;
+
+/// Target and stopping rule of a calibrated run.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Calibration {
+    /// Milliseconds one iteration should take.
+    pub target_ms: f64,
+    /// Stop sampling once the 95% confidence interval of the mean is within
+    /// this fraction of it either way (0.01 for 1%).
+    pub tolerance: f64,
+    /// Stop after this many samples regardless.
+    pub max_repeat: usize,
+}
+
+impl Default for Calibration {
+    fn default() -> Self {
+        Self {
+            target_ms: 100.0,
+            tolerance: 0.01,
+            max_repeat: 1000,
+        }
+    }
+}
+
+impl Calibration {
+    /// Whether `samples` (at least `repeat` of them) are enough.
+    pub fn done(&self, samples: &[f64], repeat: usize) -> bool {
+        samples.len() >= self.max_repeat
+            || (samples.len() >= repeat && relative_ci_half_width(samples, CONFIDENCE) <= self.tolerance)
+    }
+}
+
+fn time_pass<T: BenchElement>(config: &Config, n: usize) -> f64 {
+    let mut buffer = UnalignedBuffer::<T>::new_in(n, 0, config.allocator);
+    // The first pass faults the pages in.
+    write_read_pass(&mut buffer);
+    config.clock.time(|| {
+        write_read_pass(&mut buffer);
+    }) / 1_000_000.0
+}
+
+fn calibrate_n<T: BenchElement>(config: &Config, target_ms: f64) -> usize {
+    let max = (MAX_BYTES / size_of::<T>()).max(1);
+    let mut n = 1024.min(max);
+    loop {
+        let elapsed = time_pass::<T>(config, n);
+        // Scale linearly once a pass is long enough to time reliably.
+        if elapsed >= target_ms / 16.0 {
+            return ((n as f64 * target_ms / elapsed) as usize).clamp(1, max);
+        }
+        if n == max {
+            return n;
+        }
+        n = (n * 4).min(max);
+    }
+}
+
+impl Config {
+    /// This config with `n` sized to the calibration target for its element
+    /// type; unchanged without a calibration.
+    pub fn calibrated(&self) -> Config {
+        match self.calibration {
+            Some(calibration) => Config {
+                n: with_element_type!(self.element, T => calibrate_n::<T>(self, calibration.target_ms)),
+                ..self.clone()
+            },
+            None => self.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{run_alignment_bench, ElementType};
+
+    #[test]
+    fn test_done() {
+        let calibration = Calibration {
+            tolerance: 0.01,
+            max_repeat: 6,
+            ..Calibration::default()
+        };
+        assert!(!calibration.done(&[1.0, 1.0], 3));
+        assert!(calibration.done(&[1.0, 1.0, 1.0], 3));
+        assert!(!calibration.done(&[1.0, 2.0, 1.0, 2.0], 3));
+        assert!(calibration.done(&[1.0, 2.0, 1.0, 2.0, 1.0, 2.0], 3));
+    }
+
+    #[test]
+    fn test_calibrated_run() {
+        let config = Config {
+            element: ElementType::U32,
+            n: 1,
+            repeat: 3,
+            warmup: 0,
+            offsets: Some(0..2),
+            calibration: Some(Calibration {
+                target_ms: 2.0,
+                tolerance: 0.5,
+                max_repeat: 50,
+            }),
+            ..Config::default()
+        };
+        let calibrated = config.calibrated();
+        assert!(calibrated.n > 1024, "{}", calibrated.n);
+        for result in run_alignment_bench(config) {
+            assert!((3..=50).contains(&result.samples.len()));
+        }
+    }
+}
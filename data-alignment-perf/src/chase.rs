@@ -0,0 +1,138 @@
+//! Load-to-use latency by pointer chasing.
+//!
+//! The working set is an array of cache-line-sized nodes linked into one
+//! random cycle, and each load's address is the value of the previous load,
+//! so neither out-of-order execution nor the prefetchers can overlap them.
+//! The time per step is then the latency of whichever level of the memory
+//! hierarchy the working set fits in.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::crossing::CACHE_LINE;
+use crate::pattern::SplitMix64;
+use crate::stats::Stats;
+use crate::{summarize, Config};
+
+/// Loads per iteration.
+pub const STEPS: usize = 1 << 20;
+
+/// Working-set sizes used when none are given: roughly L1, L2, L3 and DRAM
+/// on current desktop parts.
+pub const DEFAULT_SIZES: [usize; 4] = [16 << 10, 256 << 10, 4 << 20, 256 << 20];
+
+/// One node per cache line, so consecutive steps never share a line.
+#[repr(C, align(64))]
+#[derive(Clone, Copy)]
+struct Node {
+    next: usize,
+}
+
+/// A shuffled visiting order of `0..len`. Linking each node to the next one
+/// in it gives a single cycle, so a chase sees every node before repeating.
+pub fn random_cycle(len: usize) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..len).collect();
+    let mut rng = SplitMix64::new();
+    for i in (1..len).rev() {
+        order.swap(i, rng.below(i + 1));
+    }
+    order
+}
+
+/// A random cyclic list over `bytes / CACHE_LINE` nodes (at least two).
+pub struct ChaseList {
+    nodes: Vec<Node>,
+}
+
+impl ChaseList {
+    pub fn new(bytes: usize) -> Self {
+        let len = (bytes / CACHE_LINE).max(2);
+        let order = random_cycle(len);
+        let mut nodes = vec![Node { next: 0 }; len];
+        for i in 0..len {
+            nodes[order[i]].next = order[(i + 1) % len];
+        }
+        Self { nodes }
+    }
+
+    /// Bytes spanned by the nodes.
+    pub fn bytes(&self) -> usize {
+        self.nodes.len() * CACHE_LINE
+    }
+
+    /// Follows `steps` links from node 0; returns where it stopped.
+    pub fn chase(&self, steps: usize) -> usize {
+        let mut i = 0;
+        for _ in 0..steps {
+            i = self.nodes[i].next;
+        }
+        black_box(i)
+    }
+}
+
+/// Latency for one working-set size.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChaseResult {
+    /// Working-set size in bytes.
+    pub bytes: usize,
+    /// Nanoseconds per load, one per iteration.
+    pub samples: Vec<f64>,
+    pub stats: Stats,
+}
+
+/// Times [`STEPS`] loads over a working set of `bytes`, with the warmup,
+/// repeat and outlier settings of `config`.
+pub fn measure_chase(config: &Config, bytes: usize) -> ChaseResult {
+    let list = ChaseList::new(bytes);
+    // Walk the whole cycle once so the pages are faulted in.
+    list.chase(list.nodes.len());
+    let time = || {
+        let start = Instant::now();
+        list.chase(STEPS);
+        start.elapsed().as_nanos() as f64 / STEPS as f64
+    };
+    for _ in 0..config.warmup {
+        time();
+    }
+    let samples: Vec<f64> = (0..config.repeat).map(|_| time()).collect();
+    ChaseResult {
+        bytes: list.bytes(),
+        stats: summarize(&samples, config),
+        samples,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_list_is_one_cycle() {
+        let list = ChaseList::new(64 * 100);
+        assert_eq!(list.bytes(), 6400);
+        let mut seen = [false; 100];
+        let mut i = 0;
+        for _ in 0..100 {
+            assert!(!seen[i]);
+            seen[i] = true;
+            i = list.nodes[i].next;
+        }
+        assert_eq!(i, 0);
+        assert_eq!(list.chase(100), 0);
+        assert_eq!(ChaseList::new(1).bytes(), 2 * CACHE_LINE);
+    }
+
+    #[test]
+    fn test_measure_chase() {
+        let config = Config {
+            repeat: 2,
+            warmup: 0,
+            ..Config::default()
+        };
+        let result = measure_chase(&config, 16 << 10);
+        assert_eq!((result.bytes, result.stats.count), (16 << 10, 2));
+        assert!(result.stats.min > 0.0);
+    }
+}
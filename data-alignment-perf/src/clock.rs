@@ -0,0 +1,346 @@
+//! The clocks the kernels can be timed with.
+//!
+//! `Instant` is portable, but where a pass over a small buffer takes a few
+//! hundred nanoseconds its resolution and call overhead sit right on top of
+//! the difference being measured. The time-stamp counter is read in a few
+//! cycles: `rdtsc` on x86_64, fenced so the loads and stores of the kernel
+//! cannot be reordered across it, and the virtual counter `cntvct_el0`
+//! behind an `isb` on aarch64. Both tick at a constant rate on current CPUs,
+//! whatever the core clock does; on x86_64 that rate is calibrated once
+//! against `Instant`, on aarch64 it is read from `cntfrq_el0`.
+//!
+//! Where the counter cannot be trusted (an x86_64 CPU, or a hypervisor,
+//! that does not report an invariant TSC) the OS clock is the next best:
+//! `QueryPerformanceCounter` on Windows, `CLOCK_MONOTONIC_RAW` on Linux,
+//! both without the conversion `Instant` does on every read. Each backend
+//! is a [`Timer`]; [`Clock`] picks one at runtime, and `Clock::Auto` picks
+//! the best the machine has.
+
+use std::sync::OnceLock;
+#[cfg(any(test, not(target_arch = "aarch64")))]
+use std::time::Duration;
+use std::time::Instant;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+/// A counter to time code with.
+pub trait Timer {
+    /// Reads the counter once everything before it has executed.
+    fn start(&self) -> u64;
+
+    /// Reads the counter once everything before it has executed, and before
+    /// anything after it starts.
+    #[inline(always)]
+    fn stop(&self) -> u64 {
+        self.start()
+    }
+
+    /// Counter ticks per nanosecond.
+    fn ticks_per_ns(&self) -> f64;
+
+    /// Runs `f` and returns how long it took, in nanoseconds.
+    #[inline(always)]
+    fn time(&self, f: impl FnOnce()) -> f64
+    where
+        Self: Sized,
+    {
+        let start = self.start();
+        f();
+        let end = self.stop();
+        end.wrapping_sub(start) as f64 / self.ticks_per_ns()
+    }
+}
+
+/// `std::time::Instant`, in nanoseconds since the first read.
+pub struct InstantTimer;
+
+impl Timer for InstantTimer {
+    #[inline(always)]
+    fn start(&self) -> u64 {
+        static EPOCH: OnceLock<Instant> = OnceLock::new();
+        EPOCH.get_or_init(Instant::now).elapsed().as_nanos() as u64
+    }
+
+    fn ticks_per_ns(&self) -> f64 {
+        1.0
+    }
+}
+
+/// The time-stamp counter: `rdtsc` on x86_64, `cntvct_el0` on aarch64.
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+pub struct TscTimer;
+
+#[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+impl Timer for TscTimer {
+    #[inline(always)]
+    fn start(&self) -> u64 {
+        ticks_start()
+    }
+
+    #[inline(always)]
+    fn stop(&self) -> u64 {
+        ticks_end()
+    }
+
+    fn ticks_per_ns(&self) -> f64 {
+        ticks_per_ns()
+    }
+}
+
+/// `QueryPerformanceCounter`.
+#[cfg(windows)]
+pub struct QpcTimer;
+
+#[cfg(windows)]
+#[link(name = "kernel32")]
+unsafe extern "system" {
+    fn QueryPerformanceCounter(count: *mut i64) -> i32;
+    fn QueryPerformanceFrequency(frequency: *mut i64) -> i32;
+}
+
+#[cfg(windows)]
+impl Timer for QpcTimer {
+    #[inline(always)]
+    fn start(&self) -> u64 {
+        let mut count = 0;
+        // Cannot fail on anything since Windows XP.
+        unsafe { QueryPerformanceCounter(&mut count) };
+        count as u64
+    }
+
+    fn ticks_per_ns(&self) -> f64 {
+        static RATE: OnceLock<f64> = OnceLock::new();
+        *RATE.get_or_init(|| {
+            let mut hz = 0;
+            unsafe { QueryPerformanceFrequency(&mut hz) };
+            (hz as f64 / 1e9).max(f64::MIN_POSITIVE)
+        })
+    }
+}
+
+/// `clock_gettime(CLOCK_MONOTONIC_RAW)`: not slewed by NTP, and served from
+/// the vDSO without a system call.
+#[cfg(target_os = "linux")]
+pub struct MonotonicTimer;
+
+#[cfg(target_os = "linux")]
+impl Timer for MonotonicTimer {
+    #[inline(always)]
+    fn start(&self) -> u64 {
+        let mut now = libc::timespec { tv_sec: 0, tv_nsec: 0 };
+        unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC_RAW, &mut now) };
+        now.tv_sec as u64 * 1_000_000_000 + now.tv_nsec as u64
+    }
+
+    fn ticks_per_ns(&self) -> f64 {
+        1.0
+    }
+}
+
+/// A clock to time a kernel with.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Clock {
+    /// `std::time::Instant`, everywhere.
+    #[default]
+    Instant,
+    /// The time-stamp counter: `rdtsc` on x86_64, `cntvct_el0` on aarch64.
+    Tsc,
+    /// `QueryPerformanceCounter`, on Windows.
+    Qpc,
+    /// `CLOCK_MONOTONIC_RAW`, on Linux.
+    Monotonic,
+    /// The best of the above the machine has, chosen on first use.
+    Auto,
+}
+
+impl Clock {
+    /// Whether this clock exists on the target.
+    pub fn available(self) -> bool {
+        match self {
+            Clock::Instant | Clock::Auto => true,
+            Clock::Tsc => cfg!(any(target_arch = "x86_64", target_arch = "aarch64")),
+            Clock::Qpc => cfg!(windows),
+            Clock::Monotonic => cfg!(target_os = "linux"),
+        }
+    }
+
+    /// The clock `Auto` stands for here: the counter when it ticks at a
+    /// constant rate, else the OS clock, else `Instant`. Every other clock
+    /// is itself.
+    pub fn resolve(self) -> Clock {
+        static BEST: OnceLock<Clock> = OnceLock::new();
+        match self {
+            Clock::Auto => *BEST.get_or_init(|| {
+                if invariant_tsc() {
+                    Clock::Tsc
+                } else if cfg!(windows) {
+                    Clock::Qpc
+                } else if cfg!(target_os = "linux") {
+                    Clock::Monotonic
+                } else {
+                    Clock::Instant
+                }
+            }),
+            clock => clock,
+        }
+    }
+
+    /// Runs `f` and returns how long it took, in nanoseconds. An
+    /// unavailable clock falls back to `Instant`.
+    #[inline(always)]
+    pub fn time(self, f: impl FnOnce()) -> f64 {
+        match self.resolve() {
+            #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+            Clock::Tsc => TscTimer.time(f),
+            #[cfg(windows)]
+            Clock::Qpc => QpcTimer.time(f),
+            #[cfg(target_os = "linux")]
+            Clock::Monotonic => MonotonicTimer.time(f),
+            _ => InstantTimer.time(f),
+        }
+    }
+}
+
+impl std::fmt::Display for Clock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+/// Whether the counter ticks at a constant rate through frequency changes
+/// and idle states (CPUID leaf 0x8000_0007, EDX bit 8, on x86_64; always on
+/// aarch64).
+pub fn invariant_tsc() -> bool {
+    #[cfg(target_arch = "x86_64")]
+    {
+        use std::arch::x86_64::__cpuid;
+        let max = __cpuid(0x8000_0000).eax;
+        max >= 0x8000_0007 && __cpuid(0x8000_0007).edx & (1 << 8) != 0
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        cfg!(target_arch = "aarch64")
+    }
+}
+
+/// Reads the counter once everything before it has executed.
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+fn ticks_start() -> u64 {
+    use std::arch::x86_64::{_mm_lfence, _rdtsc};
+    unsafe {
+        _mm_lfence();
+        let ticks = _rdtsc();
+        _mm_lfence();
+        ticks
+    }
+}
+
+/// Reads the counter once everything before it has executed, and before
+/// anything after it starts.
+#[cfg(target_arch = "x86_64")]
+#[inline(always)]
+fn ticks_end() -> u64 {
+    use std::arch::x86_64::{__rdtscp, _mm_lfence};
+    let mut aux = 0;
+    unsafe {
+        let ticks = __rdtscp(&mut aux);
+        _mm_lfence();
+        ticks
+    }
+}
+
+/// Reads the counter once everything before it has executed. Not `nomem`,
+/// so the compiler cannot move the kernel's loads and stores across it.
+#[cfg(target_arch = "aarch64")]
+#[inline(always)]
+fn ticks_start() -> u64 {
+    let ticks: u64;
+    unsafe { std::arch::asm!("isb", "mrs {t}, cntvct_el0", t = out(reg) ticks, options(nostack, preserves_flags)) };
+    ticks
+}
+
+#[cfg(target_arch = "aarch64")]
+#[inline(always)]
+fn ticks_end() -> u64 {
+    ticks_start()
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn ticks_start() -> u64 {
+    0
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn ticks_end() -> u64 {
+    0
+}
+
+/// Counter ticks per nanosecond, worked out on first use.
+pub fn ticks_per_ns() -> f64 {
+    static RATE: OnceLock<f64> = OnceLock::new();
+    *RATE.get_or_init(calibrate)
+}
+
+#[cfg(target_arch = "aarch64")]
+fn calibrate() -> f64 {
+    let hz: u64;
+    unsafe { std::arch::asm!("mrs {f}, cntfrq_el0", f = out(reg) hz, options(nostack, nomem, preserves_flags)) };
+    hz as f64 / 1e9
+}
+
+// Counts ticks over a 20 ms spin on `Instant`.
+#[cfg(not(target_arch = "aarch64"))]
+fn calibrate() -> f64 {
+    const CALIBRATION: Duration = Duration::from_millis(20);
+    let (start, ticks) = (Instant::now(), ticks_start());
+    while start.elapsed() < CALIBRATION {
+        std::hint::spin_loop();
+    }
+    let (ticks, elapsed) = (ticks_end().wrapping_sub(ticks), start.elapsed());
+    (ticks as f64 / elapsed.as_nanos() as f64).max(f64::MIN_POSITIVE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_clocks_agree() {
+        let spin = || {
+            let start = Instant::now();
+            while start.elapsed() < Duration::from_millis(5) {}
+        };
+        // Each clock against `Instant` around it, which however loaded the
+        // machine is can only have counted a little more.
+        for &clock in Clock::value_variants().iter().filter(|c| c.available()) {
+            // The first read calibrates the counter.
+            clock.time(|| {});
+            let mut ns = 0.0;
+            let outer = Clock::Instant.time(|| ns = clock.time(spin));
+            let ratio = ns / outer;
+            assert!((0.25..1.1).contains(&ratio), "{clock}: {ns}ns within {outer}ns");
+        }
+        assert_eq!(Clock::Tsc.to_string(), "tsc");
+    }
+
+    #[test]
+    fn test_auto_resolves() {
+        let clock = Clock::Auto.resolve();
+        assert_ne!(clock, Clock::Auto);
+        assert!(clock.available(), "{clock}");
+        assert_eq!(Clock::Instant.resolve(), Clock::Instant);
+    }
+
+    #[cfg(any(target_arch = "x86_64", target_arch = "aarch64"))]
+    #[test]
+    fn test_tsc_resolution() {
+        let ns = Clock::Tsc.time(|| {
+            std::hint::black_box(0);
+        });
+        assert!(ns < 100_000.0, "{ns}ns");
+        assert!(ticks_per_ns() > 0.0);
+    }
+}
@@ -0,0 +1,125 @@
+//! `copy_nonoverlapping` (memcpy) between buffers at every combination of
+//! source and destination misalignment.
+//!
+//! Copy routines align one side with a short head copy and then stream the
+//! rest in wide vector moves, so what matters is the relative misalignment
+//! of source and destination: with both off by the same amount the bulk
+//! stays aligned on both sides, otherwise every wide load or store of one
+//! side splits cache lines. Small copies are dominated by the head and tail
+//! handling instead, and large ones by memory bandwidth.
+
+use std::hint::black_box;
+use std::ptr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::kernel::UnalignedBuffer;
+use crate::stats::Stats;
+use crate::{summarize, Config};
+
+/// Copy sizes used when none are given.
+pub const DEFAULT_SIZES: [usize; 4] = [64, 4 << 10, 256 << 10, 16 << 20];
+
+/// Byte offsets past a cache-line boundary tried on both sides.
+pub const OFFSETS: [usize; 6] = [0, 1, 4, 8, 16, 32];
+
+/// Bytes copied per timed iteration, at least; small copies are repeated
+/// until they add up to this.
+const BYTES_PER_ITERATION: usize = 16 << 20;
+
+/// Timings of one size at one pair of offsets.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CopyResult {
+    pub bytes: usize,
+    pub src_offset: usize,
+    pub dst_offset: usize,
+    /// Copies per iteration.
+    pub copies: usize,
+    /// Milliseconds per iteration, in run order.
+    pub samples: Vec<f64>,
+    pub stats: Stats,
+    /// Bytes copied per second at the median, in GB/s (10^9 bytes).
+    pub gb_per_s: f64,
+}
+
+/// Copies `src` to `dst` (the same length) `copies` times.
+pub fn copy_pass(src: &[u8], dst: &mut [u8], copies: usize) {
+    let len = src.len().min(dst.len());
+    for _ in 0..copies {
+        // black_box keeps every copy: the compiler cannot see that the
+        // earlier ones are overwritten by the same bytes.
+        let (src, dst) = (black_box(src.as_ptr()), black_box(dst.as_mut_ptr()));
+        unsafe { ptr::copy_nonoverlapping(src, dst, len) };
+    }
+    black_box(dst);
+}
+
+/// Times copies of `bytes` bytes from `src_offset` to `dst_offset` past a
+/// cache-line boundary, with the warmup, repeat, clock and outlier settings
+/// of `config`.
+pub fn measure_copy(config: &Config, bytes: usize, src_offset: usize, dst_offset: usize) -> CopyResult {
+    let mut src = UnalignedBuffer::<u8>::new(bytes, src_offset);
+    let mut dst = UnalignedBuffer::<u8>::new(bytes, dst_offset);
+    for (i, byte) in src.as_bytes_mut().iter_mut().enumerate() {
+        *byte = i as u8;
+    }
+    let copies = (BYTES_PER_ITERATION / bytes.max(1)).max(1);
+    let src = src.as_bytes_mut();
+    let dst = dst.as_bytes_mut();
+    for _ in 0..config.warmup {
+        copy_pass(src, dst, copies);
+    }
+    let samples: Vec<f64> = (0..config.repeat)
+        .map(|_| config.clock.time(|| copy_pass(src, dst, copies)) / 1_000_000.0)
+        .collect();
+    let stats = summarize(&samples, config);
+    CopyResult {
+        bytes,
+        src_offset,
+        dst_offset,
+        copies,
+        gb_per_s: (copies * bytes) as f64 / (stats.median / 1000.0) / 1e9,
+        stats,
+        samples,
+    }
+}
+
+/// Measures every size at every pair of [`OFFSETS`].
+pub fn run_copy(config: &Config, sizes: &[usize]) -> Vec<CopyResult> {
+    let mut results = Vec::new();
+    for &bytes in sizes {
+        for src_offset in OFFSETS {
+            for dst_offset in OFFSETS {
+                results.push(measure_copy(config, bytes, src_offset, dst_offset));
+            }
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_copy_pass() {
+        let src: Vec<u8> = (0..100).collect();
+        let mut dst = vec![0u8; 100];
+        copy_pass(&src, &mut dst, 3);
+        assert_eq!(src, dst);
+    }
+
+    #[test]
+    fn test_measure_copy() {
+        let config = Config {
+            repeat: 2,
+            warmup: 0,
+            ..Config::default()
+        };
+        let result = measure_copy(&config, 4096, 1, 8);
+        assert_eq!((result.src_offset, result.dst_offset), (1, 8));
+        assert_eq!(result.copies, BYTES_PER_ITERATION / 4096);
+        assert_eq!(result.samples.len(), 2);
+        assert!(result.gb_per_s > 0.0);
+    }
+}
@@ -0,0 +1,225 @@
+//! Accesses that straddle a cache line or a page, separately from plain
+//! misalignment.
+//!
+//! The buffer is split into slots one boundary apart and every access
+//! touches one value per slot, always at the same position within it:
+//!
+//!   line           |0              63|64            127|
+//!   aligned        [vvvvvvvv·········|·················]
+//!   misaligned     [·vvvvvvvv········|·················]  still one line
+//!   split          [············vvvv|vvvv·············]  two lines
+//!
+//! Misaligned accesses that stay inside a line are usually close to free on
+//! current x86 and ARM cores; the split ones pay for a second line access.
+//! With 4 KiB slots the same split positions land on page boundaries, where
+//! each access also needs two TLB lookups (and, on some cores, a much slower
+//! microcode path); a line split inside the page is reported alongside as
+//! the baseline.
+
+use std::hint::black_box;
+use std::ptr;
+use std::sync::atomic::{fence, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use crate::kernel::{BenchElement, UnalignedBuffer};
+use crate::stats::Stats;
+use crate::{summarize, Config};
+
+/// Cache-line size assumed for the placements.
+pub const CACHE_LINE: usize = 64;
+
+/// Page size assumed for the placements.
+pub const PAGE: usize = 4096;
+
+/// Size of the buffer: enough slots to leave L1 and L2 (and, in pages, the
+/// L1 TLB) without needing `n * boundary` bytes of memory.
+pub const FOOTPRINT: usize = 32 << 20;
+
+/// Accesses per iteration; the slots are cycled through until reached.
+pub const ACCESSES: usize = FOOTPRINT / CACHE_LINE;
+
+/// Which boundary the accesses straddle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Boundary {
+    CacheLine,
+    Page,
+}
+
+impl Boundary {
+    /// Distance between slots, in bytes.
+    pub fn size(self) -> usize {
+        match self {
+            Boundary::CacheLine => CACHE_LINE,
+            Boundary::Page => PAGE,
+        }
+    }
+
+    /// Named positions within a slot for a value of `size` bytes; the split
+    /// position puts half of the value on each side of the boundary.
+    pub fn placements(self, size: usize) -> Vec<(&'static str, usize)> {
+        match self {
+            Boundary::CacheLine => vec![
+                ("aligned", 0),
+                ("misaligned", 1),
+                ("split", CACHE_LINE - size / 2),
+            ],
+            Boundary::Page => vec![
+                ("aligned", 0),
+                ("line-split", CACHE_LINE - size / 2),
+                ("page-split", PAGE - size / 2),
+            ],
+        }
+    }
+}
+
+/// Timings for one placement.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrossingResult {
+    pub placement: String,
+    /// Byte position of the value within its slot.
+    pub position: usize,
+    /// Milliseconds per iteration, in run order.
+    pub samples: Vec<f64>,
+    pub stats: Stats,
+}
+
+/// Writes then reads one `T` at `position` in each of `slots` slots
+/// `stride` bytes apart, `rounds` times over; returns the sum of the reads.
+pub fn crossing_pass<T: BenchElement>(
+    bytes: &mut [u8],
+    stride: usize,
+    position: usize,
+    slots: usize,
+    rounds: usize,
+) -> T {
+    let size = std::mem::size_of::<T>();
+
+    // Write phase with memory fence
+    for _ in 0..rounds {
+        for i in 0..slots {
+            let dst = &mut bytes[i * stride + position..][..size];
+            unsafe { ptr::write_unaligned(dst.as_mut_ptr() as *mut T, T::from_index(i % 100)) };
+            if i % 1000 == 0 { fence(Ordering::SeqCst); }
+        }
+    }
+
+    // black_box hides the bytes, so the reads cannot be folded away
+    let bytes = black_box(&*bytes);
+
+    // Read phase with accumulation
+    let mut sum = T::zero();
+    for _ in 0..rounds {
+        for i in 0..slots {
+            let src = &bytes[i * stride + position..][..size];
+            sum = sum.accumulate(unsafe { ptr::read_unaligned(src.as_ptr() as *const T) });
+            if i % 1000 == 0 { fence(Ordering::SeqCst); }
+        }
+    }
+    black_box(sum)
+}
+
+/// Times every placement of `boundary` for `T`, with the warmup, repeat and
+/// outlier settings of `config` (`config.n` is not used: the footprint is
+/// fixed at [`FOOTPRINT`]). Values of one byte cannot straddle anything, so
+/// they only get the aligned placement.
+pub fn measure_crossing<T: BenchElement>(config: &Config, boundary: Boundary) -> Vec<CrossingResult> {
+    let size = std::mem::size_of::<T>();
+    let stride = boundary.size();
+    let slots = FOOTPRINT / stride;
+    let rounds = ACCESSES / slots;
+    // One extra slot for the part of the last split value past the end.
+    let mut buffer = UnalignedBuffer::<u8>::new((slots + 1) * stride, 0);
+
+    boundary
+        .placements(size)
+        .into_iter()
+        .filter(|&(name, _)| size > 1 || name == "aligned")
+        .map(|(name, position)| {
+            for _ in 0..config.warmup {
+                crossing_pass::<T>(buffer.as_bytes_mut(), stride, position, slots, rounds);
+            }
+            let samples: Vec<f64> = (0..config.repeat)
+                .map(|_| {
+                    let start = std::time::Instant::now();
+                    crossing_pass::<T>(buffer.as_bytes_mut(), stride, position, slots, rounds);
+                    start.elapsed().as_nanos() as f64 / 1_000_000.0
+                })
+                .collect();
+            CrossingResult {
+                placement: name.to_string(),
+                position,
+                stats: summarize(&samples, config),
+                samples,
+            }
+        })
+        .collect()
+}
+
+/// [`measure_crossing`] for `config.element`.
+pub fn run_crossing(config: &Config, boundary: Boundary) -> Vec<CrossingResult> {
+    with_element_type!(config.element, T => measure_crossing::<T>(config, boundary))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_placement_straddles_the_line() {
+        for size in [2, 4, 8, 16] {
+            for (name, position) in Boundary::CacheLine.placements(size) {
+                let first_line = position / CACHE_LINE;
+                let last_line = (position + size - 1) / CACHE_LINE;
+                assert_eq!(first_line != last_line, name == "split", "{name} {size}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_page_placements() {
+        for size in [2, 4, 8, 16] {
+            let splits: Vec<(bool, bool)> = Boundary::Page
+                .placements(size)
+                .iter()
+                .map(|&(_, position)| {
+                    let last = position + size - 1;
+                    (position / CACHE_LINE != last / CACHE_LINE, position / PAGE != last / PAGE)
+                })
+                .collect();
+            assert_eq!(splits, [(false, false), (true, false), (true, true)], "{size}");
+        }
+    }
+
+    #[test]
+    fn test_crossing_pass_sums_every_slot() {
+        let mut bytes = vec![0u8; 11 * CACHE_LINE];
+        let expected: u64 = (0..10).sum();
+        assert_eq!(crossing_pass::<u64>(&mut bytes, CACHE_LINE, 60, 10, 1), expected);
+        assert_eq!(crossing_pass::<u64>(&mut bytes, CACHE_LINE, 60, 10, 3), expected * 3);
+        // The halves of slot 0 really landed on both lines.
+        assert_eq!(bytes[60..68], 0u64.to_le_bytes());
+        assert_eq!(bytes[124..132], 1u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_measure_crossing() {
+        let config = Config {
+            repeat: 2,
+            warmup: 0,
+            ..Config::default()
+        };
+        let results = measure_crossing::<u32>(&config, Boundary::CacheLine);
+        let names: Vec<&str> = results.iter().map(|r| r.placement.as_str()).collect();
+        assert_eq!(names, ["aligned", "misaligned", "split"]);
+        let pages = measure_crossing::<f64>(&config, Boundary::Page);
+        assert_eq!(pages[2].placement, "page-split");
+        assert_eq!(pages[2].position, PAGE - 4);
+        let bytes = Config {
+            element: crate::ElementType::U8,
+            ..config
+        };
+        assert_eq!(run_crossing(&bytes, Boundary::CacheLine).len(), 1);
+    }
+}
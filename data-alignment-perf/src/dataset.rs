@@ -0,0 +1,289 @@
+//! Reads from a memory-mapped file, cold (Linux): an existing dataset, or
+//! a new sparse file that can be larger than RAM without using any disk.
+//!
+//! Each iteration starts with the file dropped from the page cache, so
+//! every page it touches is a major fault unless readahead brought it in
+//! with an earlier one. The access patterns decide which pages are touched
+//! and in what order: sequential pages are what readahead is for, random
+//! pages defeat it, and `madvise` tells the kernel which to expect. Within
+//! each page every element is read, at the chosen byte offset, so the
+//! alignment cost is measured on top of the fault cost.
+//!
+//! The result counts major and minor faults per iteration and how many
+//! touched pages each major fault brought in: about 1 means readahead did
+//! nothing, more means it read ahead of the faults.
+
+use std::fs::{File, OpenOptions};
+use std::hint::black_box;
+use std::io;
+use std::os::fd::AsRawFd;
+use std::path::Path;
+use std::ptr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::kernel::BenchElement;
+use crate::pattern::{Pattern, SplitMix64};
+use crate::stats::Stats;
+use crate::{summarize, Config};
+
+/// Pages touched per iteration: 256 MiB with 4 KiB pages.
+pub const PAGES: usize = 1 << 16;
+
+/// The `madvise` hint given for the mapping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Advice {
+    /// `MADV_NORMAL`: the default readahead window.
+    Normal,
+    /// `MADV_SEQUENTIAL`: aggressive readahead.
+    Sequential,
+    /// `MADV_RANDOM`: no readahead.
+    Random,
+}
+
+impl Advice {
+    pub const ALL: [Advice; 3] = [Advice::Normal, Advice::Sequential, Advice::Random];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Advice::Normal => "normal",
+            Advice::Sequential => "sequential",
+            Advice::Random => "random",
+        }
+    }
+
+    fn flag(self) -> libc::c_int {
+        match self {
+            Advice::Normal => libc::MADV_NORMAL,
+            Advice::Sequential => libc::MADV_SEQUENTIAL,
+            Advice::Random => libc::MADV_RANDOM,
+        }
+    }
+}
+
+/// A file mapped read-only, unmapped on drop.
+pub struct Dataset {
+    file: File,
+    data: *const u8,
+    len: usize,
+    page: usize,
+}
+
+impl Dataset {
+    /// Maps the existing file at `path`.
+    pub fn open(path: &Path) -> io::Result<Self> {
+        Self::map(File::open(path)?)
+    }
+
+    /// Creates a sparse file of `bytes` at `path` (replacing any file
+    /// there) and maps it.
+    pub fn create(path: &Path, bytes: usize) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(true).open(path)?;
+        file.set_len(bytes as u64)?;
+        Self::map(file)
+    }
+
+    fn map(file: File) -> io::Result<Self> {
+        let len = file.metadata()?.len() as usize;
+        let page = unsafe { libc::sysconf(libc::_SC_PAGESIZE) } as usize;
+        if len < page {
+            return Err(io::Error::new(io::ErrorKind::InvalidInput, "dataset is smaller than a page"));
+        }
+        let data = unsafe { libc::mmap(ptr::null_mut(), len, libc::PROT_READ, libc::MAP_SHARED, file.as_raw_fd(), 0) };
+        if data == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            file,
+            data: data as *const u8,
+            len,
+            page,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Whole pages in the file.
+    pub fn pages(&self) -> usize {
+        self.len / self.page
+    }
+
+    fn advise(&self, advice: Advice) -> io::Result<()> {
+        if unsafe { libc::madvise(self.data as *mut libc::c_void, self.len, advice.flag()) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    /// Unmaps the pages from this process and drops them from the page
+    /// cache, so the next access to each is a major fault again.
+    pub fn evict(&self) -> io::Result<()> {
+        if unsafe { libc::madvise(self.data as *mut libc::c_void, self.len, libc::MADV_DONTNEED) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        match unsafe { libc::posix_fadvise(self.file.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED) } {
+            0 => Ok(()),
+            errno => Err(io::Error::from_raw_os_error(errno)),
+        }
+    }
+
+    /// Reads every `T` at `offset` bytes into each of `pages`; returns the
+    /// sum. Values that would cross into the next page are left out.
+    pub fn read_pass<T: BenchElement>(&self, pages: &[usize], offset: usize) -> T {
+        let size = size_of::<T>();
+        let per_page = self.page.saturating_sub(offset) / size;
+        let mut sum = T::zero();
+        for &page in pages {
+            let start = unsafe { self.data.add(page * self.page + offset) };
+            for i in 0..per_page {
+                sum = sum.accumulate(unsafe { ptr::read_unaligned(start.add(i * size) as *const T) });
+            }
+        }
+        black_box(sum)
+    }
+}
+
+impl Drop for Dataset {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.data as *mut libc::c_void, self.len) };
+    }
+}
+
+/// The pages `pattern` touches, in order: the first `count` for sequential
+/// reads, `count` drawn at random from the whole file, or every `K`th page
+/// from the start, wrapping around one further on.
+pub fn page_order(pattern: Pattern, pages: usize, count: usize) -> Vec<usize> {
+    let count = count.min(pages);
+    match pattern {
+        Pattern::Sequential => (0..count).collect(),
+        Pattern::Random => {
+            let mut rng = SplitMix64::new();
+            (0..count).map(|_| rng.below(pages)).collect()
+        }
+        Pattern::Stride(k) => (0..k.min(pages))
+            .flat_map(|start| (start..pages).step_by(k))
+            .take(count)
+            .collect(),
+    }
+}
+
+// Major and minor faults of this thread so far.
+fn faults() -> (u64, u64) {
+    let mut usage: libc::rusage = unsafe { std::mem::zeroed() };
+    unsafe { libc::getrusage(libc::RUSAGE_THREAD, &mut usage) };
+    (usage.ru_majflt as u64, usage.ru_minflt as u64)
+}
+
+/// Cold-read timings for one pattern, advice and offset.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DatasetResult {
+    pub pattern: String,
+    pub advice: Advice,
+    pub offset: usize,
+    /// Distinct pages touched per iteration.
+    pub pages: usize,
+    /// Milliseconds per iteration, in run order.
+    pub samples: Vec<f64>,
+    pub stats: Stats,
+    /// Major and minor faults, one of each per iteration.
+    pub major_faults: Vec<u64>,
+    pub minor_faults: Vec<u64>,
+    /// Distinct pages touched per major fault, over all iterations; `None`
+    /// when nothing faulted (the file stayed cached).
+    pub pages_per_major_fault: Option<f64>,
+}
+
+/// Times a cold read of [`PAGES`] pages of `dataset` for every pattern
+/// (sequential, random, and `config.pattern` if it is neither), advice and
+/// offset in `config.offsets` (0 and 1 by default), as `config.element`,
+/// with the repeat, clock and outlier settings of `config`. Every sample
+/// is cold, so there is no warmup.
+pub fn run_dataset(config: &Config, dataset: &Dataset) -> io::Result<Vec<DatasetResult>> {
+    let mut patterns = vec![Pattern::Sequential, Pattern::Random];
+    if !patterns.contains(&config.pattern) {
+        patterns.push(config.pattern);
+    }
+    let mut results = Vec::new();
+    for pattern in patterns {
+        let order = page_order(pattern, dataset.pages(), PAGES);
+        let mut distinct = order.clone();
+        distinct.sort_unstable();
+        distinct.dedup();
+        for advice in Advice::ALL {
+            dataset.advise(advice)?;
+            for offset in config.offsets.clone().unwrap_or(0..2) {
+                let (mut samples, mut major_faults, mut minor_faults) = (Vec::new(), Vec::new(), Vec::new());
+                for _ in 0..config.repeat {
+                    dataset.evict()?;
+                    let (major, minor) = faults();
+                    let ms = config.clock.time(|| {
+                        with_element_type!(config.element, T => {
+                            dataset.read_pass::<T>(&order, offset);
+                        })
+                    }) / 1_000_000.0;
+                    let (major_after, minor_after) = faults();
+                    samples.push(ms);
+                    major_faults.push(major_after - major);
+                    minor_faults.push(minor_after - minor);
+                }
+                let total: u64 = major_faults.iter().sum();
+                results.push(DatasetResult {
+                    pattern: pattern.to_string(),
+                    advice,
+                    offset,
+                    pages: distinct.len(),
+                    stats: summarize(&samples, config),
+                    samples,
+                    pages_per_major_fault: (total > 0)
+                        .then(|| (distinct.len() * major_faults.len()) as f64 / total as f64),
+                    major_faults,
+                    minor_faults,
+                });
+            }
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_page_order() {
+        assert_eq!(page_order(Pattern::Sequential, 10, 4), [0, 1, 2, 3]);
+        assert_eq!(page_order(Pattern::Stride(4), 10, 6), [0, 4, 8, 1, 5, 9]);
+        let random = page_order(Pattern::Random, 1000, 50);
+        assert_eq!(random.len(), 50);
+        assert!(random.iter().all(|&page| page < 1000));
+        assert_eq!(page_order(Pattern::Sequential, 3, 10).len(), 3);
+    }
+
+    #[test]
+    fn test_sparse_dataset() {
+        let path = std::env::temp_dir().join(format!("alignment-dataset-{}", std::process::id()));
+        let dataset = Dataset::create(&path, 64 << 10).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(dataset.len(), 64 << 10);
+        assert_eq!(dataset.read_pass::<u64>(&[0, 1], 3), 0);
+
+        let config = Config {
+            repeat: 2,
+            offsets: Some(0..2),
+            pattern: Pattern::Stride(2),
+            ..Config::default()
+        };
+        let results = run_dataset(&config, &dataset).unwrap();
+        // Three patterns, three kinds of advice, two offsets.
+        assert_eq!(results.len(), 18);
+        assert!(results.iter().all(|r| r.samples.len() == 2 && r.major_faults.len() == 2));
+        assert_eq!(results[0].pages, dataset.pages());
+    }
+}
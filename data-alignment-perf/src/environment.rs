@@ -0,0 +1,182 @@
+//! Checks that the machine is in a state where the numbers can be
+//! reproduced, for `--strict-env`: no boost clocks, no frequency scaling,
+//! no hyperthread sharing the measured core, and nothing else running.
+//!
+//! Each check passes, fails with the reason, or could not be made (outside
+//! Linux, or with `/sys` hidden); the outcome goes into the report next to
+//! the seed of the random orders, so a published number says what it was
+//! measured under.
+
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::affinity::parse_cpu_list;
+use crate::pattern::SEED;
+use crate::sysinfo::{detect_governor, detect_turbo, read};
+
+/// Share of CPU time other processes may use while the system counts as
+/// idle.
+pub const BUSY_THRESHOLD: f64 = 0.05;
+
+/// How long CPU usage is sampled for the idle check.
+pub const IDLE_WINDOW: Duration = Duration::from_millis(250);
+
+/// The outcome of one check.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Check {
+    pub name: String,
+    /// `None` when the check could not be made.
+    pub passed: Option<bool>,
+    pub detail: String,
+}
+
+impl Check {
+    fn new(name: &str, passed: Option<bool>, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            passed,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// The checks of one run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Environment {
+    /// Seed of every pseudo-random order (fixed in the source).
+    pub seed: u64,
+    pub checks: Vec<Check>,
+}
+
+impl Environment {
+    /// Runs every check, for a benchmark pinned to `pin` (every CPU if
+    /// empty); takes [`IDLE_WINDOW`].
+    pub fn check(pin: &[usize]) -> Self {
+        Self {
+            seed: SEED,
+            checks: vec![check_turbo(), check_governor(), check_smt(pin), check_idle()],
+        }
+    }
+
+    /// The checks that failed.
+    pub fn failed(&self) -> impl Iterator<Item = &Check> {
+        self.checks.iter().filter(|check| check.passed == Some(false))
+    }
+}
+
+fn check_turbo() -> Check {
+    match detect_turbo() {
+        Some(true) => Check::new("turbo", Some(false), "turbo boost is on: clocks depend on temperature and load"),
+        Some(false) => Check::new("turbo", Some(true), "turbo boost is off"),
+        None => Check::new("turbo", None, "no intel_pstate or cpufreq boost switch to read"),
+    }
+}
+
+fn check_governor() -> Check {
+    match detect_governor() {
+        Some(governor) if governor == "performance" => {
+            Check::new("frequency scaling", Some(true), "scaling governor is performance")
+        }
+        Some(governor) => Check::new(
+            "frequency scaling",
+            Some(false),
+            format!("scaling governor is {governor}, not performance: the clock ramps with load"),
+        ),
+        None => Check::new("frequency scaling", None, "no cpufreq scaling governor to read"),
+    }
+}
+
+/// The SMT check on already-read sysfs values: the sibling lists of the
+/// pinned CPUs, or for an unpinned run the global `smt/active` switch.
+pub fn smt_check(pin: &[usize], siblings: &[Option<String>], active: Option<&str>) -> Check {
+    const NAME: &str = "smt siblings";
+    if pin.is_empty() {
+        return match active {
+            Some("1") => Check::new(NAME, Some(false), "SMT is on and the run is not pinned: a sibling can share the core"),
+            Some(_) => Check::new(NAME, Some(true), "SMT is off"),
+            None => Check::new(NAME, None, "no smt/active switch to read"),
+        };
+    }
+    let mut shared = Vec::new();
+    for (&cpu, list) in pin.iter().zip(siblings) {
+        let Some(cpus) = list.as_deref().and_then(|list| parse_cpu_list(list).ok()) else {
+            return Check::new(NAME, None, format!("no thread siblings to read for CPU {cpu}"));
+        };
+        if cpus.len() > 1 {
+            shared.push(format!("CPU {cpu} shares its core with {list}", list = list.as_deref().unwrap_or("")));
+        }
+    }
+    if shared.is_empty() {
+        Check::new(NAME, Some(true), "the pinned CPUs have their cores to themselves")
+    } else {
+        Check::new(NAME, Some(false), shared.join("; "))
+    }
+}
+
+fn check_smt(pin: &[usize]) -> Check {
+    let siblings: Vec<Option<String>> = pin
+        .iter()
+        .map(|cpu| read(format!("/sys/devices/system/cpu/cpu{cpu}/topology/thread_siblings_list")))
+        .collect();
+    smt_check(pin, &siblings, read("/sys/devices/system/cpu/smt/active").as_deref())
+}
+
+/// Busy and total jiffies of the `cpu` line of `/proc/stat`.
+pub fn cpu_times(stat: &str) -> Option<(u64, u64)> {
+    let line = stat.lines().find(|line| line.starts_with("cpu "))?;
+    let fields: Vec<u64> = line.split_whitespace().skip(1).map(|field| field.parse().ok()).collect::<Option<_>>()?;
+    // user nice system idle iowait irq softirq steal [guest guest_nice],
+    // the guest times already counted in user and nice.
+    let total: u64 = fields.iter().take(8).sum();
+    let idle = fields.get(3)? + fields.get(4).unwrap_or(&0);
+    Some((total - idle, total))
+}
+
+fn check_idle() -> Check {
+    const NAME: &str = "idle system";
+    let before = read("/proc/stat").as_deref().and_then(cpu_times);
+    std::thread::sleep(IDLE_WINDOW);
+    let after = read("/proc/stat").as_deref().and_then(cpu_times);
+    match (before, after) {
+        (Some((busy, total)), Some((busy_after, total_after))) if total_after > total => {
+            let fraction = (busy_after - busy) as f64 / (total_after - total) as f64;
+            let detail = format!("{:.1}% of CPU time busy over {} ms", fraction * 100.0, IDLE_WINDOW.as_millis());
+            Check::new(NAME, Some(fraction <= BUSY_THRESHOLD), detail)
+        }
+        _ => Check::new(NAME, None, "no /proc/stat to read"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_smt_check() {
+        assert_eq!(smt_check(&[], &[], Some("1")).passed, Some(false));
+        assert_eq!(smt_check(&[], &[], Some("0")).passed, Some(true));
+        assert_eq!(smt_check(&[], &[], None).passed, None);
+
+        let shared = smt_check(&[2, 3], &[Some("2".into()), Some("3,11".into())], Some("1"));
+        assert_eq!(shared.passed, Some(false));
+        assert_eq!(shared.detail, "CPU 3 shares its core with 3,11");
+        assert_eq!(smt_check(&[2], &[Some("2".into())], Some("1")).passed, Some(true));
+        assert_eq!(smt_check(&[2], &[None], Some("1")).passed, None);
+    }
+
+    #[test]
+    fn test_cpu_times() {
+        let stat = "cpu  100 5 50 800 20 3 2 0 0 0\ncpu0 50 2 25 400 10 1 1 0 0 0\n";
+        assert_eq!(cpu_times(stat), Some((160, 980)));
+        assert_eq!(cpu_times("intr 1 2 3"), None);
+    }
+
+    #[test]
+    fn test_check_records_the_seed() {
+        let environment = Environment::check(&[]);
+        assert_eq!(environment.seed, SEED);
+        let names: Vec<&str> = environment.checks.iter().map(|check| check.name.as_str()).collect();
+        assert_eq!(names, ["turbo", "frequency scaling", "smt siblings", "idle system"]);
+    }
+}
@@ -0,0 +1,248 @@
+//! Store-to-load forwarding and 4K aliasing: two ways a load that follows a
+//! store is slowed down by where the two addresses sit relative to each
+//! other, not by alignment on its own.
+//!
+//! A load reading bytes still in the store buffer gets them forwarded, but
+//! only if one store covers all of them; a load that needs bytes from a
+//! store and from the cache (or from two stores) waits until the store has
+//! committed. The forwarding cases chain every store into the next load, so
+//! the time per operation is the store-to-load latency.
+//!
+//! The CPU first matches a load against older stores on the low 12 address
+//! bits only. A loop storing to `dst` and loading from `src` where
+//! `dst - src` is a little more than a multiple of 4096 makes each load look
+//! like it depends on a recent store, and waits for a full check that it
+//! did not: 4K aliasing. The aliasing sweep moves `dst` past `src` by 64 KiB
+//! plus a small delta.
+
+use std::hint::black_box;
+use std::ptr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::kernel::UnalignedBuffer;
+use crate::stats::Stats;
+use crate::{summarize, Config};
+
+/// Dependent store/load pairs per iteration of a forwarding case.
+pub const OPS: usize = 1 << 20;
+
+/// Elements stored and loaded per round of the aliasing loop: 32 KiB on
+/// each side, resident in L1 or L2.
+const ALIAS_LEN: usize = 4096;
+
+/// Rounds of the aliasing loop per iteration.
+const ALIAS_ROUNDS: usize = 64;
+
+/// Distance of `dst` past `src` in the aliasing sweep, before the delta.
+const ALIAS_BASE: usize = 64 << 10;
+
+/// Deltas added to [`ALIAS_BASE`]; the small nonzero ones alias.
+pub const DELTAS: [usize; 8] = [0, 8, 16, 32, 64, 256, 1024, 2048];
+
+// A width a case can store or load.
+trait Word: Copy {
+    fn from_u64(value: u64) -> Self;
+    fn to_u64(self) -> u64;
+}
+
+macro_rules! word {
+    ($($t:ty),*) => {$(
+        impl Word for $t {
+            #[inline(always)]
+            fn from_u64(value: u64) -> Self {
+                value as $t
+            }
+            #[inline(always)]
+            fn to_u64(self) -> u64 {
+                self as u64
+            }
+        }
+    )*};
+}
+
+word!(u8, u32, u64);
+
+// Stores `S` at `store_at`, loads `L` at `load_at` and feeds the load into
+// the next store, `ops` times. The pointer goes through black_box on every
+// access, so the compiler cannot forward the value in a register.
+fn forward_chain<S: Word, L: Word>(bytes: &mut [u8], store_at: usize, load_at: usize, ops: usize) -> u64 {
+    assert!(store_at + size_of::<S>() <= bytes.len() && load_at + size_of::<L>() <= bytes.len());
+    let base = bytes.as_mut_ptr();
+    let mut x = 0u64;
+    for _ in 0..ops {
+        unsafe {
+            ptr::write_unaligned(black_box(base).add(store_at) as *mut S, S::from_u64(x));
+            x = ptr::read_unaligned(black_box(base).add(load_at) as *const L).to_u64().wrapping_add(1);
+        }
+    }
+    black_box(x)
+}
+
+/// One store/load placement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ForwardingCase {
+    pub name: &'static str,
+    /// Width and byte position of the store, then of the load, within a
+    /// buffer that starts on a cache line.
+    pub store: (usize, usize),
+    pub load: (usize, usize),
+}
+
+/// The placements measured, from forwarded to stalled.
+pub const CASES: [ForwardingCase; 6] = [
+    ForwardingCase { name: "independent", store: (8, 0), load: (8, 64) },
+    ForwardingCase { name: "same address", store: (8, 0), load: (8, 0) },
+    ForwardingCase { name: "load inside store", store: (8, 0), load: (4, 4) },
+    ForwardingCase { name: "load straddles store", store: (4, 4), load: (8, 0) },
+    ForwardingCase { name: "wide load, byte store", store: (1, 0), load: (8, 0) },
+    ForwardingCase { name: "line-split pair", store: (8, 60), load: (8, 60) },
+];
+
+/// Runs `case` for `ops` operations.
+pub fn forwarding_pass(bytes: &mut [u8], case: &ForwardingCase, ops: usize) -> u64 {
+    let ((store_width, store_at), (load_width, load_at)) = (case.store, case.load);
+    match (store_width, load_width) {
+        (8, 8) => forward_chain::<u64, u64>(bytes, store_at, load_at, ops),
+        (8, 4) => forward_chain::<u64, u32>(bytes, store_at, load_at, ops),
+        (4, 8) => forward_chain::<u32, u64>(bytes, store_at, load_at, ops),
+        (1, 8) => forward_chain::<u8, u64>(bytes, store_at, load_at, ops),
+        widths => panic!("no kernel for store/load widths {widths:?}"),
+    }
+}
+
+/// Stores `src[i] + 1` to `dst[i]` for every element.
+pub fn alias_pass(src: &[u64], dst: &mut [u64]) {
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d = s.wrapping_add(1);
+    }
+    black_box(dst);
+}
+
+/// Timings of one forwarding case.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForwardingResult {
+    pub case: String,
+    /// Width and position of the store, e.g. `u64@0`.
+    pub store: String,
+    pub load: String,
+    /// Nanoseconds per store/load pair, one per iteration.
+    pub samples: Vec<f64>,
+    pub stats: Stats,
+}
+
+/// Timings of the aliasing loop at one distance.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AliasingResult {
+    /// Bytes from `src` to `dst`: 64 KiB plus the delta.
+    pub distance: usize,
+    pub delta: usize,
+    /// Nanoseconds per element, one per iteration.
+    pub samples: Vec<f64>,
+    pub stats: Stats,
+}
+
+/// Both experiments.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForwardingResults {
+    pub forwarding: Vec<ForwardingResult>,
+    pub aliasing: Vec<AliasingResult>,
+}
+
+// Times `pass` with the settings of `config`, in nanoseconds per `units`.
+fn time(config: &Config, units: usize, mut pass: impl FnMut()) -> Vec<f64> {
+    for _ in 0..config.warmup {
+        pass();
+    }
+    (0..config.repeat)
+        .map(|_| config.clock.time(&mut pass) / units as f64)
+        .collect()
+}
+
+fn describe((width, at): (usize, usize)) -> String {
+    format!("u{}@{}", width * 8, at)
+}
+
+/// Runs every case of [`CASES`] and every delta of [`DELTAS`] with the
+/// warmup, repeat, clock and outlier settings of `config`.
+pub fn run_forwarding(config: &Config) -> ForwardingResults {
+    let mut line = UnalignedBuffer::<u8>::new(128, 0);
+    let forwarding = CASES
+        .iter()
+        .map(|case| {
+            let samples = time(config, OPS, || {
+                forwarding_pass(line.as_bytes_mut(), case, OPS);
+            });
+            ForwardingResult {
+                case: case.name.to_string(),
+                store: describe(case.store),
+                load: describe(case.load),
+                stats: summarize(&samples, config),
+                samples,
+            }
+        })
+        .collect();
+
+    let words = (ALIAS_BASE + DELTAS[DELTAS.len() - 1]) / size_of::<u64>() + ALIAS_LEN;
+    let mut buffer = vec![0u64; words];
+    let aliasing = DELTAS
+        .iter()
+        .map(|&delta| {
+            let distance = ALIAS_BASE + delta;
+            let (src, rest) = buffer.split_at_mut(distance / size_of::<u64>());
+            let (src, dst) = (&src[..ALIAS_LEN], &mut rest[..ALIAS_LEN]);
+            let samples = time(config, ALIAS_LEN * ALIAS_ROUNDS, || {
+                for _ in 0..ALIAS_ROUNDS {
+                    alias_pass(black_box(&*src), dst);
+                }
+            });
+            AliasingResult {
+                distance,
+                delta,
+                stats: summarize(&samples, config),
+                samples,
+            }
+        })
+        .collect();
+
+    ForwardingResults { forwarding, aliasing }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_forwarding_pass() {
+        let mut bytes = vec![0u8; 128];
+        // Every load sees the value just stored, so x counts up.
+        assert_eq!(forwarding_pass(&mut bytes, &CASES[1], 10), 10);
+        // The independent load only ever reads zeros.
+        assert_eq!(forwarding_pass(&mut bytes, &CASES[0], 10), 1);
+        for case in &CASES {
+            forwarding_pass(&mut bytes, case, 3);
+        }
+    }
+
+    #[test]
+    fn test_alias_pass() {
+        let src: Vec<u64> = (0..10).collect();
+        let mut dst = vec![0; 10];
+        alias_pass(&src, &mut dst);
+        assert_eq!(dst, (1..11).collect::<Vec<u64>>());
+    }
+
+    #[test]
+    fn test_run_forwarding() {
+        let config = Config {
+            repeat: 1,
+            warmup: 0,
+            ..Config::default()
+        };
+        let results = run_forwarding(&config);
+        assert_eq!(results.forwarding.len(), CASES.len());
+        assert_eq!(results.forwarding[2].load, "u32@4");
+        assert_eq!(results.aliasing.len(), DELTAS.len());
+        assert_eq!(results.aliasing[1].distance, (64 << 10) + 8);
+    }
+}
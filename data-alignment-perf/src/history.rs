@@ -0,0 +1,188 @@
+//! Every run kept, for following a metric over time (across kernel,
+//! microcode or toolchain updates) rather than against one baseline.
+//!
+//! The store is a directory holding `runs.jsonl`: one line per run, the
+//! JSON report with the time it was recorded. Lines are only ever
+//! appended.
+
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::report::{BenchmarkReport, TypeResults};
+
+/// Where the history goes unless told otherwise.
+pub const DEFAULT_DIR: &str = "target/history";
+
+/// One recorded run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Entry {
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub report: BenchmarkReport,
+}
+
+/// The file the runs under `dir` are stored in.
+pub fn path(dir: &Path) -> PathBuf {
+    dir.join("runs.jsonl")
+}
+
+/// Appends `report` to the history in `dir`, stamped with the current time.
+pub fn append(dir: &Path, report: &BenchmarkReport) -> io::Result<()> {
+    std::fs::create_dir_all(dir)?;
+    let entry = Entry {
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+        report: report.clone(),
+    };
+    let mut line = serde_json::to_vec(&entry)?;
+    line.push(b'\n');
+    // One write per line, so an interrupted run leaves no half entry
+    // behind in the common case.
+    OpenOptions::new().create(true).append(true).open(path(dir))?.write_all(&line)
+}
+
+/// Reads every run in `dir`, oldest first.
+pub fn load(dir: &Path) -> io::Result<Vec<Entry>> {
+    let path = path(dir);
+    let file = File::open(&path).map_err(|e| io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?;
+    let mut entries = Vec::new();
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line).map_err(|e| {
+            io::Error::new(io::ErrorKind::InvalidData, format!("{}:{}: {}", path.display(), number + 1, e))
+        })?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+/// What to follow across runs.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Metric {
+    /// Median milliseconds per iteration.
+    #[default]
+    Median,
+    Mean,
+    Min,
+    /// Median over the median of the aligned offset of the same type.
+    Slowdown,
+}
+
+impl Metric {
+    fn of(self, types: &TypeResults, offset: usize) -> Option<f64> {
+        let result = types.offsets.iter().find(|o| o.offset == offset)?;
+        match self {
+            Metric::Median => Some(result.stats.median),
+            Metric::Mean => Some(result.stats.mean),
+            Metric::Min => Some(result.stats.min),
+            Metric::Slowdown => {
+                let aligned = types.offsets.iter().find(|o| o.aligned)?;
+                Some(result.stats.median / aligned.stats.median)
+            }
+        }
+    }
+}
+
+/// A metric of one type and offset, per run; `None` for runs that did not
+/// measure it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+    pub type_name: String,
+    pub offset: usize,
+    pub values: Vec<Option<f64>>,
+}
+
+/// One series per type and offset seen in any of `entries`, in the order
+/// they first appear.
+pub fn series(entries: &[Entry], metric: Metric) -> Vec<Series> {
+    let mut keys: Vec<(String, usize)> = Vec::new();
+    for entry in entries {
+        for types in &entry.report.results {
+            for result in &types.offsets {
+                let key = (types.type_name.clone(), result.offset);
+                if !keys.contains(&key) {
+                    keys.push(key);
+                }
+            }
+        }
+    }
+    keys.into_iter()
+        .map(|(type_name, offset)| Series {
+            values: entries
+                .iter()
+                .map(|entry| {
+                    let types = entry.report.results.iter().find(|t| t.type_name == type_name)?;
+                    metric.of(types, offset)
+                })
+                .collect(),
+            type_name,
+            offset,
+        })
+        .collect()
+}
+
+/// `timestamp` as `YYYY-MM-DD HH:MM` UTC.
+pub fn format_timestamp(timestamp: u64) -> String {
+    let (days, seconds) = (timestamp / 86_400, timestamp % 86_400);
+    // Civil date from days since 1970-01-01 (Howard Hinnant's algorithm).
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, seconds / 3600, seconds % 3600 / 60)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::sample_report;
+
+    /// One sample per offset, from 0 up.
+    fn report(medians: &[f64]) -> BenchmarkReport {
+        let offsets: Vec<(usize, &[f64])> = medians.iter().map(std::slice::from_ref).enumerate().collect();
+        sample_report("i32", 4, &offsets)
+    }
+
+    #[test]
+    fn test_append_and_load() {
+        let dir = std::env::temp_dir().join(format!("alignment-history-{}", std::process::id()));
+        append(&dir, &report(&[1.0, 2.0])).unwrap();
+        append(&dir, &report(&[1.5])).unwrap();
+        let entries = load(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[1].report, report(&[1.5]));
+        assert!(entries[0].timestamp <= entries[1].timestamp);
+    }
+
+    #[test]
+    fn test_series() {
+        let entries: Vec<Entry> = [report(&[1.0, 2.0]), report(&[2.0])]
+            .into_iter()
+            .map(|report| Entry { timestamp: 0, report })
+            .collect();
+        let slowdown = series(&entries, Metric::Slowdown);
+        assert_eq!(slowdown.len(), 2);
+        assert_eq!(slowdown[1].offset, 1);
+        assert_eq!(slowdown[1].values, [Some(2.0), None]);
+        assert_eq!(series(&entries, Metric::Median)[0].values, [Some(1.0), Some(2.0)]);
+    }
+
+    #[test]
+    fn test_format_timestamp() {
+        assert_eq!(format_timestamp(0), "1970-01-01 00:00");
+        assert_eq!(format_timestamp(1_709_210_096), "2024-02-29 12:34");
+    }
+}
@@ -0,0 +1,218 @@
+//! A single self-contained HTML page of a run, for sharing: machine and
+//! parameters, the per-type verdicts, the charts inlined as SVG and a table
+//! per element type. No scripts, stylesheets or images are loaded from
+//! elsewhere.
+
+use std::io::{self, Write};
+
+use crate::plot;
+use crate::report::{BenchmarkReport, MachineInfo, TypeResults, Verdict};
+
+const STYLE: &str = "body{font-family:sans-serif;max-width:60em;margin:2em auto;padding:0 1em;color:#222}\
+table{border-collapse:collapse;margin:1em 0}\
+th,td{border:1px solid #ccc;padding:.25em .6em}\
+td.n{text-align:right;font-variant-numeric:tabular-nums}\
+th{background:#f3f3f3;text-align:left}\
+svg{max-width:100%;height:auto}";
+
+/// Escapes `text` for use in HTML content and attribute values.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn key_value_table(out: &mut impl Write, rows: &[(String, String)]) -> io::Result<()> {
+    writeln!(out, "<table>")?;
+    for (key, value) in rows {
+        writeln!(out, "<tr><th>{}</th><td>{}</td></tr>", escape(key), escape(value))?;
+    }
+    writeln!(out, "</table>")
+}
+
+fn machine_rows(machine: &MachineInfo) -> Vec<(String, String)> {
+    let known = |value: &Option<String>| value.clone().unwrap_or_else(|| "unknown".to_string());
+    let mut rows = vec![
+        ("os".to_string(), machine.os.clone()),
+        ("kernel".to_string(), known(&machine.kernel)),
+        ("arch".to_string(), machine.arch.clone()),
+        ("cpu".to_string(), known(&machine.cpu)),
+        ("logical cpus".to_string(), machine.logical_cpus.to_string()),
+    ];
+    if let Some(topology) = &machine.topology {
+        rows.push((
+            "topology".to_string(),
+            format!(
+                "{} socket(s), {} core(s), {} thread(s) per core",
+                topology.sockets, topology.cores, topology.threads_per_core
+            ),
+        ));
+    }
+    for cache in &machine.caches {
+        rows.push((
+            format!("L{} {}", cache.level, cache.kind.to_lowercase()),
+            format!(
+                "{} KiB, {}-byte lines, shared by {} cpu(s)",
+                cache.size >> 10,
+                cache.line_size,
+                cache.shared_by
+            ),
+        ));
+    }
+    rows.push(("governor".to_string(), known(&machine.governor)));
+    let turbo = machine.turbo.map(|on| if on { "on" } else { "off" }.to_string());
+    rows.push(("turbo".to_string(), known(&turbo)));
+    if let Some(memory) = &machine.memory {
+        let total = memory.total.map(|bytes| format!("{:.1} GiB", bytes as f64 / (1u64 << 30) as f64));
+        rows.push(("memory".to_string(), known(&total)));
+        rows.push(("page size".to_string(), known(&memory.page_size.map(|size| size.to_string()))));
+        rows.push(("transparent huge pages".to_string(), known(&memory.transparent_hugepages)));
+        rows.push(("numa nodes".to_string(), memory.numa_nodes.to_string()));
+    }
+    rows.push(("rustc".to_string(), known(&machine.rustc)));
+    rows
+}
+
+fn offsets_table(out: &mut impl Write, result: &TypeResults) -> io::Result<()> {
+    writeln!(out, "<h3>{} ({} bytes)</h3>", escape(&result.type_name), result.size)?;
+    writeln!(
+        out,
+        "<table><tr><th>offset</th><th>aligned</th><th>median (ms)</th><th>mean (ms)</th>\
+         <th>trimmed (ms)</th><th>min (ms)</th><th>max (ms)</th><th>sd (ms)</th><th>vs aligned</th></tr>"
+    )?;
+    let aligned = result.offsets.iter().find(|o| o.aligned).map(|o| o.stats.median);
+    for offset in &result.offsets {
+        let stats = &offset.stats;
+        let change = match aligned {
+            Some(aligned) if !offset.aligned => format!("{:+.1}%", (stats.median / aligned - 1.0) * 100.0),
+            _ => String::new(),
+        };
+        writeln!(
+            out,
+            "<tr><td class=\"n\">{}</td><td>{}</td><td class=\"n\">{:.3}</td><td class=\"n\">{:.3}</td>\
+             <td class=\"n\">{:.3}</td><td class=\"n\">{:.3}</td><td class=\"n\">{:.3}</td>\
+             <td class=\"n\">{:.3}</td><td class=\"n\">{}</td></tr>",
+            offset.offset,
+            if offset.aligned { "yes" } else { "no" },
+            stats.median,
+            stats.mean,
+            stats.trimmed_mean,
+            stats.min,
+            stats.max,
+            stats.std_dev,
+            change
+        )?;
+    }
+    writeln!(out, "</table>")
+}
+
+/// Writes `report` as one HTML document.
+pub fn write_html(out: &mut impl Write, report: &BenchmarkReport) -> io::Result<()> {
+    writeln!(out, "<!DOCTYPE html>")?;
+    writeln!(out, "<html lang=\"en\"><head><meta charset=\"utf-8\">")?;
+    writeln!(out, "<title>Data alignment benchmark</title><style>{}</style></head><body>", STYLE)?;
+    writeln!(out, "<h1>Data alignment benchmark</h1>")?;
+
+    writeln!(out, "<h2>Machine</h2>")?;
+    key_value_table(out, &machine_rows(&report.machine))?;
+
+    // Every parameter, as written by `--format json`, so new ones show up
+    // without touching this page.
+    writeln!(out, "<h2>Parameters</h2>")?;
+    let parameters = match serde_json::to_value(&report.parameters)? {
+        serde_json::Value::Object(fields) => fields
+            .into_iter()
+            .map(|(key, value)| match value {
+                serde_json::Value::String(s) => (key, s),
+                serde_json::Value::Null => (key, "all".to_string()),
+                value => (key, value.to_string()),
+            })
+            .collect(),
+        _ => Vec::new(),
+    };
+    key_value_table(out, &parameters)?;
+
+    if let Some(environment) = &report.environment {
+        writeln!(out, "<h2>Environment</h2>")?;
+        let mut rows = vec![("seed".to_string(), format!("{:#x}", environment.seed))];
+        rows.extend(environment.checks.iter().map(|check| {
+            let outcome = match check.passed {
+                Some(true) => "ok",
+                Some(false) => "FAILED",
+                None => "not checked",
+            };
+            (check.name.clone(), format!("{}: {}", outcome, check.detail))
+        }));
+        key_value_table(out, &rows)?;
+    }
+
+    let verdicts: Vec<Verdict> = report.results.iter().filter_map(Verdict::from_results).collect();
+    if !verdicts.is_empty() {
+        writeln!(out, "<h2>Verdict</h2><ul>")?;
+        for verdict in verdicts {
+            writeln!(out, "<li>{}</li>", escape(&verdict.to_string()))?;
+        }
+        writeln!(out, "</ul>")?;
+    }
+
+    if !report.results.is_empty() {
+        writeln!(out, "<h2>Charts</h2>")?;
+        let svg = plot::svg_string(&report.results).map_err(|e| io::Error::other(e.to_string()))?;
+        writeln!(out, "{}", svg)?;
+    }
+
+    writeln!(out, "<h2>Results</h2>")?;
+    for result in &report.results {
+        offsets_table(out, result)?;
+    }
+    writeln!(out, "</body></html>")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::sample_report;
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(escape("a<b & \"c\""), "a&lt;b &amp; &quot;c&quot;");
+    }
+
+    #[test]
+    fn test_write_html() {
+        let mut report = sample_report("i32", 4, &[(0, &[1.0, 1.2]), (1, &[2.0, 2.4])]);
+        report.machine = MachineInfo {
+            os: "linux".to_string(),
+            arch: "x86_64".to_string(),
+            cpu: Some("Some <CPU>".to_string()),
+            logical_cpus: 4,
+            topology: None,
+            caches: Vec::new(),
+            kernel: None,
+            rustc: Some("rustc 1.95.0".to_string()),
+            governor: None,
+            turbo: Some(false),
+            memory: None,
+        };
+        let mut out = Vec::new();
+        write_html(&mut out, &report).unwrap();
+        let html = String::from_utf8(out).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>") && html.ends_with("</body></html>\n"));
+        assert!(html.contains("<td>Some &lt;CPU&gt;</td>"));
+        assert!(html.contains("<tr><th>turbo</th><td>off</td></tr>"));
+        assert!(html.contains("<tr><th>rustc</th><td>rustc 1.95.0</td></tr>"));
+        assert!(html.contains("<tr><th>pattern</th><td>sequential</td></tr>"));
+        assert!(html.contains("<tr><th>offsets</th><td>all</td></tr>"));
+        assert!(html.contains("<li>i32: offset 1 is 2.00\u{d7} slower than offset 0</li>"));
+        assert!(html.contains("<svg") && html.contains("+100.0%"));
+    }
+}
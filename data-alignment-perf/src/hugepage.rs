@@ -0,0 +1,215 @@
+//! TLB misses: the same random pointer chase over a large working set backed
+//! by 4 KiB pages, transparent huge pages and explicit huge pages (Linux).
+//!
+//! With 4 KiB pages a 512 MiB working set needs 131072 TLB entries, so nearly
+//! every load also walks the page tables; 2 MiB pages cut that to 256, which
+//! fits in the second-level TLB of most cores. The difference between the
+//! backings is the cost of the misses.
+//!
+//! Transparent huge pages are requested with `madvise(MADV_HUGEPAGE)` and
+//! granted only if `/sys/kernel/mm/transparent_hugepage/enabled` allows it
+//! and memory is not too fragmented, so the result reports how much of the
+//! mapping actually got them. Explicit huge pages (`MAP_HUGETLB`) need pages
+//! reserved beforehand (`vm.nr_hugepages`); without them that backing is
+//! reported as unavailable.
+
+use std::io;
+use std::ptr;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::chase::{random_cycle, STEPS};
+use crate::crossing::CACHE_LINE;
+use crate::stats::Stats;
+use crate::{summarize, Config};
+
+/// Size of a (2 MiB) huge page.
+pub const HUGE_PAGE: usize = 2 << 20;
+
+/// Working set used when none is given.
+pub const DEFAULT_BYTES: usize = 512 << 20;
+
+/// What backs the working set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Backing {
+    /// `madvise(MADV_NOHUGEPAGE)`: 4 KiB pages only.
+    SmallPages,
+    /// `madvise(MADV_HUGEPAGE)`.
+    Transparent,
+    /// `mmap(MAP_HUGETLB)`.
+    Explicit,
+}
+
+impl Backing {
+    pub const ALL: [Backing; 3] = [Backing::SmallPages, Backing::Transparent, Backing::Explicit];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Backing::SmallPages => "4k",
+            Backing::Transparent => "thp",
+            Backing::Explicit => "hugetlb",
+        }
+    }
+}
+
+/// An anonymous mapping of huge-page-aligned memory, unmapped on drop.
+pub(crate) struct Mapping {
+    base: *mut u8,
+    mapped: usize,
+    pub(crate) data: *mut u8,
+}
+
+impl Mapping {
+    /// Maps `bytes` (a multiple of [`HUGE_PAGE`]) with `backing`.
+    pub(crate) fn new(bytes: usize, backing: Backing) -> io::Result<Self> {
+        let protection = libc::PROT_READ | libc::PROT_WRITE;
+        let flags = libc::MAP_PRIVATE | libc::MAP_ANONYMOUS;
+        // Huge pages need 2 MiB alignment; hugetlb mappings get it from the
+        // kernel, the others are over-mapped and aligned by hand.
+        let (flags, mapped) = match backing {
+            Backing::Explicit => (flags | libc::MAP_HUGETLB, bytes),
+            _ => (flags, bytes + HUGE_PAGE),
+        };
+        let base = unsafe { libc::mmap(ptr::null_mut(), mapped, protection, flags, -1, 0) };
+        if base == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        let base = base as *mut u8;
+        let data = unsafe { base.add(base.align_offset(HUGE_PAGE)) };
+        let mapping = Self { base, mapped, data };
+
+        let advice = match backing {
+            Backing::SmallPages => libc::MADV_NOHUGEPAGE,
+            Backing::Transparent => libc::MADV_HUGEPAGE,
+            Backing::Explicit => return Ok(mapping),
+        };
+        if unsafe { libc::madvise(data as *mut libc::c_void, bytes, advice) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(mapping)
+    }
+
+    /// Fraction of the data backed by transparent huge pages, from the
+    /// `AnonHugePages` line of its `/proc/self/smaps` entry (`madvise`
+    /// split the data into an entry of its own).
+    fn transparent_fraction(&self) -> Option<f64> {
+        let smaps = std::fs::read_to_string("/proc/self/smaps").ok()?;
+        let address = self.data as usize;
+        let mut lines = smaps.lines();
+        // Entries start with `start-end perms ...` in hex.
+        lines.find(|line| {
+            line.split_once(' ')
+                .and_then(|(range, _)| range.split_once('-'))
+                .and_then(|(start, _)| usize::from_str_radix(start, 16).ok())
+                == Some(address)
+        })?;
+        let kib = |field: &str, line: &str| -> Option<f64> {
+            line.strip_prefix(field)?.trim().strip_suffix("kB")?.trim().parse().ok()
+        };
+        let (mut size, mut huge) = (None, None);
+        // Then `Field: value` lines until the next entry.
+        let fields = lines.take_while(|line| line.split_whitespace().next().is_some_and(|t| t.ends_with(':')));
+        for line in fields {
+            size = size.or_else(|| kib("Size:", line));
+            huge = huge.or_else(|| kib("AnonHugePages:", line));
+        }
+        Some(huge? / size?)
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        unsafe { libc::munmap(self.base as *mut libc::c_void, self.mapped) };
+    }
+}
+
+/// Links the `bytes / CACHE_LINE` lines at `data` into one random cycle, the
+/// next index stored in the first word of each line.
+pub(crate) fn link(data: *mut u8, bytes: usize) {
+    let len = bytes / CACHE_LINE;
+    let order = random_cycle(len);
+    for i in 0..len {
+        unsafe { (data.add(order[i] * CACHE_LINE) as *mut usize).write(order[(i + 1) % len]) };
+    }
+}
+
+/// Follows `steps` links from line 0; returns where it stopped.
+pub(crate) fn chase(data: *const u8, steps: usize) -> usize {
+    let mut i = 0;
+    for _ in 0..steps {
+        i = unsafe { (data.add(i * CACHE_LINE) as *const usize).read() };
+    }
+    std::hint::black_box(i)
+}
+
+/// Latency for one backing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HugePageResult {
+    pub backing: Backing,
+    /// Working-set size in bytes.
+    pub bytes: usize,
+    /// Fraction of the working set that is on huge pages, where it can be
+    /// read back (`None` for explicit huge pages, which are all huge).
+    pub huge_fraction: Option<f64>,
+    /// Nanoseconds per load, one per iteration.
+    pub samples: Vec<f64>,
+    pub stats: Stats,
+}
+
+/// Chases a working set of `bytes` (rounded up to whole huge pages) backed
+/// by `backing`, with the settings of `config`. Fails if the memory cannot
+/// be mapped that way, e.g. when no explicit huge pages are reserved.
+pub fn measure_backing(config: &Config, backing: Backing, bytes: usize) -> io::Result<HugePageResult> {
+    let bytes = bytes.max(1).next_multiple_of(HUGE_PAGE);
+    let mapping = Mapping::new(bytes, backing)?;
+    // Linking writes every line, so the pages are faulted in before timing.
+    link(mapping.data, bytes);
+    let time = || {
+        let start = Instant::now();
+        chase(mapping.data, STEPS);
+        start.elapsed().as_nanos() as f64 / STEPS as f64
+    };
+    for _ in 0..config.warmup {
+        time();
+    }
+    let samples: Vec<f64> = (0..config.repeat).map(|_| time()).collect();
+    let huge_fraction = match backing {
+        Backing::Explicit => None,
+        _ => mapping.transparent_fraction(),
+    };
+    Ok(HugePageResult {
+        backing,
+        bytes,
+        huge_fraction,
+        stats: summarize(&samples, config),
+        samples,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_small_pages_chase() {
+        let config = Config {
+            repeat: 2,
+            warmup: 0,
+            ..Config::default()
+        };
+        let result = measure_backing(&config, Backing::SmallPages, 1).unwrap();
+        assert_eq!((result.bytes, result.stats.count), (HUGE_PAGE, 2));
+        assert_eq!(result.huge_fraction, Some(0.0));
+    }
+
+    #[test]
+    fn test_link_makes_one_cycle() {
+        let mapping = Mapping::new(HUGE_PAGE, Backing::SmallPages).unwrap();
+        link(mapping.data, HUGE_PAGE);
+        let lines = HUGE_PAGE / CACHE_LINE;
+        assert_eq!(chase(mapping.data, lines), 0);
+        assert_ne!(chase(mapping.data, lines / 2), 0);
+    }
+}
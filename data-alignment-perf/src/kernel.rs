@@ -0,0 +1,349 @@
+//! The measured code: a deliberately (mis)aligned buffer and the write/read
+//! kernel run over it.
+
+use std::hint::black_box;
+use std::ptr;
+use std::sync::atomic::{fence, Ordering};
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::aligned::MisalignedView;
+use crate::allocator::{Allocation, Allocator};
+
+/// Alignment of the allocation itself, so that offset 0 is aligned for every
+/// tested type and offset k is exactly k bytes past a cache-line boundary.
+pub const BASE_ALIGN: usize = 64;
+
+/// `len` values of `T` stored back to back, starting `offset` bytes into a
+/// `BASE_ALIGN`-aligned byte buffer. For offsets that are not a multiple of
+/// `align_of::<T>()` every element is misaligned, so all accesses go through
+/// `ptr::read_unaligned`/`ptr::write_unaligned`. The memory starts zeroed,
+/// which is a valid value of every [`BenchElement`].
+///
+/// [`aligned`](crate::aligned) has the same pieces, an aligned allocation
+/// and an offset view into it, as standalone types.
+pub struct UnalignedBuffer<T> {
+    allocation: Allocation,
+    offset: usize,
+    len: usize,
+    _phantom: std::marker::PhantomData<T>,
+}
+
+impl<T: BenchElement> UnalignedBuffer<T> {
+    /// Allocates room for `len` elements starting `offset` bytes in; the
+    /// contents start out zeroed.
+    pub fn new(len: usize, offset: usize) -> Self {
+        Self::new_in(len, offset, Allocator::default())
+    }
+
+    /// Like [`new`](Self::new), with the memory from `allocator`.
+    pub fn new_in(len: usize, offset: usize, allocator: Allocator) -> Self {
+        let size = std::mem::size_of::<T>() * len + offset;
+        Self {
+            allocation: Allocation::new(size, allocator),
+            offset,
+            len,
+            _phantom: std::marker::PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Pointer to the first element; only valid for unaligned accesses.
+    pub fn as_ptr(&self) -> *mut T {
+        unsafe { self.allocation.as_ptr().add(self.offset) as *mut T }
+    }
+
+    /// The elements' bytes, for kernels that pick their own access widths.
+    pub fn as_bytes_mut(&mut self) -> &mut [u8] {
+        let len = self.len * std::mem::size_of::<T>();
+        // The allocation is zeroed and covers `offset + len` bytes.
+        unsafe { std::slice::from_raw_parts_mut(self.allocation.as_ptr().add(self.offset), len) }
+    }
+
+    /// Whether the elements are naturally aligned for `T`.
+    pub fn is_aligned(&self) -> bool {
+        (self.as_ptr() as usize).is_multiple_of(std::mem::align_of::<T>())
+    }
+
+    /// The elements as a [`MisalignedView`].
+    pub fn view(&mut self) -> MisalignedView<'_, T> {
+        MisalignedView::new(self.as_bytes_mut(), 0)
+    }
+
+    /// Writes element `i`; panics if `i >= len`.
+    #[inline(always)]
+    pub fn write(&mut self, i: usize, value: T) {
+        assert!(i < self.len, "index {} out of bounds for length {}", i, self.len);
+        // In bounds of the allocation, and write_unaligned has no alignment
+        // requirement.
+        unsafe { ptr::write_unaligned(self.as_ptr().add(i), value) }
+    }
+
+    /// Reads element `i`; panics if `i >= len`.
+    #[inline(always)]
+    pub fn read(&self, i: usize) -> T {
+        assert!(i < self.len, "index {} out of bounds for length {}", i, self.len);
+        unsafe { ptr::read_unaligned(self.as_ptr().add(i)) }
+    }
+}
+
+// The buffer owns its allocation like a `Vec<T>` does, and only `write`
+// needs `&mut self`.
+unsafe impl<T: Send> Send for UnalignedBuffer<T> {}
+unsafe impl<T: Sync> Sync for UnalignedBuffer<T> {}
+
+/// A numeric type the kernel can write, read back and sum.
+///
+/// Integer sums wrap: the checksum only has to depend on every read, and
+/// `u8` sums overflow after a few elements.
+pub trait BenchElement: Copy {
+    /// The value stored at index `i`, converted with `as`.
+    fn from_index(i: usize) -> Self;
+    fn zero() -> Self;
+    fn accumulate(self, other: Self) -> Self;
+    /// Converts between native and big-endian byte order (floats by their
+    /// bits); its own inverse.
+    fn to_be(self) -> Self;
+}
+
+macro_rules! bench_element_int {
+    ($($t:ty),*) => {$(
+        impl BenchElement for $t {
+            fn from_index(i: usize) -> Self {
+                i as $t
+            }
+            fn zero() -> Self {
+                0
+            }
+            fn accumulate(self, other: Self) -> Self {
+                self.wrapping_add(other)
+            }
+            #[inline(always)]
+            fn to_be(self) -> Self {
+                <$t>::to_be(self)
+            }
+        }
+    )*};
+}
+
+macro_rules! bench_element_float {
+    ($($t:ty),*) => {$(
+        impl BenchElement for $t {
+            fn from_index(i: usize) -> Self {
+                i as $t
+            }
+            fn zero() -> Self {
+                0.0
+            }
+            fn accumulate(self, other: Self) -> Self {
+                self + other
+            }
+            #[inline(always)]
+            fn to_be(self) -> Self {
+                <$t>::from_bits(self.to_bits().to_be())
+            }
+        }
+    )*};
+}
+
+bench_element_int!(u8, u16, u32, u64, i32, i64, i128);
+bench_element_float!(f32, f64);
+
+/// How the elements are stored.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ByteOrder {
+    /// As the CPU stores them.
+    #[default]
+    Native,
+    /// Big-endian, converted on every write and read.
+    Big,
+}
+
+impl std::fmt::Display for ByteOrder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+/// One write pass and one read pass over `buffer`, returning the sum of
+/// what was read. This is the measured kernel, shared by the command-line
+/// harness and the Criterion benches.
+pub fn write_read_pass<T: BenchElement>(buffer: &mut UnalignedBuffer<T>) -> T {
+    write_pass(buffer);
+    read_pass(buffer)
+}
+
+/// The write phase of [`write_read_pass`] on its own.
+pub fn write_pass<T: BenchElement>(buffer: &mut UnalignedBuffer<T>) {
+    write_elements::<T, false>(buffer)
+}
+
+/// The read phase of [`write_read_pass`] on its own, for buffers shared
+/// between threads or timed apart from the writes.
+pub fn read_pass<T: BenchElement>(buffer: &UnalignedBuffer<T>) -> T {
+    read_elements::<T, false>(buffer)
+}
+
+/// [`write_read_pass`] with the read phase visiting the elements in `order`
+/// (see [`Pattern::order`](crate::pattern::Pattern::order)) instead of
+/// front to back.
+pub fn write_read_pass_ordered<T: BenchElement>(buffer: &mut UnalignedBuffer<T>, order: &[usize]) -> T {
+    write_pass(buffer);
+    read_pass_ordered(buffer, order)
+}
+
+/// The read phase of [`write_read_pass_ordered`] on its own.
+pub fn read_pass_ordered<T: BenchElement>(buffer: &UnalignedBuffer<T>, order: &[usize]) -> T {
+    read_elements_ordered::<T, false>(buffer, order)
+}
+
+/// [`write_pass`] storing every element big-endian, as a network encoder
+/// would.
+pub fn write_pass_big_endian<T: BenchElement>(buffer: &mut UnalignedBuffer<T>) {
+    write_elements::<T, true>(buffer)
+}
+
+/// [`read_pass`] decoding every element from big-endian, as a network
+/// parser would.
+pub fn read_pass_big_endian<T: BenchElement>(buffer: &UnalignedBuffer<T>) -> T {
+    read_elements::<T, true>(buffer)
+}
+
+/// [`read_pass_ordered`] decoding every element from big-endian.
+pub fn read_pass_ordered_big_endian<T: BenchElement>(buffer: &UnalignedBuffer<T>, order: &[usize]) -> T {
+    read_elements_ordered::<T, true>(buffer, order)
+}
+
+// The passes above; `BIG` converts every element with `to_be`/`from_be`,
+// a byte swap on little-endian targets.
+fn write_elements<T: BenchElement, const BIG: bool>(buffer: &mut UnalignedBuffer<T>) {
+    // Write phase with memory fence
+    for i in 0..buffer.len() {
+        let value = T::from_index(i % 100);
+        buffer.write(i, if BIG { value.to_be() } else { value });
+        if i % 1000 == 0 { fence(Ordering::SeqCst); }
+    }
+    // black_box hides the buffer, so the compiler can neither drop
+    // the writes nor compute a later sum without reading back.
+    black_box(&*buffer);
+}
+
+fn read_elements<T: BenchElement, const BIG: bool>(buffer: &UnalignedBuffer<T>) -> T {
+    let buffer = black_box(buffer);
+    // Read phase with accumulation
+    let mut sum = T::zero();
+    for i in 0..buffer.len() {
+        let value = buffer.read(i);
+        sum = sum.accumulate(if BIG { value.to_be() } else { value });
+        if i % 1000 == 0 { fence(Ordering::SeqCst); }
+    }
+    black_box(sum)
+}
+
+fn read_elements_ordered<T: BenchElement, const BIG: bool>(buffer: &UnalignedBuffer<T>, order: &[usize]) -> T {
+    let buffer = black_box(buffer);
+    // Read phase with accumulation
+    let mut sum = T::zero();
+    for (k, &i) in order.iter().enumerate() {
+        let value = buffer.read(i);
+        sum = sum.accumulate(if BIG { value.to_be() } else { value });
+        if k % 1000 == 0 { fence(Ordering::SeqCst); }
+    }
+    black_box(sum)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip<T: BenchElement + std::fmt::Debug + PartialEq>() {
+        for offset in 0..=std::mem::size_of::<T>() {
+            let mut buffer = UnalignedBuffer::<T>::new(100, offset);
+            assert_eq!(
+                buffer.is_aligned(),
+                offset % std::mem::align_of::<T>() == 0,
+                "offset {offset}"
+            );
+            for i in 0..buffer.len() {
+                buffer.write(i, T::from_index(i * 7919));
+            }
+            for i in 0..buffer.len() {
+                assert_eq!(buffer.read(i), T::from_index(i * 7919), "offset {offset}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_round_trip_at_every_offset() {
+        round_trip::<u8>();
+        round_trip::<u16>();
+        round_trip::<u32>();
+        round_trip::<u64>();
+        round_trip::<i32>();
+        round_trip::<i64>();
+        round_trip::<i128>();
+        round_trip::<f32>();
+        round_trip::<f64>();
+    }
+
+    #[test]
+    fn test_offset_is_relative_to_an_aligned_base() {
+        let mut buffer = UnalignedBuffer::<i64>::new(1, 3);
+        assert_eq!(buffer.as_ptr() as usize % BASE_ALIGN, 3);
+        let mut view = buffer.view();
+        assert_eq!((view.as_ptr() as usize % BASE_ALIGN, view.len()), (3, 1));
+        view.write(0, -5);
+        assert_eq!(buffer.read(0), -5);
+    }
+
+    #[test]
+    fn test_pass_sums_what_it_wrote() {
+        let mut buffer = UnalignedBuffer::<i64>::new(250, 5);
+        let expected: i64 = (0..250).map(|i| i % 100).sum();
+        assert_eq!(write_read_pass(&mut buffer), expected);
+        // 250 elements of up to 99 overflow a u8; the sum wraps instead.
+        let mut bytes = UnalignedBuffer::<u8>::new(250, 0);
+        assert_eq!(write_read_pass(&mut bytes), (expected % 256) as u8);
+        let mut floats = UnalignedBuffer::<f64>::new(250, 3);
+        assert_eq!(write_read_pass(&mut floats), expected as f64);
+    }
+
+    #[test]
+    fn test_ordered_pass_matches_sequential() {
+        let mut buffer = UnalignedBuffer::<u32>::new(250, 1);
+        let expected = write_read_pass(&mut buffer);
+        let reversed: Vec<usize> = (0..250).rev().collect();
+        assert_eq!(write_read_pass_ordered(&mut buffer, &reversed), expected);
+        assert_eq!(read_pass(&buffer), expected);
+    }
+
+    #[test]
+    fn test_big_endian_pass() {
+        let mut buffer = UnalignedBuffer::<u32>::new(250, 3);
+        let expected = write_read_pass(&mut buffer);
+        write_pass_big_endian(&mut buffer);
+        assert_eq!(buffer.read(1), 1u32.to_be());
+        assert_eq!(read_pass_big_endian(&buffer), expected);
+        let reversed: Vec<usize> = (0..250).rev().collect();
+        assert_eq!(read_pass_ordered_big_endian(&buffer, &reversed), expected);
+        let mut floats = UnalignedBuffer::<f64>::new(10, 1);
+        write_pass_big_endian(&mut floats);
+        assert_eq!(read_pass_big_endian(&floats), 45.0);
+    }
+
+    #[test]
+    #[should_panic(expected = "out of bounds")]
+    fn test_read_past_end_panics() {
+        let buffer = UnalignedBuffer::<i32>::new(4, 1);
+        buffer.read(4);
+    }
+}
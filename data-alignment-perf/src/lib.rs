@@ -0,0 +1,371 @@
+//! Library side of the alignment benchmark: the kernels, the statistics and
+//! the report formats, so other tools can run measurements and consume the
+//! results as data. The command-line harness and the Criterion benches in
+//! `benches/` are thin wrappers around it.
+//!
+//! ```no_run
+//! use data_alignment_perf::{run_alignment_bench, Config, ElementType};
+//!
+//! let results = run_alignment_bench(Config {
+//!     element: ElementType::I64,
+//!     n: 1_000_000,
+//!     ..Config::default()
+//! });
+//! for result in &results {
+//!     println!("offset {}: {:.3}ms", result.offset, result.stats.median);
+//! }
+//! ```
+
+use std::ops::Range;
+
+use clap::ValueEnum;
+
+// Runs `$body` with `$t` bound to the Rust type of `$element`. Defined
+// before the modules so they can use it too.
+macro_rules! with_element_type {
+    ($element:expr, $t:ident => $body:expr) => {
+        match $element {
+            $crate::ElementType::U8 => { type $t = u8; $body }
+            $crate::ElementType::U16 => { type $t = u16; $body }
+            $crate::ElementType::U32 => { type $t = u32; $body }
+            $crate::ElementType::U64 => { type $t = u64; $body }
+            $crate::ElementType::I32 => { type $t = i32; $body }
+            $crate::ElementType::I64 => { type $t = i64; $body }
+            $crate::ElementType::I128 => { type $t = i128; $body }
+            $crate::ElementType::F32 => { type $t = f32; $body }
+            $crate::ElementType::F64 => { type $t = f64; $body }
+        }
+    };
+}
+
+pub mod affinity;
+pub mod aligned;
+pub mod allocator;
+#[cfg(target_arch = "aarch64")]
+pub mod arm;
+pub mod baseline;
+pub mod branch;
+pub mod calibrate;
+pub mod chase;
+pub mod clock;
+pub mod copy;
+pub mod crossing;
+#[cfg(target_os = "linux")]
+pub mod dataset;
+pub mod environment;
+pub mod forwarding;
+pub mod history;
+pub mod html;
+#[cfg(target_os = "linux")]
+pub mod hugepage;
+pub mod kernel;
+pub mod nontemporal;
+#[cfg(target_os = "linux")]
+pub mod numa;
+pub mod packed;
+pub mod pattern;
+pub mod payload;
+#[cfg(all(feature = "perf", target_os = "linux"))]
+pub mod perf;
+pub mod plot;
+pub mod prefetch;
+pub mod report;
+pub mod sharing;
+pub mod soa;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
+#[cfg(all(feature = "split-lock", target_arch = "x86_64"))]
+pub mod split_lock;
+pub mod stats;
+pub mod storage;
+pub mod stream;
+pub mod suite;
+pub mod sysinfo;
+pub mod sweep;
+pub mod threads;
+#[cfg(feature = "tui")]
+pub mod tui;
+
+use allocator::Allocator;
+use calibrate::Calibration;
+use clock::Clock;
+use kernel::{
+    read_pass, read_pass_big_endian, read_pass_ordered, read_pass_ordered_big_endian, write_pass,
+    write_pass_big_endian, BenchElement, ByteOrder, UnalignedBuffer,
+};
+use pattern::Pattern;
+use report::{OffsetResult, Phases};
+use stats::{reject_outliers, Stats};
+use threads::{measure_threaded, Regions};
+
+/// Element types the kernel can be run with.
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ElementType {
+    U8,
+    U16,
+    U32,
+    U64,
+    I32,
+    I64,
+    I128,
+    F32,
+    F64,
+}
+
+impl ElementType {
+    /// `size_of` the element type, in bytes.
+    pub fn size(self) -> usize {
+        with_element_type!(self, T => std::mem::size_of::<T>())
+    }
+}
+
+impl std::fmt::Display for ElementType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.to_possible_value().unwrap().get_name())
+    }
+}
+
+/// Parameters of a run over one element type; the defaults match the
+/// original hardcoded benchmark.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub element: ElementType,
+    /// Elements written and read per iteration (at least 1).
+    pub n: usize,
+    /// Timed iterations per offset (at least 1).
+    pub repeat: usize,
+    /// Untimed iterations per offset before measuring.
+    pub warmup: usize,
+    /// Byte offsets to test; `None` means `0..size_of::<T>()`.
+    pub offsets: Option<Range<usize>>,
+    /// Fraction of samples dropped from each end for the trimmed mean.
+    pub trim: f64,
+    /// Discard the trimmed samples before computing every statistic.
+    pub reject_outliers: bool,
+    /// Order of the read phase.
+    pub pattern: Pattern,
+    /// Threads running the kernel at once (at least 1).
+    pub threads: usize,
+    /// Whether those threads get a buffer each or share one.
+    pub regions: Regions,
+    /// CPUs the worker threads of a multi-threaded run are pinned to, round
+    /// robin; empty to leave them to the scheduler. A CPU that cannot be
+    /// pinned to is ignored. Single-threaded runs use the calling thread,
+    /// whose affinity is left to the caller.
+    pub pin: Vec<usize>,
+    /// Read hardware counters around the timed iterations of single-threaded
+    /// runs. Needs the `perf` feature on Linux; ignored otherwise.
+    pub counters: bool,
+    /// Clock the iterations are timed with.
+    pub clock: Clock,
+    /// Where the buffers of the read/write kernel come from.
+    pub allocator: Allocator,
+    /// Byte order of the elements in single-threaded runs; big-endian adds
+    /// a byte swap per access on little-endian CPUs.
+    pub byte_order: ByteOrder,
+    /// Size `n` to a target time per iteration and sample single-threaded
+    /// offsets until their mean is known well enough, with `repeat` as the
+    /// minimum; see [`calibrate`].
+    pub calibration: Option<Calibration>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            element: ElementType::I32,
+            n: 10_000_000,
+            repeat: 50,
+            warmup: 3,
+            offsets: None,
+            trim: 0.1,
+            reject_outliers: false,
+            pattern: Pattern::Sequential,
+            threads: 1,
+            regions: Regions::Disjoint,
+            pin: Vec::new(),
+            counters: false,
+            clock: Clock::Instant,
+            allocator: Allocator::Aligned,
+            byte_order: ByteOrder::Native,
+            calibration: None,
+        }
+    }
+}
+
+impl Config {
+    /// The offsets this run covers.
+    pub fn offsets(&self) -> Range<usize> {
+        self.offsets.clone().unwrap_or(0..self.element.size())
+    }
+}
+
+/// Measures every offset of `config`, calibrated first if it asks for it.
+pub fn run_alignment_bench(config: Config) -> Vec<OffsetResult> {
+    let config = config.calibrated();
+    config
+        .offsets()
+        .map(|offset| measure_offset(&config, offset))
+        .collect()
+}
+
+/// Measures a single offset, e.g. to report progress between offsets.
+pub fn measure_offset(config: &Config, offset: usize) -> OffsetResult {
+    measure_offset_with(config, offset, &mut |_| {})
+}
+
+/// [`measure_offset`], calling `on_sample` with each sample (milliseconds)
+/// as it is taken; with several threads, once they have all finished.
+pub fn measure_offset_with(config: &Config, offset: usize, on_sample: &mut dyn FnMut(f64)) -> OffsetResult {
+    if config.threads > 1 {
+        let result = with_element_type!(config.element, T => measure_threaded::<T>(config, offset));
+        result.samples.iter().for_each(|&sample| on_sample(sample));
+        result
+    } else {
+        with_element_type!(config.element, T => measure::<T>(config, offset, on_sample))
+    }
+}
+
+/// One [`write_read_pass`](kernel::write_read_pass) in `byte_order`, each
+/// phase timed with `clock`, reading in `order` if given; returns the write
+/// and read times in milliseconds.
+fn time_iteration<T: BenchElement>(
+    buffer: &mut UnalignedBuffer<T>,
+    order: Option<&[usize]>,
+    clock: Clock,
+    byte_order: ByteOrder,
+) -> (f64, f64) {
+    let write = clock.time(|| match byte_order {
+        ByteOrder::Native => write_pass(buffer),
+        ByteOrder::Big => write_pass_big_endian(buffer),
+    });
+    let read = clock.time(|| {
+        match (order, byte_order) {
+            (Some(order), ByteOrder::Native) => read_pass_ordered(buffer, order),
+            (Some(order), ByteOrder::Big) => read_pass_ordered_big_endian(buffer, order),
+            (None, ByteOrder::Native) => read_pass(buffer),
+            (None, ByteOrder::Big) => read_pass_big_endian(buffer),
+        };
+    });
+    // Use nanoseconds for more precision, milliseconds for display
+    (write / 1_000_000.0, read / 1_000_000.0)
+}
+
+fn measure<T: BenchElement>(config: &Config, offset: usize, on_sample: &mut dyn FnMut(f64)) -> OffsetResult {
+    let mut write_samples = Vec::with_capacity(config.repeat);
+    let mut read_samples = Vec::with_capacity(config.repeat);
+    let mut buffer = UnalignedBuffer::<T>::new_in(config.n, offset, config.allocator);
+    let order = config.pattern.order(config.n);
+    // Fault the pages in outside the timer, even without warmup rounds.
+    write_pass(&mut buffer);
+
+    // Warmup rounds fault the pages in and let the clock ramp up;
+    // their timings are thrown away.
+    for _ in 0..config.warmup {
+        time_iteration(&mut buffer, order.as_deref(), config.clock, config.byte_order);
+    }
+    #[cfg(all(feature = "perf", target_os = "linux"))]
+    let mut counters = config.counters.then(perf::Counters::open);
+    #[cfg(all(feature = "perf", target_os = "linux"))]
+    if let Some(counters) = &mut counters {
+        counters.start();
+    }
+    let mut samples = Vec::with_capacity(config.repeat);
+    while match config.calibration {
+        Some(calibration) => !calibration.done(&samples, config.repeat),
+        None => samples.len() < config.repeat,
+    } {
+        let (write, read) = time_iteration(&mut buffer, order.as_deref(), config.clock, config.byte_order);
+        write_samples.push(write);
+        read_samples.push(read);
+        samples.push(write + read);
+        on_sample(write + read);
+    }
+    #[cfg(all(feature = "perf", target_os = "linux"))]
+    let counters = counters.map(|mut counters| counters.stop(samples.len()));
+    #[cfg(not(all(feature = "perf", target_os = "linux")))]
+    let counters = None;
+
+    let (write, read) = (summarize(&write_samples, config), summarize(&read_samples, config));
+    let phases = Phases {
+        write_gb_per_s: (config.n * size_of::<T>()) as f64 / (write.median / 1000.0) / 1e9,
+        read_ns_per_element: read.median * 1_000_000.0 / config.n as f64,
+        write,
+        write_samples,
+        read,
+        read_samples,
+    };
+    OffsetResult {
+        offset,
+        aligned: buffer.is_aligned(),
+        stats: summarize(&samples, config),
+        samples,
+        threads: None,
+        counters,
+        phases: Some(phases),
+    }
+}
+
+/// Statistics of `samples` with the trimming settings of `config`.
+pub(crate) fn summarize(samples: &[f64], config: &Config) -> Stats {
+    // Rejected samples are already gone, so don't trim a second time.
+    if config.reject_outliers {
+        Stats::from_samples(&reject_outliers(samples, config.trim), 0.0)
+    } else {
+        Stats::from_samples(samples, config.trim)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_alignment_bench() {
+        let results = run_alignment_bench(Config {
+            element: ElementType::I64,
+            n: 1000,
+            repeat: 4,
+            warmup: 1,
+            ..Config::default()
+        });
+        let offsets: Vec<usize> = results.iter().map(|r| r.offset).collect();
+        assert_eq!(offsets, (0..8).collect::<Vec<_>>());
+        assert!(results[0].aligned && results[1..].iter().all(|r| !r.aligned));
+        assert!(results.iter().all(|r| r.samples.len() == 4 && r.stats.count == 4));
+        let phases = results[1].phases.as_ref().unwrap();
+        assert_eq!(results[1].samples[0], phases.write_samples[0] + phases.read_samples[0]);
+        assert!(phases.write_gb_per_s > 0.0 && phases.read_ns_per_element > 0.0);
+    }
+
+    #[test]
+    fn test_every_element_type() {
+        for element in ElementType::value_variants() {
+            let results = run_alignment_bench(Config {
+                element: *element,
+                n: 100,
+                repeat: 1,
+                warmup: 0,
+                ..Config::default()
+            });
+            assert_eq!(results.len(), element.size(), "{element}");
+        }
+        assert_eq!(ElementType::F32.to_string(), "f32");
+    }
+
+    #[test]
+    fn test_explicit_offsets_and_rejection() {
+        let results = run_alignment_bench(Config {
+            element: ElementType::I128,
+            n: 10,
+            repeat: 10,
+            warmup: 0,
+            offsets: Some(3..5),
+            trim: 0.2,
+            reject_outliers: true,
+            ..Config::default()
+        });
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].offset, 3);
+        assert_eq!((results[0].samples.len(), results[0].stats.count), (10, 6));
+    }
+}
@@ -1,112 +1,1718 @@
-/// This is a performance benchmark designed to measure the impact of memory alignment on read/write 
-/// operations for different numeric types (i32, i64, i128). 
-/// It tests how unaligned memory access affects execution time by allocating a buffer with intentional 
-/// offsets and performing a large number of operations on it.
-
-use std::alloc::{alloc, Layout, dealloc};
-use std::time::Instant;
-use std::fmt::Debug;
-use std::ptr;
-use std::sync::atomic::{fence, Ordering};
-use num_traits::ToPrimitive;
-
-struct UnalignedBuffer<T> {
-    ptr: *mut u8,
-    layout: Layout,
-    _phantom: std::marker::PhantomData<T>,
-}
-
-impl<T> UnalignedBuffer<T> {
-    fn new(elements: usize, offset: usize) -> Self {
-        let size = std::mem::size_of::<T>() * elements + offset;
-        let layout = Layout::from_size_align(size, 1)
-            .expect("Invalid layout");
-        let ptr = unsafe { alloc(layout) };
-        Self {
-            ptr,
-            layout,
-            _phantom: std::marker::PhantomData,
-        }
-    }
-
-    fn get_ptr(&self, offset: usize) -> *mut T {
-        unsafe {
-            self.ptr.add(offset) as *mut T
-        }
-    }
-}
-
-impl<T> Drop for UnalignedBuffer<T> {
-    fn drop(&mut self) {
-        unsafe {
-            dealloc(self.ptr, self.layout);
-        }
-    }
-}
-
-fn run_test<T>(type_name: &str)
-where
-    T: Copy + Debug + 
-       std::ops::Add<Output = T> + 
-       std::ops::AddAssign +
-       From<i32> + std::cmp::PartialEq +
-       ToPrimitive,
-{
-    // Increased workload
-    const N: usize = 10_000_000;
-    const REPEAT: usize = 50;
-    
-    println!("\nProcessing {} ({} bytes)", type_name, std::mem::size_of::<T>());
-    
-    for offset in 0..std::mem::size_of::<T>() {
-        let mut sum_time = 0f64;
-        print!("offset {}: ", offset);
-        
-        let buffer = UnalignedBuffer::<T>::new(N, offset);
-        let mut results = Vec::new();
-        
-        for _ in 0..REPEAT {
-            let start = Instant::now();
-            
-            unsafe {
-                let data = buffer.get_ptr(offset);
-                
-                // Write phase with memory fence
-                for i in 0..N {
-                    ptr::write(data.add(i), T::from(i as i32 % 100));
-                    if i % 1000 == 0 { fence(Ordering::SeqCst); }
+//! This is a performance benchmark designed to measure the impact of memory alignment on read/write
+//! operations for different numeric types (u8..u64, i32..i128, f32, f64).
+//! It tests how unaligned memory access affects execution time by allocating a buffer with intentional
+//! offsets and performing a large number of operations on it.
+//!
+//! Usage: data-alignment-perf [--n 10000000] [--repeat 50] [--warmup 3] [--types i32,i64,i128] [--offsets 0..8]
+//!                            [--trim 0.1] [--reject-outliers] [--format text|csv|json|md]
+//!                            [--plot out.svg] [--report out.html] [--struct 1:1,8:8,2:2] [--scenario offsets|storage|aos-soa|cache-line|page|dataset|packed|copy|false-sharing|stream|chase|sweep|hugepages|prefetch|non-temporal|branch|forwarding|numa]
+//!                            [--pattern sequential|random|stride:K] [--stream-sizes 1000000,10000000]
+//!                            [--working-sets 16K,256K,4M,256M] [--dataset FILE] [--prefetch-distances 0,4,16,64]
+//!                            [--threads 1] [--regions disjoint|shared] [--pin 0,2] [--annotate-caches]
+//!                            [--clock instant|tsc|qpc|monotonic|auto] [--allocator aligned|system|pretouched|vec|boxed-slice|mmap|file-mmap]
+//!                            [--byte-order native|big] [--target-time 100 [--ci 1] [--max-repeat 1000]]
+//!                            [--save-baseline NAME] [--compare NAME] [--tolerance 5] [--confidence 95]
+//!                            [--baseline-dir target/baselines] [--history DIR] [--sqlite FILE] [--suite bench.toml] [--counters] [--strict-env [warn|abort]] [--tui] [--quiet] [--verbose]
+//!
+//! On aarch64, `--scenario arm` times `ldp`/`stp` pairs and 128-bit loads
+//! and stores across 16-byte and cache-line boundaries, and warns about the
+//! placements that would fault under strict alignment.
+//!
+//! Built with `--features split-lock` (x86_64 only), `--scenario split-lock`
+//! also times locked adds on a `u64` split across two cache lines.
+//!
+//! `data-alignment-perf compare [--history target/history] [--metric median|mean|min|slowdown]`
+//! tabulates the runs recorded with `--history`.
+//!
+//! `--suite bench.toml` runs every `[[run]]` of a TOML file in turn; the
+//! `bench.toml` next to the manifest is an example.
+//!
+//! `--features jemalloc` and `--features mimalloc` add those allocators to
+//! `--allocator`.
+//!
+//! Built with `--features perf` on Linux, `--counters` adds hardware counters per
+//! iteration to every single-threaded offset.
+//!
+//! Built with `--features sqlite`, `--sqlite results.db` adds the report of
+//! the offsets scenario to a SQLite database (see `src/sqlite.rs` for the
+//! tables).
+//!
+//! Built with `--features tui`, `--tui` replaces the progress lines of the
+//! offsets scenario with a live dashboard.
+//!
+//! `cargo bench --bench alignment` measures the same kernel with Criterion.
+
+use std::io::Write;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use clap::{Parser, Subcommand, ValueEnum};
+use indicatif::{ProgressBar, ProgressStyle};
+use data_alignment_perf::report::{
+    write_csv, write_json, write_markdown, BenchmarkReport, CounterValues, MachineInfo, OffsetResult, Parameters,
+    TypeResults, Verdict,
+};
+use data_alignment_perf::stats::{reject_outliers, welch_t_test, Stats};
+use data_alignment_perf::allocator::Allocator;
+use data_alignment_perf::baseline::{self, Delta};
+use data_alignment_perf::calibrate::Calibration;
+use data_alignment_perf::history::{self, format_timestamp, Metric};
+use data_alignment_perf::affinity::{parse_cpu_list, pin_current_thread};
+use data_alignment_perf::clock::{ticks_per_ns, Clock};
+use data_alignment_perf::branch::run_branch;
+use data_alignment_perf::forwarding::run_forwarding;
+use data_alignment_perf::chase::{measure_chase, DEFAULT_SIZES};
+use data_alignment_perf::copy::{run_copy, DEFAULT_SIZES as COPY_SIZES, OFFSETS as COPY_OFFSETS};
+use data_alignment_perf::crossing::{run_crossing, Boundary};
+use data_alignment_perf::environment::Environment;
+use data_alignment_perf::kernel::ByteOrder;
+use data_alignment_perf::nontemporal::{run_nontemporal, DEFAULT_BYTES as NONTEMPORAL_BYTES};
+use data_alignment_perf::packed::run_packed;
+use data_alignment_perf::soa::run_soa;
+use data_alignment_perf::storage::run_storage;
+use data_alignment_perf::pattern::Pattern;
+use data_alignment_perf::payload::{run_struct_bench, LayoutResult, RecordLayout};
+use data_alignment_perf::prefetch::{run_prefetch, DEFAULT_DISTANCES};
+use data_alignment_perf::sharing::run_false_sharing;
+use data_alignment_perf::stream::run_stream;
+use data_alignment_perf::suite::load_suite;
+use data_alignment_perf::threads::Regions;
+use data_alignment_perf::sweep::{detect_levels, level_for, run_sweep, CacheLevel};
+use data_alignment_perf::{html, measure_offset_with, plot, Config, ElementType};
+use struct_alignment_and_padding::TypeInfo;
+
+/// Benchmark parameters; the defaults match the original hardcoded run.
+#[derive(Parser, Debug)]
+#[command(about = "Measures the cost of unaligned reads and writes")]
+struct Args {
+    /// Elements written and read per iteration
+    #[arg(long, default_value_t = 10_000_000, value_parser = positive)]
+    n: usize,
+
+    /// Timed iterations per offset
+    #[arg(long, default_value_t = 50, value_parser = positive)]
+    repeat: usize,
+
+    /// Element types to test, comma separated
+    #[arg(long, value_delimiter = ',', default_values_t = [ElementType::I32, ElementType::I64, ElementType::I128])]
+    types: Vec<ElementType>,
+
+    /// Byte offsets to test, as `start..end` (default: 0..size_of::<T>() per type)
+    #[arg(long, value_parser = parse_range)]
+    offsets: Option<Range<usize>>,
+
+    /// Untimed iterations per offset before measuring
+    #[arg(long, default_value_t = 3)]
+    warmup: usize,
+
+    /// Fraction of samples dropped from each end for the trimmed mean
+    #[arg(long, default_value_t = 0.1, value_parser = fraction)]
+    trim: f64,
+
+    /// Discard the trimmed samples before computing every statistic
+    #[arg(long)]
+    reject_outliers: bool,
+
+    /// Output format; everything but text is written once the run is done
+    #[arg(long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Also draw latency vs offset charts to this SVG file
+    #[arg(long, value_name = "PATH")]
+    plot: Option<PathBuf>,
+
+    /// Also write a self-contained HTML report (machine, parameters, charts
+    /// and tables) of the offsets scenario to this file
+    #[arg(long, value_name = "PATH")]
+    report: Option<PathBuf>,
+
+    /// Instead of primitives, traverse `--n` records of a struct with these
+    /// SIZE:ALIGN members, padded, reordered and packed (text or json output)
+    #[arg(long = "struct", value_name = "SIZE:ALIGN", value_delimiter = ',', value_parser = parse_member)]
+    members: Vec<TypeInfo>,
+
+    /// What to measure for each type: every byte offset, or values placed to
+    /// straddle a boundary (text or json output)
+    #[arg(long, value_enum, default_value_t = Scenario::Offsets)]
+    scenario: Scenario,
+
+    /// Order of the read phase: sequential, random (a fixed permutation) or
+    /// stride:K
+    #[arg(long, default_value_t = Pattern::Sequential)]
+    pattern: Pattern,
+
+    /// Array lengths for the stream scenario, comma separated (default: `--n`)
+    #[arg(long, value_delimiter = ',', value_parser = positive)]
+    stream_sizes: Vec<usize>,
+
+    /// Working-set sizes for the chase, copy, hugepages, non-temporal and numa scenarios, comma separated, with an
+    /// optional K, M or G suffix (powers of 1024); for the dataset scenario,
+    /// the size of the sparse file it creates
+    #[arg(long, value_delimiter = ',', value_parser = parse_size)]
+    working_sets: Vec<usize>,
+
+    /// File the dataset scenario maps, instead of a new sparse file twice
+    /// the size of RAM
+    #[arg(long, value_name = "FILE")]
+    dataset: Option<PathBuf>,
+
+    /// Prefetch distances for the prefetch scenario, in accesses ahead; 0 is
+    /// the baseline without prefetches
+    #[arg(long, value_delimiter = ',', default_values_t = DEFAULT_DISTANCES)]
+    prefetch_distances: Vec<usize>,
+
+    /// Threads running the read/write kernel at once
+    #[arg(long, default_value_t = 1, value_parser = positive)]
+    threads: usize,
+
+    /// With several threads, a buffer each or one shared buffer (read
+    /// phase only)
+    #[arg(long, value_enum, default_value_t = Regions::Disjoint)]
+    regions: Regions,
+
+    /// Pin to these CPUs (e.g. `0,2` or `0-3`): the main thread to the first,
+    /// `--threads` workers round robin over all of them
+    #[arg(long, value_parser = parse_cpus)]
+    pin: Option<CpuList>,
+
+    /// Run a short sweep first and label working sets with the cache level
+    /// they fit in
+    #[arg(long)]
+    annotate_caches: bool,
+
+    /// Clock to time the kernel with: instant, tsc for the time-stamp
+    /// counter (x86_64 and aarch64), qpc for QueryPerformanceCounter
+    /// (Windows), monotonic for CLOCK_MONOTONIC_RAW (Linux), or auto for the
+    /// best of these the machine has; the finer ones matter for small `--n`
+    #[arg(long, value_enum, default_value_t = Clock::Instant)]
+    clock: Clock,
+
+    /// Where the kernel's buffers come from: aligned (the global allocator
+    /// asked for 64-byte alignment), system (plain malloc, aligned by hand),
+    /// pretouched (aligned, pages faulted in up front), or jemalloc and
+    /// mimalloc when built with those features
+    #[arg(long, value_enum, default_value_t = Allocator::Aligned)]
+    allocator: Allocator,
+
+    /// Run every `[[run]]` of this TOML suite instead; see the `suite`
+    /// module for the format
+    #[arg(long, value_name = "PATH")]
+    suite: Option<PathBuf>,
+
+    /// Append the report of the offsets scenario to the run history in this
+    /// directory; see `compare` to read it back
+    #[arg(long, value_name = "DIR")]
+    history: Option<PathBuf>,
+
+    /// Add the report of the offsets scenario to this SQLite database,
+    /// creating it if needed (built with `--features sqlite`)
+    #[arg(long, value_name = "FILE")]
+    sqlite: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Save the results of the offsets scenario as this baseline
+    #[arg(long, value_name = "NAME")]
+    save_baseline: Option<String>,
+
+    /// Compare the offsets scenario against this saved baseline and fail if
+    /// any median slowed down by more than `--tolerance`
+    #[arg(long, value_name = "NAME")]
+    compare: Option<String>,
+
+    /// Allowed slowdown against the baseline, in percent
+    #[arg(long, default_value_t = 5.0, value_parser = percent)]
+    tolerance: f64,
+
+    /// Calibrate instead of using `--n` as given: size each type's buffer so
+    /// one iteration takes about this many milliseconds, and keep sampling
+    /// each offset past `--repeat` until `--ci` is met
+    #[arg(long, value_name = "MS", value_parser = milliseconds)]
+    target_time: Option<f64>,
+
+    /// With `--target-time`, stop once the 95% confidence interval of an
+    /// offset's mean is within this many percent of it
+    #[arg(long, default_value_t = 1.0, value_parser = percent)]
+    ci: f64,
+
+    /// With `--target-time`, never take more samples per offset than this
+    #[arg(long, default_value_t = 1000, value_parser = positive)]
+    max_repeat: usize,
+
+    /// Confidence, in percent, at which the text summary marks an offset as
+    /// significantly different from the aligned one (Welch's t-test)
+    #[arg(long, default_value_t = 95.0, value_parser = confidence)]
+    confidence: f64,
+
+    /// Directory baselines are saved in
+    #[arg(long, value_name = "PATH", default_value = baseline::DEFAULT_DIR)]
+    baseline_dir: PathBuf,
+
+    /// Store the elements native or big-endian (a byte swap per access on
+    /// little-endian CPUs, like a network parser)
+    #[arg(long, value_enum, default_value_t = ByteOrder::Native)]
+    byte_order: ByteOrder,
+
+    /// Read cycles, instructions and cache and TLB misses around the timed
+    /// iterations of single-threaded offsets (Linux, built with `--features
+    /// perf`)
+    #[arg(long)]
+    counters: bool,
+
+    /// Check for turbo boost, frequency scaling, SMT siblings on the core
+    /// and other load before running, record the outcome (and the seed of
+    /// the random orders) in the report, and warn about failed checks, or
+    /// abort on them with `abort`
+    #[arg(long, value_enum, value_name = "ACTION", num_args = 0..=1, default_missing_value = "warn")]
+    strict_env: Option<StrictEnv>,
+
+    /// Show a live dashboard instead of the progress lines while the offsets
+    /// scenario runs (text format, built with `--features tui`)
+    #[arg(long)]
+    tui: bool,
+
+    /// Only print one summary line per type and offset
+    #[arg(long, short)]
+    quiet: bool,
+
+    /// Also print every raw sample of each offset
+    #[arg(long, short)]
+    verbose: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Tabulate a metric of every type and offset across the runs recorded
+    /// with `--history`, oldest first
+    Compare {
+        /// The history directory
+        #[arg(long, value_name = "DIR", default_value = history::DEFAULT_DIR)]
+        history: PathBuf,
+
+        #[arg(long, value_enum, default_value_t = Metric::Median)]
+        metric: Metric,
+
+        /// Only these element types, comma separated
+        #[arg(long, value_delimiter = ',')]
+        types: Vec<String>,
+
+        /// Only the most recent runs
+        #[arg(long, value_name = "RUNS")]
+        last: Option<usize>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Scenario {
+    /// Every byte offset of a contiguous array
+    Offsets,
+    /// Every offset of `--types` once per `--allocator` backend (heap, Vec,
+    /// boxed slice, anonymous and file-backed mmap, ...), plus the cost of
+    /// allocating and first touching each
+    Storage,
+    /// Aligned vs misaligned-within-a-line vs line-splitting accesses
+    CacheLine,
+    /// Aligned vs line-splitting vs page-splitting accesses
+    Page,
+    /// `--n` `#[repr(packed)]` structs vs their naturally aligned twins
+    Packed,
+    /// One member and every member of `--n` `--struct` records (default
+    /// 1:1,8:8,2:2) summed as an array of structs vs a struct of arrays
+    AosSoa,
+    /// copy_nonoverlapping at every pair of source and destination offsets,
+    /// for each of `--working-sets` (default 64,4K,256K,16M)
+    Copy,
+    /// Per-thread counters packed in one line vs padded to one line each
+    /// (`--n` increments per thread, one thread per CPU, 2 to 8)
+    FalseSharing,
+    /// STREAM copy/scale/add/triad bandwidth over f64 arrays of
+    /// `--stream-sizes` elements
+    Stream,
+    /// Pointer-chasing load latency over `--working-sets`
+    Chase,
+    /// Chase latency from 4 KiB to 256 MiB, with the cache sizes it implies
+    Sweep,
+    /// Chase over `--working-sets` (default 512M) on 4 KiB pages vs
+    /// transparent vs explicit huge pages
+    #[cfg(target_os = "linux")]
+    Hugepages,
+    /// Latency and bandwidth for every (CPU node, memory node) pair, over
+    /// the first `--working-sets` size (default 256M)
+    #[cfg(target_os = "linux")]
+    Numa,
+    /// Cold reads of 256 MiB of a memory-mapped `--dataset` file (default: a
+    /// sparse file twice the size of RAM) per access pattern and madvise
+    /// hint, with major faults and readahead
+    #[cfg(target_os = "linux")]
+    Dataset,
+    /// Store-to-load forwarding latency for overlapping store/load pairs,
+    /// and 4K aliasing in a loop storing 64 KiB + delta past its loads
+    Forwarding,
+    /// Branchy vs branchless sums over `--n` shuffled and sorted bytes
+    Branch,
+    /// Regular vs streaming stores filling the first `--working-sets` size
+    /// (default 128M), and how much of a hot 1 MiB set survives the fill
+    NonTemporal,
+    /// Strided walk and pointer chase with software prefetches
+    /// `--prefetch-distances` accesses ahead
+    Prefetch,
+    /// Locked adds on an aligned vs a line-splitting u64, in cycles per op
+    /// (`--n` operations per iteration, at most 100000)
+    #[cfg(all(feature = "split-lock", target_arch = "x86_64"))]
+    SplitLock,
+    /// ldp/stp pairs and 128-bit loads and stores at aligned, 8-aligned,
+    /// misaligned and line-splitting positions, with the placements that
+    /// would fault under strict alignment
+    #[cfg(target_arch = "aarch64")]
+    Arm,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum StrictEnv {
+    /// Print the failed checks and run anyway
+    Warn,
+    /// Print the failed checks and exit
+    Abort,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    /// Progress and summaries as the run goes
+    Text,
+    /// Every sample plus summary rows (see `report::write_csv`)
+    Csv,
+    /// A `report::BenchmarkReport` with machine info, parameters and samples
+    Json,
+    /// Markdown tables of mean and median time per offset
+    Md,
+}
+
+// Counts must be at least 1: an empty buffer cannot be allocated and zero
+// iterations have no average.
+fn positive(s: &str) -> Result<usize, String> {
+    match s.parse() {
+        Ok(0) => Err("must be at least 1".to_string()),
+        Ok(n) => Ok(n),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// Trim fractions are per end, so at most half of the samples.
+fn fraction(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(f) if (0.0..=0.5).contains(&f) => Ok(f),
+        Ok(_) => Err("must be between 0 and 0.5".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn percent(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(p) if p >= 0.0 => Ok(p),
+        Ok(_) => Err("must not be negative".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn milliseconds(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(ms) if ms > 0.0 => Ok(ms),
+        Ok(_) => Err("must be positive".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn confidence(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(c) if c > 0.0 && c < 100.0 => Ok(c),
+        Ok(_) => Err("must be between 0 and 100".to_string()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// Parses a struct member as `size:align`, e.g. `8:8`.
+fn parse_member(s: &str) -> Result<TypeInfo, String> {
+    let (size, alignment) = s
+        .split_once(':')
+        .ok_or_else(|| format!("expected SIZE:ALIGN, got `{s}`"))?;
+    let size: usize = size.trim().parse().map_err(|e| format!("bad size `{size}`: {e}"))?;
+    let alignment: usize = alignment
+        .trim()
+        .parse()
+        .map_err(|e| format!("bad alignment `{alignment}`: {e}"))?;
+    if size == 0 || !alignment.is_power_of_two() {
+        return Err(format!("`{s}`: size must be positive and alignment a power of two"));
+    }
+    Ok(TypeInfo { size, alignment })
+}
+
+// Parses a byte count such as `4096`, `16K` or `256M`.
+fn parse_size(s: &str) -> Result<usize, String> {
+    let t = s.trim();
+    let (digits, shift) = match t.char_indices().last() {
+        Some((i, 'K' | 'k')) => (&t[..i], 10),
+        Some((i, 'M' | 'm')) => (&t[..i], 20),
+        Some((i, 'G' | 'g')) => (&t[..i], 30),
+        _ => (t, 0),
+    };
+    let n: usize = digits.parse().map_err(|e| format!("bad size `{s}`: {e}"))?;
+    n.checked_shl(shift)
+        .filter(|&bytes| bytes > 0 && bytes >> shift == n)
+        .ok_or_else(|| format!("size `{s}` must be positive and fit in memory"))
+}
+
+/// CPUs given to `--pin`; a newtype so clap takes the list as one value.
+#[derive(Clone, Debug)]
+struct CpuList(Vec<usize>);
+
+fn parse_cpus(s: &str) -> Result<CpuList, String> {
+    let cpus = parse_cpu_list(s)?;
+    if cpus.is_empty() {
+        return Err("no CPUs given".to_string());
+    }
+    Ok(CpuList(cpus))
+}
+
+// Pins the main thread for `--pin`, after checking that every listed CPU
+// can be used, so the workers' best-effort pinning cannot fail silently.
+fn apply_pin(cpus: &[usize]) -> std::io::Result<()> {
+    for &cpu in cpus {
+        pin_current_thread(&[cpu])
+            .map_err(|e| std::io::Error::new(e.kind(), format!("cannot pin to CPU {}: {}", cpu, e)))?;
+    }
+    pin_current_thread(&cpus[..1])
+}
+
+// Parses `start..end` (exclusive), e.g. `0..8`.
+fn parse_range(s: &str) -> Result<Range<usize>, String> {
+    let (start, end) = s
+        .split_once("..")
+        .ok_or_else(|| format!("expected start..end, got `{s}`"))?;
+    let start: usize = start.trim().parse().map_err(|e| format!("bad start `{start}`: {e}"))?;
+    let end: usize = end.trim().parse().map_err(|e| format!("bad end `{end}`: {e}"))?;
+    if start >= end {
+        return Err(format!("empty range `{s}`"));
+    }
+    Ok(start..end)
+}
+
+impl Args {
+    fn config(&self, element: ElementType) -> Config {
+        Config {
+            element,
+            n: self.n,
+            repeat: self.repeat,
+            warmup: self.warmup,
+            offsets: self.offsets.clone(),
+            trim: self.trim,
+            reject_outliers: self.reject_outliers,
+            pattern: self.pattern,
+            threads: self.threads,
+            regions: self.regions,
+            pin: self.pin.as_ref().map_or(Vec::new(), |cpus| cpus.0.clone()),
+            counters: self.counters,
+            clock: self.clock,
+            allocator: self.allocator,
+            byte_order: self.byte_order,
+            calibration: self.calibration(),
+        }
+    }
+
+    fn calibration(&self) -> Option<Calibration> {
+        self.target_time.map(|target_ms| Calibration {
+            target_ms,
+            tolerance: self.ci / 100.0,
+            max_repeat: self.max_repeat,
+        })
+    }
+}
+
+// Runs every offset of one type, printing progress in text mode.
+fn run_test(
+    element: ElementType,
+    args: &Args,
+    levels: &[CacheLevel],
+    progress: &ProgressBar,
+    live: &mut Live,
+) -> TypeResults {
+    let config = args.config(element).calibrated();
+    let text = args.format == Format::Text && !live.is_on();
+    let mut results = TypeResults {
+        type_name: element.to_string(),
+        size: element.size(),
+        offsets: Vec::new(),
+    };
+
+    if text && !args.quiet {
+        let mut note = level_note(levels, config.n * element.size());
+        if config.calibration.is_some() {
+            note = format!(" (calibrated to n = {}){}", config.n, note);
+        }
+        say(progress, format!("\nProcessing {} ({} bytes){}", element, element.size(), note));
+    }
+
+    for offset in config.offsets() {
+        progress.set_message(format!("{} offset {}", element, offset));
+        live.begin(element, offset);
+        let result = measure_offset_with(&config, offset, &mut |ms| live.sample(ms));
+        live.finish(&result);
+        progress.inc(1);
+        if text && args.quiet {
+            say(progress, format!("{} offset {}: {}", element, offset, format_stats(&result.stats)));
+        } else if text {
+            let note = if result.aligned { "" } else { " (unaligned)" };
+            say(progress, format!("offset {}{}: {}", offset, note, format_stats(&result.stats)));
+            if let Some(threads) = &result.threads {
+                let per_thread: Vec<String> =
+                    threads.per_thread_gb_per_s.iter().map(|rate| format!("{:.2}", rate)).collect();
+                say(progress, format!(
+                    "  {} threads: {:.2} GB/s aggregate, per thread: {} GB/s",
+                    threads.count,
+                    threads.aggregate_gb_per_s,
+                    per_thread.join(" ")
+                ));
+            }
+            if let Some(phases) = &result.phases {
+                say(
+                    progress,
+                    format!(
+                        "  write: {:.3}ms ({:.2} GB/s) read: {:.3}ms ({:.3} ns/element)",
+                        phases.write.median, phases.write_gb_per_s, phases.read.median, phases.read_ns_per_element
+                    ),
+                );
+            }
+            if let Some(counters) = &result.counters {
+                say(progress, format!("  {}", format_counters(counters)));
+            }
+        }
+        if text && args.verbose {
+            let samples: Vec<String> = result.samples.iter().map(|ms| format!("{:.4}", ms)).collect();
+            say(progress, format!("  samples (ms): {}", samples.join(" ")));
+        }
+        results.offsets.push(result);
+    }
+    results
+}
+
+// The `--tui` dashboard while it is on; without the feature it never is.
+struct Live {
+    #[cfg(feature = "tui")]
+    screen: Option<(data_alignment_perf::tui::Tui, data_alignment_perf::tui::Dashboard)>,
+}
+
+#[cfg(feature = "tui")]
+impl Live {
+    fn new(args: &Args) -> std::io::Result<Self> {
+        use data_alignment_perf::tui::{Dashboard, Tui};
+
+        if !args.tui {
+            return Ok(Self { screen: None });
+        }
+        let offsets = args.offsets.as_ref().map(|range| range.len());
+        let tasks = args.types.iter().map(|element| (element.to_string(), offsets.unwrap_or(element.size())));
+        let dashboard = Dashboard::new(format!("offsets, {} reads", args.pattern), tasks);
+        Ok(Self {
+            screen: Some((Tui::start()?, dashboard)),
+        })
+    }
+
+    fn is_on(&self) -> bool {
+        self.screen.is_some()
+    }
+
+    // Redraws, and leaves the dashboard (and the program) when asked to
+    // quit or when the terminal fails.
+    fn draw(&mut self, force: bool) {
+        let Some((tui, dashboard)) = &mut self.screen else { return };
+        match tui.draw(dashboard, force) {
+            Ok(false) => {}
+            Ok(true) => {
+                self.screen = None;
+                eprintln!("interrupted");
+                std::process::exit(130);
+            }
+            Err(e) => {
+                self.screen = None;
+                eprintln!("error: drawing the dashboard: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    fn begin(&mut self, element: ElementType, offset: usize) {
+        if let Some((_, dashboard)) = &mut self.screen {
+            dashboard.begin(&element.to_string(), offset);
+            self.draw(true);
+        }
+    }
+
+    fn sample(&mut self, ms: f64) {
+        if let Some((_, dashboard)) = &mut self.screen {
+            dashboard.sample(ms);
+            self.draw(false);
+        }
+    }
+
+    fn finish(&mut self, result: &OffsetResult) {
+        if let Some((_, dashboard)) = &mut self.screen {
+            dashboard.finish(result);
+            self.draw(true);
+        }
+    }
+
+    fn close(self) {}
+}
+
+#[cfg(not(feature = "tui"))]
+impl Live {
+    fn new(_args: &Args) -> std::io::Result<Self> {
+        Ok(Self {})
+    }
+
+    fn is_on(&self) -> bool {
+        false
+    }
+
+    fn begin(&mut self, _element: ElementType, _offset: usize) {}
+
+    fn sample(&mut self, _ms: f64) {}
+
+    fn finish(&mut self, _result: &OffsetResult) {}
+
+    fn close(self) {}
+}
+
+// A bar over every offset of the run on stderr, hidden when stderr is not
+// a terminal; lines printed through it land above the bar.
+fn offsets_progress(args: &Args) -> ProgressBar {
+    let offsets = args.offsets.as_ref().map(|range| range.len());
+    let total: usize = args.types.iter().map(|element| offsets.unwrap_or(element.size())).sum();
+    let progress = ProgressBar::new(total as u64);
+    progress.set_style(
+        ProgressStyle::with_template("{bar:40} {pos}/{len} offsets, {elapsed} ({eta} left) {msg}")
+            .unwrap(),
+    );
+    progress
+}
+
+// Prints `line` above the bar, or straight to stdout when the bar is
+// hidden (which would swallow it).
+fn say(progress: &ProgressBar, line: String) {
+    if progress.is_hidden() {
+        println!("{}", line);
+    } else {
+        progress.println(line);
+    }
+}
+
+// One line per type and offset: the median, and how that compares to the
+// type's first aligned offset.
+// The p column compares each offset's samples (after outlier rejection, if
+// on) with the aligned offset's by Welch's t-test, starred below
+// `--confidence`.
+fn write_summary(out: &mut impl Write, results: &[TypeResults], args: &Args) -> std::io::Result<()> {
+    let kept = |samples: &[f64]| {
+        if args.reject_outliers {
+            reject_outliers(samples, args.trim)
+        } else {
+            samples.to_vec()
+        }
+    };
+    writeln!(out, "\nSummary (median per iteration):")?;
+    writeln!(out, "{:<6} {:>6} {:>12} {:>11} {:>8}", "type", "offset", "median", "vs aligned", "p")?;
+    let mut any_significant = false;
+    for result in results {
+        let aligned = result.offsets.iter().find(|offset| offset.aligned);
+        for offset in &result.offsets {
+            let (change, p) = match aligned {
+                Some(aligned) if !offset.aligned => {
+                    let change = format!("{:+.1}%", (offset.stats.median / aligned.stats.median - 1.0) * 100.0);
+                    let p = match welch_t_test(&kept(&offset.samples), &kept(&aligned.samples)) {
+                        Some(test) if test.significant(args.confidence / 100.0) => {
+                            any_significant = true;
+                            format!("{:.3}*", test.p_value)
+                        }
+                        Some(test) => format!("{:.3} ", test.p_value),
+                        None => String::new(),
+                    };
+                    (change, p)
                 }
-                
-                // Read phase with accumulation
-                let mut sum = T::from(0);
-                for i in 0..N {
-                    sum += ptr::read(data.add(i));
-                    if i % 1000 == 0 { fence(Ordering::SeqCst); }
+                _ => (String::new(), String::new()),
+            };
+            writeln!(
+                out,
+                "{:<6} {:>6} {:>10.3}ms {:>11} {:>8}",
+                result.type_name, offset.offset, offset.stats.median, change, p
+            )?;
+        }
+    }
+    if any_significant {
+        writeln!(out, "* significantly different from aligned at {}% confidence", args.confidence)?;
+    }
+    let verdicts: Vec<Verdict> = results.iter().filter_map(Verdict::from_results).collect();
+    if !verdicts.is_empty() {
+        writeln!(out)?;
+    }
+    for verdict in verdicts {
+        writeln!(out, "{}", verdict)?;
+    }
+    Ok(())
+}
+
+// Counters per iteration, `n/a` where the machine does not have them.
+fn format_counters(counters: &CounterValues) -> String {
+    let show = |value: Option<f64>| value.map_or("n/a".to_string(), |v| format!("{:.0}", v));
+    let ipc = match (counters.instructions, counters.cycles) {
+        (Some(instructions), Some(cycles)) if cycles > 0.0 => format!("{:.2}", instructions / cycles),
+        _ => "n/a".to_string(),
+    };
+    format!(
+        "cycles: {} instructions: {} ipc: {} l1d misses: {} llc misses: {} dtlb misses: {}",
+        show(counters.cycles),
+        show(counters.instructions),
+        ipc,
+        show(counters.l1d_misses),
+        show(counters.llc_misses),
+        show(counters.dtlb_misses)
+    )
+}
+
+fn format_stats(stats: &Stats) -> String {
+    format!(
+        "median: {:.3}ms mean: {:.3}ms trimmed: {:.3}ms min: {:.3}ms max: {:.3}ms sd: {:.3}ms",
+        stats.median, stats.mean, stats.trimmed_mean, stats.min, stats.max, stats.std_dev
+    )
+}
+
+// Runs the struct payload scenario; text output unless json was asked for.
+fn run_structs(args: &Args) -> std::io::Result<()> {
+    let results: Vec<LayoutResult> = run_struct_bench(&args.config(ElementType::U8), &args.members);
+    if args.format == Format::Json {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &results)?;
+        return writeln!(out);
+    }
+    if !args.quiet {
+        println!("Traversing {} records per iteration...", args.n);
+    }
+    for result in &results {
+        let layout = &result.layout;
+        println!(
+            "{} (stride {}, {} padding): {}",
+            layout.name,
+            layout.stride,
+            layout.padding(),
+            format_stats(&result.stats)
+        );
+    }
+    Ok(())
+}
+
+// Runs a boundary-crossing scenario for every type; text output unless json
+// was asked for.
+fn run_crossings(args: &Args, boundary: Boundary) -> std::io::Result<()> {
+    let json = args.format == Format::Json;
+    let mut all = Vec::new();
+    for &element in &args.types {
+        if !json && !args.quiet {
+            println!("\nProcessing {} ({} bytes)", element, element.size());
+        }
+        let results = run_crossing(&args.config(element), boundary);
+        let aligned = results[0].stats.median;
+        for result in &results {
+            if json {
+                continue;
+            }
+            let prefix = if args.quiet { format!("{} ", element) } else { String::new() };
+            println!(
+                "{}{} (position {}): {} ({:+.1}% vs aligned)",
+                prefix,
+                result.placement,
+                result.position,
+                format_stats(&result.stats),
+                (result.stats.median / aligned - 1.0) * 100.0
+            );
+        }
+        all.push(serde_json::json!({ "type": element.to_string(), "placements": results }));
+    }
+    if json {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &all)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+// Runs the packed-struct scenario once (it does not depend on `--types`),
+// both structs in both representations; text output unless json was asked
+// for.
+fn run_packeds(args: &Args) -> std::io::Result<()> {
+    let results = run_packed(&args.config(ElementType::U8));
+    if args.format == Format::Json {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &results)?;
+        return writeln!(out);
+    }
+    if !args.quiet {
+        println!("{} records per iteration...", args.n);
+    }
+    for result in &results {
+        println!(
+            "{:<15} {:<7} {:>2} B  {:.3} ns/record {:>7.2} GB/s  {}",
+            result.fields,
+            result.repr,
+            result.size,
+            result.ns_per_record,
+            result.gb_per_s,
+            format_stats(&result.stats)
+        );
+    }
+    Ok(())
+}
+
+// Runs the offsets of every type once per allocator and prints each one's
+// first-touch time, aligned and worst offsets; text output unless json was
+// asked for.
+fn run_storages(args: &Args) -> std::io::Result<()> {
+    let results = run_storage(&args.config(ElementType::U8), &args.types);
+    if args.format == Format::Json {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &results)?;
+        return writeln!(out);
+    }
+    if !args.quiet {
+        println!("{} elements per buffer; medians per iteration", args.n);
+    }
+    for result in &results {
+        let aligned = result.offsets.iter().find(|offset| offset.aligned);
+        let worst = result.offsets.iter().max_by(|a, b| a.stats.median.total_cmp(&b.stats.median));
+        let offsets = match (aligned, worst) {
+            (Some(aligned), Some(worst)) => format!(
+                "aligned {:.3}ms, worst {:.3}ms at offset {}",
+                aligned.stats.median, worst.stats.median, worst.offset
+            ),
+            _ => String::new(),
+        };
+        println!(
+            "{:<12} {:<5} first touch {:.3}ms  {}",
+            result.allocator.to_string(),
+            result.type_name,
+            result.first_touch.median,
+            offsets
+        );
+    }
+    Ok(())
+}
+
+// Runs the AoS and SoA record sums; text output unless json was asked for.
+fn run_soas(args: &Args) -> std::io::Result<()> {
+    let members = if args.members.is_empty() {
+        [(1, 1), (8, 8), (2, 2)].map(|(size, alignment)| TypeInfo { size, alignment }).to_vec()
+    } else {
+        args.members.clone()
+    };
+    let results = run_soa(&args.config(ElementType::U8), &members);
+    if args.format == Format::Json {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &results)?;
+        return writeln!(out);
+    }
+    if !args.quiet {
+        let aos = RecordLayout::padded(&members);
+        println!(
+            "Summing {} records (array of structs: stride {}, {} padding)...",
+            args.n,
+            aos.stride,
+            aos.padding()
+        );
+    }
+    for pair in results.chunks(2) {
+        let (aos, soa) = (&pair[0], &pair[1]);
+        println!(
+            "{:<11} aos {:.3}ms ({:.2}ns/record)  soa {:.3}ms ({:.2}ns/record)  soa {:.2}x faster",
+            aos.access.name(),
+            aos.stats.median,
+            aos.ns_per_record,
+            soa.stats.median,
+            soa.ns_per_record,
+            aos.stats.median / soa.stats.median
+        );
+    }
+    Ok(())
+}
+
+// Prints one GB/s matrix per size, source offsets down and destination
+// offsets across.
+fn run_copies(args: &Args) -> std::io::Result<()> {
+    let sizes = if args.working_sets.is_empty() { COPY_SIZES.to_vec() } else { args.working_sets.clone() };
+    let results = run_copy(&args.config(ElementType::U8), &sizes);
+    if args.format == Format::Json {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &results)?;
+        return writeln!(out);
+    }
+    for (size, rows) in sizes.iter().zip(results.chunks(COPY_OFFSETS.len() * COPY_OFFSETS.len())) {
+        println!("\n{} bytes, GB/s (source offset down, destination offset across)", size);
+        let header: Vec<String> = COPY_OFFSETS.iter().map(|offset| format!("{:>8}", offset)).collect();
+        println!("{:>6}{}", "", header.join(""));
+        for row in rows.chunks(COPY_OFFSETS.len()) {
+            let rates: Vec<String> = row.iter().map(|result| format!("{:>8.2}", result.gb_per_s)).collect();
+            println!("{:>6}{}", row[0].src_offset, rates.join(""));
+        }
+    }
+    Ok(())
+}
+
+// Runs the store-forwarding cases; text output unless json was asked for.
+fn run_forwardings(args: &Args) -> std::io::Result<()> {
+    let results = run_forwarding(&args.config(ElementType::U64));
+    if args.format == Format::Json {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &results)?;
+        return writeln!(out);
+    }
+    if !args.quiet {
+        println!("Store then dependent load, ns per pair:");
+    }
+    for result in &results.forwarding {
+        println!(
+            "{:<22} store {:<6} load {:<6} median: {:.3}ns min: {:.3}ns max: {:.3}ns",
+            result.case, result.store, result.load, result.stats.median, result.stats.min, result.stats.max
+        );
+    }
+    if !args.quiet {
+        println!("\ndst[i] = src[i] + 1 with dst 64 KiB + delta past src, ns per element:");
+    }
+    for result in &results.aliasing {
+        println!(
+            "delta {:>5}: median: {:.3}ns min: {:.3}ns max: {:.3}ns",
+            result.delta, result.stats.median, result.stats.min, result.stats.max
+        );
+    }
+    Ok(())
+}
+
+// Runs the non-temporal store fills; text output unless json was asked for.
+fn run_nontemporals(args: &Args) -> std::io::Result<()> {
+    let bytes = args.working_sets.first().copied().unwrap_or(NONTEMPORAL_BYTES);
+    let results = run_nontemporal(&args.config(ElementType::U64), bytes);
+    if args.format == Format::Json {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &results)?;
+        return writeln!(out);
+    }
+    if !args.quiet {
+        println!("Filling {} MiB, then re-reading a hot 1 MiB set...", bytes.div_ceil(1 << 20));
+    }
+    for result in &results {
+        println!(
+            "{:<9} {:>7.2} GB/s  {}  hot set after: {:.2}ns per line",
+            result.store.name(),
+            result.gb_per_s,
+            format_stats(&result.write),
+            result.hot_read.median
+        );
+    }
+    Ok(())
+}
+
+// Runs the branch kernels; text output unless json was asked for.
+fn run_branches(args: &Args) -> std::io::Result<()> {
+    let results = run_branch(&args.config(ElementType::U8));
+    if args.format == Format::Json {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &results)?;
+        return writeln!(out);
+    }
+    if !args.quiet {
+        println!("Summing the bytes >= 128 of {} values...", args.n);
+    }
+    for result in &results {
+        println!(
+            "{:<10} {:<8} {:.3} ns/value  {}",
+            result.kernel.name(),
+            if result.sorted { "sorted" } else { "shuffled" },
+            result.ns_per_element,
+            format_stats(&result.stats)
+        );
+    }
+    Ok(())
+}
+
+// Runs the false-sharing scenario once (it does not depend on `--types`);
+// text output unless json was asked for.
+fn run_sharing(args: &Args) -> std::io::Result<()> {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get()).clamp(2, 8);
+    let results = run_false_sharing(&args.config(ElementType::U64), threads);
+    if args.format == Format::Json {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &results)?;
+        return writeln!(out);
+    }
+    if !args.quiet {
+        println!("{} threads, {} increments each per iteration...", threads, args.n);
+    }
+    for result in &results {
+        println!(
+            "{}: {} ({:.1} Mops/s)",
+            result.layout.name(),
+            format_stats(&result.stats),
+            result.ops_per_sec / 1e6
+        );
+    }
+    Ok(())
+}
+
+// Runs the STREAM kernels at every requested size; text output unless json
+// was asked for.
+fn run_streams(args: &Args, levels: &[CacheLevel]) -> std::io::Result<()> {
+    let config = args.config(ElementType::F64);
+    let sizes = if args.stream_sizes.is_empty() { vec![args.n] } else { args.stream_sizes.clone() };
+    let json = args.format == Format::Json;
+    let mut all = Vec::new();
+    for len in sizes {
+        if !json && !args.quiet {
+            let mib = (len * 8) as f64 / (1 << 20) as f64;
+            println!("\nArrays of {} f64 ({:.1} MiB each){}", len, mib, level_note(levels, 3 * len * 8));
+        }
+        let results = run_stream(&config, len);
+        if !json {
+            for result in &results {
+                println!(
+                    "{:<5} {:>9.2} GB/s  {}",
+                    result.kernel.name(),
+                    result.gb_per_s,
+                    format_stats(&result.stats)
+                );
+            }
+        }
+        all.extend(results);
+    }
+    if json {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &all)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+// Runs the pointer chase over every working set; text output unless json
+// was asked for.
+fn run_chases(args: &Args, levels: &[CacheLevel]) -> std::io::Result<()> {
+    let config = args.config(ElementType::U64);
+    let sizes = if args.working_sets.is_empty() { DEFAULT_SIZES.to_vec() } else { args.working_sets.clone() };
+    let json = args.format == Format::Json;
+    let mut all = Vec::new();
+    for bytes in sizes {
+        let result = measure_chase(&config, bytes);
+        if !json {
+            let stats = &result.stats;
+            println!(
+                "{:>8} KiB: median: {:.2}ns mean: {:.2}ns min: {:.2}ns max: {:.2}ns per load{}",
+                result.bytes >> 10,
+                stats.median,
+                stats.mean,
+                stats.min,
+                stats.max,
+                level_note(levels, result.bytes)
+            );
+        }
+        all.push(result);
+    }
+    if json {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &all)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+// Runs the chase on every page backing for each working set; text output
+// unless json was asked for. Backings that cannot be mapped are reported and
+// skipped.
+#[cfg(target_os = "linux")]
+fn run_hugepages(args: &Args) -> std::io::Result<()> {
+    use data_alignment_perf::hugepage::{measure_backing, Backing, DEFAULT_BYTES};
+
+    let config = args.config(ElementType::U64);
+    let sizes = if args.working_sets.is_empty() { vec![DEFAULT_BYTES] } else { args.working_sets.clone() };
+    let json = args.format == Format::Json;
+    let mut all = Vec::new();
+    for bytes in sizes {
+        if !json && !args.quiet {
+            println!("\nWorking set of {} MiB", bytes.div_ceil(1 << 20));
+        }
+        let mut results = Vec::new();
+        for backing in Backing::ALL {
+            match measure_backing(&config, backing, bytes) {
+                Ok(result) => results.push(result),
+                Err(e) if backing == Backing::Explicit => {
+                    eprintln!("{}: unavailable: {} (reserve pages with sysctl vm.nr_hugepages)", backing.name(), e)
                 }
-                
-                results.push(sum);
+                Err(e) => eprintln!("{}: unavailable: {}", backing.name(), e),
             }
-            
-            // Use nanoseconds for more precision
-            let elapsed = start.elapsed().as_nanos();
-            sum_time += elapsed as f64;
         }
-        
-        // Convert back to milliseconds for display
-        println!(" avg: {:.3}ms", (sum_time / REPEAT as f64) / 1_000_000.0);
-        
-        // Print a checksum to prevent optimization, using i64 to avoid overflow
-        let checksum: i64 = results.iter()
-            .map(|&x| x.to_i64().unwrap())
-            .fold(0, |acc, x| acc + x);
-        print!(".");
-        if checksum == 0 { print!("!"); }
+        let fastest = results.iter().map(|r| r.stats.median).fold(f64::INFINITY, f64::min);
+        for result in results.iter().filter(|_| !json) {
+            let huge = result
+                .huge_fraction
+                .map_or(String::new(), |fraction| format!(", {:.0}% huge", fraction * 100.0));
+            println!(
+                "{:<7} median: {:.2}ns mean: {:.2}ns min: {:.2}ns per load ({:.2}x fastest{})",
+                result.backing.name(),
+                result.stats.median,
+                result.stats.mean,
+                result.stats.min,
+                result.stats.median / fastest,
+                huge
+            );
+        }
+        all.extend(results);
+    }
+    if json {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &all)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+// Maps the dataset and runs the cold reads for every type; text output
+// unless json was asked for.
+#[cfg(target_os = "linux")]
+fn run_datasets(args: &Args) -> std::io::Result<()> {
+    use data_alignment_perf::dataset::{run_dataset, Dataset};
+    use data_alignment_perf::sysinfo::detect_memory;
+
+    let dataset = match &args.dataset {
+        Some(path) => Dataset::open(path).map_err(|e| std::io::Error::new(e.kind(), format!("{}: {}", path.display(), e)))?,
+        None => {
+            let bytes = args.working_sets.first().copied().unwrap_or_else(|| {
+                detect_memory().total.map_or(8 << 30, |total| 2 * total as usize)
+            });
+            let path = std::env::temp_dir().join(format!("alignment-dataset-{}", std::process::id()));
+            let dataset = Dataset::create(&path, bytes);
+            // The mapping keeps the file alive.
+            let _ = std::fs::remove_file(&path);
+            dataset?
+        }
+    };
+    let json = args.format == Format::Json;
+    if !json && !args.quiet {
+        println!("Reading a {} MiB dataset cold...", dataset.len() >> 20);
+    }
+    let mut all = Vec::new();
+    for &element in &args.types {
+        if !json && !args.quiet {
+            println!("\nProcessing {} ({} bytes)", element, element.size());
+        }
+        let results = run_dataset(&args.config(element), &dataset)?;
+        for result in results.iter().filter(|_| !json) {
+            let mut majors = result.major_faults.clone();
+            majors.sort_unstable();
+            let readahead = result
+                .pages_per_major_fault
+                .map_or(String::new(), |pages| format!(", {:.1} pages per fault", pages));
+            println!(
+                "{:<10} {:<10} offset {}: {} ({} major faults{})",
+                result.pattern,
+                result.advice.name(),
+                result.offset,
+                format_stats(&result.stats),
+                majors[majors.len() / 2],
+                readahead
+            );
+        }
+        all.push(serde_json::json!({ "type": element.to_string(), "reads": results }));
     }
+    if json {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &all)?;
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+// Runs the NUMA matrix; text output unless json was asked for.
+#[cfg(target_os = "linux")]
+fn run_numas(args: &Args) -> std::io::Result<()> {
+    use data_alignment_perf::numa::{nodes, run_numa, DEFAULT_BYTES};
+
+    let nodes = nodes();
+    if nodes.is_empty() {
+        return Err(std::io::Error::other("no NUMA nodes in /sys/devices/system/node"));
+    }
+    let json = args.format == Format::Json;
+    if !json && nodes.len() == 1 {
+        println!("Only one NUMA node: measuring local access only");
+    }
+    let bytes = args.working_sets.first().copied().unwrap_or(DEFAULT_BYTES);
+    let results = run_numa(&args.config(ElementType::U64), &nodes, bytes)?;
+    if json {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &results)?;
+        return writeln!(out);
+    }
+    for result in &results {
+        let local = results
+            .iter()
+            .find(|r| r.cpu_node == result.cpu_node && r.memory_node == result.cpu_node)
+            .map_or(1.0, |r| r.latency.median);
+        println!(
+            "cpu node {} -> memory node {}: {:.2}ns per load ({:.2}x local), {:.2} GB/s",
+            result.cpu_node,
+            result.memory_node,
+            result.latency.median,
+            result.latency.median / local,
+            result.bandwidth.median
+        );
+    }
+    Ok(())
+}
+
+// Runs the prefetch kernels; text output unless json was asked for.
+fn run_prefetches(args: &Args) -> std::io::Result<()> {
+    let results = run_prefetch(&args.config(ElementType::U64), &args.prefetch_distances);
+    if args.format == Format::Json {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &results)?;
+        return writeln!(out);
+    }
+    for result in &results {
+        // Compare against the same pattern without prefetches, if measured.
+        let baseline = results
+            .iter()
+            .find(|r| r.pattern == result.pattern && r.distance == 0)
+            .map_or(String::new(), |r| format!(" ({:+.1}% vs none)", (result.stats.median / r.stats.median - 1.0) * 100.0));
+        println!(
+            "{:<6} distance {:>3}: median: {:.2}ns mean: {:.2}ns min: {:.2}ns per access{}",
+            result.pattern.name(),
+            result.distance,
+            result.stats.median,
+            result.stats.mean,
+            result.stats.min,
+            baseline
+        );
+    }
+    Ok(())
+}
+
+// Runs the split-lock scenario once (it does not depend on `--types`); text
+// output unless json was asked for.
+#[cfg(all(feature = "split-lock", target_arch = "x86_64"))]
+fn run_split_locks(args: &Args) -> std::io::Result<()> {
+    use data_alignment_perf::split_lock::{run_split_lock, WARNING};
+
+    eprintln!("{}", WARNING);
+    let results = run_split_lock(&args.config(ElementType::U64));
+    if args.format == Format::Json {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &results)?;
+        return writeln!(out);
+    }
+    let aligned = results[0].stats.median;
+    for result in &results {
+        let stats = &result.stats;
+        println!(
+            "{} (position {}): median: {:.1} mean: {:.1} min: {:.1} max: {:.1} cycles/op ({:.1}x aligned)",
+            result.placement,
+            result.position,
+            stats.median,
+            stats.mean,
+            stats.min,
+            stats.max,
+            stats.median / aligned
+        );
+    }
+    Ok(())
+}
+
+// Runs the aarch64 scenario once (it does not depend on `--types`); text
+// output unless json was asked for.
+#[cfg(target_arch = "aarch64")]
+fn run_arms(args: &Args) -> std::io::Result<()> {
+    use data_alignment_perf::arm::run_arm;
+
+    let results = run_arm(&args.config(ElementType::U64));
+    if args.format == Format::Json {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &results)?;
+        return writeln!(out);
+    }
+    for result in &results {
+        let aligned = results.iter().find(|r| r.access == result.access).unwrap().stats.median;
+        println!(
+            "{} {} (position {}): {} ({:.2}ns/access, {:+.1}% vs aligned)",
+            result.access.name(),
+            result.placement,
+            result.position,
+            format_stats(&result.stats),
+            result.ns_per_access,
+            (result.stats.median / aligned - 1.0) * 100.0
+        );
+    }
+    for warning in results.iter().filter_map(|r| r.warning()) {
+        eprintln!("{}", warning);
+    }
+    Ok(())
+}
+
+// Runs the sweep and prints the staircase; text output unless json was
+// asked for.
+fn run_sweeps(args: &Args) -> std::io::Result<()> {
+    let sweep = run_sweep(&args.config(ElementType::U64));
+    let levels = detect_levels(&sweep);
+    if args.format == Format::Json {
+        let mut out = std::io::stdout().lock();
+        serde_json::to_writer_pretty(&mut out, &serde_json::json!({ "sweep": sweep, "levels": levels }))?;
+        return writeln!(out);
+    }
+    for result in &sweep {
+        let ends = levels.iter().find(|level| level.bytes == result.bytes);
+        println!(
+            "{:>8} KiB: {:>8.2}ns{}",
+            result.bytes >> 10,
+            result.stats.median,
+            ends.map_or(String::new(), |level| format!("  <- end of {}", level.name))
+        );
+    }
+    print_levels(&levels);
+    Ok(())
+}
+
+// `--annotate-caches`: a short sweep before the run, whose levels are then
+// used to label working sets.
+fn detect_caches(args: &Args) -> Vec<CacheLevel> {
+    let config = Config {
+        repeat: 3,
+        warmup: 1,
+        ..args.config(ElementType::U64)
+    };
+    let levels = detect_levels(&run_sweep(&config));
+    if args.format == Format::Text {
+        print_levels(&levels);
+    }
+    levels
+}
+
+fn print_levels(levels: &[CacheLevel]) {
+    let found: Vec<String> = levels
+        .iter()
+        .map(|level| format!("{} {} KiB ({:.1}ns)", level.name, level.bytes >> 10, level.latency_ns))
+        .collect();
+    println!("Detected caches: {}", if found.is_empty() { "none".to_string() } else { found.join(", ") });
+}
+
+// " (fits in L2)" for a working set of `bytes`, or nothing without
+// `--annotate-caches`.
+fn level_note(levels: &[CacheLevel], bytes: usize) -> String {
+    if levels.is_empty() {
+        return String::new();
+    }
+    match level_for(levels, bytes) {
+        "memory" => " (larger than the caches)".to_string(),
+        name => format!(" (fits in {})", name),
+    }
+}
+
+// Runs every offset of every type, then writes the chart and the output
+// format asked for.
+fn run_offsets(args: &Args, levels: &[CacheLevel], environment: Option<Environment>) -> std::io::Result<()> {
+    if args.format == Format::Text && !args.quiet {
+        println!("Testing true unaligned memory access...");
+        match args.clock.resolve() {
+            Clock::Tsc => println!("Timing with the time-stamp counter at {:.3} GHz", ticks_per_ns()),
+            clock if args.clock == Clock::Auto => println!("Timing with {}", clock),
+            _ => {}
+        }
+    }
+    let mut live = Live::new(args)?;
+    let progress = if args.format == Format::Text && !live.is_on() {
+        offsets_progress(args)
+    } else {
+        ProgressBar::hidden()
+    };
+    let mut results = Vec::new();
+    for &element in &args.types {
+        results.push(run_test(element, args, levels, &progress, &mut live));
+    }
+    progress.finish_and_clear();
+    // Back to the normal screen for the summary.
+    live.close();
+
+    if let Some(path) = &args.plot
+        && let Err(e) = plot::plot_svg(path, &results)
+    {
+        eprintln!("error: plotting to {}: {}", path.display(), e);
+        std::process::exit(1);
+    }
+
+    let report = BenchmarkReport {
+        machine: MachineInfo::detect(),
+        parameters: Parameters {
+            n: args.n,
+            repeat: args.repeat,
+            warmup: args.warmup,
+            types: args.types.iter().map(ToString::to_string).collect(),
+            offsets: args.offsets.clone(),
+            trim: args.trim,
+            reject_outliers: args.reject_outliers,
+            pattern: args.pattern.to_string(),
+            threads: args.threads,
+            regions: args.regions,
+            clock: args.clock,
+            allocator: args.allocator,
+            byte_order: args.byte_order,
+            calibration: args.calibration(),
+        },
+        results,
+        environment,
+    };
+    {
+        let mut out = std::io::stdout().lock();
+        match args.format {
+            Format::Text if !args.quiet => write_summary(&mut out, &report.results, args)?,
+            Format::Text => {}
+            Format::Csv => write_csv(&mut out, &report.results)?,
+            Format::Md => write_markdown(&mut out, &report.results)?,
+            Format::Json => write_json(&mut out, &report)?,
+        }
+    }
+
+    if let Some(path) = &args.report
+        && let Err(e) = write_report(path, &report)
+    {
+        eprintln!("error: writing the report to {}: {}", path.display(), e);
+        std::process::exit(1);
+    }
+
+    if let Some(name) = &args.compare {
+        let old = baseline::load(&args.baseline_dir, name)?;
+        if old.parameters != report.parameters {
+            eprintln!("warning: baseline `{}` was run with different parameters", name);
+        }
+        let deltas = baseline::compare(&old, &report, args.tolerance / 100.0);
+        // Keep stdout parseable for the machine formats.
+        let mut out: Box<dyn Write> = if args.format == Format::Text {
+            Box::new(std::io::stdout().lock())
+        } else {
+            Box::new(std::io::stderr().lock())
+        };
+        write_deltas(&mut out, name, &deltas, args.tolerance)?;
+        let regressions = deltas.iter().filter(|delta| delta.regressed).count();
+        if regressions > 0 {
+            eprintln!(
+                "error: {} offset(s) regressed by more than {}% against baseline `{}`",
+                regressions, args.tolerance, name
+            );
+            std::process::exit(1);
+        }
+    }
+    if let Some(name) = &args.save_baseline {
+        baseline::save(&args.baseline_dir, name, &report)?;
+    }
+    if let Some(dir) = &args.history {
+        history::append(dir, &report)?;
+    }
+    #[cfg(feature = "sqlite")]
+    if let Some(path) = &args.sqlite {
+        use data_alignment_perf::sqlite;
+
+        sqlite::open(path)
+            .and_then(|mut connection| sqlite::insert(&mut connection, &report, "offsets"))
+            .map_err(|e| std::io::Error::other(format!("{}: {}", path.display(), e)))?;
+    }
+    Ok(())
+}
+
+fn write_report(path: &Path, report: &BenchmarkReport) -> std::io::Result<()> {
+    let mut page = Vec::new();
+    html::write_html(&mut page, report)?;
+    std::fs::write(path, page)
+}
+
+fn write_deltas(out: &mut impl Write, name: &str, deltas: &[Delta], tolerance: f64) -> std::io::Result<()> {
+    writeln!(out, "\nAgainst baseline `{}` (tolerance {}%):", name, tolerance)?;
+    for delta in deltas {
+        writeln!(
+            out,
+            "{} offset {}: {:.3}ms -> {:.3}ms ({:+.1}%){}",
+            delta.type_name,
+            delta.offset,
+            delta.baseline,
+            delta.current,
+            delta.change * 100.0,
+            if delta.regressed { " REGRESSED" } else { "" }
+        )?;
+    }
+    Ok(())
 }
 
 fn main() {
-    println!("Testing true unaligned memory access...");
-    run_test::<i32>("i32");
-    run_test::<i64>("i64");
-    run_test::<i128>("i128");
+    let args = Args::parse();
+    if let Some(Command::Compare { history, metric, types, last }) = &args.command {
+        if let Err(e) = run_history(history, *metric, types, *last) {
+            eprintln!("error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    match &args.suite {
+        Some(path) => run_suite(path),
+        None => run(&args),
+    }
+}
+
+// Prints the runs in `dir`, then one row per type and offset with `metric`
+// in each run and the change from the first run to the last.
+fn run_history(dir: &Path, metric: Metric, types: &[String], last: Option<usize>) -> std::io::Result<()> {
+    let mut entries = history::load(dir)?;
+    if let Some(last) = last {
+        entries.drain(..entries.len().saturating_sub(last));
+    }
+    if entries.is_empty() {
+        println!("No runs in {}", history::path(dir).display());
+        return Ok(());
+    }
+    println!("Runs:");
+    for (i, entry) in entries.iter().enumerate() {
+        let machine = &entry.report.machine;
+        println!(
+            "  #{:<3} {}  {}, {} {}, {}",
+            i + 1,
+            format_timestamp(entry.timestamp),
+            machine.cpu.as_deref().unwrap_or("unknown cpu"),
+            machine.os,
+            machine.kernel.as_deref().unwrap_or(""),
+            machine.rustc.as_deref().unwrap_or("unknown rustc")
+        );
+    }
+    println!("\n{:?} per run:", metric);
+    let columns: Vec<String> = (1..=entries.len()).map(|i| format!("{:>9}", format!("#{}", i))).collect();
+    println!("{:<6} {:>6} {} {:>9}", "type", "offset", columns.join(""), "change");
+    for series in history::series(&entries, metric) {
+        if !types.is_empty() && !types.contains(&series.type_name) {
+            continue;
+        }
+        let cells: Vec<String> = series
+            .values
+            .iter()
+            .map(|value| value.map_or(format!("{:>9}", "-"), |v| format!("{:>9.3}", v)))
+            .collect();
+        let measured: Vec<f64> = series.values.iter().flatten().copied().collect();
+        let change = match (measured.first(), measured.last()) {
+            (Some(first), Some(last)) if measured.len() > 1 => format!("{:+.1}%", (last / first - 1.0) * 100.0),
+            _ => String::new(),
+        };
+        println!("{:<6} {:>6} {} {:>9}", series.type_name, series.offset, cells.join(""), change);
+    }
+    Ok(())
+}
+
+// Runs each entry of the suite at `path` as if it had been given on the
+// command line.
+fn run_suite(path: &Path) {
+    let runs = load_suite(path).unwrap_or_else(|e| {
+        eprintln!("error: reading suite: {}", e);
+        std::process::exit(1);
+    });
+    for suite_run in runs {
+        let argv = std::iter::once("data-alignment-perf".to_string()).chain(suite_run.args);
+        let args = Args::try_parse_from(argv).unwrap_or_else(|e| {
+            eprintln!("error: suite run `{}`:", suite_run.name);
+            e.exit()
+        });
+        if args.suite.is_some() {
+            eprintln!("error: suite run `{}` names another suite", suite_run.name);
+            std::process::exit(1);
+        }
+        if args.format == Format::Text {
+            println!("\n=== {} ===", suite_run.name);
+        } else {
+            eprintln!("=== {} ===", suite_run.name);
+        }
+        run(&args);
+    }
+}
+
+// Runs the `--strict-env` checks and reports the ones that failed or could
+// not be made; exits on a failure when asked to abort.
+fn check_environment(args: &Args, action: StrictEnv) -> Environment {
+    let pin = args.pin.as_ref().map_or(&[][..], |cpus| &cpus.0[..]);
+    let environment = Environment::check(pin);
+    for check in &environment.checks {
+        match check.passed {
+            Some(false) => eprintln!("warning: {}: {}", check.name, check.detail),
+            None if !args.quiet => eprintln!("note: {} not checked: {}", check.name, check.detail),
+            _ => {}
+        }
+    }
+    if action == StrictEnv::Abort && environment.failed().next().is_some() {
+        eprintln!("error: the environment checks failed (--strict-env abort)");
+        std::process::exit(1);
+    }
+    environment
+}
+
+fn run(args: &Args) {
+    if let Some(cpus) = &args.pin
+        && let Err(e) = apply_pin(&cpus.0)
+    {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+    if !args.clock.available() {
+        eprintln!("error: --clock {} is not available on {} {}", args.clock, std::env::consts::OS, std::env::consts::ARCH);
+        std::process::exit(1);
+    }
+    if args.counters && !cfg!(all(feature = "perf", target_os = "linux")) {
+        eprintln!("error: --counters needs a Linux build with `--features perf`");
+        std::process::exit(1);
+    }
+    if args.sqlite.is_some() && !cfg!(feature = "sqlite") {
+        eprintln!("error: --sqlite needs a build with `--features sqlite`");
+        std::process::exit(1);
+    }
+    if args.tui && !cfg!(feature = "tui") {
+        eprintln!("error: --tui needs a build with `--features tui`");
+        std::process::exit(1);
+    }
+    if args.tui && (args.scenario != Scenario::Offsets || args.format != Format::Text) {
+        eprintln!("error: --tui only applies to the offsets scenario in the text format");
+        std::process::exit(1);
+    }
+    let environment = args.strict_env.map(|action| check_environment(args, action));
+    let levels = if args.annotate_caches { detect_caches(args) } else { Vec::new() };
+    let run = match args.scenario {
+        Scenario::Offsets if !args.members.is_empty() => run_structs(args),
+        Scenario::Offsets => run_offsets(args, &levels, environment),
+        Scenario::Storage => run_storages(args),
+        Scenario::CacheLine => run_crossings(args, Boundary::CacheLine),
+        Scenario::Page => run_crossings(args, Boundary::Page),
+        Scenario::Packed => run_packeds(args),
+        Scenario::AosSoa => run_soas(args),
+        Scenario::Copy => run_copies(args),
+        Scenario::FalseSharing => run_sharing(args),
+        Scenario::Stream => run_streams(args, &levels),
+        Scenario::Chase => run_chases(args, &levels),
+        Scenario::Sweep => run_sweeps(args),
+        Scenario::Prefetch => run_prefetches(args),
+        Scenario::NonTemporal => run_nontemporals(args),
+        Scenario::Branch => run_branches(args),
+        Scenario::Forwarding => run_forwardings(args),
+        #[cfg(target_os = "linux")]
+        Scenario::Numa => run_numas(args),
+        #[cfg(target_os = "linux")]
+        Scenario::Dataset => run_datasets(args),
+        #[cfg(target_os = "linux")]
+        Scenario::Hugepages => run_hugepages(args),
+        #[cfg(all(feature = "split-lock", target_arch = "x86_64"))]
+        Scenario::SplitLock => run_split_locks(args),
+        #[cfg(target_arch = "aarch64")]
+        Scenario::Arm => run_arms(args),
+    };
+    if let Err(e) = run {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
 }
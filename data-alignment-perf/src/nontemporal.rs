@@ -0,0 +1,191 @@
+//! Non-temporal (streaming) stores against regular ones, for buffers that
+//! are only written.
+//!
+//! A regular store first reads the line into the cache (read for
+//! ownership), then writes it back when it is evicted, and on the way
+//! evicts whatever was cached before. A streaming store goes through a
+//! write-combining buffer straight to memory: no read, and the caches are
+//! left alone. So besides the write bandwidth, each iteration re-reads a
+//! small hot set that was cached before the fill, to show how much of it
+//! the fill pushed out.
+//!
+//! Streaming stores are `movntdq` (`_mm_stream_si128`, then `sfence`) on
+//! x86_64 and `stnp` on aarch64; elsewhere both kernels use regular stores.
+
+use std::hint::black_box;
+
+use serde::{Deserialize, Serialize};
+
+use crate::allocator::{Allocation, Allocator};
+use crate::crossing::CACHE_LINE;
+use crate::stats::Stats;
+use crate::{summarize, Config};
+
+/// Buffer written when no size is given; larger than the last-level cache
+/// of most machines.
+pub const DEFAULT_BYTES: usize = 128 << 20;
+
+/// The set re-read after each fill: small enough to stay cached otherwise.
+pub const HOT_BYTES: usize = 1 << 20;
+
+/// How the buffer is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StoreKind {
+    Regular,
+    Streaming,
+}
+
+impl StoreKind {
+    pub const ALL: [StoreKind; 2] = [StoreKind::Regular, StoreKind::Streaming];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StoreKind::Regular => "regular",
+            StoreKind::Streaming => "streaming",
+        }
+    }
+}
+
+/// Writes `value ^ i` to every word `i` with ordinary stores.
+pub fn regular_fill(words: &mut [u64], value: u64) {
+    // Index-dependent values, so the loop is not turned into a memset,
+    // which may use streaming stores itself for large buffers.
+    for (i, word) in words.iter_mut().enumerate() {
+        *word = value ^ i as u64;
+    }
+    black_box(words);
+}
+
+/// [`regular_fill`] with streaming stores. `words` must start on a 16-byte
+/// boundary and hold an even number of words.
+pub fn streaming_fill(words: &mut [u64], value: u64) {
+    assert!((words.as_ptr() as usize).is_multiple_of(16) && words.len().is_multiple_of(2));
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        use std::arch::x86_64::{__m128i, _mm_set_epi64x, _mm_sfence, _mm_stream_si128};
+        let base = words.as_mut_ptr();
+        for i in (0..words.len()).step_by(2) {
+            let pair = _mm_set_epi64x((value ^ (i + 1) as u64) as i64, (value ^ i as u64) as i64);
+            _mm_stream_si128(base.add(i) as *mut __m128i, pair);
+        }
+        // Streaming stores are weakly ordered; make them visible before
+        // anything that follows.
+        _mm_sfence();
+    }
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        let base = words.as_mut_ptr();
+        for i in (0..words.len()).step_by(2) {
+            std::arch::asm!(
+                "stnp {a}, {b}, [{p}]",
+                a = in(reg) value ^ i as u64,
+                b = in(reg) value ^ (i + 1) as u64,
+                p = in(reg) base.add(i),
+                options(nostack, preserves_flags)
+            );
+        }
+        std::arch::asm!("dmb ishst", options(nostack, preserves_flags));
+    }
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    regular_fill(words, value);
+    black_box(words);
+}
+
+// Reads one word per cache line of `hot`.
+fn touch_lines(hot: &[u64]) -> u64 {
+    let step = CACHE_LINE / size_of::<u64>();
+    black_box(hot.iter().step_by(step).fold(0u64, |sum, &w| sum.wrapping_add(w)))
+}
+
+/// Timings of one store kind.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NonTemporalResult {
+    pub store: StoreKind,
+    pub bytes: usize,
+    /// Milliseconds per fill, one per iteration.
+    pub write_samples: Vec<f64>,
+    pub write: Stats,
+    /// Bytes written per second at the median fill, in GB/s (10^9 bytes).
+    pub gb_per_s: f64,
+    /// Nanoseconds per cache line to re-read the [`HOT_BYTES`] hot set right
+    /// after each fill.
+    pub hot_read_samples: Vec<f64>,
+    pub hot_read: Stats,
+}
+
+/// Times filling `bytes` (rounded up to a cache line) with `store`, with
+/// the warmup, repeat, clock and outlier settings of `config`.
+pub fn measure_store(config: &Config, store: StoreKind, bytes: usize) -> NonTemporalResult {
+    let bytes = bytes.max(1).next_multiple_of(CACHE_LINE);
+    let allocation = Allocation::new(bytes, Allocator::Aligned);
+    // The allocation is zeroed, CACHE_LINE-aligned and `bytes` long.
+    let words = unsafe { std::slice::from_raw_parts_mut(allocation.as_ptr() as *mut u64, bytes / 8) };
+    let hot: Vec<u64> = (0..(HOT_BYTES / 8) as u64).collect();
+    let lines = HOT_BYTES / CACHE_LINE;
+
+    let mut write_samples = Vec::with_capacity(config.repeat);
+    let mut hot_read_samples = Vec::with_capacity(config.repeat);
+    for iteration in 0..config.warmup + config.repeat {
+        touch_lines(&hot);
+        let write = config.clock.time(|| match store {
+            StoreKind::Regular => regular_fill(words, iteration as u64),
+            StoreKind::Streaming => streaming_fill(words, iteration as u64),
+        });
+        let hot_read = config.clock.time(|| {
+            touch_lines(&hot);
+        });
+        if iteration >= config.warmup {
+            write_samples.push(write / 1_000_000.0);
+            hot_read_samples.push(hot_read / lines as f64);
+        }
+    }
+    let write = summarize(&write_samples, config);
+    NonTemporalResult {
+        store,
+        bytes,
+        gb_per_s: bytes as f64 / (write.median / 1000.0) / 1e9,
+        write,
+        write_samples,
+        hot_read: summarize(&hot_read_samples, config),
+        hot_read_samples,
+    }
+}
+
+/// Measures both store kinds over `bytes`.
+pub fn run_nontemporal(config: &Config, bytes: usize) -> Vec<NonTemporalResult> {
+    StoreKind::ALL
+        .iter()
+        .map(|&store| measure_store(config, store, bytes))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fills_agree() {
+        let allocation = Allocation::new(1024, Allocator::Aligned);
+        let words = unsafe { std::slice::from_raw_parts_mut(allocation.as_ptr() as *mut u64, 128) };
+        streaming_fill(words, 7);
+        let streamed = words.to_vec();
+        regular_fill(words, 7);
+        assert_eq!(streamed, words);
+        assert_eq!(words[3], 7 ^ 3);
+    }
+
+    #[test]
+    fn test_run_nontemporal() {
+        let config = Config {
+            repeat: 2,
+            warmup: 0,
+            ..Config::default()
+        };
+        let results = run_nontemporal(&config, 100_000);
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].store, StoreKind::Streaming);
+        assert_eq!(results[0].bytes, 100_032);
+        assert!(results.iter().all(|r| r.gb_per_s > 0.0 && r.hot_read_samples.len() == 2));
+    }
+}
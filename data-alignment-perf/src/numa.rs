@@ -0,0 +1,176 @@
+//! Cross-node memory access on multi-socket (NUMA) Linux machines.
+//!
+//! For every pair of nodes the working set is bound to one node with
+//! `mbind(MPOL_BIND)` and the measuring thread pinned to the CPUs of the
+//! other with `sched_setaffinity`, then the pointer chase of
+//! [`hugepage`](crate::hugepage) gives the load latency and a sequential sum
+//! the read bandwidth. The diagonal is local access; everything else pays
+//! for the interconnect. On a single-node machine only the diagonal exists.
+//!
+//! The node list comes from `/sys/devices/system/node`, so no libnuma is
+//! needed.
+
+use std::io;
+use std::path::Path;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::affinity::{parse_cpu_list, pin_current_thread};
+use crate::chase::STEPS;
+use crate::hugepage::{chase, link, Backing, Mapping, HUGE_PAGE};
+use crate::stats::Stats;
+use crate::{summarize, Config};
+
+/// Working set used when none is given.
+pub const DEFAULT_BYTES: usize = 256 << 20;
+
+// From <linux/mempolicy.h>: fail if pages cannot follow the policy, and
+// move any already placed elsewhere.
+const MPOL_MF_STRICT: libc::c_uint = 1 << 0;
+const MPOL_MF_MOVE: libc::c_uint = 1 << 1;
+
+/// A node and the CPUs attached to it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NumaNode {
+    pub id: usize,
+    pub cpus: Vec<usize>,
+}
+
+/// The online nodes that have CPUs, from sysfs; empty where sysfs has no
+/// node directory (non-NUMA kernels).
+pub fn nodes() -> Vec<NumaNode> {
+    let root = Path::new("/sys/devices/system/node");
+    let Ok(entries) = std::fs::read_dir(root) else {
+        return Vec::new();
+    };
+    let mut nodes: Vec<NumaNode> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let id = entry.file_name().to_str()?.strip_prefix("node")?.parse().ok()?;
+            let list = std::fs::read_to_string(entry.path().join("cpulist")).ok()?;
+            let cpus = parse_cpu_list(&list).ok()?;
+            (!cpus.is_empty()).then_some(NumaNode { id, cpus })
+        })
+        .collect();
+    nodes.sort_by_key(|node| node.id);
+    nodes
+}
+
+/// Binds the `bytes` at `data` (page aligned) to `node`, moving pages that
+/// are already elsewhere.
+fn bind_memory(data: *mut u8, bytes: usize, node: usize) -> io::Result<()> {
+    const BITS: usize = libc::c_ulong::BITS as usize;
+    let mut mask: Vec<libc::c_ulong> = vec![0; node / BITS + 1];
+    mask[node / BITS] |= 1 << (node % BITS);
+    // The kernel reads `maxnode - 1` bits of the mask.
+    let maxnode = mask.len() * BITS + 1;
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_mbind,
+            data,
+            bytes,
+            libc::MPOL_BIND,
+            mask.as_ptr(),
+            maxnode,
+            MPOL_MF_STRICT | MPOL_MF_MOVE,
+        )
+    };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Latency and bandwidth with the thread on one node and memory on another.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NumaResult {
+    pub cpu_node: usize,
+    pub memory_node: usize,
+    /// Nanoseconds per chased load, one per iteration.
+    pub latency_samples: Vec<f64>,
+    pub latency: Stats,
+    /// Sequential read bandwidth in GB/s (10^9 bytes), one per iteration.
+    pub bandwidth_samples: Vec<f64>,
+    pub bandwidth: Stats,
+}
+
+// Sums the `u64`s of the working set; the bandwidth half of a measurement.
+fn read_pass(data: *const u8, bytes: usize) -> u64 {
+    let values = unsafe { std::slice::from_raw_parts(data as *const u64, bytes / 8) };
+    std::hint::black_box(values.iter().fold(0u64, |sum, &v| sum.wrapping_add(v)))
+}
+
+/// Measures `bytes` (rounded up to 2 MiB) bound to `memory` from a thread
+/// pinned to the CPUs of `cpu`, with the settings of `config`.
+pub fn measure_pair(config: &Config, cpu: &NumaNode, memory: &NumaNode, bytes: usize) -> io::Result<NumaResult> {
+    let bytes = bytes.max(1).next_multiple_of(HUGE_PAGE);
+    // Pin a fresh thread, so the caller keeps its own affinity.
+    std::thread::scope(|scope| {
+        scope
+            .spawn(|| {
+                pin_current_thread(&cpu.cpus)?;
+                let mapping = Mapping::new(bytes, Backing::SmallPages)?;
+                bind_memory(mapping.data, bytes, memory.id)?;
+                // First touch, now that the policy is in place.
+                link(mapping.data, bytes);
+
+                let mut latency_samples = Vec::with_capacity(config.repeat);
+                let mut bandwidth_samples = Vec::with_capacity(config.repeat);
+                for iteration in 0..config.warmup + config.repeat {
+                    let start = Instant::now();
+                    chase(mapping.data, STEPS);
+                    let latency = start.elapsed().as_nanos() as f64 / STEPS as f64;
+                    let start = Instant::now();
+                    read_pass(mapping.data, bytes);
+                    let bandwidth = bytes as f64 / start.elapsed().as_secs_f64() / 1e9;
+                    if iteration >= config.warmup {
+                        latency_samples.push(latency);
+                        bandwidth_samples.push(bandwidth);
+                    }
+                }
+                Ok(NumaResult {
+                    cpu_node: cpu.id,
+                    memory_node: memory.id,
+                    latency: summarize(&latency_samples, config),
+                    latency_samples,
+                    bandwidth: summarize(&bandwidth_samples, config),
+                    bandwidth_samples,
+                })
+            })
+            .join()
+            .unwrap()
+    })
+}
+
+/// Measures every (CPU node, memory node) pair of `nodes`.
+pub fn run_numa(config: &Config, nodes: &[NumaNode], bytes: usize) -> io::Result<Vec<NumaResult>> {
+    let mut results = Vec::new();
+    for cpu in nodes {
+        for memory in nodes {
+            results.push(measure_pair(config, cpu, memory, bytes)?);
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_local_node() {
+        let Some(node) = nodes().into_iter().next() else {
+            return;
+        };
+        let config = Config {
+            repeat: 2,
+            warmup: 0,
+            ..Config::default()
+        };
+        let result = measure_pair(&config, &node, &node, 1).unwrap();
+        assert_eq!((result.cpu_node, result.memory_node), (node.id, node.id));
+        assert_eq!(result.latency.count, 2);
+        assert!(result.bandwidth.min > 0.0);
+    }
+}
@@ -0,0 +1,189 @@
+//! Real `#[repr(packed)]` structs against their naturally aligned twins.
+//!
+//! The struct payload scenario lays records out by hand and always uses
+//! unaligned accesses. Here the compiler sees the types: for the
+//! `#[repr(C)]` struct it knows every field is aligned, and for the
+//! `#[repr(C, packed)]` one it has to emit whatever unaligned loads and
+//! stores the target needs (byte-wise on strict-alignment CPUs), and may
+//! vectorize less. The packed array is also smaller, so per record it
+//! moves fewer bytes; compare the time per record and the bandwidth.
+//!
+//!   u8,u64,u16        natural 24 bytes, packed 11
+//!   u16,u32,u8,f64    natural 24 bytes, packed 15
+
+use std::hint::black_box;
+use std::sync::atomic::{fence, Ordering};
+
+use serde::{Deserialize, Serialize};
+
+use crate::stats::Stats;
+use crate::{summarize, Config};
+
+/// A record type the kernel can fill and sum.
+pub trait Record: Copy + Default {
+    /// Sets every field to `value`, converted with `as`.
+    fn fill(&mut self, value: usize);
+    /// The wrapping sum of the fields, converted with `as`.
+    fn sum(&self) -> u64;
+}
+
+macro_rules! record_pair {
+    ($natural:ident, $packed:ident { $($field:ident: $t:ty),* }) => {
+        #[repr(C)]
+        #[derive(Clone, Copy, Default)]
+        pub struct $natural { $(pub $field: $t),* }
+
+        #[repr(C, packed)]
+        #[derive(Clone, Copy, Default)]
+        pub struct $packed { $(pub $field: $t),* }
+
+        impl Record for $natural {
+            #[inline(always)]
+            fn fill(&mut self, value: usize) {
+                $(self.$field = value as $t;)*
+            }
+            #[inline(always)]
+            fn sum(&self) -> u64 {
+                0u64 $(.wrapping_add(self.$field as u64))*
+            }
+        }
+
+        // Packed fields are only read and written by value; taking a
+        // reference to one would be unsound.
+        impl Record for $packed {
+            #[inline(always)]
+            fn fill(&mut self, value: usize) {
+                $(self.$field = value as $t;)*
+            }
+            #[inline(always)]
+            fn sum(&self) -> u64 {
+                0u64 $(.wrapping_add({ self.$field } as u64))*
+            }
+        }
+    };
+}
+
+record_pair!(Small, SmallPacked { a: u8, b: u64, c: u16 });
+record_pair!(Mixed, MixedPacked { a: u16, b: u32, c: u8, d: f64 });
+
+/// Writes every field of every record, then sums them all back.
+pub fn packed_pass<R: Record>(records: &mut [R]) -> u64 {
+    // Write phase with memory fence
+    for (i, record) in records.iter_mut().enumerate() {
+        record.fill(i % 100);
+        if i % 1000 == 0 { fence(Ordering::SeqCst); }
+    }
+
+    // black_box hides the records, so the reads cannot be folded away
+    let records = black_box(&*records);
+
+    // Read phase with accumulation
+    let mut sum = 0u64;
+    for (i, record) in records.iter().enumerate() {
+        sum = sum.wrapping_add(record.sum());
+        if i % 1000 == 0 { fence(Ordering::SeqCst); }
+    }
+    black_box(sum)
+}
+
+/// Timings of one struct in one representation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackedResult {
+    /// The field types, e.g. `u8,u64,u16`.
+    pub fields: String,
+    /// `natural` or `packed`.
+    pub repr: String,
+    /// `size_of` the struct.
+    pub size: usize,
+    /// Milliseconds per iteration, in run order.
+    pub samples: Vec<f64>,
+    pub stats: Stats,
+    /// Nanoseconds per record at the median.
+    pub ns_per_record: f64,
+    /// Bytes written and read back per second at the median, in GB/s
+    /// (10^9 bytes).
+    pub gb_per_s: f64,
+}
+
+fn measure<R: Record>(config: &Config, fields: &str, repr: &str) -> PackedResult {
+    let mut records = vec![R::default(); config.n];
+    for _ in 0..config.warmup {
+        packed_pass(&mut records);
+    }
+    let samples: Vec<f64> = (0..config.repeat)
+        .map(|_| {
+            config.clock.time(|| {
+                packed_pass(&mut records);
+            }) / 1_000_000.0
+        })
+        .collect();
+    let stats = summarize(&samples, config);
+    let bytes = 2 * config.n * size_of::<R>();
+    PackedResult {
+        fields: fields.to_string(),
+        repr: repr.to_string(),
+        size: size_of::<R>(),
+        ns_per_record: stats.median * 1_000_000.0 / config.n as f64,
+        gb_per_s: bytes as f64 / (stats.median / 1000.0) / 1e9,
+        stats,
+        samples,
+    }
+}
+
+/// Times both structs in both representations over `config.n` records,
+/// with the warmup, repeat, clock and outlier settings of `config`.
+pub fn run_packed(config: &Config) -> Vec<PackedResult> {
+    vec![
+        measure::<Small>(config, "u8,u64,u16", "natural"),
+        measure::<SmallPacked>(config, "u8,u64,u16", "packed"),
+        measure::<Mixed>(config, "u16,u32,u8,f64", "natural"),
+        measure::<MixedPacked>(config, "u16,u32,u8,f64", "packed"),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use struct_alignment_and_padding::{StructLayout, TypeInfo};
+
+    fn info<T>() -> TypeInfo {
+        TypeInfo {
+            size: size_of::<T>(),
+            alignment: align_of::<T>(),
+        }
+    }
+
+    #[test]
+    fn test_sizes_match_the_layout_crate() {
+        let small = [info::<u8>(), info::<u64>(), info::<u16>()];
+        assert_eq!(size_of::<Small>(), StructLayout::compute(&small, None).total_size);
+        assert_eq!(size_of::<SmallPacked>(), 11);
+        assert_eq!(size_of::<SmallPacked>(), StructLayout::compute(&small, Some(1)).total_size);
+        let mixed = [info::<u16>(), info::<u32>(), info::<u8>(), info::<f64>()];
+        assert_eq!(size_of::<Mixed>(), StructLayout::compute(&mixed, None).total_size);
+        assert_eq!(size_of::<MixedPacked>(), 15);
+        assert_eq!(size_of::<MixedPacked>(), StructLayout::compute(&mixed, Some(1)).total_size);
+    }
+
+    #[test]
+    fn test_packed_pass_sums_agree() {
+        let mut natural = vec![Small::default(); 1000];
+        let mut packed = vec![SmallPacked::default(); 1000];
+        assert_eq!(packed_pass(&mut natural), packed_pass(&mut packed));
+        assert_eq!(packed_pass(&mut natural), 3 * 10 * (0..100).sum::<u64>());
+    }
+
+    #[test]
+    fn test_run_packed() {
+        let config = Config {
+            n: 100,
+            repeat: 2,
+            warmup: 0,
+            ..Config::default()
+        };
+        let results = run_packed(&config);
+        let sizes: Vec<usize> = results.iter().map(|r| r.size).collect();
+        assert_eq!(sizes, [24, 11, 24, 15]);
+        assert!(results.iter().all(|r| r.samples.len() == 2 && r.gb_per_s > 0.0));
+    }
+}
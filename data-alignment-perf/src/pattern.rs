@@ -0,0 +1,134 @@
+//! Orders in which the read phase visits the elements.
+//!
+//! Sequential reads are what the hardware prefetchers are best at, so they
+//! can hide part of an alignment penalty; a random permutation defeats them
+//! and a stride sits in between. The orders are computed before timing
+//! starts, so only the reads themselves are measured.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// Seed of the random permutation, fixed so runs are comparable.
+pub const SEED: u64 = 0x9e37_79b9_7f4a_7c15;
+
+/// Read-phase access pattern.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Pattern {
+    /// Index 0, 1, 2, ...
+    #[default]
+    Sequential,
+    /// A fixed pseudo-random permutation of the indices.
+    Random,
+    /// Every `K`th index, starting again one further on at the end until
+    /// all have been visited: 0, K, 2K, ..., 1, K + 1, ...
+    Stride(usize),
+}
+
+impl Pattern {
+    /// The read order for `len` elements, or `None` for sequential reads,
+    /// which the kernel does without an index table.
+    pub fn order(self, len: usize) -> Option<Vec<usize>> {
+        match self {
+            Pattern::Sequential => None,
+            Pattern::Random => {
+                let mut order: Vec<usize> = (0..len).collect();
+                let mut rng = SplitMix64::new();
+                // Fisher-Yates.
+                for i in (1..len).rev() {
+                    order.swap(i, rng.below(i + 1));
+                }
+                Some(order)
+            }
+            Pattern::Stride(k) => Some(
+                (0..k.min(len))
+                    .flat_map(|start| (start..len).step_by(k))
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// splitmix64, a small fixed-seed generator for the random orders; quality
+/// only needs to beat the prefetchers.
+pub(crate) struct SplitMix64(u64);
+
+impl SplitMix64 {
+    pub(crate) fn new() -> Self {
+        Self(SEED)
+    }
+
+    pub(crate) fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A value in `0..n`.
+    pub(crate) fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Pattern::Sequential => f.write_str("sequential"),
+            Pattern::Random => f.write_str("random"),
+            Pattern::Stride(k) => write!(f, "stride:{k}"),
+        }
+    }
+}
+
+impl FromStr for Pattern {
+    type Err = String;
+
+    /// Parses `sequential`, `random` or `stride:K` with K at least 1.
+    fn from_str(s: &str) -> Result<Self, String> {
+        match s {
+            "sequential" => Ok(Pattern::Sequential),
+            "random" => Ok(Pattern::Random),
+            _ => {
+                let k = s
+                    .strip_prefix("stride:")
+                    .ok_or_else(|| format!("expected sequential, random or stride:K, got `{s}`"))?;
+                match k.parse() {
+                    Ok(0) => Err("stride must be at least 1".to_string()),
+                    Ok(k) => Ok(Pattern::Stride(k)),
+                    Err(e) => Err(format!("bad stride `{k}`: {e}")),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_permutation(mut order: Vec<usize>, len: usize) -> bool {
+        order.sort_unstable();
+        order == (0..len).collect::<Vec<_>>()
+    }
+
+    #[test]
+    fn test_orders_visit_every_index_once() {
+        assert_eq!(Pattern::Sequential.order(5), None);
+        assert_eq!(Pattern::Stride(3).order(7).unwrap(), [0, 3, 6, 1, 4, 2, 5]);
+        assert_eq!(Pattern::Stride(10).order(3).unwrap(), [0, 1, 2]);
+        let random = Pattern::Random.order(1000).unwrap();
+        assert_ne!(random[..10], (0..10).collect::<Vec<_>>());
+        assert_eq!(Pattern::Random.order(1000).unwrap(), random);
+        assert!(is_permutation(random, 1000));
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        for s in ["sequential", "random", "stride:16"] {
+            assert_eq!(s.parse::<Pattern>().unwrap().to_string(), s);
+        }
+        assert!("stride:0".parse::<Pattern>().is_err());
+        assert!("zigzag".parse::<Pattern>().is_err());
+    }
+}
@@ -0,0 +1,221 @@
+//! Arrays of user-described structs instead of primitives.
+//!
+//! A struct is a list of member sizes and alignments; its layout comes from
+//! the struct-alignment-and-padding crate. The same members are measured in
+//! three arrangements, so the cost of padding and packing shows up directly:
+//!
+//!   members 1:1, 8:8, 2:2
+//!   padded     [a·······|bbbbbbbb|cc······]  stride 24 (declaration order)
+//!   reordered  [bbbbbbbb|cca·····]           stride 16 (largest alignment first)
+//!   packed     [abbbbbbbbcc]                 stride 11 (no padding, b misaligned)
+//!
+//! The kernel writes then reads every member of every record, each with one
+//! unaligned load/store of the member's width (members wider than 8 bytes
+//! take several 8-byte accesses).
+
+use std::hint::black_box;
+use std::ptr;
+use std::sync::atomic::{fence, Ordering};
+
+use serde::{Deserialize, Serialize};
+use struct_alignment_and_padding::{StructLayout, TypeInfo};
+
+use crate::kernel::UnalignedBuffer;
+use crate::stats::Stats;
+use crate::{summarize, Config};
+
+/// Where each member of a record lives, and how far apart records are.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordLayout {
+    pub name: String,
+    pub member_offsets: Vec<usize>,
+    pub member_sizes: Vec<usize>,
+    /// Bytes from one record to the next.
+    pub stride: usize,
+}
+
+impl RecordLayout {
+    /// Natural C layout in declaration order.
+    pub fn padded(members: &[TypeInfo]) -> Self {
+        Self::from_struct("padded", members, &StructLayout::compute(members, None))
+    }
+
+    /// Natural layout after sorting members by descending alignment, which
+    /// minimizes padding.
+    pub fn reordered(members: &[TypeInfo]) -> Self {
+        let mut sorted = members.to_vec();
+        sorted.sort_by_key(|m| std::cmp::Reverse(m.alignment));
+        Self::from_struct("reordered", &sorted, &StructLayout::compute(&sorted, None))
+    }
+
+    /// No padding at all, like `#[repr(packed)]`.
+    pub fn packed(members: &[TypeInfo]) -> Self {
+        Self::from_struct("packed", members, &StructLayout::compute(members, Some(1)))
+    }
+
+    fn from_struct(name: &str, members: &[TypeInfo], layout: &StructLayout) -> Self {
+        Self {
+            name: name.to_string(),
+            member_offsets: layout.member_offsets.clone(),
+            member_sizes: members.iter().map(|m| m.size).collect(),
+            stride: layout.total_size,
+        }
+    }
+
+    /// Bytes of padding per record.
+    pub fn padding(&self) -> usize {
+        self.stride - self.member_sizes.iter().sum::<usize>()
+    }
+}
+
+/// One layout's timings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayoutResult {
+    pub layout: RecordLayout,
+    /// Milliseconds per iteration, in run order.
+    pub samples: Vec<f64>,
+    pub stats: Stats,
+}
+
+// Stores the low `size` bytes of `value` at `at`, in accesses of at most 8
+// bytes. Indexing bounds-checks every access.
+#[inline(always)]
+pub(crate) fn store(bytes: &mut [u8], at: usize, size: usize, value: u64) {
+    let dst = &mut bytes[at..at + size];
+    unsafe {
+        match size {
+            1 => dst[0] = value as u8,
+            2 => ptr::write_unaligned(dst.as_mut_ptr() as *mut u16, value as u16),
+            4 => ptr::write_unaligned(dst.as_mut_ptr() as *mut u32, value as u32),
+            8 => ptr::write_unaligned(dst.as_mut_ptr() as *mut u64, value),
+            _ => {
+                for chunk in dst.chunks_mut(8) {
+                    chunk.copy_from_slice(&value.to_le_bytes()[..chunk.len()]);
+                }
+            }
+        }
+    }
+}
+
+#[inline(always)]
+pub(crate) fn load(bytes: &[u8], at: usize, size: usize) -> u64 {
+    let src = &bytes[at..at + size];
+    unsafe {
+        match size {
+            1 => src[0] as u64,
+            2 => ptr::read_unaligned(src.as_ptr() as *const u16) as u64,
+            4 => ptr::read_unaligned(src.as_ptr() as *const u32) as u64,
+            8 => ptr::read_unaligned(src.as_ptr() as *const u64),
+            _ => src.chunks(8).fold(0, |sum, chunk| {
+                let mut word = [0u8; 8];
+                word[..chunk.len()].copy_from_slice(chunk);
+                sum.wrapping_add(u64::from_le_bytes(word))
+            }),
+        }
+    }
+}
+
+/// Writes then reads every member of `records` records laid out as
+/// `layout`, returning the wrapping sum of what was read.
+pub fn traverse(bytes: &mut [u8], layout: &RecordLayout, records: usize) -> u64 {
+    let members: Vec<(usize, usize)> = layout
+        .member_offsets
+        .iter()
+        .copied()
+        .zip(layout.member_sizes.iter().copied())
+        .collect();
+
+    // Write phase with memory fence
+    for r in 0..records {
+        let base = r * layout.stride;
+        for &(offset, size) in &members {
+            store(bytes, base + offset, size, (r % 100) as u64);
+        }
+        if r % 1000 == 0 { fence(Ordering::SeqCst); }
+    }
+
+    // black_box hides the bytes, so the reads cannot be folded away
+    let bytes = black_box(&*bytes);
+
+    // Read phase with accumulation
+    let mut sum = 0u64;
+    for r in 0..records {
+        let base = r * layout.stride;
+        for &(offset, size) in &members {
+            sum = sum.wrapping_add(load(bytes, base + offset, size));
+        }
+        if r % 1000 == 0 { fence(Ordering::SeqCst); }
+    }
+    black_box(sum)
+}
+
+/// Times [`traverse`] over `config.n` records of `layout`, with the warmup,
+/// repeat and outlier settings of `config` (its element type and offsets
+/// are not used).
+pub fn measure_layout(config: &Config, layout: &RecordLayout) -> LayoutResult {
+    let mut buffer = UnalignedBuffer::<u8>::new(config.n * layout.stride, 0);
+    let records = config.n;
+    for _ in 0..config.warmup {
+        traverse(buffer.as_bytes_mut(), layout, records);
+    }
+    let samples: Vec<f64> = (0..config.repeat)
+        .map(|_| {
+            let start = std::time::Instant::now();
+            traverse(buffer.as_bytes_mut(), layout, records);
+            start.elapsed().as_nanos() as f64 / 1_000_000.0
+        })
+        .collect();
+    LayoutResult {
+        layout: layout.clone(),
+        stats: summarize(&samples, config),
+        samples,
+    }
+}
+
+/// Measures the padded, reordered and packed layouts of `members`.
+pub fn run_struct_bench(config: &Config, members: &[TypeInfo]) -> Vec<LayoutResult> {
+    [
+        RecordLayout::padded(members),
+        RecordLayout::reordered(members),
+        RecordLayout::packed(members),
+    ]
+    .iter()
+    .map(|layout| measure_layout(config, layout))
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn members() -> Vec<TypeInfo> {
+        [(1, 1), (8, 8), (2, 2)]
+            .iter()
+            .map(|&(size, alignment)| TypeInfo { size, alignment })
+            .collect()
+    }
+
+    #[test]
+    fn test_layouts() {
+        let padded = RecordLayout::padded(&members());
+        assert_eq!((padded.member_offsets.clone(), padded.stride), (vec![0, 8, 16], 24));
+        assert_eq!(padded.padding(), 13);
+        let reordered = RecordLayout::reordered(&members());
+        assert_eq!((reordered.member_sizes.clone(), reordered.stride), (vec![8, 2, 1], 16));
+        let packed = RecordLayout::packed(&members());
+        assert_eq!((packed.member_offsets.clone(), packed.stride), (vec![0, 1, 9], 11));
+        assert_eq!(packed.padding(), 0);
+    }
+
+    #[test]
+    fn test_traverse_reads_back_every_member() {
+        let mut members = members();
+        members.push(TypeInfo { size: 12, alignment: 4 });
+        for layout in [RecordLayout::padded(&members), RecordLayout::packed(&members)] {
+            let mut bytes = vec![0u8; 250 * layout.stride];
+            // 3 single-word members, plus 2 words for the 12-byte one
+            let expected: u64 = (0..250).map(|r| (r % 100) * 5).sum();
+            assert_eq!(traverse(&mut bytes, &layout, 250), expected, "{}", layout.name);
+        }
+    }
+}
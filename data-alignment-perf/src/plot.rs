@@ -0,0 +1,119 @@
+//! SVG charts of latency against offset, one panel per element type.
+//!
+//! Each panel draws the mean time per offset as a line, with error bars one
+//! standard deviation either side, so an alignment penalty shows up as a step
+//! or a saw-tooth at a glance.
+
+use std::error::Error;
+use std::path::Path;
+
+use plotters::coord::Shift;
+use plotters::prelude::*;
+
+use crate::report::TypeResults;
+
+const PANEL_HEIGHT: u32 = 320;
+const WIDTH: u32 = 800;
+
+fn size(results: &[TypeResults]) -> (u32, u32) {
+    (WIDTH, PANEL_HEIGHT * results.len().max(1) as u32)
+}
+
+/// Writes the charts for `results` to the SVG file at `path`.
+pub fn plot_svg(path: &Path, results: &[TypeResults]) -> Result<(), Box<dyn Error>> {
+    draw(SVGBackend::new(path, size(results)).into_drawing_area(), results)
+}
+
+/// The charts for `results` as an SVG document.
+pub fn svg_string(results: &[TypeResults]) -> Result<String, Box<dyn Error>> {
+    let mut svg = String::new();
+    draw(SVGBackend::with_string(&mut svg, size(results)).into_drawing_area(), results)?;
+    Ok(svg)
+}
+
+fn draw(root: DrawingArea<SVGBackend<'_>, Shift>, results: &[TypeResults]) -> Result<(), Box<dyn Error>> {
+    root.fill(&WHITE)?;
+
+    for (result, area) in results.iter().zip(root.split_evenly((results.len(), 1))) {
+        let max_offset = result.offsets.iter().map(|o| o.offset).max().unwrap_or(0);
+        let min_offset = result.offsets.iter().map(|o| o.offset).min().unwrap_or(0);
+        let top = result
+            .offsets
+            .iter()
+            .map(|o| o.stats.mean + o.stats.std_dev)
+            .fold(0.0, f64::max);
+
+        let mut chart = ChartBuilder::on(&area)
+            .caption(
+                format!("{} ({} bytes)", result.type_name, result.size),
+                ("sans-serif", 20),
+            )
+            .margin(10)
+            .x_label_area_size(35)
+            .y_label_area_size(55)
+            .build_cartesian_2d(
+                min_offset as f64 - 0.5..max_offset as f64 + 0.5,
+                0.0..top * 1.1 + f64::EPSILON,
+            )?;
+        chart
+            .configure_mesh()
+            .x_desc("offset (bytes)")
+            .y_desc("time (ms)")
+            .x_labels(result.offsets.len().min(16))
+            .x_label_formatter(&|x| format!("{x:.0}"))
+            .draw()?;
+
+        let means = result
+            .offsets
+            .iter()
+            .map(|o| (o.offset as f64, o.stats.mean));
+        chart.draw_series(LineSeries::new(means.clone(), BLUE.stroke_width(2)))?;
+        chart.draw_series(means.map(|point| Circle::new(point, 3, BLUE.filled())))?;
+        chart.draw_series(result.offsets.iter().map(|o| {
+            let x = o.offset as f64;
+            let (mean, sd) = (o.stats.mean, o.stats.std_dev);
+            ErrorBar::new_vertical(x, (mean - sd).max(0.0), mean, mean + sd, RED, 6)
+        }))?;
+    }
+
+    root.present()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::OffsetResult;
+    use crate::stats::Stats;
+
+    #[test]
+    fn test_writes_one_panel_per_type() {
+        let results: Vec<TypeResults> = ["i32", "i64"]
+            .iter()
+            .map(|&name| TypeResults {
+                type_name: name.to_string(),
+                size: 4,
+                offsets: (0..4)
+                    .map(|offset| {
+                        let samples = vec![1.0 + offset as f64, 2.0 + offset as f64];
+                        OffsetResult {
+                            offset,
+                            aligned: offset == 0,
+                            stats: Stats::from_samples(&samples, 0.0),
+                            samples,
+                            threads: None,
+                            counters: None,
+                            phases: None,
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+        let path = std::env::temp_dir().join(format!("alignment-plot-{}.svg", std::process::id()));
+        plot_svg(&path, &results).unwrap();
+        let svg = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains("i32 (4 bytes)") && svg.contains("i64 (4 bytes)"));
+    }
+}
@@ -0,0 +1,206 @@
+//! Software prefetching at a range of distances, for a strided walk and a
+//! pointer chase over a working set much larger than the caches.
+//!
+//! A strided walk is easy for the hardware prefetchers, so a manual prefetch
+//! mostly adds instructions; a pointer chase is invisible to them, but a
+//! prefetch can only be issued once the address is known. The chase here
+//! stores a jump pointer in every node, to the node `distance` steps ahead,
+//! so the prefetch does not wait for the chase to get there (Luk and Mowry's
+//! jump-pointer prefetching). Distance 0 is the baseline without prefetches.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::chase::{random_cycle, STEPS};
+use crate::crossing::CACHE_LINE;
+use crate::stats::Stats;
+use crate::{summarize, Config};
+
+/// Working set of both kernels.
+pub const WORKING_SET: usize = 64 << 20;
+
+/// Distance between the `u64`s the strided walk reads, in bytes.
+pub const STRIDE: usize = 4 * CACHE_LINE;
+
+/// Distances used when none are given, in accesses ahead.
+pub const DEFAULT_DISTANCES: [usize; 4] = [0, 4, 16, 64];
+
+/// Hints that the line holding `p` will be read soon. Never faults, so `p`
+/// may point anywhere.
+#[inline(always)]
+pub fn prefetch<T>(p: *const T) {
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        std::arch::x86_64::_mm_prefetch::<{ std::arch::x86_64::_MM_HINT_T0 }>(p as *const i8)
+    };
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        std::arch::asm!("prfm pldl1keep, [{p}]", p = in(reg) p, options(nostack, readonly, preserves_flags))
+    };
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    let _ = p;
+}
+
+/// The access patterns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PrefetchPattern {
+    Stride,
+    Chase,
+}
+
+impl PrefetchPattern {
+    pub fn name(self) -> &'static str {
+        match self {
+            PrefetchPattern::Stride => "stride",
+            PrefetchPattern::Chase => "chase",
+        }
+    }
+}
+
+/// Reads every [`STRIDE`]th `u64` of `values`, then the ones one further
+/// on, and so forth until all are read, prefetching `distance` reads ahead
+/// (not at all for 0); returns the sum.
+pub fn strided_pass(values: &[u64], distance: usize) -> u64 {
+    let step = STRIDE / size_of::<u64>();
+    let ahead = distance * step;
+    let mut sum = 0u64;
+    for start in 0..step.min(values.len()) {
+        for i in (start..values.len()).step_by(step) {
+            if distance > 0 && i + ahead < values.len() {
+                prefetch(&values[i + ahead]);
+            }
+            sum = sum.wrapping_add(values[i]);
+        }
+    }
+    black_box(sum)
+}
+
+#[repr(C, align(64))]
+#[derive(Clone, Copy)]
+struct Node {
+    next: usize,
+    /// The node `distance` steps ahead.
+    ahead: usize,
+}
+
+/// A random cyclic list with jump pointers `distance` nodes ahead.
+pub struct JumpList {
+    nodes: Vec<Node>,
+    distance: usize,
+}
+
+impl JumpList {
+    /// A list over `bytes / CACHE_LINE` nodes (at least two).
+    pub fn new(bytes: usize, distance: usize) -> Self {
+        let len = (bytes / CACHE_LINE).max(2);
+        let order = random_cycle(len);
+        let mut nodes = vec![Node { next: 0, ahead: 0 }; len];
+        for i in 0..len {
+            nodes[order[i]] = Node {
+                next: order[(i + 1) % len],
+                ahead: order[(i + distance) % len],
+            };
+        }
+        Self { nodes, distance }
+    }
+
+    /// Follows `steps` links from node 0, prefetching the jump pointers
+    /// unless the distance is 0; returns where it stopped.
+    pub fn chase(&self, steps: usize) -> usize {
+        let mut i = 0;
+        for _ in 0..steps {
+            let node = &self.nodes[i];
+            if self.distance > 0 {
+                prefetch(&self.nodes[node.ahead]);
+            }
+            i = node.next;
+        }
+        black_box(i)
+    }
+}
+
+/// Timings for one pattern at one distance.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PrefetchResult {
+    pub pattern: PrefetchPattern,
+    /// Accesses ahead that were prefetched; 0 for none.
+    pub distance: usize,
+    /// Nanoseconds per access, one per iteration.
+    pub samples: Vec<f64>,
+    pub stats: Stats,
+}
+
+// Times `pass`, which makes `accesses` accesses, with the settings of
+// `config`; returns the samples in nanoseconds per access.
+fn time(config: &Config, accesses: usize, mut pass: impl FnMut()) -> Vec<f64> {
+    let mut once = || {
+        let start = Instant::now();
+        pass();
+        start.elapsed().as_nanos() as f64 / accesses as f64
+    };
+    for _ in 0..config.warmup {
+        once();
+    }
+    (0..config.repeat).map(|_| once()).collect()
+}
+
+/// Times both patterns at every distance, over [`WORKING_SET`] bytes, with
+/// the warmup, repeat and outlier settings of `config`.
+pub fn run_prefetch(config: &Config, distances: &[usize]) -> Vec<PrefetchResult> {
+    let values: Vec<u64> = (0..(WORKING_SET / size_of::<u64>()) as u64).collect();
+    let mut results = Vec::new();
+    for &distance in distances {
+        let samples = time(config, values.len(), || {
+            strided_pass(&values, distance);
+        });
+        results.push(PrefetchResult {
+            pattern: PrefetchPattern::Stride,
+            distance,
+            stats: summarize(&samples, config),
+            samples,
+        });
+    }
+    for &distance in distances {
+        let list = JumpList::new(WORKING_SET, distance);
+        let samples = time(config, STEPS, || {
+            list.chase(STEPS);
+        });
+        results.push(PrefetchResult {
+            pattern: PrefetchPattern::Chase,
+            distance,
+            stats: summarize(&samples, config),
+            samples,
+        });
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strided_pass_reads_everything_once() {
+        let values: Vec<u64> = (0..1000).collect();
+        for distance in [0, 1, 8, 1000] {
+            assert_eq!(strided_pass(&values, distance), 999 * 1000 / 2, "{distance}");
+        }
+    }
+
+    #[test]
+    fn test_jump_pointers() {
+        let list = JumpList::new(100 * CACHE_LINE, 5);
+        for node in &list.nodes {
+            let mut i = node.next;
+            for _ in 1..5 {
+                i = list.nodes[i].next;
+            }
+            assert_eq!(i, node.ahead);
+        }
+        assert_eq!(list.chase(100), 0);
+        prefetch(std::ptr::null::<u8>());
+    }
+}
@@ -0,0 +1,390 @@
+//! Collected results and machine-readable output formats.
+
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::Range;
+
+use serde::{Deserialize, Serialize};
+
+use crate::allocator::Allocator;
+use crate::calibrate::Calibration;
+use crate::clock::Clock;
+use crate::environment::Environment;
+use crate::kernel::ByteOrder;
+use crate::stats::Stats;
+use crate::sysinfo::{self, CacheInfo, MemoryInfo, Topology};
+use crate::threads::{Regions, ThreadSummary};
+
+/// Everything a run produced, as written by `--format json`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    pub machine: MachineInfo,
+    pub parameters: Parameters,
+    pub results: Vec<TypeResults>,
+    /// The `--strict-env` checks, if they were made.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<Environment>,
+}
+
+/// Where the numbers were measured.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MachineInfo {
+    pub os: String,
+    pub arch: String,
+    /// CPU model name, where the platform exposes it (`/proc/cpuinfo`).
+    pub cpu: Option<String>,
+    pub logical_cpus: usize,
+    #[serde(default)]
+    pub topology: Option<Topology>,
+    #[serde(default)]
+    pub caches: Vec<CacheInfo>,
+    /// Kernel release, on Linux.
+    #[serde(default)]
+    pub kernel: Option<String>,
+    /// `rustc --version` of the build.
+    #[serde(default)]
+    pub rustc: Option<String>,
+    /// cpufreq governor of CPU 0.
+    #[serde(default)]
+    pub governor: Option<String>,
+    #[serde(default)]
+    pub turbo: Option<bool>,
+    #[serde(default)]
+    pub memory: Option<MemoryInfo>,
+}
+
+impl MachineInfo {
+    pub fn detect() -> Self {
+        let cpu = std::fs::read_to_string("/proc/cpuinfo").ok().and_then(|info| {
+            info.lines()
+                .find(|line| line.starts_with("model name"))
+                .and_then(|line| line.split_once(':'))
+                .map(|(_, name)| name.trim().to_string())
+        });
+        Self {
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            cpu,
+            logical_cpus: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            topology: sysinfo::detect_topology(),
+            caches: sysinfo::detect_caches(),
+            kernel: sysinfo::detect_kernel(),
+            rustc: sysinfo::RUSTC_VERSION.map(str::to_string),
+            governor: sysinfo::detect_governor(),
+            turbo: sysinfo::detect_turbo(),
+            memory: Some(sysinfo::detect_memory()),
+        }
+    }
+}
+
+/// The command-line settings of the run.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Parameters {
+    pub n: usize,
+    pub repeat: usize,
+    pub warmup: usize,
+    pub types: Vec<String>,
+    /// `None` means every offset in `0..size_of::<T>()`.
+    pub offsets: Option<Range<usize>>,
+    pub trim: f64,
+    pub reject_outliers: bool,
+    /// Read-phase pattern, as passed to `--pattern`.
+    pub pattern: String,
+    pub threads: usize,
+    pub regions: Regions,
+    #[serde(default)]
+    pub clock: Clock,
+    #[serde(default)]
+    pub allocator: Allocator,
+    #[serde(default)]
+    pub byte_order: ByteOrder,
+    /// Target and stopping rule, for calibrated runs; `n` is then the
+    /// starting point only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub calibration: Option<Calibration>,
+}
+
+/// Every timed iteration of one offset, plus its statistics.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OffsetResult {
+    pub offset: usize,
+    /// Whether elements at this offset are naturally aligned.
+    pub aligned: bool,
+    /// Milliseconds per iteration, in run order (before outlier rejection);
+    /// wall times when several threads ran.
+    pub samples: Vec<f64>,
+    pub stats: Stats,
+    /// Per-thread results, for runs on more than one thread.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub threads: Option<ThreadSummary>,
+    /// Hardware counters over the timed iterations, with `--counters`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub counters: Option<CounterValues>,
+    /// The write and read phases timed apart, for single-threaded runs.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub phases: Option<Phases>,
+}
+
+/// The two halves of each iteration, timed separately; `samples` of the
+/// offset are their sums.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Phases {
+    /// Milliseconds of the write phase, per iteration.
+    pub write_samples: Vec<f64>,
+    pub write: Stats,
+    /// Milliseconds of the read phase, per iteration.
+    pub read_samples: Vec<f64>,
+    pub read: Stats,
+    /// Bytes written per second at the median write time, in GB/s (10^9
+    /// bytes).
+    pub write_gb_per_s: f64,
+    /// Nanoseconds per element read at the median read time.
+    pub read_ns_per_element: f64,
+}
+
+/// Hardware counter totals per timed iteration, each `None` where the
+/// counter is not available (see the `perf` module).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct CounterValues {
+    pub cycles: Option<f64>,
+    pub instructions: Option<f64>,
+    pub l1d_misses: Option<f64>,
+    pub llc_misses: Option<f64>,
+    pub dtlb_misses: Option<f64>,
+}
+
+/// All offsets measured for one element type.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TypeResults {
+    pub type_name: String,
+    /// `size_of` the element type, in bytes.
+    pub size: usize,
+    pub offsets: Vec<OffsetResult>,
+}
+
+/// The fastest and slowest offset of one type, by median.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verdict {
+    pub type_name: String,
+    pub fastest: usize,
+    pub slowest: usize,
+    /// Median of the slowest offset over the median of the fastest.
+    pub slowdown: f64,
+}
+
+impl Verdict {
+    /// `None` unless at least two offsets were measured.
+    pub fn from_results(result: &TypeResults) -> Option<Self> {
+        if result.offsets.len() < 2 {
+            return None;
+        }
+        let by_median = |a: &&OffsetResult, b: &&OffsetResult| a.stats.median.total_cmp(&b.stats.median);
+        let fastest = result.offsets.iter().min_by(by_median)?;
+        let slowest = result.offsets.iter().max_by(by_median)?;
+        Some(Self {
+            type_name: result.type_name.clone(),
+            fastest: fastest.offset,
+            slowest: slowest.offset,
+            slowdown: slowest.stats.median / fastest.stats.median,
+        })
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: offset {} is {:.2}\u{d7} slower than offset {}",
+            self.type_name, self.slowest, self.slowdown, self.fastest
+        )
+    }
+}
+
+/// Writes one `type,offset,iteration,millis` row per sample, followed by
+/// summary rows for each offset whose `iteration` column names the
+/// statistic (`median`, `mean`, `trimmed_mean`, `min`, `max`, `std_dev`).
+pub fn write_csv(out: &mut impl Write, results: &[TypeResults]) -> io::Result<()> {
+    writeln!(out, "type,offset,iteration,millis")?;
+    for result in results {
+        for offset in &result.offsets {
+            for (iteration, millis) in offset.samples.iter().enumerate() {
+                writeln!(out, "{},{},{},{}", result.type_name, offset.offset, iteration, millis)?;
+            }
+        }
+    }
+    for result in results {
+        for offset in &result.offsets {
+            let stats = &offset.stats;
+            let summary = [
+                ("median", stats.median),
+                ("mean", stats.mean),
+                ("trimmed_mean", stats.trimmed_mean),
+                ("min", stats.min),
+                ("max", stats.max),
+                ("std_dev", stats.std_dev),
+            ];
+            for (name, value) in summary {
+                writeln!(out, "{},{},{},{}", result.type_name, offset.offset, name, value)?;
+            }
+            if let Some(phases) = &offset.phases {
+                for (name, value) in [("write_median", phases.write.median), ("read_median", phases.read.median)] {
+                    writeln!(out, "{},{},{},{}", result.type_name, offset.offset, name, value)?;
+                }
+            }
+            if let Some(counters) = &offset.counters {
+                let counters = [
+                    ("cycles", counters.cycles),
+                    ("instructions", counters.instructions),
+                    ("l1d_misses", counters.l1d_misses),
+                    ("llc_misses", counters.llc_misses),
+                    ("dtlb_misses", counters.dtlb_misses),
+                ];
+                for (name, value) in counters {
+                    if let Some(value) = value {
+                        writeln!(out, "{},{},{},{}", result.type_name, offset.offset, name, value)?;
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Writes one Markdown table per type, offset against mean and median time.
+pub fn write_markdown(out: &mut impl Write, results: &[TypeResults]) -> io::Result<()> {
+    for (i, result) in results.iter().enumerate() {
+        if i > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "### {} ({} bytes)", result.type_name, result.size)?;
+        writeln!(out)?;
+        writeln!(out, "| offset | aligned | mean (ms) | median (ms) |")?;
+        writeln!(out, "|-------:|:-------:|----------:|------------:|")?;
+        for offset in &result.offsets {
+            writeln!(
+                out,
+                "| {} | {} | {:.3} | {:.3} |",
+                offset.offset,
+                if offset.aligned { "yes" } else { "no" },
+                offset.stats.mean,
+                offset.stats.median
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes `report` as pretty-printed JSON.
+pub fn write_json(out: &mut impl Write, report: &BenchmarkReport) -> io::Result<()> {
+    serde_json::to_writer_pretty(&mut *out, report)?;
+    writeln!(out)
+}
+
+/// A report of one type, `size` bytes, with an offset per `(offset,
+/// samples)`, aligned only at 0, and otherwise default parameters: the
+/// fixture of the tests of every report format.
+#[cfg(test)]
+pub(crate) fn sample_report(type_name: &str, size: usize, offsets: &[(usize, &[f64])]) -> BenchmarkReport {
+    BenchmarkReport {
+        machine: MachineInfo::detect(),
+        parameters: Parameters {
+            n: 10,
+            repeat: offsets.first().map_or(1, |(_, samples)| samples.len()),
+            warmup: 0,
+            types: vec![type_name.to_string()],
+            offsets: None,
+            trim: 0.0,
+            reject_outliers: false,
+            pattern: "sequential".to_string(),
+            threads: 1,
+            regions: Regions::Disjoint,
+            clock: Clock::default(),
+            allocator: Allocator::default(),
+            byte_order: ByteOrder::default(),
+            calibration: None,
+        },
+        environment: None,
+        results: vec![TypeResults {
+            type_name: type_name.to_string(),
+            size,
+            offsets: offsets
+                .iter()
+                .map(|&(offset, samples)| OffsetResult {
+                    offset,
+                    aligned: offset == 0,
+                    stats: Stats::from_samples(samples, 0.0),
+                    samples: samples.to_vec(),
+                    threads: None,
+                    counters: None,
+                    phases: None,
+                })
+                .collect(),
+        }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_rows() {
+        let results = sample_report("i32", 4, &[(1, &[2.0, 4.0])]).results;
+        let mut out = Vec::new();
+        write_csv(&mut out, &results).unwrap();
+        let csv = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[..4],
+            ["type,offset,iteration,millis", "i32,1,0,2", "i32,1,1,4", "i32,1,median,3"]
+        );
+        assert_eq!(lines.len(), 1 + 2 + 6);
+        assert_eq!(lines[8], "i32,1,std_dev,1.4142135623730951");
+    }
+
+    #[test]
+    fn test_markdown_table() {
+        let results = sample_report("i64", 8, &[(3, &[1.0, 2.0, 6.0])]).results;
+        let mut out = Vec::new();
+        write_markdown(&mut out, &results).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "### i64 (8 bytes)\n\n\
+             | offset | aligned | mean (ms) | median (ms) |\n\
+             |-------:|:-------:|----------:|------------:|\n\
+             | 3 | no | 3.000 | 2.000 |\n"
+        );
+    }
+
+    #[test]
+    fn test_verdict() {
+        let mut result = sample_report("i64", 8, &[(0, &[2.0]), (4, &[3.4]), (1, &[1.9])]).results.remove(0);
+        let verdict = Verdict::from_results(&result).unwrap();
+        assert_eq!((verdict.fastest, verdict.slowest), (1, 4));
+        assert_eq!(verdict.to_string(), "i64: offset 4 is 1.79\u{d7} slower than offset 1");
+
+        result.offsets.truncate(1);
+        assert_eq!(Verdict::from_results(&result), None);
+    }
+
+    #[test]
+    fn test_json_round_trip() {
+        let mut report = sample_report("i64", 8, &[(0, &[1.5, 2.5, 2.0])]);
+        report.parameters.offsets = Some(0..2);
+        report.parameters.pattern = "random".to_string();
+        report.parameters.clock = Clock::Tsc;
+        report.parameters.allocator = Allocator::System;
+        report.results[0].offsets[0].counters = Some(CounterValues {
+            cycles: Some(1000.0),
+            ..CounterValues::default()
+        });
+        let mut out = Vec::new();
+        write_json(&mut out, &report).unwrap();
+        let parsed: BenchmarkReport = serde_json::from_slice(&out).unwrap();
+        assert_eq!(parsed, report);
+        let value: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(value["results"][0]["offsets"][0]["stats"]["median"], 2.0);
+        assert_eq!(value["parameters"]["offsets"]["end"], 2);
+        assert_eq!(value["results"][0]["offsets"][0]["counters"]["cycles"], 1000.0);
+    }
+}
@@ -0,0 +1,155 @@
+//! False sharing: threads incrementing their own counters, either packed
+//! next to each other or padded to one cache line each.
+//!
+//! No counter is shared, but when several live in one line every increment
+//! takes the line away from the other cores, so the packed layout loses most
+//! of its throughput as threads are added while the padded one scales.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Barrier;
+use std::thread;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::crossing::CACHE_LINE;
+use crate::stats::Stats;
+use crate::{summarize, Config};
+
+const PER_LINE: usize = CACHE_LINE / 8;
+
+/// Eight counters sharing one line.
+#[repr(C, align(64))]
+struct PackedLine([AtomicU64; PER_LINE]);
+
+/// One counter alone in its line.
+#[repr(C, align(64))]
+struct PaddedCounter(AtomicU64);
+
+/// How the per-thread counters are laid out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CounterLayout {
+    /// Adjacent `u64`s, eight to a line.
+    Packed,
+    /// Each counter padded to its own line.
+    Padded,
+}
+
+impl CounterLayout {
+    pub fn name(self) -> &'static str {
+        match self {
+            CounterLayout::Packed => "packed",
+            CounterLayout::Padded => "padded",
+        }
+    }
+}
+
+/// Timings for one layout.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SharingResult {
+    pub layout: CounterLayout,
+    pub threads: usize,
+    /// Milliseconds per iteration (every thread doing `n` increments).
+    pub samples: Vec<f64>,
+    pub stats: Stats,
+    /// Total increments per second at the median time.
+    pub ops_per_sec: f64,
+}
+
+// Runs `threads` threads doing `n` increments each on `counter(i)`, released
+// together by a barrier; returns milliseconds until the last one finishes.
+fn time_increments<'a>(threads: usize, n: usize, counter: impl Fn(usize) -> &'a AtomicU64 + Sync) -> f64 {
+    let barrier = Barrier::new(threads + 1);
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|i| {
+                let (barrier, counter) = (&barrier, counter(i));
+                scope.spawn(move || {
+                    barrier.wait();
+                    for _ in 0..n {
+                        counter.fetch_add(1, Ordering::Relaxed);
+                    }
+                })
+            })
+            .collect();
+        barrier.wait();
+        let start = Instant::now();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        start.elapsed().as_nanos() as f64 / 1_000_000.0
+    })
+}
+
+/// One iteration of `layout`; returns milliseconds and the counter total.
+pub fn sharing_pass(layout: CounterLayout, threads: usize, n: usize) -> (f64, u64) {
+    match layout {
+        CounterLayout::Packed => {
+            let lines: Vec<PackedLine> = (0..threads.div_ceil(PER_LINE))
+                .map(|_| PackedLine(std::array::from_fn(|_| AtomicU64::new(0))))
+                .collect();
+            let millis = time_increments(threads, n, |i| &lines[i / PER_LINE].0[i % PER_LINE]);
+            let total = lines.iter().flat_map(|l| &l.0).map(|c| c.load(Ordering::Relaxed)).sum();
+            (millis, total)
+        }
+        CounterLayout::Padded => {
+            let counters: Vec<PaddedCounter> =
+                (0..threads).map(|_| PaddedCounter(AtomicU64::new(0))).collect();
+            let millis = time_increments(threads, n, |i| &counters[i].0);
+            let total = counters.iter().map(|c| c.0.load(Ordering::Relaxed)).sum();
+            (millis, total)
+        }
+    }
+}
+
+/// Times both layouts with `threads` threads doing `config.n` increments
+/// each per iteration.
+pub fn run_false_sharing(config: &Config, threads: usize) -> Vec<SharingResult> {
+    [CounterLayout::Packed, CounterLayout::Padded]
+        .into_iter()
+        .map(|layout| {
+            for _ in 0..config.warmup {
+                sharing_pass(layout, threads, config.n);
+            }
+            let samples: Vec<f64> = (0..config.repeat)
+                .map(|_| sharing_pass(layout, threads, config.n).0)
+                .collect();
+            let stats = summarize(&samples, config);
+            SharingResult {
+                layout,
+                threads,
+                ops_per_sec: (threads * config.n) as f64 / (stats.median / 1000.0),
+                stats,
+                samples,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_layouts() {
+        assert_eq!(std::mem::size_of::<PackedLine>(), CACHE_LINE);
+        assert_eq!(std::mem::size_of::<PaddedCounter>(), CACHE_LINE);
+        for layout in [CounterLayout::Packed, CounterLayout::Padded] {
+            assert_eq!(sharing_pass(layout, 10, 1000).1, 10_000, "{layout:?}");
+        }
+    }
+
+    #[test]
+    fn test_run_false_sharing() {
+        let config = Config {
+            n: 1000,
+            repeat: 3,
+            warmup: 0,
+            ..Config::default()
+        };
+        let results = run_false_sharing(&config, 2);
+        assert_eq!(results[0].layout, CounterLayout::Packed);
+        assert!(results.iter().all(|r| r.threads == 2 && r.stats.count == 3 && r.ops_per_sec > 0.0));
+    }
+}
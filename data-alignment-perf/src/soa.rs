@@ -0,0 +1,221 @@
+//! Array of structs against struct of arrays, for the members of a
+//! `--struct` description.
+//!
+//! The array of structs is the padded C layout from
+//! [`RecordLayout::padded`]; the struct of arrays keeps each member in its
+//! own contiguous, cache-line-aligned column. Both are summed the same way,
+//! over one member and over all of them:
+//!
+//!   members 1:1, 8:8, 2:2, summing the first
+//!   aos   [a·······|bbbbbbbb|cc······] [a·······|...   one byte used per 24
+//!   soa   [aaaaaaaaaaaaaaaa...]                     every byte used
+//!
+//! Reading one member of an array of structs drags the others (and the
+//! padding) through the cache and needs a strided load per record; a
+//! column is read in full cache lines and in vector loads.
+
+use std::hint::black_box;
+
+use serde::{Deserialize, Serialize};
+use struct_alignment_and_padding::TypeInfo;
+
+use crate::kernel::UnalignedBuffer;
+use crate::payload::{load, store, RecordLayout};
+use crate::stats::Stats;
+use crate::{summarize, Config};
+
+/// The two arrangements.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Arrangement {
+    Aos,
+    Soa,
+}
+
+/// Which members the computation reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Access {
+    /// The first member only.
+    One,
+    All,
+}
+
+impl Access {
+    pub fn name(self) -> &'static str {
+        match self {
+            Access::One => "one member",
+            Access::All => "all members",
+        }
+    }
+
+    fn members(self, count: usize) -> std::ops::Range<usize> {
+        match self {
+            Access::One => 0..count.min(1),
+            Access::All => 0..count,
+        }
+    }
+}
+
+/// `records` records of `members`, held both ways with the same values.
+pub struct Records {
+    pub aos: RecordLayout,
+    aos_bytes: UnalignedBuffer<u8>,
+    columns: Vec<UnalignedBuffer<u8>>,
+    records: usize,
+}
+
+impl Records {
+    /// Stores `r % 100` in every member of record `r`.
+    pub fn new(members: &[TypeInfo], records: usize) -> Self {
+        let aos = RecordLayout::padded(members);
+        let mut aos_bytes = UnalignedBuffer::<u8>::new(records * aos.stride, 0);
+        let mut columns: Vec<UnalignedBuffer<u8>> =
+            members.iter().map(|m| UnalignedBuffer::new(records * m.size, 0)).collect();
+        for r in 0..records {
+            let value = (r % 100) as u64;
+            for (m, member) in members.iter().enumerate() {
+                store(aos_bytes.as_bytes_mut(), r * aos.stride + aos.member_offsets[m], member.size, value);
+                store(columns[m].as_bytes_mut(), r * member.size, member.size, value);
+            }
+        }
+        Self {
+            aos,
+            aos_bytes,
+            columns,
+            records,
+        }
+    }
+
+    /// Sums the members of `access` over every record, laid out as
+    /// `arrangement`.
+    pub fn sum(&mut self, arrangement: Arrangement, access: Access) -> u64 {
+        let selected = access.members(self.aos.member_sizes.len());
+        let mut sum = 0u64;
+        match arrangement {
+            Arrangement::Aos => {
+                let bytes = black_box(&*self.aos_bytes.as_bytes_mut());
+                let members: Vec<(usize, usize)> = selected
+                    .map(|m| (self.aos.member_offsets[m], self.aos.member_sizes[m]))
+                    .collect();
+                for r in 0..self.records {
+                    let base = r * self.aos.stride;
+                    for &(offset, size) in &members {
+                        sum = sum.wrapping_add(load(bytes, base + offset, size));
+                    }
+                }
+            }
+            Arrangement::Soa => {
+                for m in selected {
+                    let size = self.aos.member_sizes[m];
+                    let column = black_box(&*self.columns[m].as_bytes_mut());
+                    sum = sum.wrapping_add(column_sum(column, size));
+                }
+            }
+        }
+        black_box(sum)
+    }
+}
+
+// Sums a column of `size`-byte values; the common widths in a loop the
+// compiler can vectorize.
+fn column_sum(column: &[u8], size: usize) -> u64 {
+    let sum = |chunks: std::slice::ChunksExact<'_, u8>, f: fn(&[u8]) -> u64| {
+        chunks.map(f).fold(0u64, u64::wrapping_add)
+    };
+    match size {
+        1 => column.iter().map(|&b| b as u64).fold(0, u64::wrapping_add),
+        2 => sum(column.chunks_exact(2), |c| u16::from_ne_bytes([c[0], c[1]]) as u64),
+        4 => sum(column.chunks_exact(4), |c| u32::from_ne_bytes(c.try_into().unwrap()) as u64),
+        8 => sum(column.chunks_exact(8), |c| u64::from_ne_bytes(c.try_into().unwrap())),
+        _ => (0..column.len() / size).fold(0, |total, r| total.wrapping_add(load(column, r * size, size))),
+    }
+}
+
+/// Timings of one arrangement and access.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SoaResult {
+    pub arrangement: Arrangement,
+    pub access: Access,
+    /// Milliseconds per iteration, in run order.
+    pub samples: Vec<f64>,
+    pub stats: Stats,
+    /// Nanoseconds per record at the median.
+    pub ns_per_record: f64,
+}
+
+/// Times both arrangements over `config.n` records of `members`, summing
+/// one member and then all of them, with the warmup, repeat, clock and
+/// outlier settings of `config`.
+pub fn run_soa(config: &Config, members: &[TypeInfo]) -> Vec<SoaResult> {
+    let mut records = Records::new(members, config.n);
+    let mut results = Vec::new();
+    for access in [Access::One, Access::All] {
+        for arrangement in [Arrangement::Aos, Arrangement::Soa] {
+            for _ in 0..config.warmup {
+                records.sum(arrangement, access);
+            }
+            let samples: Vec<f64> = (0..config.repeat)
+                .map(|_| {
+                    config.clock.time(|| {
+                        records.sum(arrangement, access);
+                    }) / 1_000_000.0
+                })
+                .collect();
+            let stats = summarize(&samples, config);
+            results.push(SoaResult {
+                arrangement,
+                access,
+                ns_per_record: stats.median * 1_000_000.0 / config.n.max(1) as f64,
+                stats,
+                samples,
+            });
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn members() -> Vec<TypeInfo> {
+        [(1, 1), (8, 8), (2, 2), (12, 4)]
+            .iter()
+            .map(|&(size, alignment)| TypeInfo { size, alignment })
+            .collect()
+    }
+
+    #[test]
+    fn test_arrangements_agree() {
+        let mut records = Records::new(&members(), 250);
+        let one: u64 = (0..250).map(|r| r % 100).sum();
+        assert_eq!(records.sum(Arrangement::Aos, Access::One), one);
+        assert_eq!(records.sum(Arrangement::Soa, Access::One), one);
+        // Three single-word members, plus two words for the 12-byte one.
+        assert_eq!(records.sum(Arrangement::Aos, Access::All), one * 5);
+        assert_eq!(records.sum(Arrangement::Soa, Access::All), one * 5);
+    }
+
+    #[test]
+    fn test_run_soa() {
+        let config = Config {
+            n: 1000,
+            repeat: 2,
+            warmup: 0,
+            ..Config::default()
+        };
+        let results = run_soa(&config, &members());
+        let variants: Vec<(Arrangement, Access)> = results.iter().map(|r| (r.arrangement, r.access)).collect();
+        assert_eq!(
+            variants,
+            [
+                (Arrangement::Aos, Access::One),
+                (Arrangement::Soa, Access::One),
+                (Arrangement::Aos, Access::All),
+                (Arrangement::Soa, Access::All)
+            ]
+        );
+        assert!(results.iter().all(|r| r.samples.len() == 2));
+    }
+}
@@ -0,0 +1,138 @@
+//! Locked read-modify-writes on a value that straddles a cache line.
+//!
+//! An atomic that fits in one line is handled in the core's cache; one split
+//! across two lines cannot be, so x86 falls back to a bus lock that stalls
+//! every core on the machine. Rust's atomic types refuse misaligned
+//! addresses, so the operation is a plain `lock xadd` in inline assembly.
+//!
+//! This is opt-in (the `split-lock` feature) because the result depends on
+//! the platform rather than the CPU alone: Linux with `split_lock_detect`
+//! warns about, throttles or kills (`SIGBUS`) processes that split locks,
+//! and some hypervisors fault on them.
+
+use std::arch::asm;
+use std::arch::x86_64::_rdtsc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::crossing::CACHE_LINE;
+use crate::kernel::UnalignedBuffer;
+use crate::stats::Stats;
+use crate::{summarize, Config};
+
+/// Printed before the scenario runs.
+pub const WARNING: &str = "warning: the split-lock scenario bus-locks the whole machine; \
+     Linux with split_lock_detect may throttle this process or kill it with SIGBUS";
+
+/// Upper bound on the operations per iteration: a split lock can take
+/// thousands of cycles, far more when the kernel throttles it.
+pub const MAX_OPS: usize = 100_000;
+
+/// Cycles per operation for one placement.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SplitLockResult {
+    pub placement: String,
+    /// Byte position of the value within its cache line.
+    pub position: usize,
+    /// Time-stamp counter cycles per operation, one per iteration.
+    pub samples: Vec<f64>,
+    pub stats: Stats,
+}
+
+/// Named positions of the `u64` within a line: aligned, and half on each
+/// side of the line boundary.
+pub fn placements() -> [(&'static str, usize); 2] {
+    [("aligned", 0), ("split", CACHE_LINE - 4)]
+}
+
+/// Atomically adds `value` to the `u64` at `ptr`, whatever its alignment;
+/// returns the previous value.
+///
+/// # Safety
+///
+/// `ptr` must be valid for reads and writes of 8 bytes.
+unsafe fn locked_add(ptr: *mut u8, value: u64) -> u64 {
+    let mut previous = value;
+    unsafe {
+        asm!(
+            "lock xadd qword ptr [{ptr}], {value}",
+            ptr = in(reg) ptr,
+            value = inout(reg) previous,
+            options(nostack),
+        );
+    }
+    previous
+}
+
+/// Performs `ops` locked adds of 1 on the `u64` at `position` of `bytes`;
+/// returns the value it ends up with.
+pub fn split_lock_pass(bytes: &mut [u8], position: usize, ops: usize) -> u64 {
+    let dst = &mut bytes[position..][..8];
+    for _ in 0..ops {
+        unsafe { locked_add(dst.as_mut_ptr(), 1) };
+    }
+    u64::from_le_bytes(dst.try_into().unwrap())
+}
+
+// One timed pass; returns cycles per operation.
+fn time_pass(bytes: &mut [u8], position: usize, ops: usize) -> f64 {
+    let start = unsafe { _rdtsc() };
+    split_lock_pass(bytes, position, ops);
+    let cycles = unsafe { _rdtsc() } - start;
+    cycles as f64 / ops as f64
+}
+
+/// Times both placements with the settings of `config`; `config.n` is the
+/// number of operations per iteration, capped at [`MAX_OPS`].
+pub fn run_split_lock(config: &Config) -> Vec<SplitLockResult> {
+    let ops = config.n.min(MAX_OPS);
+    let mut buffer = UnalignedBuffer::<u8>::new(2 * CACHE_LINE, 0);
+
+    placements()
+        .into_iter()
+        .map(|(name, position)| {
+            for _ in 0..config.warmup {
+                time_pass(buffer.as_bytes_mut(), position, ops);
+            }
+            let samples: Vec<f64> = (0..config.repeat)
+                .map(|_| time_pass(buffer.as_bytes_mut(), position, ops))
+                .collect();
+            SplitLockResult {
+                placement: name.to_string(),
+                position,
+                stats: summarize(&samples, config),
+                samples,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_locked_add_across_the_line() {
+        let mut bytes = vec![0u8; 2 * CACHE_LINE];
+        let (_, split) = placements()[1];
+        assert_eq!(split_lock_pass(&mut bytes, split, 5), 5);
+        assert_eq!(split_lock_pass(&mut bytes, split, 5), 10);
+        // Four bytes on each side of the boundary.
+        assert_eq!(bytes[split..split + 8], 10u64.to_le_bytes());
+        assert_eq!(unsafe { locked_add(bytes.as_mut_ptr(), 3) }, 0);
+        assert_eq!(bytes[0], 3);
+    }
+
+    #[test]
+    fn test_run_split_lock() {
+        let results = run_split_lock(&Config {
+            n: 100,
+            repeat: 2,
+            warmup: 0,
+            ..Config::default()
+        });
+        let names: Vec<&str> = results.iter().map(|r| r.placement.as_str()).collect();
+        assert_eq!(names, ["aligned", "split"]);
+        assert!(results.iter().all(|r| r.stats.count == 2 && r.stats.min > 0.0));
+    }
+}
@@ -0,0 +1,200 @@
+//! Results in a SQLite database (the `sqlite` feature), for tracking over
+//! time and for ad-hoc SQL instead of parsing JSON:
+//!
+//! ```text
+//! machine   one row per distinct machine description
+//! run       one row per run: when, on which machine, with what parameters
+//! scenario  one row per measured (scenario, type, offset) of a run, with
+//!           its summary statistics
+//! sample    every sample of a scenario row, in run order
+//! ```
+//!
+//! The JSON of the machine and the parameters is kept alongside the
+//! columns, so nothing the report holds is lost. For example, the median
+//! slowdown of every unaligned offset against offset 0, per run:
+//!
+//! ```sql
+//! SELECT run.timestamp, s.type_name, s.offset, s.median / a.median
+//! FROM scenario s
+//! JOIN scenario a ON a.run_id = s.run_id AND a.type_name = s.type_name AND a.offset = 0
+//! JOIN run ON run.id = s.run_id
+//! WHERE NOT s.aligned;
+//! ```
+
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection, Result};
+use serde::Serialize;
+
+use crate::report::BenchmarkReport;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS machine (
+    id INTEGER PRIMARY KEY,
+    os TEXT NOT NULL,
+    arch TEXT NOT NULL,
+    cpu TEXT,
+    logical_cpus INTEGER NOT NULL,
+    kernel TEXT,
+    rustc TEXT,
+    json TEXT NOT NULL UNIQUE
+);
+CREATE TABLE IF NOT EXISTS run (
+    id INTEGER PRIMARY KEY,
+    machine_id INTEGER NOT NULL REFERENCES machine(id),
+    timestamp INTEGER NOT NULL,
+    n INTEGER NOT NULL,
+    repeat INTEGER NOT NULL,
+    pattern TEXT NOT NULL,
+    threads INTEGER NOT NULL,
+    parameters TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS scenario (
+    id INTEGER PRIMARY KEY,
+    run_id INTEGER NOT NULL REFERENCES run(id),
+    name TEXT NOT NULL,
+    type_name TEXT NOT NULL,
+    size INTEGER NOT NULL,
+    offset INTEGER NOT NULL,
+    aligned INTEGER NOT NULL,
+    count INTEGER NOT NULL,
+    median REAL NOT NULL,
+    mean REAL NOT NULL,
+    min REAL NOT NULL,
+    max REAL NOT NULL,
+    std_dev REAL NOT NULL
+);
+CREATE TABLE IF NOT EXISTS sample (
+    scenario_id INTEGER NOT NULL REFERENCES scenario(id),
+    iteration INTEGER NOT NULL,
+    ms REAL NOT NULL,
+    PRIMARY KEY (scenario_id, iteration)
+);
+";
+
+/// Opens (creating if needed) the database at `path`, with the tables in
+/// place.
+pub fn open(path: &Path) -> Result<Connection> {
+    let connection = Connection::open(path)?;
+    connection.execute_batch(SCHEMA)?;
+    Ok(connection)
+}
+
+/// Stores `report` as one run of scenario `name`, stamped with the current
+/// time; returns the id of the run.
+pub fn insert(connection: &mut Connection, report: &BenchmarkReport, name: &str) -> Result<i64> {
+    let machine_json = json(&report.machine)?;
+    let parameters_json = json(&report.parameters)?;
+    let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()) as i64;
+
+    let transaction = connection.transaction()?;
+    let machine = &report.machine;
+    transaction.execute(
+        "INSERT OR IGNORE INTO machine (os, arch, cpu, logical_cpus, kernel, rustc, json)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            machine.os,
+            machine.arch,
+            machine.cpu,
+            machine.logical_cpus as i64,
+            machine.kernel,
+            machine.rustc,
+            machine_json
+        ],
+    )?;
+    let machine_id: i64 =
+        transaction.query_row("SELECT id FROM machine WHERE json = ?1", [&machine_json], |row| row.get(0))?;
+
+    let parameters = &report.parameters;
+    transaction.execute(
+        "INSERT INTO run (machine_id, timestamp, n, repeat, pattern, threads, parameters)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            machine_id,
+            timestamp,
+            parameters.n as i64,
+            parameters.repeat as i64,
+            parameters.pattern,
+            parameters.threads as i64,
+            parameters_json
+        ],
+    )?;
+    let run_id = transaction.last_insert_rowid();
+
+    {
+        let mut scenario = transaction.prepare(
+            "INSERT INTO scenario
+             (run_id, name, type_name, size, offset, aligned, count, median, mean, min, max, std_dev)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+        )?;
+        let mut sample = transaction.prepare("INSERT INTO sample (scenario_id, iteration, ms) VALUES (?1, ?2, ?3)")?;
+        for types in &report.results {
+            for result in &types.offsets {
+                let stats = &result.stats;
+                scenario.execute(params![
+                    run_id,
+                    name,
+                    types.type_name,
+                    types.size as i64,
+                    result.offset as i64,
+                    result.aligned,
+                    stats.count as i64,
+                    stats.median,
+                    stats.mean,
+                    stats.min,
+                    stats.max,
+                    stats.std_dev
+                ])?;
+                let scenario_id = transaction.last_insert_rowid();
+                for (iteration, ms) in result.samples.iter().enumerate() {
+                    sample.execute(params![scenario_id, iteration as i64, ms])?;
+                }
+            }
+        }
+    }
+    transaction.commit()?;
+    Ok(run_id)
+}
+
+fn json(value: &impl Serialize) -> Result<String> {
+    serde_json::to_string(value).map_err(|e| rusqlite::Error::ToSqlConversionFailure(e.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::report::sample_report;
+
+    fn report() -> BenchmarkReport {
+        sample_report("u32", 4, &[(0, &[1.0, 1.2]), (1, &[1.5, 1.7])])
+    }
+
+    #[test]
+    fn test_insert() {
+        let path = std::env::temp_dir().join(format!("alignment-results-{}.sqlite", std::process::id()));
+        let mut connection = open(&path).unwrap();
+        let first = insert(&mut connection, &report(), "offsets").unwrap();
+        let second = insert(&mut connection, &report(), "offsets").unwrap();
+        assert_ne!(first, second);
+
+        let count = |sql: &str| -> i64 { connection.query_row(sql, [], |row| row.get(0)).unwrap() };
+        // The same machine is stored once.
+        assert_eq!(count("SELECT COUNT(*) FROM machine"), 1);
+        assert_eq!(count("SELECT COUNT(*) FROM run"), 2);
+        assert_eq!(count("SELECT COUNT(*) FROM scenario"), 4);
+        assert_eq!(count("SELECT COUNT(*) FROM sample"), 8);
+        let slowdown: f64 = connection
+            .query_row(
+                "SELECT s.median / a.median FROM scenario s
+                 JOIN scenario a ON a.run_id = s.run_id AND a.type_name = s.type_name AND a.offset = 0
+                 WHERE NOT s.aligned AND s.run_id = ?1",
+                [second],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert!((slowdown - 1.6 / 1.1).abs() < 1e-9, "{slowdown}");
+        drop(connection);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,295 @@
+//! Summary statistics over the timed iterations of one offset.
+//!
+//! A single OS hiccup can double one sample, so besides the mean we report
+//! the median, the extremes, the spread and a trimmed mean that ignores the
+//! slowest and fastest fraction of runs.
+//!
+//! To tell a real slowdown from noise, [`welch_t_test`] compares the samples
+//! of two offsets without assuming their variances are equal.
+
+use serde::{Deserialize, Serialize};
+
+/// Statistics of a set of samples, in the samples' unit (milliseconds here).
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    pub count: usize,
+    pub mean: f64,
+    pub median: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Sample standard deviation (n - 1 denominator); 0 for a single sample.
+    pub std_dev: f64,
+    /// Mean after dropping `trim` of the samples from each end.
+    pub trimmed_mean: f64,
+}
+
+impl Stats {
+    /// Computes the statistics of `samples`; `trim` is the fraction dropped
+    /// from each end for the trimmed mean (0.1 drops the top and bottom 10%).
+    /// Panics if `samples` is empty.
+    pub fn from_samples(samples: &[f64], trim: f64) -> Self {
+        assert!(!samples.is_empty(), "no samples");
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let count = sorted.len();
+
+        let mean = sorted.iter().sum::<f64>() / count as f64;
+        let median = if count % 2 == 1 {
+            sorted[count / 2]
+        } else {
+            (sorted[count / 2 - 1] + sorted[count / 2]) / 2.0
+        };
+        let std_dev = if count > 1 {
+            let variance =
+                sorted.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / (count - 1) as f64;
+            variance.sqrt()
+        } else {
+            0.0
+        };
+        let kept = trimmed(&sorted, trim);
+        let trimmed_mean = kept.iter().sum::<f64>() / kept.len() as f64;
+
+        Self {
+            count,
+            mean,
+            median,
+            min: sorted[0],
+            max: sorted[count - 1],
+            std_dev,
+            trimmed_mean,
+        }
+    }
+}
+
+/// The samples left after dropping `trim` of them from each end (at least
+/// one is always kept). `sorted` must be in ascending order.
+pub fn trimmed(sorted: &[f64], trim: f64) -> &[f64] {
+    let drop = ((sorted.len() as f64 * trim.clamp(0.0, 0.5)).floor() as usize)
+        .min((sorted.len() - 1) / 2);
+    &sorted[drop..sorted.len() - drop]
+}
+
+/// Removes the top and bottom `trim` fraction of `samples` (outlier
+/// rejection), keeping the rest in ascending order.
+pub fn reject_outliers(samples: &[f64], trim: f64) -> Vec<f64> {
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(f64::total_cmp);
+    trimmed(&sorted, trim).to_vec()
+}
+
+/// Result of a two-sided Welch t-test.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TTest {
+    pub t: f64,
+    /// Welch-Satterthwaite degrees of freedom.
+    pub df: f64,
+    /// Probability of a difference in means at least this large if the two
+    /// sets of samples came from the same distribution.
+    pub p_value: f64,
+}
+
+impl TTest {
+    /// Whether the difference is significant at `confidence` (0.95 for 95%).
+    pub fn significant(&self, confidence: f64) -> bool {
+        self.p_value < 1.0 - confidence
+    }
+}
+
+/// Welch's t-test of `a` against `b`; `None` unless both have at least two
+/// samples.
+pub fn welch_t_test(a: &[f64], b: &[f64]) -> Option<TTest> {
+    if a.len() < 2 || b.len() < 2 {
+        return None;
+    }
+    let (a, b) = (Stats::from_samples(a, 0.0), Stats::from_samples(b, 0.0));
+    let va = a.std_dev.powi(2) / a.count as f64;
+    let vb = b.std_dev.powi(2) / b.count as f64;
+    let diff = a.mean - b.mean;
+    if va + vb == 0.0 {
+        // Two constant sets: either identical or certainly different.
+        let (t, p_value) = if diff == 0.0 { (0.0, 1.0) } else { (diff.signum() * f64::INFINITY, 0.0) };
+        return Some(TTest { t, df: (a.count + b.count - 2) as f64, p_value });
+    }
+    let t = diff / (va + vb).sqrt();
+    let df = (va + vb).powi(2) / (va.powi(2) / (a.count - 1) as f64 + vb.powi(2) / (b.count - 1) as f64);
+    Some(TTest {
+        t,
+        df,
+        p_value: student_t_two_sided(t, df),
+    })
+}
+
+/// The `t` with P(|T| >= t) = 1 - `confidence` for Student's t with `df`
+/// degrees of freedom, e.g. about 1.96 for 95% and many samples.
+pub fn t_quantile(confidence: f64, df: f64) -> f64 {
+    let alpha = 1.0 - confidence;
+    // The tail probability falls as t grows; bisect on it.
+    let (mut low, mut high) = (0.0, 1.0);
+    while student_t_two_sided(high, df) > alpha {
+        high *= 2.0;
+    }
+    for _ in 0..100 {
+        let mid = (low + high) / 2.0;
+        if student_t_two_sided(mid, df) > alpha {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.0
+}
+
+/// Half the width of the `confidence` interval of the mean of `samples`,
+/// relative to the mean: 0.01 means the mean is known to within 1%.
+/// Infinite for fewer than two samples.
+pub fn relative_ci_half_width(samples: &[f64], confidence: f64) -> f64 {
+    if samples.len() < 2 {
+        return f64::INFINITY;
+    }
+    let stats = Stats::from_samples(samples, 0.0);
+    let standard_error = stats.std_dev / (samples.len() as f64).sqrt();
+    t_quantile(confidence, (samples.len() - 1) as f64) * standard_error / stats.mean.abs()
+}
+
+// P(|T| >= |t|) for Student's t with `df` degrees of freedom.
+fn student_t_two_sided(t: f64, df: f64) -> f64 {
+    regularized_beta(df / 2.0, 0.5, df / (df + t * t))
+}
+
+// ln Γ(x) for x > 0 (Lanczos, g = 7).
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    if x < 0.5 {
+        // Reflection: Γ(x) Γ(1 - x) = π / sin(πx).
+        return (std::f64::consts::PI / (std::f64::consts::PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFICIENTS[1..]
+        .iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |sum, (i, c)| sum + c / (x + i as f64 + 1.0));
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+// The regularized incomplete beta function I_x(a, b).
+fn regularized_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    // The continued fraction converges fast on this side of the mean.
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+// Continued fraction for the incomplete beta function (modified Lentz).
+fn beta_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    d = 1.0 / if d.abs() < TINY { TINY } else { d };
+    let mut h = d;
+    for m in 1..=200 {
+        let m = m as f64;
+        for numerator in [
+            m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m)),
+            -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0)),
+        ] {
+            d = 1.0 + numerator * d;
+            d = 1.0 / if d.abs() < TINY { TINY } else { d };
+            c = 1.0 + numerator / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            h *= d * c;
+        }
+        if (d * c - 1.0).abs() < 1e-15 {
+            break;
+        }
+    }
+    h
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_one_hiccup_moves_the_mean_not_the_median() {
+        let mut samples = vec![10.0; 9];
+        samples.push(110.0);
+        let stats = Stats::from_samples(&samples, 0.1);
+        assert_eq!(stats.mean, 20.0);
+        assert_eq!(stats.median, 10.0);
+        assert_eq!(stats.trimmed_mean, 10.0);
+        assert_eq!((stats.min, stats.max), (10.0, 110.0));
+        assert!((stats.std_dev - 1000.0f64.sqrt()).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_small_inputs() {
+        let stats = Stats::from_samples(&[3.0], 0.25);
+        assert_eq!((stats.median, stats.std_dev, stats.trimmed_mean), (3.0, 0.0, 3.0));
+        assert_eq!(Stats::from_samples(&[4.0, 1.0], 0.5).median, 2.5);
+        assert_eq!(trimmed(&[1.0, 2.0], 0.5), &[1.0, 2.0]);
+    }
+
+    #[test]
+    fn test_student_t() {
+        // Cauchy at t = 1: half the mass lies beyond ±1.
+        assert!((student_t_two_sided(1.0, 1.0) - 0.5).abs() < 1e-9);
+        assert!((student_t_two_sided(2.0, 10.0) - 0.073_388_9).abs() < 1e-6);
+        assert_eq!(student_t_two_sided(0.0, 5.0), 1.0);
+    }
+
+    #[test]
+    fn test_confidence_interval() {
+        assert!((t_quantile(0.95, 10.0) - 2.228_139).abs() < 1e-5);
+        assert!((t_quantile(0.95, 1e6) - 1.959_964).abs() < 1e-4);
+        // t(3) times the standard error sd / sqrt(4), over the mean of 10.
+        let samples = [9.0, 9.0, 11.0, 11.0];
+        let sd = Stats::from_samples(&samples, 0.0).std_dev;
+        let expected = t_quantile(0.95, 3.0) * sd / 2.0 / 10.0;
+        assert!((relative_ci_half_width(&samples, 0.95) - expected).abs() < 1e-12);
+        assert_eq!(relative_ci_half_width(&[1.0], 0.95), f64::INFINITY);
+    }
+
+    #[test]
+    fn test_welch_t_test() {
+        let test = welch_t_test(&[1.0, 2.0, 3.0, 4.0, 5.0], &[2.0, 4.0, 6.0, 8.0, 10.0]).unwrap();
+        assert!((test.t + 3.0 / 2.5f64.sqrt()).abs() < 1e-12);
+        assert!((test.df - 6.25 / 1.0625).abs() < 1e-12);
+        assert!(test.p_value > 0.05 && test.p_value < 0.2);
+        assert!(!test.significant(0.95));
+
+        let slow: Vec<f64> = (0..20).map(|i| 12.0 + (i % 3) as f64 * 0.1).collect();
+        let fast: Vec<f64> = (0..20).map(|i| 10.0 + (i % 3) as f64 * 0.1).collect();
+        assert!(welch_t_test(&slow, &fast).unwrap().significant(0.999));
+        assert_eq!(welch_t_test(&[1.0, 1.0], &[1.0, 1.0]).unwrap().p_value, 1.0);
+        assert_eq!(welch_t_test(&[1.0], &[1.0, 2.0]), None);
+    }
+
+    #[test]
+    fn test_reject_outliers() {
+        let samples: Vec<f64> = (1..=10).rev().map(f64::from).collect();
+        assert_eq!(reject_outliers(&samples, 0.2), [3.0, 4.0, 5.0, 6.0, 7.0, 8.0]);
+        assert_eq!(reject_outliers(&samples, 0.0).len(), 10);
+    }
+}
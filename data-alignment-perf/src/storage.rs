@@ -0,0 +1,88 @@
+//! The offsets benchmark once per backing storage: every [`Allocator`],
+//! from the aligned heap through `Vec`, `Box<[T]>` and both kinds of
+//! `mmap`.
+//!
+//! Besides the usual per-offset timings (taken after the buffer has been
+//! written once), each backing gets a first-touch time: allocating the
+//! buffer, writing it once and freeing it again, which is where page faults
+//! and a backend's own bookkeeping show up.
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::allocator::Allocator;
+use crate::kernel::{write_pass, BenchElement, UnalignedBuffer};
+use crate::report::OffsetResult;
+use crate::stats::Stats;
+use crate::{run_alignment_bench, summarize, Config, ElementType};
+
+/// One element type on one backing.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StorageResult {
+    pub allocator: Allocator,
+    pub type_name: String,
+    /// Milliseconds to allocate, write once and free the buffer, one per
+    /// iteration.
+    pub first_touch_samples: Vec<f64>,
+    pub first_touch: Stats,
+    pub offsets: Vec<OffsetResult>,
+}
+
+fn first_touch<T: BenchElement>(config: &Config) -> Vec<f64> {
+    (0..config.repeat)
+        .map(|_| {
+            config.clock.time(|| {
+                let mut buffer = UnalignedBuffer::<T>::new_in(config.n, 0, config.allocator);
+                write_pass(&mut buffer);
+            }) / 1_000_000.0
+        })
+        .collect()
+}
+
+/// Runs `config` for every type of `types`, once per allocator; the
+/// `allocator` of `config` is ignored.
+pub fn run_storage(config: &Config, types: &[ElementType]) -> Vec<StorageResult> {
+    let mut results = Vec::new();
+    for &allocator in Allocator::value_variants() {
+        for &element in types {
+            let config = Config {
+                element,
+                allocator,
+                ..config.clone()
+            };
+            let first_touch_samples = with_element_type!(element, T => first_touch::<T>(&config));
+            results.push(StorageResult {
+                allocator,
+                type_name: element.to_string(),
+                first_touch: summarize(&first_touch_samples, &config),
+                first_touch_samples,
+                offsets: run_alignment_bench(config),
+            });
+        }
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_storage() {
+        let config = Config {
+            n: 1000,
+            repeat: 2,
+            warmup: 0,
+            offsets: Some(0..2),
+            ..Config::default()
+        };
+        let results = run_storage(&config, &[ElementType::U32]);
+        assert_eq!(results.len(), Allocator::value_variants().len());
+        assert!(results.iter().any(|r| r.allocator == Allocator::BoxedSlice));
+        for result in &results {
+            assert_eq!(result.type_name, "u32");
+            assert_eq!(result.first_touch_samples.len(), 2);
+            assert_eq!(result.offsets.len(), 2);
+        }
+    }
+}
@@ -0,0 +1,169 @@
+//! STREAM-style memory bandwidth kernels, to put the alignment numbers in
+//! context: a misaligned pass that runs at the machine's bandwidth limit is
+//! bound by memory, not by the split accesses.
+//!
+//! The four kernels and their byte counts follow McCalpin's STREAM:
+//!
+//!   copy   c = a          16 bytes per element
+//!   scale  b = s * c      16
+//!   add    c = a + b      24
+//!   triad  a = b + s * c  24
+//!
+//! As in STREAM, bandwidth is computed from the best iteration.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use serde::{Deserialize, Serialize};
+
+use crate::stats::Stats;
+use crate::{summarize, Config};
+
+const SCALAR: f64 = 3.0;
+
+/// One of the STREAM kernels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum StreamKernel {
+    Copy,
+    Scale,
+    Add,
+    Triad,
+}
+
+impl StreamKernel {
+    pub const ALL: [StreamKernel; 4] =
+        [StreamKernel::Copy, StreamKernel::Scale, StreamKernel::Add, StreamKernel::Triad];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            StreamKernel::Copy => "copy",
+            StreamKernel::Scale => "scale",
+            StreamKernel::Add => "add",
+            StreamKernel::Triad => "triad",
+        }
+    }
+
+    /// Bytes read and written per element.
+    pub fn bytes_per_element(self) -> usize {
+        match self {
+            StreamKernel::Copy | StreamKernel::Scale => 2 * size_of::<f64>(),
+            StreamKernel::Add | StreamKernel::Triad => 3 * size_of::<f64>(),
+        }
+    }
+}
+
+/// The three STREAM arrays.
+pub struct StreamArrays {
+    pub a: Vec<f64>,
+    pub b: Vec<f64>,
+    pub c: Vec<f64>,
+}
+
+impl StreamArrays {
+    /// Arrays of `len` elements with STREAM's initial values.
+    pub fn new(len: usize) -> Self {
+        Self {
+            a: vec![1.0; len],
+            b: vec![2.0; len],
+            c: vec![0.0; len],
+        }
+    }
+
+    /// Runs `kernel` once over the arrays.
+    pub fn run(&mut self, kernel: StreamKernel) {
+        let (a, b, c) = (&mut self.a, &mut self.b, &mut self.c);
+        match kernel {
+            StreamKernel::Copy => c.copy_from_slice(a),
+            StreamKernel::Scale => b.iter_mut().zip(c.iter()).for_each(|(b, c)| *b = SCALAR * c),
+            StreamKernel::Add => {
+                for ((c, a), b) in c.iter_mut().zip(a.iter()).zip(b.iter()) {
+                    *c = a + b;
+                }
+            }
+            StreamKernel::Triad => {
+                for ((a, b), c) in a.iter_mut().zip(b.iter()).zip(c.iter()) {
+                    *a = b + SCALAR * c;
+                }
+            }
+        }
+        black_box(&mut *self);
+    }
+}
+
+/// Timings for one kernel at one array size.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StreamResult {
+    pub kernel: StreamKernel,
+    /// Elements per array.
+    pub len: usize,
+    /// Milliseconds per iteration, in run order.
+    pub samples: Vec<f64>,
+    pub stats: Stats,
+    /// Bandwidth at the fastest iteration, in GB/s (10^9 bytes).
+    pub gb_per_s: f64,
+}
+
+/// Times every kernel over arrays of `len` elements, with the warmup,
+/// repeat and outlier settings of `config`. The kernels run in STREAM's
+/// order within each iteration, so each one reads what the previous wrote.
+pub fn run_stream(config: &Config, len: usize) -> Vec<StreamResult> {
+    let mut arrays = StreamArrays::new(len);
+    let mut samples = vec![Vec::with_capacity(config.repeat); StreamKernel::ALL.len()];
+    for iteration in 0..config.warmup + config.repeat {
+        for (kernel, samples) in StreamKernel::ALL.into_iter().zip(&mut samples) {
+            let start = Instant::now();
+            arrays.run(kernel);
+            let millis = start.elapsed().as_nanos() as f64 / 1_000_000.0;
+            if iteration >= config.warmup {
+                samples.push(millis);
+            }
+        }
+    }
+
+    StreamKernel::ALL
+        .into_iter()
+        .zip(samples)
+        .map(|(kernel, samples)| {
+            let stats = summarize(&samples, config);
+            let bytes = (kernel.bytes_per_element() * len) as f64;
+            StreamResult {
+                kernel,
+                len,
+                gb_per_s: bytes / (stats.min / 1000.0) / 1e9,
+                stats,
+                samples,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kernels_compute_stream_values() {
+        let mut arrays = StreamArrays::new(10);
+        for kernel in StreamKernel::ALL {
+            arrays.run(kernel);
+        }
+        // c = 1, b = 3, c = 1 + 3, a = 3 + 3 * 4
+        assert!(arrays.a.iter().all(|&a| a == 15.0));
+        assert!(arrays.b.iter().all(|&b| b == 3.0));
+        assert!(arrays.c.iter().all(|&c| c == 4.0));
+    }
+
+    #[test]
+    fn test_run_stream() {
+        let config = Config {
+            repeat: 3,
+            warmup: 1,
+            ..Config::default()
+        };
+        let results = run_stream(&config, 10_000);
+        let names: Vec<&str> = results.iter().map(|r| r.kernel.name()).collect();
+        assert_eq!(names, ["copy", "scale", "add", "triad"]);
+        assert!(results.iter().all(|r| r.stats.count == 3 && r.gb_per_s > 0.0));
+    }
+}
@@ -0,0 +1,150 @@
+//! Benchmark suites: several runs described in one TOML file, so an
+//! experiment matrix can be checked in and rerun as a whole.
+//!
+//! ```toml
+//! # Settings shared by every run; a run's own keys win.
+//! [defaults]
+//! n = 1_000_000
+//! repeat = 20
+//!
+//! [[run]]
+//! name = "sequential"
+//! types = ["i32", "i64"]
+//!
+//! [[run]]
+//! name = "random, 4 threads"
+//! types = ["i64"]
+//! pattern = "random"
+//! threads = 4
+//! ```
+//!
+//! Keys are the command-line flags without the dashes (`reject_outliers`
+//! or `reject-outliers`). Each run is turned back into arguments, so it
+//! accepts exactly what the command line does and is validated the same
+//! way: strings and numbers become `--key value`, arrays are joined with
+//! commas, and `true` becomes a bare `--key`.
+
+use std::io;
+use std::path::Path;
+
+use toml::{Table, Value};
+
+/// One run of a suite.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SuiteRun {
+    /// The run's `name`, or its position (`run 1`, ...) without one.
+    pub name: String,
+    /// The command-line arguments of the run, without the program name.
+    pub args: Vec<String>,
+}
+
+/// Parses a suite file's contents into its runs.
+pub fn parse_suite(text: &str) -> Result<Vec<SuiteRun>, String> {
+    let mut table: Table = text.parse().map_err(|e: toml::de::Error| e.to_string())?;
+    let defaults = match table.remove("defaults") {
+        Some(Value::Table(defaults)) => defaults,
+        Some(_) => return Err("`defaults` must be a table".to_string()),
+        None => Table::new(),
+    };
+    let runs = match table.remove("run") {
+        Some(Value::Array(runs)) => runs,
+        Some(_) => return Err("`run` must be an array of tables (`[[run]]`)".to_string()),
+        None => return Err("no `[[run]]` tables".to_string()),
+    };
+    if let Some(key) = table.keys().next() {
+        return Err(format!("unknown top-level key `{key}`"));
+    }
+
+    runs.into_iter()
+        .enumerate()
+        .map(|(i, run)| {
+            let Value::Table(mut run) = run else {
+                return Err(format!("run {} is not a table", i + 1));
+            };
+            let name = match run.remove("name") {
+                Some(Value::String(name)) => name,
+                Some(_) => return Err(format!("run {}: `name` must be a string", i + 1)),
+                None => format!("run {}", i + 1),
+            };
+            let mut merged = defaults.clone();
+            merged.extend(run);
+            let args = to_args(&merged).map_err(|e| format!("{name}: {e}"))?;
+            Ok(SuiteRun { name, args })
+        })
+        .collect()
+}
+
+/// Reads and parses the suite at `path`.
+pub fn load_suite(path: &Path) -> io::Result<Vec<SuiteRun>> {
+    let text = std::fs::read_to_string(path)?;
+    parse_suite(&text).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), e)))
+}
+
+fn to_args(table: &Table) -> Result<Vec<String>, String> {
+    let mut args = Vec::new();
+    for (key, value) in table {
+        let flag = format!("--{}", key.replace('_', "-"));
+        match value {
+            Value::Boolean(true) => args.push(flag),
+            Value::Boolean(false) => {}
+            Value::Array(values) => {
+                let values: Result<Vec<String>, String> = values.iter().map(|v| scalar(key, v)).collect();
+                args.push(flag);
+                args.push(values?.join(","));
+            }
+            value => {
+                args.push(flag);
+                args.push(scalar(key, value)?);
+            }
+        }
+    }
+    Ok(args)
+}
+
+fn scalar(key: &str, value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(s) => Ok(s.clone()),
+        Value::Integer(i) => Ok(i.to_string()),
+        Value::Float(f) => Ok(f.to_string()),
+        _ => Err(format!("`{key}` must be a string, number, boolean or array of those")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_suite() {
+        let runs = parse_suite(
+            r#"
+            [defaults]
+            n = 1000
+            reject_outliers = true
+
+            [[run]]
+            name = "small"
+            types = ["i32", "i64"]
+
+            [[run]]
+            n = 2000
+            reject_outliers = false
+            trim = 0.2
+            "#,
+        )
+        .unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!(runs[0].name, "small");
+        assert_eq!(runs[0].args, ["--n", "1000", "--reject-outliers", "--types", "i32,i64"]);
+        assert_eq!(runs[1].name, "run 2");
+        assert_eq!(runs[1].args, ["--n", "2000", "--trim", "0.2"]);
+    }
+
+    #[test]
+    fn test_bad_suites() {
+        assert!(parse_suite("").is_err());
+        assert!(parse_suite("[[run]]\nn = { a = 1 }").is_err());
+        assert!(parse_suite("run = 1").is_err());
+        assert!(parse_suite("[[run]]\n[other]").is_err());
+    }
+}
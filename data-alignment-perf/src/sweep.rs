@@ -0,0 +1,141 @@
+//! Cache sizes from a pointer-chase sweep.
+//!
+//! Latency plotted against working-set size is a staircase: flat while the
+//! set fits in a level, rising when it spills into the next. The sweep
+//! measures [`chase`](crate::chase) latency from 4 KiB to 256 MiB and takes
+//! each step up as the capacity of one level. Steps are often ramps spread
+//! over several points (associativity, TLB reach), so a level ends where
+//! latency first doubles from the start of its plateau rather than at the
+//! first increase.
+
+use serde::{Deserialize, Serialize};
+
+use crate::chase::{measure_chase, ChaseResult};
+use crate::Config;
+
+/// Smallest and largest working sets of the sweep.
+pub const SWEEP_MIN: usize = 4 << 10;
+pub const SWEEP_MAX: usize = 256 << 20;
+
+/// Latency growth over the start of a plateau that ends the level.
+const STEP: f64 = 2.0;
+
+/// Names given to the detected levels, innermost first.
+const LEVEL_NAMES: [&str; 3] = ["L1", "L2", "L3"];
+
+/// A detected cache level.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CacheLevel {
+    pub name: String,
+    /// Largest swept working set that still had this level's latency.
+    pub bytes: usize,
+    /// Median load latency at the start of the level's plateau, in
+    /// nanoseconds.
+    pub latency_ns: f64,
+}
+
+/// The sweep's working sets: every power of two from [`SWEEP_MIN`] to
+/// [`SWEEP_MAX`] and the points halfway between them (1.5 times the power).
+pub fn sweep_sizes() -> Vec<usize> {
+    let mut sizes = Vec::new();
+    let mut size = SWEEP_MIN;
+    while size <= SWEEP_MAX {
+        sizes.push(size);
+        if size < SWEEP_MAX {
+            sizes.push(size + size / 2);
+        }
+        size *= 2;
+    }
+    sizes
+}
+
+/// Chases every [`sweep_sizes`] working set with the settings of `config`.
+pub fn run_sweep(config: &Config) -> Vec<ChaseResult> {
+    sweep_sizes()
+        .into_iter()
+        .map(|bytes| measure_chase(config, bytes))
+        .collect()
+}
+
+/// Finds the levels in a sweep ordered by size: a level ends at the last
+/// point before the median latency exceeds twice the latency at the start
+/// of its plateau, and the next plateau starts at the point that did. At
+/// most three levels are named; the last plateau is memory.
+pub fn detect_levels(sweep: &[ChaseResult]) -> Vec<CacheLevel> {
+    let mut levels = Vec::new();
+    let Some(first) = sweep.first() else {
+        return levels;
+    };
+    let mut plateau = first.stats.median;
+    for pair in sweep.windows(2) {
+        if levels.len() == LEVEL_NAMES.len() {
+            break;
+        }
+        if pair[1].stats.median > plateau * STEP {
+            levels.push(CacheLevel {
+                name: LEVEL_NAMES[levels.len()].to_string(),
+                bytes: pair[0].bytes,
+                latency_ns: plateau,
+            });
+            plateau = pair[1].stats.median;
+        }
+    }
+    levels
+}
+
+/// Name of the innermost level `bytes` fits in, or `"memory"`.
+pub fn level_for(levels: &[CacheLevel], bytes: usize) -> &str {
+    levels
+        .iter()
+        .find(|level| bytes <= level.bytes)
+        .map_or("memory", |level| level.name.as_str())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::Stats;
+
+    fn point(bytes: usize, latency: f64) -> ChaseResult {
+        ChaseResult {
+            bytes,
+            samples: vec![latency],
+            stats: Stats::from_samples(&[latency], 0.0),
+        }
+    }
+
+    #[test]
+    fn test_sweep_sizes() {
+        let sizes = sweep_sizes();
+        assert_eq!(sizes[..4], [4 << 10, 6 << 10, 8 << 10, 12 << 10]);
+        assert_eq!(*sizes.last().unwrap(), SWEEP_MAX);
+        assert!(sizes.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_detect_levels_from_a_staircase() {
+        let sweep: Vec<ChaseResult> = [
+            (32, 1.0),
+            (48, 1.1),
+            (64, 3.0),
+            (96, 3.2),
+            (128, 3.1),
+            (192, 5.0),
+            (256, 10.0),
+            (384, 11.0),
+            (512, 60.0),
+            (768, 62.0),
+        ]
+        .iter()
+        .map(|&(kib, ns)| point(kib << 10, ns))
+        .collect();
+        let levels = detect_levels(&sweep);
+        let found: Vec<(&str, usize)> = levels.iter().map(|l| (l.name.as_str(), l.bytes >> 10)).collect();
+        assert_eq!(found, [("L1", 48), ("L2", 192), ("L3", 384)]);
+        assert_eq!(levels[1].latency_ns, 3.0);
+        assert_eq!(level_for(&levels, 100 << 10), "L2");
+        assert_eq!(level_for(&levels, 1 << 20), "memory");
+        assert!(detect_levels(&sweep[..2]).is_empty());
+        assert!(detect_levels(&[]).is_empty());
+    }
+}
@@ -0,0 +1,211 @@
+//! What else about the machine affects the numbers: core topology, caches,
+//! frequency scaling, memory and the toolchain.
+//!
+//! Everything comes from `/proc` and `/sys`, so outside Linux (or in a
+//! container that hides them) the fields are `None` or empty rather than
+//! errors.
+
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+/// The `rustc --version` the binary was built with, recorded by `build.rs`.
+pub const RUSTC_VERSION: Option<&str> = option_env!("RUSTC_VERSION");
+
+/// How the logical CPUs map onto cores and sockets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Topology {
+    pub sockets: usize,
+    /// Physical cores over all sockets.
+    pub cores: usize,
+    /// Logical CPUs per core (2 with SMT/hyper-threading).
+    pub threads_per_core: usize,
+}
+
+/// One cache level as seen from CPU 0.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CacheInfo {
+    pub level: u32,
+    /// `Data`, `Instruction` or `Unified`.
+    pub kind: String,
+    pub size: usize,
+    pub line_size: usize,
+    /// Logical CPUs sharing this cache.
+    pub shared_by: usize,
+}
+
+/// Memory size and the settings that change how it is mapped.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryInfo {
+    /// Physical memory, in bytes.
+    pub total: Option<u64>,
+    pub page_size: Option<usize>,
+    /// Transparent huge page mode: `always`, `madvise` or `never`.
+    pub transparent_hugepages: Option<String>,
+    pub numa_nodes: usize,
+}
+
+pub(crate) fn read(path: impl AsRef<Path>) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// Parses a sysfs cache size such as `32K` or `8M`.
+pub fn parse_size(s: &str) -> Option<usize> {
+    let s = s.trim();
+    let (digits, unit) = match s.char_indices().find(|(_, c)| !c.is_ascii_digit()) {
+        Some((i, _)) => s.split_at(i),
+        None => (s, ""),
+    };
+    let value: usize = digits.parse().ok()?;
+    match unit {
+        "" => Some(value),
+        "K" => Some(value << 10),
+        "M" => Some(value << 20),
+        "G" => Some(value << 30),
+        _ => None,
+    }
+}
+
+/// The selected entry of a sysfs choice list, e.g. `madvise` from
+/// `always [madvise] never`.
+pub fn selected(choices: &str) -> Option<&str> {
+    let start = choices.find('[')? + 1;
+    let end = start + choices[start..].find(']')?;
+    Some(&choices[start..end])
+}
+
+/// `MemTotal` of `/proc/meminfo` text, in bytes.
+pub fn mem_total(meminfo: &str) -> Option<u64> {
+    let line = meminfo.lines().find(|line| line.starts_with("MemTotal:"))?;
+    let kib: u64 = line.split_whitespace().nth(1)?.parse().ok()?;
+    Some(kib * 1024)
+}
+
+/// Counts sockets and cores from the per-CPU topology files.
+pub fn detect_topology() -> Option<Topology> {
+    let mut packages = BTreeSet::new();
+    let mut cores = BTreeSet::new();
+    let mut logical = 0;
+    for entry in fs::read_dir("/sys/devices/system/cpu").ok()?.flatten() {
+        let name = entry.file_name();
+        let Some(id) = name.to_str().and_then(|name| name.strip_prefix("cpu")) else {
+            continue;
+        };
+        if id.is_empty() || !id.bytes().all(|b| b.is_ascii_digit()) {
+            continue;
+        }
+        let topology = entry.path().join("topology");
+        let (Some(package), Some(core)) = (
+            read(topology.join("physical_package_id")),
+            read(topology.join("core_id")),
+        ) else {
+            continue;
+        };
+        logical += 1;
+        cores.insert((package.clone(), core));
+        packages.insert(package);
+    }
+    if logical == 0 {
+        return None;
+    }
+    Some(Topology {
+        sockets: packages.len(),
+        cores: cores.len(),
+        threads_per_core: logical / cores.len(),
+    })
+}
+
+/// The caches of CPU 0, innermost first.
+pub fn detect_caches() -> Vec<CacheInfo> {
+    let Ok(entries) = fs::read_dir("/sys/devices/system/cpu/cpu0/cache") else {
+        return Vec::new();
+    };
+    let mut caches: Vec<CacheInfo> = entries
+        .flatten()
+        .filter(|entry| entry.file_name().to_str().is_some_and(|name| name.starts_with("index")))
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let shared = read(dir.join("shared_cpu_list"))
+                .and_then(|list| crate::affinity::parse_cpu_list(&list).ok())
+                .map_or(1, |cpus| cpus.len());
+            Some(CacheInfo {
+                level: read(dir.join("level"))?.parse().ok()?,
+                kind: read(dir.join("type"))?,
+                size: parse_size(&read(dir.join("size"))?)?,
+                line_size: read(dir.join("coherency_line_size"))?.parse().ok()?,
+                shared_by: shared,
+            })
+        })
+        .collect();
+    caches.sort_by(|a, b| (a.level, &a.kind).cmp(&(b.level, &b.kind)));
+    caches
+}
+
+/// The cpufreq governor of CPU 0, e.g. `performance` or `powersave`.
+pub fn detect_governor() -> Option<String> {
+    read("/sys/devices/system/cpu/cpu0/cpufreq/scaling_governor")
+}
+
+/// Whether turbo/boost clocks are enabled, from intel_pstate or the generic
+/// cpufreq boost switch.
+pub fn detect_turbo() -> Option<bool> {
+    if let Some(no_turbo) = read("/sys/devices/system/cpu/intel_pstate/no_turbo") {
+        return Some(no_turbo == "0");
+    }
+    read("/sys/devices/system/cpu/cpufreq/boost").map(|boost| boost == "1")
+}
+
+/// The running kernel release, e.g. `6.8.0-45-generic`.
+pub fn detect_kernel() -> Option<String> {
+    read("/proc/sys/kernel/osrelease")
+}
+
+/// Memory size, page size, huge page mode and NUMA node count.
+pub fn detect_memory() -> MemoryInfo {
+    #[cfg(target_os = "linux")]
+    let page_size = usize::try_from(unsafe { libc::sysconf(libc::_SC_PAGESIZE) }).ok();
+    #[cfg(not(target_os = "linux"))]
+    let page_size = None;
+    let numa_nodes = fs::read_dir("/sys/devices/system/node").map_or(0, |entries| {
+        entries
+            .flatten()
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .and_then(|name| name.strip_prefix("node"))
+                    .is_some_and(|id| id.parse::<usize>().is_ok())
+            })
+            .count()
+    });
+    MemoryInfo {
+        total: read("/proc/meminfo").and_then(|info| mem_total(&info)),
+        page_size,
+        transparent_hugepages: read("/sys/kernel/mm/transparent_hugepage/enabled")
+            .and_then(|choices| selected(&choices).map(str::to_string)),
+        numa_nodes,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("32K"), Some(32 << 10));
+        assert_eq!(parse_size("8M\n"), Some(8 << 20));
+        assert_eq!(parse_size("64"), Some(64));
+        assert_eq!(parse_size("1T"), None);
+    }
+
+    #[test]
+    fn test_sysfs_text() {
+        assert_eq!(selected("always [madvise] never"), Some("madvise"));
+        assert_eq!(selected("always madvise never"), None);
+        let meminfo = "MemTotal:       16318720 kB\nMemFree:         1234 kB\n";
+        assert_eq!(mem_total(meminfo), Some(16318720 * 1024));
+    }
+}
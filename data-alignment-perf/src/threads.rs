@@ -0,0 +1,169 @@
+//! The read/write kernel on several threads at once, to show where scaling
+//! stops: per-thread throughput holds until the threads together saturate
+//! a shared cache or memory bandwidth, then drops as more are added.
+//!
+//! With disjoint regions every thread runs the full kernel over a buffer of
+//! its own, at the same offset. With a shared region all threads read one
+//! buffer, written once beforehand: concurrent writes to the same elements
+//! would be a data race, so the shared mode measures the read phase only.
+
+use std::sync::Barrier;
+use std::thread;
+use std::time::Instant;
+
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::affinity::{cpu_for, pin_current_thread};
+use crate::kernel::{
+    read_pass, read_pass_ordered, write_pass, write_read_pass, write_read_pass_ordered, BenchElement, UnalignedBuffer,
+};
+use crate::report::OffsetResult;
+use crate::stats::Stats;
+use crate::{summarize, Config};
+
+/// How the threads' memory is laid out.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Regions {
+    /// One buffer per thread.
+    #[default]
+    Disjoint,
+    /// One buffer read by all threads (read phase only).
+    Shared,
+}
+
+/// Per-thread and aggregate throughput of a threaded offset.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThreadSummary {
+    pub count: usize,
+    pub regions: Regions,
+    /// Bytes moved by all threads per second at the median wall time, in
+    /// GB/s (10^9 bytes).
+    pub aggregate_gb_per_s: f64,
+    /// Each thread's own rate at its median time.
+    pub per_thread_gb_per_s: Vec<f64>,
+    /// Each thread's own times, in milliseconds.
+    pub per_thread: Vec<Stats>,
+}
+
+fn millis(start: Instant) -> f64 {
+    start.elapsed().as_nanos() as f64 / 1_000_000.0
+}
+
+/// Measures one offset on `config.threads` threads. The samples of the
+/// result are wall times per iteration, from releasing the threads until
+/// the last one finishes, always taken with `Instant`; the per-thread times
+/// in `threads` use `config.clock`.
+pub fn measure_threaded<T: BenchElement + Send + Sync>(config: &Config, offset: usize) -> OffsetResult {
+    let threads = config.threads;
+    let iterations = config.warmup + config.repeat;
+    let order = config.pattern.order(config.n);
+    let shared = (config.regions == Regions::Shared).then(|| {
+        let mut buffer = UnalignedBuffer::<T>::new_in(config.n, offset, config.allocator);
+        write_read_pass(&mut buffer);
+        buffer
+    });
+    let (start, end) = (Barrier::new(threads + 1), Barrier::new(threads + 1));
+
+    let (wall, per_thread) = thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads)
+            .map(|index| {
+                let (shared, order, start, end) = (&shared, &order, &start, &end);
+                scope.spawn(move || {
+                    if let Some(cpu) = cpu_for(&config.pin, index) {
+                        // Best effort: the command line checks the CPUs up front.
+                        let _ = pin_current_thread(&[cpu]);
+                    }
+                    // Fault the pages in outside the timer, as `measure` does.
+                    let mut own = shared.is_none().then(|| {
+                        let mut buffer = UnalignedBuffer::<T>::new_in(config.n, offset, config.allocator);
+                        write_pass(&mut buffer);
+                        buffer
+                    });
+                    let mut times = Vec::with_capacity(iterations);
+                    for _ in 0..iterations {
+                        start.wait();
+                        let nanos = config.clock.time(|| {
+                            match (&mut own, shared, order.as_deref()) {
+                                (Some(buffer), _, Some(order)) => write_read_pass_ordered(buffer, order),
+                                (Some(buffer), _, None) => write_read_pass(buffer),
+                                (None, Some(buffer), Some(order)) => read_pass_ordered(buffer, order),
+                                (None, Some(buffer), None) => read_pass(buffer),
+                                (None, None, _) => unreachable!("no buffer"),
+                            };
+                        });
+                        times.push(nanos / 1_000_000.0);
+                        end.wait();
+                    }
+                    times
+                })
+            })
+            .collect();
+        let mut wall = Vec::with_capacity(iterations);
+        for _ in 0..iterations {
+            start.wait();
+            let started = Instant::now();
+            end.wait();
+            wall.push(millis(started));
+        }
+        let per_thread: Vec<Vec<f64>> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        (wall, per_thread)
+    });
+
+    let passes = if shared.is_some() { 1 } else { 2 };
+    let bytes = (passes * config.n * size_of::<T>()) as f64;
+    let samples = wall[config.warmup..].to_vec();
+    let stats = summarize(&samples, config);
+    let per_thread: Vec<Stats> = per_thread
+        .iter()
+        .map(|times| summarize(&times[config.warmup..], config))
+        .collect();
+    OffsetResult {
+        offset,
+        aligned: offset.is_multiple_of(align_of::<T>()),
+        threads: Some(ThreadSummary {
+            count: threads,
+            regions: config.regions,
+            aggregate_gb_per_s: threads as f64 * bytes / (stats.median / 1000.0) / 1e9,
+            per_thread_gb_per_s: per_thread.iter().map(|s| bytes / (s.median / 1000.0) / 1e9).collect(),
+            per_thread,
+        }),
+        stats,
+        samples,
+        counters: None,
+        phases: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pattern::Pattern;
+
+    #[test]
+    fn test_measure_threaded() {
+        let cases = [Regions::Disjoint, Regions::Shared].into_iter().flat_map(|regions| {
+            [Pattern::Sequential, Pattern::Random].map(|pattern| (regions, pattern))
+        });
+        for (regions, pattern) in cases {
+            let config = Config {
+                n: 1000,
+                repeat: 3,
+                warmup: 1,
+                threads: 3,
+                regions,
+                pattern,
+                ..Config::default()
+            };
+            let result = measure_threaded::<i64>(&config, 1);
+            assert!(!result.aligned);
+            assert_eq!(result.samples.len(), 3);
+            let summary = result.threads.unwrap();
+            assert_eq!((summary.count, summary.regions), (3, regions));
+            assert_eq!(summary.per_thread.len(), 3);
+            assert!(summary.per_thread.iter().all(|s| s.count == 3));
+            assert!(summary.aggregate_gb_per_s > 0.0);
+        }
+    }
+}
@@ -0,0 +1,305 @@
+//! A live dashboard for long offset runs (the `tui` feature): progress per
+//! type and overall, a rolling sparkline of the latest samples, the medians
+//! of the current type by offset, and the best and worst offset of each
+//! type so far.
+//!
+//! [`Dashboard`] is only state and drawing, so it can be rendered to any
+//! ratatui backend; [`Tui`] puts it on the terminal.
+
+use std::collections::VecDeque;
+use std::io;
+use std::time::{Duration, Instant};
+
+use ratatui::buffer::Buffer;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Style, Stylize};
+use ratatui::widgets::{Block, Gauge, Row, Sparkline, Table, Widget};
+use ratatui::DefaultTerminal;
+
+use crate::report::OffsetResult;
+
+/// Samples kept for the rolling sparkline.
+pub const RECENT: usize = 200;
+
+/// Least time between two redraws.
+const FRAME: Duration = Duration::from_millis(50);
+
+struct Task {
+    name: String,
+    total: usize,
+    /// (offset, median) of every finished offset.
+    medians: Vec<(usize, f64)>,
+}
+
+/// What the dashboard shows.
+pub struct Dashboard {
+    title: String,
+    tasks: Vec<Task>,
+    /// Index into `tasks` and offset being measured.
+    current: Option<(usize, usize)>,
+    recent: VecDeque<f64>,
+    started: Instant,
+}
+
+impl Dashboard {
+    /// A dashboard for `tasks`: a name (the element type) and how many
+    /// offsets it has.
+    pub fn new(title: impl Into<String>, tasks: impl IntoIterator<Item = (String, usize)>) -> Self {
+        Self {
+            title: title.into(),
+            tasks: tasks
+                .into_iter()
+                .map(|(name, total)| Task {
+                    name,
+                    total,
+                    medians: Vec::new(),
+                })
+                .collect(),
+            current: None,
+            recent: VecDeque::with_capacity(RECENT),
+            started: Instant::now(),
+        }
+    }
+
+    /// Marks `offset` of task `name` as the one being measured.
+    pub fn begin(&mut self, name: &str, offset: usize) {
+        self.current = self.tasks.iter().position(|task| task.name == name).map(|task| (task, offset));
+    }
+
+    /// Adds a sample (milliseconds) of the current offset.
+    pub fn sample(&mut self, ms: f64) {
+        if self.recent.len() == RECENT {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(ms);
+    }
+
+    /// Records the result of the current offset.
+    pub fn finish(&mut self, result: &OffsetResult) {
+        if let Some((task, _)) = self.current {
+            self.tasks[task].medians.push((result.offset, result.stats.median));
+        }
+    }
+
+    /// Offsets finished and in total.
+    pub fn progress(&self) -> (usize, usize) {
+        self.tasks
+            .iter()
+            .fold((0, 0), |(done, total), task| (done + task.medians.len(), total + task.total))
+    }
+
+    /// The (offset, median) of the fastest and the slowest offset of task
+    /// `name` so far.
+    pub fn best_worst(&self, name: &str) -> Option<((usize, f64), (usize, f64))> {
+        let task = self.tasks.iter().find(|task| task.name == name)?;
+        let by_median = |a: &&(usize, f64), b: &&(usize, f64)| a.1.total_cmp(&b.1);
+        let best = task.medians.iter().min_by(by_median)?;
+        let worst = task.medians.iter().max_by(by_median)?;
+        Some((*best, *worst))
+    }
+
+    fn header(&self) -> String {
+        let (done, total) = self.progress();
+        let elapsed = self.started.elapsed();
+        let eta = if done > 0 {
+            format_duration(elapsed.mul_f64((total - done) as f64 / done as f64))
+        } else {
+            "?".to_string()
+        };
+        let current = match self.current {
+            Some((task, offset)) => format!(", now {} offset {}", self.tasks[task].name, offset),
+            None => String::new(),
+        };
+        format!(
+            " {}: {}/{} offsets, {} ({} left){}; q to quit ",
+            self.title,
+            done,
+            total,
+            format_duration(elapsed),
+            eta,
+            current
+        )
+    }
+}
+
+impl Widget for &Dashboard {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::bordered().title(self.header().bold());
+        let inner = block.inner(area);
+        block.render(area, buf);
+        let [overall, table, recent, medians] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(self.tasks.len() as u16 + 3),
+            Constraint::Min(4),
+            Constraint::Min(4),
+        ])
+        .areas(inner);
+
+        let (done, total) = self.progress();
+        Gauge::default()
+            .gauge_style(Style::new().fg(Color::Green))
+            .ratio(if total > 0 { done as f64 / total as f64 } else { 0.0 })
+            .render(overall, buf);
+
+        let rows = self.tasks.iter().map(|task| {
+            let (best, worst, slowdown) = match self.best_worst(&task.name) {
+                Some(((best, fast), (worst, slow))) => (
+                    format!("{} ({:.3}ms)", best, fast),
+                    format!("{} ({:.3}ms)", worst, slow),
+                    format!("{:.2}x", slow / fast),
+                ),
+                None => Default::default(),
+            };
+            Row::new([task.name.clone(), format!("{}/{}", task.medians.len(), task.total), best, worst, slowdown])
+        });
+        Table::new(
+            rows,
+            [
+                Constraint::Length(6),
+                Constraint::Length(7),
+                Constraint::Length(20),
+                Constraint::Length(20),
+                Constraint::Length(9),
+            ],
+        )
+        .header(Row::new(["type", "done", "best offset", "worst offset", "slowdown"]).bold())
+        .block(Block::bordered().title(" per type "))
+        .render(table, buf);
+
+        let (min, max) = self
+            .recent
+            .iter()
+            .fold((f64::INFINITY, 0.0f64), |(min, max), &ms| (min.min(ms), max.max(ms)));
+        let title = if self.recent.is_empty() {
+            " latest samples ".to_string()
+        } else {
+            format!(" latest {} samples, {:.3} to {:.3}ms ", self.recent.len(), min, max)
+        };
+        Sparkline::default()
+            .block(Block::bordered().title(title))
+            .style(Style::new().fg(Color::Cyan))
+            .data(self.recent.iter().map(|&ms| micros(ms)))
+            .render(recent, buf);
+
+        let (title, data) = match self.current {
+            Some((task, _)) => {
+                let task = &self.tasks[task];
+                (
+                    format!(" median by offset, {} ", task.name),
+                    task.medians.iter().map(|&(_, ms)| micros(ms)).collect(),
+                )
+            }
+            None => (" median by offset ".to_string(), Vec::new()),
+        };
+        Sparkline::default()
+            .block(Block::bordered().title(title))
+            .style(Style::new().fg(Color::Yellow))
+            .data(data)
+            .render(medians, buf);
+    }
+}
+
+// Sparklines plot integers; microseconds keep enough of the shape.
+fn micros(ms: f64) -> u64 {
+    (ms * 1000.0).round() as u64
+}
+
+fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs();
+    if seconds >= 60 {
+        format!("{}m{:02}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}s", seconds)
+    }
+}
+
+/// The dashboard on the terminal's alternate screen, restored on drop.
+pub struct Tui {
+    terminal: DefaultTerminal,
+    drawn: Option<Instant>,
+}
+
+impl Tui {
+    pub fn start() -> io::Result<Self> {
+        Ok(Self {
+            terminal: ratatui::try_init()?,
+            drawn: None,
+        })
+    }
+
+    /// Redraws `dashboard`, at most every 50 ms unless `force`; returns
+    /// whether q, Esc or Ctrl-C was pressed since the last call.
+    pub fn draw(&mut self, dashboard: &Dashboard, force: bool) -> io::Result<bool> {
+        let mut quit = false;
+        while event::poll(Duration::ZERO)? {
+            if let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                quit |= matches!(key.code, KeyCode::Char('q') | KeyCode::Esc)
+                    || (key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL));
+            }
+        }
+        if force || self.drawn.is_none_or(|drawn| drawn.elapsed() >= FRAME) {
+            self.terminal.draw(|frame| frame.render_widget(dashboard, frame.area()))?;
+            self.drawn = Some(Instant::now());
+        }
+        Ok(quit)
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        ratatui::restore();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stats::Stats;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+
+    fn finished(offset: usize, median: f64) -> OffsetResult {
+        OffsetResult {
+            offset,
+            aligned: offset == 0,
+            stats: Stats::from_samples(&[median], 0.0),
+            samples: vec![median],
+            threads: None,
+            counters: None,
+            phases: None,
+        }
+    }
+
+    #[test]
+    fn test_dashboard() {
+        let mut dashboard = Dashboard::new("offsets", [("u32".to_string(), 4), ("i64".to_string(), 8)]);
+        for (offset, median) in [(0, 1.0), (1, 1.5), (2, 1.2)] {
+            dashboard.begin("u32", offset);
+            dashboard.sample(median);
+            dashboard.finish(&finished(offset, median));
+        }
+        assert_eq!(dashboard.progress(), (3, 12));
+        assert_eq!(dashboard.best_worst("u32"), Some(((0, 1.0), (1, 1.5))));
+        assert_eq!(dashboard.best_worst("i64"), None);
+
+        let mut terminal = Terminal::new(TestBackend::new(90, 24)).unwrap();
+        terminal.draw(|frame| frame.render_widget(&dashboard, frame.area())).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("3/12 offsets"), "{screen}");
+        assert!(screen.contains("1 (1.500ms)"), "{screen}");
+        assert!(screen.contains("1.50x"), "{screen}");
+    }
+
+    #[test]
+    fn test_recent_samples_roll() {
+        let mut dashboard = Dashboard::new("offsets", [("u8".to_string(), 1)]);
+        for i in 0..RECENT + 10 {
+            dashboard.sample(i as f64);
+        }
+        assert_eq!(dashboard.recent.len(), RECENT);
+        assert_eq!(dashboard.recent[0], 10.0);
+    }
+}
@@ -0,0 +1,26 @@
+// Simulator throughput on the busy beaver champions, in steps per second.
+// Run with `cargo bench --bench busy_beaver`.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use parantheses_rs::beaver::{CHAMPIONS, Limits, parse_machine, run_beaver};
+
+const REPEAT: usize = 2000;
+const LIMITS: Limits = Limits {
+    max_steps: 1_000_000,
+    max_tape: 1_000_000,
+};
+
+fn main() {
+    for &(name, text, steps, _) in CHAMPIONS {
+        let machine = parse_machine(text).unwrap();
+        let start = Instant::now();
+        for _ in 0..REPEAT {
+            black_box(run_beaver(name, black_box(&machine), LIMITS));
+        }
+        let elapsed = start.elapsed().as_secs_f64();
+        let rate = (steps * REPEAT) as f64 / elapsed / 1e6;
+        println!("{name:<12} {steps:>4} steps {rate:>8.2} Msteps/s");
+    }
+}
@@ -0,0 +1,41 @@
+// Char-loop vs bit-parallel fast path (and the parallel reduction) on a large, mostly-text input.
+// Run with `cargo bench --bench fast_path`; the parallel reduction needs the
+// default `parallel` feature.
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use parantheses_rs::check_parentheses;
+use parantheses_rs::fast::{check_pairs_independently, check_parentheses_fast};
+
+const SIZE: usize = 64 * 1024 * 1024;
+const REPEAT: usize = 5;
+
+fn time(name: &str, input: &str, check: impl Fn(&str) -> bool) {
+    let mut best = f64::MAX;
+    for _ in 0..REPEAT {
+        let start = Instant::now();
+        black_box(check(black_box(input)));
+        best = best.min(start.elapsed().as_secs_f64());
+    }
+    let gbps = input.len() as f64 / best / 1e9;
+    println!("{name:<28} {:>8.3}ms {gbps:>8.2} GB/s", best * 1000.0);
+}
+
+fn main() {
+    let unit = "fn f(a: [u8; 4]) { g(h(a), (1 + 2) * 3) }\n";
+    let input = unit.repeat(SIZE / unit.len());
+    println!("Input: {} MiB", input.len() / (1024 * 1024));
+
+    time("check_parentheses", &input, check_parentheses);
+    time("check_parentheses_fast", &input, check_parentheses_fast);
+    time(
+        "check_pairs_independently",
+        &input,
+        check_pairs_independently,
+    );
+    #[cfg(feature = "parallel")]
+    time("check_parallel", &input, |input| {
+        parantheses_rs::parallel::check_parallel(input.as_bytes())
+    });
+}
@@ -0,0 +1,46 @@
+// Streams a generated ndjson file through the checker and the JSON validator
+// and reports throughput.
+// Run with `cargo bench --bench large_file`; set LARGE_FILE_MIB to resize.
+
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::Instant;
+
+use parantheses_rs::json::{JsonMode, validate_reader};
+use parantheses_rs::stream::check_reader;
+
+fn main() -> std::io::Result<()> {
+    let mib: usize = std::env::var("LARGE_FILE_MIB")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(512);
+    let path = std::env::temp_dir().join("parantheses-rs-large-file.ndjson");
+
+    let line = r#"{"id": 1, "tags": ["a", "b"], "nested": {"xs": [[1, 2], [3, 4]]}}"#;
+    let mut writer = BufWriter::new(File::create(&path)?);
+    for _ in 0..mib * 1024 * 1024 / (line.len() + 1) {
+        writeln!(writer, "{line}")?;
+    }
+    writer.flush()?;
+    drop(writer);
+
+    let report = check_reader(File::open(&path)?)?;
+    println!(
+        "{} MiB: balanced={} in {:.3}s ({:.2} GB/s)",
+        report.bytes / (1024 * 1024),
+        report.balanced,
+        report.elapsed.as_secs_f64(),
+        report.throughput_gbps()
+    );
+
+    let start = Instant::now();
+    let result = validate_reader(File::open(&path)?, JsonMode::Lines)?;
+    let elapsed = start.elapsed().as_secs_f64();
+    println!(
+        "validate_reader: valid={} in {elapsed:.3}s ({:.2} GB/s)",
+        result.is_ok(),
+        report.bytes as f64 / elapsed / 1e9
+    );
+
+    std::fs::remove_file(&path)
+}
@@ -0,0 +1,173 @@
+// Busy beaver candidates on the `tm` simulator: 2-symbol machines written in
+// the standard text format, one `_`-separated group per state, each group the
+// (write, move, next) triple for reading 0 and then 1:
+//
+//   1RB1LB_1LA1RZ      A: 0 → 1RB, 1 → 1LB    B: 0 → 1LA, 1 → 1RZ
+//
+// A next state past the last group (conventionally Z or H) halts, and "---"
+// leaves a transition undefined, which halts too. The score is the number of
+// 1s left on the tape.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::tm::{Move, TuringMachine};
+
+/// Known champions: (name, machine, steps, ones). For three states no single
+/// machine holds both records.
+pub const CHAMPIONS: &[(&str, &str, usize, usize)] = &[
+    ("bb2", "1RB1LB_1LA1RZ", 6, 4),
+    ("bb3 (ones)", "1RB1RZ_0RC1RB_1LC1LA", 14, 6),
+    ("bb3 (steps)", "1RB1RZ_1LB0RC_1LC1LA", 21, 5),
+    ("bb4", "1RB1LB_1LA0LC_1RZ1LD_1RD0RA", 107, 13),
+];
+
+/// A malformed machine description; `group` is the 0-based state index,
+/// or 26 if there are more states than letters to name them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub group: usize,
+}
+
+/// Parses the standard text format into a machine with blank `0` that
+/// starts in state `A`.
+pub fn parse_machine(text: &str) -> Result<TuringMachine, ParseError> {
+    let groups: Vec<&str> = text.split('_').collect();
+    if groups.len() > 26 {
+        return Err(ParseError { group: 26 });
+    }
+    let mut tm = TuringMachine::new("A").blank('0');
+    for (group, rules) in groups.iter().enumerate() {
+        let chars: Vec<char> = rules.chars().collect();
+        if chars.len() != 6 {
+            return Err(ParseError { group });
+        }
+        let from = state_name(group);
+        for (read, rule) in ['0', '1'].into_iter().zip(chars.chunks(3)) {
+            if rule == ['-', '-', '-'] {
+                continue;
+            }
+            let movement = match rule[1] {
+                'L' => Move::Left,
+                'R' => Move::Right,
+                _ => return Err(ParseError { group }),
+            };
+            let (write, next) = (rule[0], rule[2]);
+            if !matches!(write, '0' | '1') || !next.is_ascii_uppercase() {
+                return Err(ParseError { group });
+            }
+            let to = next.to_string();
+            if (next as u8 - b'A') as usize >= groups.len() && !tm.accepting.contains(&to) {
+                tm = tm.accept(&to);
+            }
+            tm = tm.transition(&from, read, write, movement, &to);
+        }
+    }
+    Ok(tm)
+}
+
+fn state_name(index: usize) -> String {
+    char::from(b'A' + index as u8).to_string()
+}
+
+/// Resource limits for one run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    pub max_steps: usize,
+    /// Maximum number of visited tape cells.
+    pub max_tape: usize,
+}
+
+/// Why a run stopped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Halted,
+    StepLimit,
+    TapeLimit,
+}
+
+/// Outcome and score of one candidate.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BeaverReport {
+    pub name: String,
+    pub status: Status,
+    pub steps: usize,
+    /// 1s on the tape when the run stopped.
+    pub ones: usize,
+    pub tape_len: usize,
+}
+
+/// Runs `machine` from a blank tape within `limits`.
+pub fn run_beaver(name: &str, machine: &TuringMachine, limits: Limits) -> BeaverReport {
+    let mut config = machine.initial("");
+    let status = loop {
+        if !machine.step(&mut config) {
+            break Status::Halted;
+        }
+        if config.tape.len() > limits.max_tape {
+            break Status::TapeLimit;
+        }
+        if config.steps >= limits.max_steps && machine.applicable(&config).is_some() {
+            break Status::StepLimit;
+        }
+    };
+    BeaverReport {
+        name: name.to_string(),
+        status,
+        steps: config.steps,
+        ones: config.tape.count('1'),
+        tape_len: config.tape.len(),
+    }
+}
+
+/// Parses and runs every `(name, machine)` candidate, in order.
+pub fn run_candidates(
+    candidates: &[(&str, &str)],
+    limits: Limits,
+) -> Vec<Result<BeaverReport, ParseError>> {
+    candidates
+        .iter()
+        .map(|&(name, text)| parse_machine(text).map(|tm| run_beaver(name, &tm, limits)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMITS: Limits = Limits {
+        max_steps: 10_000,
+        max_tape: 1_000,
+    };
+
+    #[test]
+    fn test_champions() {
+        for &(name, text, steps, ones) in CHAMPIONS {
+            let report = run_beaver(name, &parse_machine(text).unwrap(), LIMITS);
+            assert_eq!(report.status, Status::Halted, "{name}");
+            assert_eq!((report.steps, report.ones), (steps, ones), "{name}");
+        }
+    }
+
+    #[test]
+    fn test_limits() {
+        let reports = run_candidates(
+            &[("runaway", "1RA---"), ("pingpong", "0RB---_0LA---")],
+            LIMITS,
+        );
+        assert_eq!(reports[0].as_ref().unwrap().status, Status::TapeLimit);
+        let pingpong = reports[1].as_ref().unwrap();
+        assert_eq!(pingpong.status, Status::StepLimit);
+        assert_eq!(pingpong.steps, LIMITS.max_steps);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse_machine("1RB1LB_1LA").unwrap_err().group, 1);
+        assert_eq!(parse_machine("1XB---").unwrap_err().group, 0);
+        assert_eq!(parse_machine("2RB---").unwrap_err().group, 0);
+        let states = ["------"; 27].join("_");
+        assert_eq!(parse_machine(&states).unwrap_err().group, 26);
+        assert!(parse_machine(&states[7..]).is_ok());
+    }
+}
@@ -0,0 +1,222 @@
+// Nesting complexity metric for C-like source files.
+//
+// Every top-level `{ ... }` block is treated as a unit (usually a function,
+// struct or impl). For each unit the checker's depth tracking records the
+// deepest nesting and the average nesting depth of its code characters,
+// with comments and strings skipped so they don't inflate the numbers.
+
+use std::io;
+use std::path::Path;
+
+use crate::skip::code_chars;
+use crate::{closer_for, opener_for};
+
+/// Nesting metrics for one top-level block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnitComplexity {
+    /// Best-effort name: the identifier before the header's `(`, or the whole
+    /// header (e.g. `struct Foo`) when there is none.
+    pub name: String,
+    /// 1-based line of the block's opening `{`.
+    pub line: usize,
+    pub max_depth: usize,
+    pub average_depth: f64,
+}
+
+/// Nesting metrics for a whole file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComplexityReport {
+    pub units: Vec<UnitComplexity>,
+    pub max_depth: usize,
+    pub average_depth: f64,
+}
+
+impl ComplexityReport {
+    /// Units nested deeper than `limit`, for failing a CI gate.
+    pub fn exceeding(&self, limit: usize) -> impl Iterator<Item = &UnitComplexity> {
+        self.units.iter().filter(move |u| u.max_depth > limit)
+    }
+}
+
+/// Reads the file at `path` and computes its [`ComplexityReport`].
+pub fn complexity_report(path: impl AsRef<Path>) -> io::Result<ComplexityReport> {
+    let source = std::fs::read_to_string(path)?;
+    Ok(complexity_of(&source))
+}
+
+// Accumulates one unit while its top-level block is open.
+struct OpenUnit {
+    name: String,
+    line: usize,
+    max_depth: usize,
+    depth_sum: usize,
+    chars: usize,
+}
+
+/// Computes the [`ComplexityReport`] of C-like `source`.
+/// Example: "void f() { if (x) { g(); } }" → one unit `f` with max depth 3.
+pub fn complexity_of(source: &str) -> ComplexityReport {
+    let mut units = Vec::new();
+    let mut current: Option<OpenUnit> = None;
+    let mut depth = 0usize;
+    // Line of `source[..counted]`, counted over the raw source so newlines
+    // in comments count too.
+    let (mut line, mut counted) = (1, 0);
+    // Code characters since the last top-level `;` or `}`, which name the
+    // next unit, and where the last one ended.
+    let mut header = String::new();
+    let mut header_end = 0;
+    let mut total_sum = 0;
+    let mut total_chars = 0;
+    let mut max_depth = 0;
+
+    for (pos, c) in code_chars(source) {
+        if depth == 0 {
+            // A skipped comment or string separates what is around it.
+            if pos > header_end {
+                header.push(' ');
+            }
+            header.push(c);
+            header_end = pos + c.len_utf8();
+        }
+
+        if closer_for(c).is_some() {
+            if depth == 0 && c == '{' {
+                line += source[counted..pos].matches('\n').count();
+                counted = pos;
+                current = Some(OpenUnit {
+                    name: unit_name(&header[..header.len() - 1]),
+                    line,
+                    max_depth: 0,
+                    depth_sum: 0,
+                    chars: 0,
+                });
+            }
+            depth += 1;
+            max_depth = max_depth.max(depth);
+        }
+
+        if !c.is_whitespace() {
+            total_sum += depth;
+            total_chars += 1;
+            if let Some(unit) = current.as_mut() {
+                unit.max_depth = unit.max_depth.max(depth);
+                unit.depth_sum += depth;
+                unit.chars += 1;
+            }
+        }
+
+        if opener_for(c).is_some() {
+            depth = depth.saturating_sub(1);
+            if depth == 0 && c == '}' {
+                header.clear();
+                if let Some(unit) = current.take() {
+                    units.push(finish(unit));
+                }
+            }
+        } else if depth == 0 && c == ';' {
+            header.clear();
+        }
+    }
+
+    // An unterminated final block still counts.
+    if let Some(unit) = current.take() {
+        units.push(finish(unit));
+    }
+
+    ComplexityReport {
+        units,
+        max_depth,
+        average_depth: average(total_sum, total_chars),
+    }
+}
+
+fn finish(unit: OpenUnit) -> UnitComplexity {
+    UnitComplexity {
+        name: unit.name,
+        line: unit.line,
+        max_depth: unit.max_depth,
+        average_depth: average(unit.depth_sum, unit.chars),
+    }
+}
+
+fn average(sum: usize, count: usize) -> f64 {
+    if count == 0 {
+        0.0
+    } else {
+        sum as f64 / count as f64
+    }
+}
+
+// "static int main(void)" → "main", "struct Foo" → "struct Foo"
+fn unit_name(header: &str) -> String {
+    let header = header
+        .split('\n')
+        .filter(|l| !l.trim_start().starts_with('#'));
+    let header = header.collect::<Vec<_>>().join(" ");
+    let before_paren = match header.find('(') {
+        Some(p) => header[..p]
+            .rsplit(|c: char| !(c.is_alphanumeric() || c == '_' || c == ':'))
+            .find(|s| !s.is_empty())
+            .unwrap_or(""),
+        None => header.as_str(),
+    };
+    before_paren
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_units_and_depths() {
+        let source =
+            "#include <x.h>\nstruct P { int x; };\n\nint main(void) {\n    if (a[0]) { g(); }\n}\n";
+        let report = complexity_of(source);
+        let names: Vec<&str> = report.units.iter().map(|u| u.name.as_str()).collect();
+        assert_eq!(names, vec!["struct P", "main"]);
+        assert_eq!(report.units[1].line, 4);
+        // main { if ( a [ ...
+        assert_eq!(report.units[1].max_depth, 3);
+        assert_eq!(report.max_depth, 3);
+    }
+
+    #[test]
+    fn test_comments_and_strings_do_not_count() {
+        let report = complexity_of("void f() { puts(\"((((\"); /* { { */ }");
+        assert_eq!(report.units.len(), 1);
+        assert_eq!(report.units[0].max_depth, 2);
+    }
+
+    #[test]
+    fn test_lines_count_comments() {
+        let report = complexity_of("/* Doc\n * more\n */\nint main(void) {\n}\nvoid g() {}\n");
+        let lines: Vec<usize> = report.units.iter().map(|u| u.line).collect();
+        assert_eq!(lines, vec![4, 6]);
+    }
+
+    #[test]
+    fn test_names_skip_comments() {
+        let report = complexity_of("/* see f(x) */\nint main(void) {\n}\nint/**/g() {}\n");
+        let names: Vec<&str> = report.units.iter().map(|u| u.name.as_str()).collect();
+        assert_eq!(names, vec!["main", "g"]);
+    }
+
+    #[test]
+    fn test_average_depth() {
+        // Code chars: '{' at depth 1, 'x' at 1, '}' at 1
+        let report = complexity_of("{x}");
+        assert_eq!(report.units[0].average_depth, 1.0);
+        assert_eq!(complexity_of("").average_depth, 0.0);
+    }
+
+    #[test]
+    fn test_exceeding_limit() {
+        let report = complexity_of("void a() { } void b() { { { } } }");
+        let deep: Vec<&str> = report.exceeding(2).map(|u| u.name.as_str()).collect();
+        assert_eq!(deep, vec!["b"]);
+    }
+}
@@ -0,0 +1,208 @@
+// Step-through debugger for `Pda` machines.
+//
+// Every configuration the machine passes through is recorded, so stepping
+// backwards is just popping the history, and reverse-continue walks it until
+// a breakpoint matches again.
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::pda::{Config, Halt, Pda, Transition};
+
+/// Where [`Debugger::continue_run`] stops before halting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// When the step counter reaches this value.
+    Step(usize),
+    /// When this symbol is the next unread input.
+    Symbol(char),
+    /// When the machine enters this state.
+    State(String),
+    /// When the stack grows to at least this many symbols.
+    StackDepth(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stop {
+    /// Breakpoint with this index matched.
+    Breakpoint(usize),
+    /// The machine halted (or ran out of fuel).
+    Halted(Halt),
+    /// Reverse execution reached the initial configuration.
+    Start,
+}
+
+pub struct Debugger<'a> {
+    pda: &'a Pda,
+    input: Vec<char>,
+    history: Vec<Config>,
+    breakpoints: Vec<Option<Breakpoint>>,
+}
+
+impl<'a> Debugger<'a> {
+    /// Loads `input` into `pda`, paused before the first step.
+    pub fn new(pda: &'a Pda, input: &str) -> Self {
+        Debugger {
+            pda,
+            input: input.chars().collect(),
+            history: vec![pda.initial()],
+            breakpoints: Vec::new(),
+        }
+    }
+
+    /// Adds a breakpoint and returns its index.
+    pub fn add_breakpoint(&mut self, breakpoint: Breakpoint) -> usize {
+        self.breakpoints.push(Some(breakpoint));
+        self.breakpoints.len() - 1
+    }
+
+    /// Removes a breakpoint; other indices stay valid.
+    pub fn remove_breakpoint(&mut self, index: usize) {
+        if let Some(slot) = self.breakpoints.get_mut(index) {
+            *slot = None;
+        }
+    }
+
+    /// The current configuration.
+    pub fn config(&self) -> &Config {
+        self.history.last().unwrap()
+    }
+
+    /// The current stack, bottom first.
+    pub fn stack(&self) -> &[char] {
+        &self.config().stack
+    }
+
+    /// Steps taken so far.
+    pub fn steps(&self) -> usize {
+        self.history.len() - 1
+    }
+
+    /// Every configuration so far, initial one first.
+    pub fn history(&self) -> &[Config] {
+        &self.history
+    }
+
+    /// The transition the next step would take.
+    pub fn next_transition(&self) -> Option<&Transition> {
+        self.pda.applicable(&self.input, self.config())
+    }
+
+    /// `Some` once no transition applies.
+    pub fn halted(&self) -> Option<Halt> {
+        if self.next_transition().is_some() {
+            None
+        } else if self.pda.accepts(&self.input, self.config()) {
+            Some(Halt::Accepted)
+        } else {
+            Some(Halt::Rejected)
+        }
+    }
+
+    /// Takes one step. Returns false if the machine had already halted.
+    pub fn step(&mut self) -> bool {
+        match self.pda.step(&self.input, self.config()) {
+            Some(next) => {
+                self.history.push(next);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Undoes one step. Returns false at the initial configuration.
+    pub fn step_back(&mut self) -> bool {
+        if self.history.len() > 1 {
+            self.history.pop();
+            true
+        } else {
+            false
+        }
+    }
+
+    // Index of the first breakpoint matching the current configuration.
+    fn hit(&self) -> Option<usize> {
+        let config = self.config();
+        self.breakpoints.iter().position(|bp| match bp {
+            Some(Breakpoint::Step(n)) => self.steps() == *n,
+            Some(Breakpoint::Symbol(c)) => self.input.get(config.pos) == Some(c),
+            Some(Breakpoint::State(s)) => config.state == *s,
+            Some(Breakpoint::StackDepth(d)) => config.stack.len() >= *d,
+            None => false,
+        })
+    }
+
+    /// Runs until a breakpoint matches after a step, the machine halts, or
+    /// `fuel` steps have been taken.
+    pub fn continue_run(&mut self, fuel: usize) -> Stop {
+        for _ in 0..fuel {
+            if !self.step() {
+                return Stop::Halted(self.halted().unwrap());
+            }
+            if let Some(index) = self.hit() {
+                return Stop::Breakpoint(index);
+            }
+        }
+        Stop::Halted(Halt::OutOfFuel)
+    }
+
+    /// Steps backwards until a breakpoint matches or the start is reached.
+    pub fn reverse_continue(&mut self) -> Stop {
+        while self.step_back() {
+            if let Some(index) = self.hit() {
+                return Stop::Breakpoint(index);
+            }
+        }
+        Stop::Start
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_single_step_and_inspect() {
+        let pda = Pda::balanced_parentheses();
+        let mut debugger = Debugger::new(&pda, "(())");
+        assert_eq!(debugger.next_transition().unwrap().push, vec!['(']);
+        assert!(debugger.step());
+        assert!(debugger.step());
+        assert_eq!(debugger.stack(), &['$', '(', '(']);
+        assert_eq!(debugger.steps(), 2);
+        assert_eq!(debugger.config().pos, 2);
+    }
+
+    #[test]
+    fn test_breakpoints() {
+        let pda = Pda::balanced_parentheses();
+        let mut debugger = Debugger::new(&pda, "(()())");
+        let depth = debugger.add_breakpoint(Breakpoint::StackDepth(3));
+        assert_eq!(debugger.continue_run(100), Stop::Breakpoint(depth));
+        assert_eq!(debugger.steps(), 2);
+
+        debugger.remove_breakpoint(depth);
+        let accept = debugger.add_breakpoint(Breakpoint::State("accept".into()));
+        assert_eq!(debugger.continue_run(100), Stop::Breakpoint(accept));
+        assert_eq!(debugger.continue_run(100), Stop::Halted(Halt::Accepted));
+    }
+
+    #[test]
+    fn test_reverse_execution() {
+        let pda = Pda::balanced_parentheses();
+        let mut debugger = Debugger::new(&pda, "(()");
+        assert_eq!(debugger.continue_run(100), Stop::Halted(Halt::Rejected));
+        let steps = debugger.steps();
+
+        let symbol = debugger.add_breakpoint(Breakpoint::Symbol(')'));
+        assert_eq!(debugger.reverse_continue(), Stop::Breakpoint(symbol));
+        assert_eq!(debugger.config().pos, 2);
+        assert!(debugger.steps() < steps);
+
+        debugger.remove_breakpoint(symbol);
+        assert_eq!(debugger.reverse_continue(), Stop::Start);
+        assert_eq!(debugger.config(), &pda.initial());
+        assert!(!debugger.step_back());
+    }
+}
@@ -0,0 +1,311 @@
+// Structural diff of two bracket trees (see `tree`): which pairs were added,
+// removed or moved between two versions of a text. Edits that only touch the
+// text between delimiters are not structural and are not reported.
+//
+// Pairs are matched in two passes, GumTree style:
+// 1. Identical subtrees: pairs whose contents (whitespace-normalised) are
+//    equal, largest first, together with everything inside them.
+// 2. Top-down: below two matched pairs, the remaining child pairs are
+//    aligned in order by delimiter type (so `f(a, [1])` → `f(a, [1, 2])`
+//    matches both pairs).
+// A matched pair whose parent is not matched to the new parent, or that was
+// reordered among its siblings, has moved. Unmatched pairs were removed
+// (old tree) or added (new tree).
+
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::tree::{Node, NodeKind};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StructuralChange {
+    /// A pair only in the new version; `span` is a byte range of the new input.
+    Added { open: char, span: Range<usize> },
+    /// A pair only in the old version; `span` is a byte range of the old input.
+    Removed { open: char, span: Range<usize> },
+    /// A pair present in both, under a different parent or sibling order.
+    Moved {
+        open: char,
+        from: Range<usize>,
+        to: Range<usize>,
+    },
+}
+
+// A pair flattened out of the tree; index 0 is the root.
+struct Flat {
+    open: char,
+    span: Range<usize>,
+    parent: usize,
+    children: Vec<usize>,
+    key: String,
+}
+
+fn flatten(root: &Node, input: &str) -> Vec<Flat> {
+    let mut flat = vec![Flat {
+        open: '\0',
+        span: root.span.clone(),
+        parent: 0,
+        children: Vec::new(),
+        key: String::new(),
+    }];
+    let mut pending: Vec<(&Node, usize)> = root.children.iter().rev().map(|n| (n, 0)).collect();
+    while let Some((node, parent)) = pending.pop() {
+        let NodeKind::Pair { open, .. } = node.kind else {
+            continue;
+        };
+        let index = flat.len();
+        let key = node
+            .text(input)
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ");
+        flat.push(Flat {
+            open,
+            span: node.span.clone(),
+            parent,
+            children: Vec::new(),
+            key,
+        });
+        flat[parent].children.push(index);
+        pending.extend(node.children.iter().rev().map(|n| (n, index)));
+    }
+    flat
+}
+
+// Matches two identical subtrees pair by pair.
+fn match_subtree(
+    a: &[Flat],
+    b: &[Flat],
+    i: usize,
+    j: usize,
+    to_b: &mut [Option<usize>],
+    to_a: &mut [Option<usize>],
+) {
+    to_b[i] = Some(j);
+    to_a[j] = Some(i);
+    for (&ci, &cj) in a[i].children.iter().zip(&b[j].children) {
+        match_subtree(a, b, ci, cj, to_b, to_a);
+    }
+}
+
+// Longest common subsequence of `xs` and `ys`, as the kept elements of `xs`.
+fn lcs_mask<T: PartialEq>(xs: &[T], ys: &[T]) -> Vec<bool> {
+    let (n, m) = (xs.len(), ys.len());
+    let mut table = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            table[i][j] = if xs[i] == ys[j] {
+                table[i + 1][j + 1] + 1
+            } else {
+                table[i + 1][j].max(table[i][j + 1])
+            };
+        }
+    }
+    let mut kept = vec![false; n];
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if xs[i] == ys[j] {
+            kept[i] = true;
+            i += 1;
+            j += 1;
+        } else if table[i + 1][j] >= table[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    kept
+}
+
+/// Compares the tree of `old_input` with the tree of `new_input`.
+/// Example: `f(a)[b]` → `[f(a) b]` reports only `(` as moved, into `[`.
+pub fn diff(old: &Node, old_input: &str, new: &Node, new_input: &str) -> Vec<StructuralChange> {
+    let a = flatten(old, old_input);
+    let b = flatten(new, new_input);
+    let mut to_b: Vec<Option<usize>> = vec![None; a.len()];
+    let mut to_a: Vec<Option<usize>> = vec![None; b.len()];
+    to_b[0] = Some(0);
+    to_a[0] = Some(0);
+
+    // Pass 1: identical subtrees, largest first, in document order.
+    let mut by_key: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    for (j, pair) in b.iter().enumerate().skip(1) {
+        by_key.entry(&pair.key).or_default().push(j);
+    }
+    let mut order: Vec<usize> = (1..a.len()).collect();
+    order.sort_by_key(|&i| core::cmp::Reverse(a[i].key.len()));
+    for i in order {
+        if to_b[i].is_some() {
+            continue;
+        }
+        let candidates = by_key.get(a[i].key.as_str());
+        if let Some(&j) = candidates.and_then(|c| c.iter().find(|&&j| to_a[j].is_none())) {
+            match_subtree(&a, &b, i, j, &mut to_b, &mut to_a);
+        }
+    }
+
+    // Pass 2: top-down alignment of the leftover children of matched pairs.
+    let mut queue = vec![0];
+    while let Some(i) = queue.pop() {
+        let j = to_b[i].unwrap();
+        let left: Vec<usize> = a[i]
+            .children
+            .iter()
+            .copied()
+            .filter(|&c| to_b[c].is_none())
+            .collect();
+        let right: Vec<usize> = b[j]
+            .children
+            .iter()
+            .copied()
+            .filter(|&c| to_a[c].is_none())
+            .collect();
+        let left_opens: Vec<char> = left.iter().map(|&c| a[c].open).collect();
+        let right_opens: Vec<char> = right.iter().map(|&c| b[c].open).collect();
+        let kept_left = lcs_mask(&left_opens, &right_opens);
+        let kept_right = lcs_mask(&right_opens, &left_opens);
+        let lefts = left
+            .iter()
+            .zip(kept_left)
+            .filter(|(_, k)| *k)
+            .map(|(&c, _)| c);
+        let rights = right
+            .iter()
+            .zip(kept_right)
+            .filter(|(_, k)| *k)
+            .map(|(&c, _)| c);
+        for (ci, cj) in lefts.zip(rights) {
+            to_b[ci] = Some(cj);
+            to_a[cj] = Some(ci);
+        }
+        queue.extend(a[i].children.iter().copied().filter(|&c| to_b[c].is_some()));
+    }
+
+    let mut changes = Vec::new();
+    for (i, pair) in a.iter().enumerate().skip(1) {
+        if to_b[i].is_none() {
+            changes.push(StructuralChange::Removed {
+                open: pair.open,
+                span: pair.span.clone(),
+            });
+        }
+    }
+    for (j, pair) in b.iter().enumerate().skip(1) {
+        if to_a[j].is_none() {
+            changes.push(StructuralChange::Added {
+                open: pair.open,
+                span: pair.span.clone(),
+            });
+        }
+    }
+
+    // Moves: a new parent, or out of order among siblings that stayed put.
+    let mut reordered = vec![false; a.len()];
+    for (i, pair) in a.iter().enumerate() {
+        let Some(j) = to_b[i] else { continue };
+        let stayed: Vec<usize> = pair
+            .children
+            .iter()
+            .copied()
+            .filter(|&c| to_b[c].is_some_and(|cj| b[cj].parent == j))
+            .collect();
+        let old_order: Vec<usize> = stayed.iter().map(|&c| to_b[c].unwrap()).collect();
+        let new_order: Vec<usize> = b[j]
+            .children
+            .iter()
+            .copied()
+            .filter(|c| old_order.contains(c))
+            .collect();
+        for (&c, kept) in stayed.iter().zip(lcs_mask(&old_order, &new_order)) {
+            reordered[c] = !kept;
+        }
+    }
+    for (i, pair) in a.iter().enumerate().skip(1) {
+        let Some(j) = to_b[i] else { continue };
+        if to_b[pair.parent] != Some(b[j].parent) || reordered[i] {
+            changes.push(StructuralChange::Moved {
+                open: pair.open,
+                from: pair.span.clone(),
+                to: b[j].span.clone(),
+            });
+        }
+    }
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree::parse_tree;
+
+    fn changes(old: &str, new: &str) -> Vec<StructuralChange> {
+        diff(
+            &parse_tree(old).unwrap(),
+            old,
+            &parse_tree(new).unwrap(),
+            new,
+        )
+    }
+
+    #[test]
+    fn test_text_edits_are_not_structural() {
+        assert_eq!(changes("f(a, [1])", "f(a, [1, 2])"), vec![]);
+        assert_eq!(changes("{ g(x) }", "{\n    g( x )\n}"), vec![]);
+    }
+
+    #[test]
+    fn test_added_and_removed() {
+        assert_eq!(
+            changes("f(a)", "f(a, [b])"),
+            vec![StructuralChange::Added {
+                open: '[',
+                span: 5..8
+            }]
+        );
+        assert_eq!(
+            changes("{ x[0] }", "{ x }"),
+            vec![StructuralChange::Removed {
+                open: '[',
+                span: 3..6
+            }]
+        );
+    }
+
+    #[test]
+    fn test_moved_into_another_pair() {
+        let found = changes("f(a) { }", "{ f(a) }");
+        assert_eq!(
+            found,
+            vec![StructuralChange::Moved {
+                open: '(',
+                from: 1..4,
+                to: 3..6
+            }]
+        );
+        // Out of a removed pair, up to the top level.
+        assert_eq!(
+            changes("[ (x) ]", "(x)"),
+            vec![
+                StructuralChange::Removed {
+                    open: '[',
+                    span: 0..7
+                },
+                StructuralChange::Moved {
+                    open: '(',
+                    from: 2..5,
+                    to: 0..3
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn test_reordered_siblings() {
+        let found = changes("{ a(1) b[2] }", "{ b[2] a(1) }");
+        assert_eq!(found.len(), 1);
+        assert!(matches!(found[0], StructuralChange::Moved { .. }));
+    }
+}
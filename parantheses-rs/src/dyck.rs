@@ -0,0 +1,211 @@
+// The Dyck language with k pair types: the well-nested words over k opening
+// and k closing symbols. `check_parentheses` decides Dyck-1 and
+// `check_parentheses_and_brackets` decides Dyck-2 (ignoring other characters);
+// this module handles any k and also counts and enumerates the words.
+//
+// Counting: a Dyck word of length 2m has the shape of one of the Catalan(m)
+// well-nested parenthesisations, and each of its m pairs independently picks
+// one of k types, so |Dyck-k words of length 2m| = Catalan(m) * k^m.
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// A Dyck language over the given `(open, close)` pairs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Dyck {
+    pairs: Vec<(char, char)>,
+}
+
+impl Dyck {
+    /// Creates Dyck-k for `pairs.len() == k`. Every symbol must be distinct.
+    pub fn new(pairs: &[(char, char)]) -> Self {
+        Dyck {
+            pairs: pairs.to_vec(),
+        }
+    }
+
+    /// Number of pair types.
+    pub fn k(&self) -> usize {
+        self.pairs.len()
+    }
+
+    fn open_index(&self, c: char) -> Option<usize> {
+        self.pairs.iter().position(|&(o, _)| o == c)
+    }
+
+    fn close_index(&self, c: char) -> Option<usize> {
+        self.pairs.iter().position(|&(_, cl)| cl == c)
+    }
+
+    // Shared membership loop; `strict` rejects characters outside the alphabet.
+    fn accepts(&self, input: &str, strict: bool) -> bool {
+        let mut stack: Vec<usize> = Vec::new();
+        for c in input.chars() {
+            if let Some(i) = self.open_index(c) {
+                stack.push(i);
+            } else if let Some(i) = self.close_index(c) {
+                if stack.pop() != Some(i) {
+                    return false;
+                }
+            } else if strict {
+                return false;
+            }
+        }
+        stack.is_empty()
+    }
+
+    /// True if `word` is a Dyck word: only alphabet symbols, well nested.
+    /// Example: Dyck-2 over () and [] contains "([])[]" but not "([)]" or "(a)"
+    pub fn contains(&self, word: &str) -> bool {
+        self.accepts(word, true)
+    }
+
+    /// Like [`contains`](Self::contains), but ignores characters outside the
+    /// alphabet, as the crate's checkers do.
+    pub fn check(&self, input: &str) -> bool {
+        self.accepts(input, false)
+    }
+
+    /// Number of Dyck words of length `n`, or `None` on u128 overflow.
+    /// Example: Dyck-2 has count(4) = Catalan(2) * 2^2 = 8
+    pub fn count(&self, n: usize) -> Option<u128> {
+        if !n.is_multiple_of(2) {
+            return Some(0);
+        }
+        let m = (n / 2) as u128;
+        catalan(m)?.checked_mul((self.k() as u128).checked_pow(m.try_into().ok()?)?)
+    }
+
+    /// Enumerates the Dyck words of length `n`. At each position the openers
+    /// (in pair order) come before the closer, so "(())" precedes "()()".
+    pub fn words(&self, n: usize) -> Words<'_> {
+        let pending = if n.is_multiple_of(2) && (n == 0 || self.k() > 0) {
+            vec![Partial {
+                word: String::new(),
+                stack: Vec::new(),
+            }]
+        } else {
+            Vec::new()
+        };
+        Words {
+            dyck: self,
+            len: n,
+            pending,
+        }
+    }
+}
+
+/// Catalan(m), or `None` on overflow.
+pub fn catalan(m: u128) -> Option<u128> {
+    // C(i+1) = C(i) * 2(2i+1) / (i+2), exact at every step.
+    let mut c: u128 = 1;
+    for i in 0..m {
+        c = c.checked_mul(2 * (2 * i + 1))? / (i + 2);
+    }
+    Some(c)
+}
+
+// A prefix of a Dyck word and the pair types it still has open.
+struct Partial {
+    word: String,
+    stack: Vec<usize>,
+}
+
+/// Iterator returned by [`Dyck::words`]; a depth-first search over prefixes.
+pub struct Words<'a> {
+    dyck: &'a Dyck,
+    len: usize,
+    pending: Vec<Partial>,
+}
+
+impl Iterator for Words<'_> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        while let Some(partial) = self.pending.pop() {
+            let length = partial.word.chars().count();
+            if length == self.len {
+                return Some(partial.word);
+            }
+            let remaining = self.len - length;
+
+            // Pushed in reverse so the first choice is explored first:
+            // openers in pair order, then the closer for the top of stack.
+            if let Some(&top) = partial.stack.last() {
+                let mut word = partial.word.clone();
+                word.push(self.dyck.pairs[top].1);
+                let mut stack = partial.stack.clone();
+                stack.pop();
+                self.pending.push(Partial { word, stack });
+            }
+            if partial.stack.len() + 1 < remaining {
+                for (i, &(open, _)) in self.dyck.pairs.iter().enumerate().rev() {
+                    let mut word = partial.word.clone();
+                    word.push(open);
+                    let mut stack = partial.stack.clone();
+                    stack.push(i);
+                    self.pending.push(Partial { word, stack });
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_parentheses_and_brackets;
+
+    fn dyck2() -> Dyck {
+        Dyck::new(&[('(', ')'), ('[', ']')])
+    }
+
+    #[test]
+    fn test_membership() {
+        let d = dyck2();
+        assert!(d.contains("([])[]"));
+        assert!(d.contains(""));
+        assert!(!d.contains("([)]"));
+        assert!(!d.contains("(a)"));
+        assert!(d.check("(a)"));
+    }
+
+    #[test]
+    fn test_matches_two_pair_checker() {
+        let d = dyck2();
+        for input in ["(())[]", "([]]", "a(b)[c]", "([)]", "[(])", "(()"] {
+            assert_eq!(
+                d.check(input),
+                check_parentheses_and_brackets(input),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_counts() {
+        assert_eq!(catalan(5), Some(42));
+        let d = dyck2();
+        assert_eq!(d.count(3), Some(0));
+        assert_eq!(d.count(4), Some(8));
+        assert_eq!(
+            Dyck::new(&[('a', 'b'), ('c', 'd'), ('e', 'f')]).count(6),
+            Some(135)
+        );
+        assert_eq!(d.count(1000), None);
+    }
+
+    #[test]
+    fn test_enumeration_matches_count() {
+        let d = dyck2();
+        let words: Vec<String> = d.words(4).collect();
+        assert_eq!(words[..3], ["(())", "([])", "()()"]);
+        for n in 0..=8 {
+            let words: Vec<String> = d.words(n).collect();
+            assert_eq!(words.len() as u128, d.count(n).unwrap(), "n = {n}");
+            assert!(words.iter().all(|w| d.contains(w)));
+        }
+    }
+}
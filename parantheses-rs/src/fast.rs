@@ -0,0 +1,207 @@
+// Bit-parallel balance checking for large inputs.
+//
+// Each chunk is classified at once into +1 (opener), -1 (closer) and 0, and
+// reduced to a summary: its net sum and its minimum prefix sum. The input is
+// balanced iff the running depth plus every chunk's minimum prefix stays
+// non-negative and the final depth is zero, so chunk summaries replace the
+// byte-at-a-time loop.
+//
+// On x86_64 the summary is computed in an SSE2 register (SSE2 is baseline
+// there): a log-step prefix sum over 16 lanes followed by a horizontal min.
+// Other targets count bytes on u64 words (SWAR) and only scan chunks that
+// contain a closer.
+
+use crate::PAIRS;
+
+const CHUNK: usize = 16;
+
+/// Checks that `open`/`close` bytes are balanced, like
+/// [`check_parentheses`](crate::check_parentheses) but much faster on big inputs.
+/// Example: `check_pair_fast(b"(a(b))", b'(', b')')` → true
+pub fn check_pair_fast(input: &[u8], open: u8, close: u8) -> bool {
+    let mut depth = 0isize;
+    let mut chunks = input.chunks_exact(CHUNK);
+
+    for chunk in &mut chunks {
+        let (net, min_prefix) = summarize(chunk, open, close);
+        if depth + min_prefix < 0 {
+            return false;
+        }
+        depth += net;
+    }
+
+    scan(chunks.remainder(), depth, open, close) == Some(0)
+}
+
+/// Checks `()` with the fast path.
+pub fn check_parentheses_fast(input: &str) -> bool {
+    check_pair_fast(input.as_bytes(), b'(', b')')
+}
+
+/// Checks every pair in [`PAIRS`] independently with the fast path.
+///
+/// This is a necessary but not sufficient condition for proper nesting:
+/// "([)]" passes because each pair is balanced on its own. Use it as a cheap
+/// rejection filter before a full stack check.
+pub fn check_pairs_independently(input: &str) -> bool {
+    PAIRS
+        .iter()
+        .all(|&(open, close)| check_pair_fast(input.as_bytes(), open as u8, close as u8))
+}
+
+// Byte-at-a-time prefix sum; None if the depth goes negative.
+fn scan(bytes: &[u8], mut depth: isize, open: u8, close: u8) -> Option<isize> {
+    for &b in bytes {
+        if b == open {
+            depth += 1;
+        } else if b == close {
+            depth -= 1;
+            if depth < 0 {
+                return None;
+            }
+        }
+    }
+    Some(depth)
+}
+
+// (net sum, min(0, min prefix sum)) of one CHUNK-byte chunk.
+#[cfg(target_arch = "x86_64")]
+fn summarize(chunk: &[u8], open: u8, close: u8) -> (isize, isize) {
+    use core::arch::x86_64::*;
+
+    // SAFETY: SSE2 is part of the x86_64 baseline, and the 16-byte load reads
+    // exactly `chunk` (CHUNK == 16).
+    unsafe {
+        let v = _mm_loadu_si128(chunk.as_ptr() as *const __m128i);
+        let opens = _mm_cmpeq_epi8(v, _mm_set1_epi8(open as i8));
+        let closes = _mm_cmpeq_epi8(v, _mm_set1_epi8(close as i8));
+        if _mm_movemask_epi8(_mm_or_si128(opens, closes)) == 0 {
+            return (0, 0);
+        }
+
+        // Equal bytes are -1, so closes - opens is +1/-1/0 per lane.
+        let mut sum = _mm_sub_epi8(closes, opens);
+        sum = _mm_add_epi8(sum, _mm_slli_si128::<1>(sum));
+        sum = _mm_add_epi8(sum, _mm_slli_si128::<2>(sum));
+        sum = _mm_add_epi8(sum, _mm_slli_si128::<4>(sum));
+        sum = _mm_add_epi8(sum, _mm_slli_si128::<8>(sum));
+        let net = (_mm_extract_epi16::<7>(sum) >> 8) as i8;
+
+        // Bias to unsigned for min_epu8. The shifts pull zeros into the top
+        // lanes, but lane 0 only ever combines with unshifted lanes.
+        let mut min = _mm_xor_si128(sum, _mm_set1_epi8(i8::MIN));
+        min = _mm_min_epu8(min, _mm_srli_si128::<8>(min));
+        min = _mm_min_epu8(min, _mm_srli_si128::<4>(min));
+        min = _mm_min_epu8(min, _mm_srli_si128::<2>(min));
+        min = _mm_min_epu8(min, _mm_srli_si128::<1>(min));
+        let min = (_mm_cvtsi128_si32(min) as u8 ^ 0x80) as i8;
+
+        (net as isize, (min as isize).min(0))
+    }
+}
+
+#[cfg(not(target_arch = "x86_64"))]
+fn summarize(chunk: &[u8], open: u8, close: u8) -> (isize, isize) {
+    let mut opens = 0;
+    let mut closes = 0;
+    for word in chunk.chunks_exact(8) {
+        let word = u64::from_ne_bytes(word.try_into().unwrap());
+        opens += count_byte(word, open);
+        closes += count_byte(word, close);
+    }
+    if closes == 0 {
+        return (opens, 0);
+    }
+
+    let (mut depth, mut min) = (0isize, 0isize);
+    for &b in chunk {
+        if b == open {
+            depth += 1;
+        } else if b == close {
+            depth -= 1;
+            min = min.min(depth);
+        }
+    }
+    (depth, min)
+}
+
+// Number of bytes of `word` equal to `byte`, without per-byte branches.
+#[cfg(not(target_arch = "x86_64"))]
+fn count_byte(word: u64, byte: u8) -> isize {
+    const LOW7: u64 = 0x7f7f_7f7f_7f7f_7f7f;
+    let v = word ^ (u64::from(byte) * 0x0101_0101_0101_0101);
+    // High bit set exactly in the bytes of `v` that are zero.
+    let zero = !(((v & LOW7) + LOW7) | v | LOW7);
+    zero.count_ones() as isize
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_parentheses;
+
+    // Deterministic strings over "()x" that cover every chunk path.
+    fn samples() -> Vec<String> {
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        (0..300)
+            .map(|i| {
+                let len = i * 3 % 400;
+                (0..len)
+                    .map(|_| {
+                        state ^= state << 13;
+                        state ^= state >> 7;
+                        state ^= state << 17;
+                        ['(', ')', 'x', '('][(state % 4) as usize]
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_matches_char_loop() {
+        for s in samples() {
+            assert_eq!(check_parentheses_fast(&s), check_parentheses(&s), "{s}");
+        }
+    }
+
+    #[test]
+    fn test_completed_samples_are_balanced() {
+        for s in samples() {
+            // Drop closers that would underflow, then close what is left open.
+            let mut depth = 0;
+            let mut balanced: String = s
+                .chars()
+                .filter(|&c| match c {
+                    '(' => {
+                        depth += 1;
+                        true
+                    }
+                    ')' if depth == 0 => false,
+                    ')' => {
+                        depth -= 1;
+                        true
+                    }
+                    _ => true,
+                })
+                .collect();
+            balanced.push_str(&")".repeat(depth));
+            assert!(check_parentheses_fast(&balanced), "{balanced}");
+        }
+    }
+
+    #[test]
+    fn test_long_balanced_and_underflow() {
+        let deep = "(".repeat(1000) + &")".repeat(1000);
+        assert!(check_parentheses_fast(&deep));
+        let underflow = "x".repeat(100) + ")" + &"(".repeat(200);
+        assert!(!check_parentheses_fast(&underflow));
+    }
+
+    #[test]
+    fn test_independent_pairs_is_only_necessary() {
+        assert!(check_pairs_independently("([)]"));
+        assert!(!check_pairs_independently("([]"));
+        assert!(check_pairs_independently(&"{[()]}".repeat(50)));
+    }
+}
@@ -0,0 +1,294 @@
+// HTML/XML tag matching: the same single-stack check, with `<name>` and
+// `</name>` as the open/close symbols instead of '(' and ')'.
+//
+// `check_tags("<p><b>hi</p>", TagMode::Html)`:
+//   [ ]
+// Read <p>  → push "p"        [ p ]
+// Read <b>  → push "b"        [ p , b ]
+// Read </p> → "p" is deeper in the stack, so "b" is reported unclosed
+//                             [ ]
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// How tag names and childless elements are treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagMode {
+    /// Case-insensitive names, void elements (`<br>`, `<img>`...) and raw-text
+    /// `<script>`/`<style>` bodies.
+    Html,
+    /// Case-sensitive names; only `<x/>` is childless.
+    Xml,
+}
+
+/// Elements that never take a closing tag in HTML.
+pub const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+// Elements whose content is not markup, so '<' inside them is not a tag.
+const RAW_TEXT_ELEMENTS: &[&str] = &["script", "style"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagKind {
+    Open,
+    Close,
+    /// `<x/>`, or an HTML void element.
+    SelfClosing,
+}
+
+/// A tag found by [`tags`]. `span` covers `<` through `>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tag {
+    pub kind: TagKind,
+    pub name: String,
+    pub span: Range<usize>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TagErrorKind {
+    /// An open tag never closed: still open at the end of input, or skipped
+    /// over by the close tag of an outer element.
+    Unclosed,
+    /// A close tag that matches nothing on the stack.
+    UnexpectedClose,
+    /// A close tag that matches nothing on the stack while `expected` is open.
+    Mismatched {
+        expected: String,
+        expected_pos: usize,
+    },
+    /// A `<` that never reaches a `>`.
+    Unterminated,
+}
+
+/// A tag problem at byte offset `pos` (the tag's `<`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TagError {
+    pub kind: TagErrorKind,
+    pub name: String,
+    pub pos: usize,
+}
+
+/// Tokenizes the tags in `input`, skipping comments, doctypes, processing
+/// instructions, CDATA and (in HTML mode) raw-text element bodies.
+/// Stops at the first unterminated tag, whose position is returned as `Err`.
+pub fn tags(input: &str, mode: TagMode) -> Result<Vec<Tag>, usize> {
+    let bytes = input.as_bytes();
+    let mut found = Vec::new();
+    let mut pos = 0;
+
+    while let Some(offset) = input[pos..].find('<') {
+        let start = pos + offset;
+        let rest = &input[start..];
+
+        // Markup declarations are skipped wholesale.
+        let skip_to = if rest.starts_with("<!--") {
+            Some("-->")
+        } else if rest.starts_with("<![CDATA[") {
+            Some("]]>")
+        } else if rest.starts_with("<?") {
+            Some("?>")
+        } else if rest.starts_with("<!") {
+            Some(">")
+        } else {
+            None
+        };
+        if let Some(terminator) = skip_to {
+            match rest.find(terminator) {
+                Some(end) => pos = start + end + terminator.len(),
+                None => return Err(start),
+            }
+            continue;
+        }
+
+        let closing = bytes.get(start + 1) == Some(&b'/');
+        let name_start = start + 1 + closing as usize;
+        let name_len = input[name_start..]
+            .find(|c: char| !(c.is_alphanumeric() || matches!(c, '-' | '_' | ':' | '.')))
+            .unwrap_or(input.len() - name_start);
+        if name_len == 0 {
+            // A bare '<' (e.g. "a < b") is text, not a tag.
+            pos = start + 1;
+            continue;
+        }
+
+        // Find the closing '>', ignoring any inside quoted attribute values.
+        let mut quote = None;
+        let mut end = None;
+        for (i, c) in input[name_start + name_len..].char_indices() {
+            match (quote, c) {
+                (Some(q), c) if c == q => quote = None,
+                (Some(_), _) => {}
+                (None, '"' | '\'') => quote = Some(c),
+                (None, '>') => {
+                    end = Some(name_start + name_len + i + 1);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let Some(end) = end else {
+            return Err(start);
+        };
+
+        let mut name = input[name_start..name_start + name_len].to_string();
+        if mode == TagMode::Html {
+            name.make_ascii_lowercase();
+        }
+        let kind = if closing {
+            TagKind::Close
+        } else if bytes[end - 2] == b'/'
+            || (mode == TagMode::Html && VOID_ELEMENTS.contains(&name.as_str()))
+        {
+            TagKind::SelfClosing
+        } else {
+            TagKind::Open
+        };
+
+        pos = end;
+        if mode == TagMode::Html
+            && kind == TagKind::Open
+            && RAW_TEXT_ELEMENTS.contains(&name.as_str())
+        {
+            // Jump straight to the matching close tag; its body is not markup.
+            let close = format!("</{name}");
+            if let Some(offset) = input[end..].to_ascii_lowercase().find(&close) {
+                pos = end + offset;
+            }
+        }
+
+        found.push(Tag {
+            kind,
+            name,
+            span: start..end,
+        });
+    }
+
+    Ok(found)
+}
+
+/// Checks that every open tag has a matching close tag, in order.
+/// Returns every problem found, not just the first.
+/// Example: `<ul><li>a</li></ul>` → Ok, `<b><i></b></i>` → Err (i unclosed, then
+/// an unexpected `</i>`)
+pub fn check_tags(input: &str, mode: TagMode) -> Result<(), Vec<TagError>> {
+    let mut errors = Vec::new();
+    let found = match tags(input, mode) {
+        Ok(found) => found,
+        Err(pos) => {
+            errors.push(TagError {
+                kind: TagErrorKind::Unterminated,
+                name: String::new(),
+                pos,
+            });
+            return Err(errors);
+        }
+    };
+
+    let mut stack: Vec<Tag> = Vec::new();
+    for tag in found {
+        match tag.kind {
+            TagKind::Open => stack.push(tag),
+            TagKind::SelfClosing => {}
+            TagKind::Close => match stack.iter().rposition(|open| open.name == tag.name) {
+                Some(index) => {
+                    // Everything opened after the match was never closed.
+                    for open in stack.drain(index + 1..) {
+                        errors.push(TagError {
+                            kind: TagErrorKind::Unclosed,
+                            name: open.name,
+                            pos: open.span.start,
+                        });
+                    }
+                    stack.pop();
+                }
+                None => errors.push(TagError {
+                    kind: match stack.last() {
+                        Some(open) => TagErrorKind::Mismatched {
+                            expected: open.name.clone(),
+                            expected_pos: open.span.start,
+                        },
+                        None => TagErrorKind::UnexpectedClose,
+                    },
+                    name: tag.name,
+                    pos: tag.span.start,
+                }),
+            },
+        }
+    }
+
+    for open in stack {
+        errors.push(TagError {
+            kind: TagErrorKind::Unclosed,
+            name: open.name,
+            pos: open.span.start,
+        });
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balanced_html() {
+        let input = r#"<!DOCTYPE html><html><body class="a>b"><p>Hi<br>there<img src="x"/></p>
+            <!-- <div> --><script>if (a < b && "</div>") {}</script></BODY></html>"#;
+        assert_eq!(check_tags(input, TagMode::Html), Ok(()));
+    }
+
+    #[test]
+    fn test_unclosed_inner_tag() {
+        let errors = check_tags("<p><b>hi</p>", TagMode::Html).unwrap_err();
+        assert_eq!(
+            errors,
+            vec![TagError {
+                kind: TagErrorKind::Unclosed,
+                name: "b".to_string(),
+                pos: 3
+            }]
+        );
+    }
+
+    #[test]
+    fn test_mismatched_close() {
+        let errors = check_tags("<a></b></a>", TagMode::Xml).unwrap_err();
+        assert_eq!(
+            errors[0].kind,
+            TagErrorKind::Mismatched {
+                expected: "a".to_string(),
+                expected_pos: 0
+            }
+        );
+        assert_eq!(errors[0].pos, 3);
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn test_xml_is_case_sensitive_and_has_no_void_elements() {
+        assert!(check_tags("<br>", TagMode::Xml).is_err());
+        assert!(check_tags("<A></a>", TagMode::Xml).is_err());
+        assert_eq!(check_tags("<br/><A></A>", TagMode::Xml), Ok(()));
+    }
+
+    #[test]
+    fn test_unterminated_tag() {
+        let errors = check_tags("<p>a</p", TagMode::Html).unwrap_err();
+        assert_eq!(errors[0].kind, TagErrorKind::Unterminated);
+        assert_eq!(errors[0].pos, 4);
+    }
+
+    #[test]
+    fn test_bare_less_than_is_text() {
+        assert_eq!(check_tags("<p>1 < 2</p>", TagMode::Html), Ok(()));
+    }
+}
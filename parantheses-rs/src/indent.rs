@@ -0,0 +1,166 @@
+// Off-side rule (Python-style) indentation checking.
+//
+// Indentation is the same single-stack language in disguise: a deeper indent
+// is a virtual '(' that pushes its width, and a shallower one is a run of
+// virtual ')' that pop until the width matches an open level.
+//
+// `check_indentation("a\n  b\n    c\n d")`:
+//   [ 0 ]
+// Line 2: width 2 > 0 → push       [ 0 , 2 ]
+// Line 3: width 4 > 2 → push       [ 0 , 2 , 4 ]
+// Line 4: width 1 → pop 4, pop 2   [ 0 ]   (1 ≠ 0 → dedent matches no level)
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::skip::hash;
+use crate::{closer_for, opener_for};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IndentErrorKind {
+    /// A dedent to `found` columns, which is none of the `open_levels`.
+    UnmatchedDedent {
+        found: usize,
+        open_levels: Vec<usize>,
+    },
+    /// Leading whitespace mixing tabs and spaces, so its width is ambiguous.
+    MixedTabsAndSpaces,
+}
+
+/// An indentation problem on the 1-based `line`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndentError {
+    pub line: usize,
+    pub kind: IndentErrorKind,
+}
+
+/// Checks that every dedent returns to a previously opened indentation level.
+/// Blank and comment-only lines are ignored, as are continuation lines inside an open
+/// `(`, `[` or `{` (Python's implicit line joining) or a string. Brackets in
+/// `#` comments and string literals do not count.
+/// Returns the deepest block nesting on success.
+/// Example: "if a:\n    b\nc" → Ok(1), "if a:\n    b\n  c" → Err on line 3
+pub fn check_indentation(input: &str) -> Result<usize, IndentError> {
+    let mut levels: Vec<usize> = vec![0];
+    let mut max_depth = 0;
+    let mut bracket_depth = 0usize;
+    // Next byte to scan; past the start of a line inside a multi-line string.
+    let mut pos = 0;
+    let mut line_start = 0;
+
+    for (index, raw) in input.split_inclusive('\n').enumerate() {
+        let line_no = index + 1;
+        let line = raw.strip_suffix('\n').unwrap_or(raw);
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        let continuation = bracket_depth > 0 || pos > line_start;
+        line_start += raw.len();
+
+        while pos < line_start {
+            if let Some(len) = hash(input, pos).filter(|&len| len > 0) {
+                pos += len;
+                continue;
+            }
+            let c = input[pos..].chars().next().unwrap();
+            if closer_for(c).is_some() {
+                bracket_depth += 1;
+            } else if opener_for(c).is_some() {
+                bracket_depth = bracket_depth.saturating_sub(1);
+            }
+            pos += c.len_utf8();
+        }
+
+        // A comment-only line, like a blank one, opens or closes nothing.
+        let content = line.trim_start_matches([' ', '\t']);
+        if continuation || content.is_empty() || content.starts_with('#') {
+            continue;
+        }
+
+        let leading = &line[..line.len() - content.len()];
+        if leading.contains(' ') && leading.contains('\t') {
+            return Err(IndentError {
+                line: line_no,
+                kind: IndentErrorKind::MixedTabsAndSpaces,
+            });
+        }
+        let width = leading.len();
+
+        let top = *levels.last().unwrap();
+        if width > top {
+            // Virtual open symbol
+            levels.push(width);
+            max_depth = max_depth.max(levels.len() - 1);
+        } else if width < top {
+            // Virtual close symbols, one per level left
+            let open_levels = levels.clone();
+            while *levels.last().unwrap() > width {
+                levels.pop();
+            }
+            if *levels.last().unwrap() != width {
+                return Err(IndentError {
+                    line: line_no,
+                    kind: IndentErrorKind::UnmatchedDedent {
+                        found: width,
+                        open_levels,
+                    },
+                });
+            }
+        }
+    }
+
+    Ok(max_depth)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_consistent_blocks() {
+        let input = "def f(x):\n    if x:\n        return 1\n\n    return 2\nprint(f(1))\n";
+        assert_eq!(check_indentation(input), Ok(2));
+        assert_eq!(check_indentation(""), Ok(0));
+    }
+
+    #[test]
+    fn test_unmatched_dedent() {
+        let input = "a\n    b\n        c\n  d";
+        assert_eq!(
+            check_indentation(input),
+            Err(IndentError {
+                line: 4,
+                kind: IndentErrorKind::UnmatchedDedent {
+                    found: 2,
+                    open_levels: vec![0, 4, 8]
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_continuation_lines_are_ignored() {
+        let input = "x = foo(1,\n          2,\n   3)\ny = 4";
+        assert_eq!(check_indentation(input), Ok(0));
+    }
+
+    #[test]
+    fn test_brackets_in_strings_and_comments() {
+        let input = "print(\"(\")  # [\nif a:\n    b\n  c";
+        assert_eq!(check_indentation(input).unwrap_err().line, 4);
+        // A string running over lines is one continuation.
+        let input = "x = '''(\n   y\n'''\nz";
+        assert_eq!(check_indentation(input), Ok(0));
+    }
+
+    #[test]
+    fn test_comment_lines_are_ignored() {
+        let input = "def f():\n    x = 1\n  # note\n    y = 2\n";
+        assert_eq!(check_indentation(input), Ok(1));
+    }
+
+    #[test]
+    fn test_mixed_tabs_and_spaces() {
+        let err = check_indentation("a:\n\t b").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert_eq!(err.kind, IndentErrorKind::MixedTabsAndSpaces);
+    }
+}
@@ -0,0 +1,472 @@
+// Structural JSON validation without building a DOM: `{}`/`[]` nesting on
+// the usual single stack, plus just enough of the grammar around it (strings
+// and escapes, literals, numbers, `:` and `,` placement) to reject anything
+// `serde_json` would. Input is fed in chunks of any size, so huge files and
+// ndjson streams run in constant memory apart from the nesting stack.
+//
+// `validate_json(br#"{"a": [1, 2,]}"#, JsonMode::Single)`:
+// Read {     → push {            [ { ]
+// Read "a":  → key, colon        [ { ]
+// Read [     → push [            [ { , [ ]
+// Read 1, 2, → values            [ { , [ ]
+// Read ]     → right after ',': TrailingComma at byte 12
+//
+// String contents are not checked for valid UTF-8.
+
+use alloc::vec::Vec;
+
+/// What the input holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonMode {
+    /// Exactly one value, surrounded by optional whitespace.
+    Single,
+    /// ndjson / JSON Lines: one value per line; blank lines are allowed.
+    /// Newlines inside a container are tolerated like any whitespace.
+    Lines,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonErrorKind {
+    /// A byte that cannot start or continue anything here.
+    UnexpectedByte(u8),
+    /// A closer that does not match the container opened at `open`.
+    Mismatched {
+        open: usize,
+        expected: u8,
+    },
+    /// `]` or `}` straight after a `,`.
+    TrailingComma,
+    /// An object key that is not a string.
+    NonStringKey,
+    MissingColon,
+    /// Two values (or members) without a `,` between them.
+    MissingComma,
+    /// A backslash escape other than `\" \\ \/ \b \f \n \r \t \uXXXX`.
+    InvalidEscape,
+    /// An unescaped byte below 0x20 inside a string.
+    ControlCharacter,
+    /// Not `true`, `false` or `null`.
+    InvalidLiteral,
+    InvalidNumber,
+    /// Anything after the value (or, in lines mode, after a value on its line).
+    TrailingContent,
+    /// The input ended inside a value.
+    UnexpectedEnd,
+}
+
+/// The first structural error, at byte offset `pos` of the whole input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JsonError {
+    pub pos: usize,
+    pub kind: JsonErrorKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Number {
+    Minus,
+    Zero,
+    Int,
+    Dot,
+    Fraction,
+    Exponent,
+    ExponentSign,
+    ExponentDigits,
+}
+
+impl Number {
+    // Whether the number may end here.
+    fn complete(self) -> bool {
+        matches!(
+            self,
+            Number::Zero | Number::Int | Number::Fraction | Number::ExponentDigits
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Top level in lines mode: a value or blank lines.
+    Line,
+    /// A value must follow (top level, after ':' or after ',' in an array).
+    Value,
+    /// After '[': a value or ']'.
+    ArrayStart,
+    /// After '{': a key or '}'.
+    ObjectStart,
+    /// After ',' in an object.
+    Key,
+    Colon,
+    CommaOrClose,
+    String {
+        key: bool,
+    },
+    Escape {
+        key: bool,
+    },
+    Unicode {
+        key: bool,
+        left: u8,
+    },
+    Literal {
+        rest: &'static [u8],
+    },
+    Number(Number),
+    /// After the top-level value.
+    Done,
+}
+
+fn is_whitespace(b: u8) -> bool {
+    matches!(b, b' ' | b'\t' | b'\n' | b'\r')
+}
+
+/// A push-based validator: [`feed`](Self::feed) chunks, then
+/// [`finish`](Self::finish).
+#[derive(Debug, Clone)]
+pub struct JsonValidator {
+    mode: JsonMode,
+    state: State,
+    // Open containers: (offset, '{' or '[').
+    stack: Vec<(usize, u8)>,
+    offset: usize,
+    error: Option<JsonError>,
+}
+
+impl JsonValidator {
+    pub fn new(mode: JsonMode) -> Self {
+        JsonValidator {
+            mode,
+            state: match mode {
+                JsonMode::Single => State::Value,
+                JsonMode::Lines => State::Line,
+            },
+            stack: Vec::new(),
+            offset: 0,
+            error: None,
+        }
+    }
+
+    /// Bytes fed so far.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Validates the next chunk. After an error, keeps returning it.
+    pub fn feed(&mut self, chunk: &[u8]) -> Result<(), JsonError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        let mut i = 0;
+        while i < chunk.len() {
+            if let State::String { .. } = self.state {
+                // Plain string bytes need no state changes; skip them at once.
+                match chunk[i..]
+                    .iter()
+                    .position(|&b| b == b'"' || b == b'\\' || b < 0x20)
+                {
+                    Some(n) => i += n,
+                    None => break,
+                }
+            }
+            let pos = self.offset + i;
+            match self.byte(pos, chunk[i]) {
+                Ok(true) => i += 1,
+                Ok(false) => {} // The byte ended a number; look at it again.
+                Err(kind) => return Err(self.fail(pos, kind)),
+            }
+        }
+        self.offset += chunk.len();
+        Ok(())
+    }
+
+    /// Checks that the input did not end inside a value.
+    pub fn finish(mut self) -> Result<(), JsonError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        if let State::Number(n) = self.state {
+            if !n.complete() {
+                return Err(self.fail(self.offset, JsonErrorKind::InvalidNumber));
+            }
+            self.value_done();
+        }
+        match self.state {
+            State::Done | State::Line => Ok(()),
+            _ => Err(self.fail(self.offset, JsonErrorKind::UnexpectedEnd)),
+        }
+    }
+
+    fn fail(&mut self, pos: usize, kind: JsonErrorKind) -> JsonError {
+        let error = JsonError { pos, kind };
+        self.error = Some(error);
+        error
+    }
+
+    fn value_done(&mut self) {
+        self.state = if self.stack.is_empty() {
+            State::Done
+        } else {
+            State::CommaOrClose
+        };
+    }
+
+    fn begin_value(&mut self, pos: usize, b: u8) -> Result<(), JsonErrorKind> {
+        self.state = match b {
+            b'{' => {
+                self.stack.push((pos, b));
+                State::ObjectStart
+            }
+            b'[' => {
+                self.stack.push((pos, b));
+                State::ArrayStart
+            }
+            b'"' => State::String { key: false },
+            b't' => State::Literal { rest: b"rue" },
+            b'f' => State::Literal { rest: b"alse" },
+            b'n' => State::Literal { rest: b"ull" },
+            b'-' => State::Number(Number::Minus),
+            b'0' => State::Number(Number::Zero),
+            b'1'..=b'9' => State::Number(Number::Int),
+            _ => return Err(JsonErrorKind::UnexpectedByte(b)),
+        };
+        Ok(())
+    }
+
+    fn close(&mut self, b: u8) -> Result<(), JsonErrorKind> {
+        let Some(&(open, top)) = self.stack.last() else {
+            return Err(JsonErrorKind::UnexpectedByte(b));
+        };
+        let expected = if top == b'{' { b'}' } else { b']' };
+        if b != expected {
+            return Err(JsonErrorKind::Mismatched { open, expected });
+        }
+        self.stack.pop();
+        self.value_done();
+        Ok(())
+    }
+
+    // Handles one byte; `Ok(false)` means it was not consumed.
+    fn byte(&mut self, pos: usize, b: u8) -> Result<bool, JsonErrorKind> {
+        match self.state {
+            State::Line
+            | State::Value
+            | State::ArrayStart
+            | State::ObjectStart
+            | State::Key
+            | State::Colon
+            | State::CommaOrClose
+            | State::Done
+                if is_whitespace(b) =>
+            {
+                // In lines mode a value ends its line.
+                if b == b'\n' && self.state == State::Done && self.mode == JsonMode::Lines {
+                    self.state = State::Line;
+                }
+            }
+            State::Line => self.begin_value(pos, b)?,
+            State::Value => {
+                if b == b']' && self.stack.last().is_some_and(|&(_, top)| top == b'[') {
+                    return Err(JsonErrorKind::TrailingComma);
+                }
+                self.begin_value(pos, b)?;
+            }
+            State::ArrayStart if b == b']' => self.close(b)?,
+            State::ArrayStart => self.begin_value(pos, b)?,
+            State::ObjectStart | State::Key => match b {
+                b'"' => self.state = State::String { key: true },
+                b'}' if self.state == State::ObjectStart => self.close(b)?,
+                b'}' => return Err(JsonErrorKind::TrailingComma),
+                _ => return Err(JsonErrorKind::NonStringKey),
+            },
+            State::Colon if b == b':' => self.state = State::Value,
+            State::Colon => return Err(JsonErrorKind::MissingColon),
+            State::CommaOrClose => match b {
+                b',' => {
+                    let in_object = self.stack.last().is_some_and(|&(_, top)| top == b'{');
+                    self.state = if in_object { State::Key } else { State::Value };
+                }
+                b'}' | b']' => self.close(b)?,
+                _ => return Err(JsonErrorKind::MissingComma),
+            },
+            State::String { key } => match b {
+                b'"' if key => self.state = State::Colon,
+                b'"' => self.value_done(),
+                b'\\' => self.state = State::Escape { key },
+                0..0x20 => return Err(JsonErrorKind::ControlCharacter),
+                _ => {}
+            },
+            State::Escape { key } => match b {
+                b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't' => {
+                    self.state = State::String { key }
+                }
+                b'u' => self.state = State::Unicode { key, left: 4 },
+                _ => return Err(JsonErrorKind::InvalidEscape),
+            },
+            State::Unicode { key, left } => {
+                if !b.is_ascii_hexdigit() {
+                    return Err(JsonErrorKind::InvalidEscape);
+                }
+                self.state = match left {
+                    1 => State::String { key },
+                    _ => State::Unicode {
+                        key,
+                        left: left - 1,
+                    },
+                };
+            }
+            State::Literal { rest } => {
+                if b != rest[0] {
+                    return Err(JsonErrorKind::InvalidLiteral);
+                }
+                match &rest[1..] {
+                    [] => self.value_done(),
+                    rest => self.state = State::Literal { rest },
+                }
+            }
+            State::Number(n) => {
+                let next = match (n, b) {
+                    (Number::Minus, b'0') => Number::Zero,
+                    (Number::Minus, b'1'..=b'9') => Number::Int,
+                    (Number::Int, b'0'..=b'9') => Number::Int,
+                    (Number::Zero | Number::Int, b'.') => Number::Dot,
+                    (Number::Dot | Number::Fraction, b'0'..=b'9') => Number::Fraction,
+                    (Number::Zero | Number::Int | Number::Fraction, b'e' | b'E') => {
+                        Number::Exponent
+                    }
+                    (Number::Exponent, b'+' | b'-') => Number::ExponentSign,
+                    (
+                        Number::Exponent | Number::ExponentSign | Number::ExponentDigits,
+                        b'0'..=b'9',
+                    ) => Number::ExponentDigits,
+                    (n, _) if n.complete() => {
+                        self.value_done();
+                        return Ok(false);
+                    }
+                    _ => return Err(JsonErrorKind::InvalidNumber),
+                };
+                self.state = State::Number(next);
+            }
+            State::Done => return Err(JsonErrorKind::TrailingContent),
+        }
+        Ok(true)
+    }
+}
+
+/// Validates a whole input.
+/// Example: `validate_json(b"[1, {\"a\": null}]", JsonMode::Single)` → Ok
+pub fn validate_json(input: &[u8], mode: JsonMode) -> Result<(), JsonError> {
+    let mut validator = JsonValidator::new(mode);
+    validator.feed(input)?;
+    validator.finish()
+}
+
+/// Validates everything `reader` yields, in [`BLOCK_SIZE`](crate::stream::BLOCK_SIZE)
+/// chunks, stopping at the first error.
+#[cfg(feature = "std")]
+pub fn validate_reader(
+    mut reader: impl std::io::Read,
+    mode: JsonMode,
+) -> std::io::Result<Result<(), JsonError>> {
+    let mut buffer = alloc::vec![0u8; crate::stream::BLOCK_SIZE];
+    let mut validator = JsonValidator::new(mode);
+    loop {
+        let n = match reader.read(&mut buffer) {
+            Ok(0) => return Ok(validator.finish()),
+            Ok(n) => n,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        if let Err(error) = validator.feed(&buffer[..n]) {
+            return Ok(Err(error));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(input: &str) -> (usize, JsonErrorKind) {
+        let e = validate_json(input.as_bytes(), JsonMode::Single).unwrap_err();
+        (e.pos, e.kind)
+    }
+
+    #[test]
+    fn test_valid_documents() {
+        for input in [
+            r#"{"a": [1, -2.5e+3, 0, true, false, null], "b\"é": {}}"#,
+            " [ ] ",
+            r#""just a string""#,
+            "0.5",
+            r#"[[[{"x": [{}]}]]]"#,
+        ] {
+            assert_eq!(
+                validate_json(input.as_bytes(), JsonMode::Single),
+                Ok(()),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_structural_errors() {
+        assert_eq!(
+            error(r#"{"a": [1, 2,]}"#),
+            (12, JsonErrorKind::TrailingComma)
+        );
+        assert_eq!(error(r#"{"a": 1,}"#), (8, JsonErrorKind::TrailingComma));
+        assert_eq!(
+            error(r#"{"a": [1}"#),
+            (
+                8,
+                JsonErrorKind::Mismatched {
+                    open: 6,
+                    expected: b']'
+                }
+            )
+        );
+        assert_eq!(error("{1: 2}"), (1, JsonErrorKind::NonStringKey));
+        assert_eq!(error(r#"{"a" 1}"#), (5, JsonErrorKind::MissingColon));
+        assert_eq!(error("[1 2]"), (3, JsonErrorKind::MissingComma));
+        assert_eq!(error("[1] [2]"), (4, JsonErrorKind::TrailingContent));
+        assert_eq!(error("]"), (0, JsonErrorKind::UnexpectedByte(b']')));
+        assert_eq!(error(r#"{"a": [1"#), (8, JsonErrorKind::UnexpectedEnd));
+        assert_eq!(error(""), (0, JsonErrorKind::UnexpectedEnd));
+    }
+
+    #[test]
+    fn test_token_errors() {
+        assert_eq!(error(r#"["\x"]"#), (3, JsonErrorKind::InvalidEscape));
+        assert_eq!(error(r#"["\u12G4"]"#), (6, JsonErrorKind::InvalidEscape));
+        assert_eq!(error("[\"a\nb\"]"), (3, JsonErrorKind::ControlCharacter));
+        assert_eq!(error("[nul]"), (4, JsonErrorKind::InvalidLiteral));
+        assert_eq!(error("[1.]"), (3, JsonErrorKind::InvalidNumber));
+        assert_eq!(error("-"), (1, JsonErrorKind::InvalidNumber));
+        // Delimiters inside strings are text.
+        assert_eq!(validate_json(br#"["]}{["]"#, JsonMode::Single), Ok(()));
+    }
+
+    #[test]
+    fn test_lines_mode() {
+        let input = b"{\"a\": 1}\n\n[2]\r\n3\n";
+        assert_eq!(validate_json(input, JsonMode::Lines), Ok(()));
+        assert_eq!(validate_json(b"", JsonMode::Lines), Ok(()));
+        let e = validate_json(b"1\n2 3\n", JsonMode::Lines).unwrap_err();
+        assert_eq!((e.pos, e.kind), (4, JsonErrorKind::TrailingContent));
+    }
+
+    #[test]
+    fn test_chunking_does_not_matter() {
+        let input = br#"{"key": ["str\"ing", 12.5e-3, true], "n": null} "#;
+        let whole = validate_json(input, JsonMode::Single);
+        for size in 1..8 {
+            let mut validator = JsonValidator::new(JsonMode::Single);
+            for chunk in input.chunks(size) {
+                validator.feed(chunk).unwrap();
+            }
+            assert_eq!(validator.finish(), whole, "chunk size {size}");
+        }
+        let mut validator = JsonValidator::new(JsonMode::Single);
+        let first = validator.feed(b"[1,]").unwrap_err();
+        assert_eq!(validator.feed(b"ignored"), Err(first));
+    }
+}
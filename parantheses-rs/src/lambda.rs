@@ -0,0 +1,457 @@
+// Untyped lambda calculus, another Turing-complete machine next to `queue`
+// and `tm`. Terms are written `\x. body` (or `λx. body`), application is
+// juxtaposition and associates to the left, and a lambda body extends as far
+// right as possible:
+//
+//   \f x. f (f x)      = λf. λx. f (f x)      (Church numeral 2)
+//
+// Grouping comes straight from the bracket matcher: `parse_tree` validates
+// the parentheses and each pair node becomes a sub-term. Reduction is normal
+// order (leftmost-outermost) with capture-avoiding substitution, so it finds
+// a normal form whenever one exists, within the given fuel.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeSet;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::spans::MismatchError;
+use crate::tree::{Node, NodeKind, parse_tree};
+
+/// Church addition: `ADD m n` reduces to the numeral m + n.
+pub const ADD: &str = r"\m n f x. m f (n f x)";
+/// Church multiplication.
+pub const MUL: &str = r"\m n f. m (n f)";
+/// Church successor.
+pub const SUCC: &str = r"\n f x. f (n f x)";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Term {
+    Var(String),
+    Abs(String, Box<Term>),
+    App(Box<Term>, Box<Term>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LambdaErrorKind {
+    /// The parentheses do not match.
+    Brackets(MismatchError),
+    /// A character that is not part of the syntax, including `[` and `{`.
+    UnexpectedChar(char),
+    /// `\` not followed by a parameter name.
+    MissingParameter,
+    /// Parameters not followed by `.`.
+    MissingDot,
+    /// An empty term: `()`, a lambda without body, or empty input.
+    MissingTerm,
+}
+
+/// A parse error at byte offset `pos`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LambdaError {
+    pub pos: usize,
+    pub kind: LambdaErrorKind,
+}
+
+// Lexical items of one bracket level; groups are parsed recursively.
+enum Token<'a> {
+    Ident(usize, &'a str),
+    Lambda(usize),
+    Dot(usize),
+    Group(&'a Node),
+}
+
+impl Token<'_> {
+    fn pos(&self) -> usize {
+        match *self {
+            Token::Ident(pos, _) | Token::Lambda(pos) | Token::Dot(pos) => pos,
+            Token::Group(node) => node.span.start,
+        }
+    }
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_alphanumeric() || c == '_' || c == '\''
+}
+
+fn tokenize<'a>(input: &'a str, nodes: &'a [Node]) -> Result<Vec<Token<'a>>, LambdaError> {
+    let mut tokens = Vec::new();
+    for node in nodes {
+        match node.kind {
+            NodeKind::Pair { open: '(', .. } => tokens.push(Token::Group(node)),
+            NodeKind::Pair { open, .. } => {
+                return Err(LambdaError {
+                    pos: node.span.start,
+                    kind: LambdaErrorKind::UnexpectedChar(open),
+                });
+            }
+            _ => {
+                let text = node.text(input);
+                let mut chars = text.char_indices().peekable();
+                while let Some((i, c)) = chars.next() {
+                    let pos = node.span.start + i;
+                    match c {
+                        '\\' | 'λ' => tokens.push(Token::Lambda(pos)),
+                        '.' => tokens.push(Token::Dot(pos)),
+                        c if c.is_whitespace() => {}
+                        c if is_ident(c) => {
+                            let mut end = i + c.len_utf8();
+                            while let Some((j, d)) = chars.next_if(|&(_, d)| is_ident(d)) {
+                                end = j + d.len_utf8();
+                            }
+                            tokens.push(Token::Ident(pos, &text[i..end]));
+                        }
+                        c => {
+                            return Err(LambdaError {
+                                pos,
+                                kind: LambdaErrorKind::UnexpectedChar(c),
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+// Parses one bracket level; `end` is where an empty level is reported.
+fn parse_level(input: &str, nodes: &[Node], end: usize) -> Result<Term, LambdaError> {
+    let tokens = tokenize(input, nodes)?;
+    parse_tokens(input, &tokens, end)
+}
+
+fn parse_tokens(input: &str, tokens: &[Token], end: usize) -> Result<Term, LambdaError> {
+    let error = |pos, kind| Err(LambdaError { pos, kind });
+    let mut term: Option<Term> = None;
+    let mut i = 0;
+    while i < tokens.len() {
+        let next = match tokens[i] {
+            Token::Ident(_, name) => Term::var(name),
+            Token::Group(node) => {
+                let inner = node.inner();
+                parse_level(input, &node.children, inner.end)?
+            }
+            Token::Dot(pos) => return error(pos, LambdaErrorKind::UnexpectedChar('.')),
+            Token::Lambda(pos) => {
+                let mut params = Vec::new();
+                i += 1;
+                while let Some(&Token::Ident(_, name)) = tokens.get(i) {
+                    params.push(name);
+                    i += 1;
+                }
+                let at = tokens.get(i).map_or(end, Token::pos);
+                if params.is_empty() {
+                    return error(pos, LambdaErrorKind::MissingParameter);
+                }
+                if !matches!(tokens.get(i), Some(Token::Dot(_))) {
+                    return error(at, LambdaErrorKind::MissingDot);
+                }
+                // The body takes the rest of this level.
+                let body = parse_tokens(input, &tokens[i + 1..], end)?;
+                let abs = params
+                    .into_iter()
+                    .rev()
+                    .fold(body, |body, param| Term::abs(param, body));
+                return Ok(match term {
+                    Some(f) => Term::app(f, abs),
+                    None => abs,
+                });
+            }
+        };
+        term = Some(match term {
+            Some(f) => Term::app(f, next),
+            None => next,
+        });
+        i += 1;
+    }
+    term.ok_or(LambdaError {
+        pos: end,
+        kind: LambdaErrorKind::MissingTerm,
+    })
+}
+
+/// Parses a term.
+/// Example: `parse_term(r"(\x. x) y")` is `App(Abs("x", Var("x")), Var("y"))`
+pub fn parse_term(input: &str) -> Result<Term, LambdaError> {
+    let root = parse_tree(input).map_err(|e| LambdaError {
+        pos: e.pos,
+        kind: LambdaErrorKind::Brackets(e),
+    })?;
+    parse_level(input, &root.children, input.len())
+}
+
+/// The Church numeral for `n`: `\f x. f (… (f x))` with `n` applications.
+pub fn church(n: usize) -> Term {
+    let body = (0..n).fold(Term::var("x"), |x, _| Term::app(Term::var("f"), x));
+    Term::abs("f", Term::abs("x", body))
+}
+
+/// The number a Church numeral in normal form stands for, under any names.
+pub fn unchurch(term: &Term) -> Option<usize> {
+    let Term::Abs(f, body) = term else {
+        return None;
+    };
+    let Term::Abs(x, body) = &**body else {
+        return None;
+    };
+    if f == x {
+        return None;
+    }
+    let (mut n, mut body): (usize, &Term) = (0, body);
+    loop {
+        match body {
+            Term::Var(v) if v == x => return Some(n),
+            Term::App(g, rest) if matches!(&**g, Term::Var(v) if v == f) => {
+                n += 1;
+                body = rest;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Result of [`Term::reduce`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reduction {
+    pub term: Term,
+    /// Beta steps taken.
+    pub steps: usize,
+    /// False if the fuel ran out before a normal form was reached.
+    pub normal: bool,
+}
+
+impl Term {
+    pub fn var(name: &str) -> Term {
+        Term::Var(name.to_string())
+    }
+
+    pub fn abs(param: &str, body: Term) -> Term {
+        Term::Abs(param.to_string(), Box::new(body))
+    }
+
+    pub fn app(f: Term, arg: Term) -> Term {
+        Term::App(Box::new(f), Box::new(arg))
+    }
+
+    /// Variables occurring free in the term.
+    pub fn free_vars(&self) -> BTreeSet<String> {
+        let mut free = BTreeSet::new();
+        self.collect_free(&mut Vec::new(), &mut free);
+        free
+    }
+
+    fn collect_free<'a>(&'a self, bound: &mut Vec<&'a str>, free: &mut BTreeSet<String>) {
+        match self {
+            Term::Var(x) => {
+                if !bound.contains(&x.as_str()) {
+                    free.insert(x.clone());
+                }
+            }
+            Term::Abs(x, body) => {
+                bound.push(x);
+                body.collect_free(bound, free);
+                bound.pop();
+            }
+            Term::App(f, a) => {
+                f.collect_free(bound, free);
+                a.collect_free(bound, free);
+            }
+        }
+    }
+
+    /// Capture-avoiding substitution of `value` for free `x`. Binders that
+    /// would capture a free variable of `value` are renamed by priming them.
+    /// Example: `(\y. x y)[x := y]` is `\y'. y y'`
+    pub fn substitute(&self, x: &str, value: &Term) -> Term {
+        self.subst(x, value, &value.free_vars())
+    }
+
+    fn subst(&self, x: &str, value: &Term, value_free: &BTreeSet<String>) -> Term {
+        match self {
+            Term::Var(y) if y == x => value.clone(),
+            Term::Var(_) => self.clone(),
+            Term::App(f, a) => {
+                Term::app(f.subst(x, value, value_free), a.subst(x, value, value_free))
+            }
+            Term::Abs(y, _) if y == x => self.clone(),
+            Term::Abs(y, body) => {
+                let body_free = body.free_vars();
+                if !body_free.contains(x) {
+                    return self.clone();
+                }
+                if !value_free.contains(y) {
+                    return Term::abs(y, body.subst(x, value, value_free));
+                }
+                // Alpha-rename the binder away from everything in sight.
+                let mut fresh = y.clone();
+                while value_free.contains(&fresh) || body_free.contains(&fresh) {
+                    fresh.push('\'');
+                }
+                let renamed = body.substitute(y, &Term::Var(fresh.clone()));
+                Term::abs(&fresh, renamed.subst(x, value, value_free))
+            }
+        }
+    }
+
+    /// One normal-order beta step, or `None` for a normal form.
+    pub fn step(&self) -> Option<Term> {
+        match self {
+            Term::Var(_) => None,
+            Term::Abs(x, body) => body.step().map(|body| Term::abs(x, body)),
+            Term::App(f, a) => {
+                if let Term::Abs(x, body) = &**f {
+                    return Some(body.substitute(x, a));
+                }
+                if let Some(f) = f.step() {
+                    return Some(Term::App(Box::new(f), a.clone()));
+                }
+                a.step().map(|a| Term::App(f.clone(), Box::new(a)))
+            }
+        }
+    }
+
+    /// Reduces in normal order, taking at most `fuel` beta steps.
+    pub fn reduce(&self, fuel: usize) -> Reduction {
+        let mut term = self.clone();
+        for steps in 0..fuel {
+            match term.step() {
+                Some(next) => term = next,
+                None => {
+                    return Reduction {
+                        term,
+                        steps,
+                        normal: true,
+                    };
+                }
+            }
+        }
+        let normal = term.step().is_none();
+        Reduction {
+            term,
+            steps: fuel,
+            normal,
+        }
+    }
+
+    /// Equality up to the names of bound variables.
+    pub fn alpha_eq(&self, other: &Term) -> bool {
+        fn eq<'a>(a: &'a Term, b: &'a Term, env: &mut Vec<(&'a str, &'a str)>) -> bool {
+            match (a, b) {
+                (Term::Var(x), Term::Var(y)) => {
+                    let i = env.iter().rposition(|&(bx, _)| bx == x);
+                    let j = env.iter().rposition(|&(_, by)| by == y);
+                    match (i, j) {
+                        (None, None) => x == y,
+                        (i, j) => i == j,
+                    }
+                }
+                (Term::Abs(x, a), Term::Abs(y, b)) => {
+                    env.push((x, y));
+                    let same = eq(a, b, env);
+                    env.pop();
+                    same
+                }
+                (Term::App(f, a), Term::App(g, b)) => eq(f, g, env) && eq(a, b, env),
+                _ => false,
+            }
+        }
+        eq(self, other, &mut Vec::new())
+    }
+}
+
+/// Prints with the fewest parentheses that parse back to the same term.
+impl fmt::Display for Term {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Term::Var(x) => write!(f, "{x}"),
+            Term::Abs(x, body) => write!(f, "\\{x}. {body}"),
+            Term::App(g, a) => {
+                match **g {
+                    Term::Abs(..) => write!(f, "({g})")?,
+                    _ => write!(f, "{g}")?,
+                }
+                match **a {
+                    Term::Var(_) => write!(f, " {a}"),
+                    _ => write!(f, " ({a})"),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spans::MismatchKind;
+
+    fn parse(input: &str) -> Term {
+        parse_term(input).unwrap()
+    }
+
+    fn eval(input: &str) -> Term {
+        let reduction = parse(input).reduce(10_000);
+        assert!(reduction.normal, "{input}");
+        reduction.term
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        for input in [
+            r"\f. \x. f (f x)",
+            r"(\x. x) y",
+            r"a b c",
+            r"a (b c)",
+            r"\x. x (\y. y)",
+        ] {
+            assert_eq!(parse(input).to_string(), input);
+        }
+        assert_eq!(parse(r"λf x. f x").to_string(), r"\f. \x. f x");
+        assert_eq!(parse(r"((a)) (b)").to_string(), "a b");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let kind = |input| parse_term(input).unwrap_err().kind;
+        assert!(matches!(
+            kind(r"(\x. x"),
+            LambdaErrorKind::Brackets(MismatchError {
+                kind: MismatchKind::Unclosed,
+                ..
+            })
+        ));
+        assert_eq!(kind(r"\. x"), LambdaErrorKind::MissingParameter);
+        assert_eq!(kind(r"\x x"), LambdaErrorKind::MissingDot);
+        assert_eq!(kind(r"\x."), LambdaErrorKind::MissingTerm);
+        assert_eq!(kind("f ()"), LambdaErrorKind::MissingTerm);
+        assert_eq!(kind("[x]"), LambdaErrorKind::UnexpectedChar('['));
+        assert_eq!(parse_term("a + b").unwrap_err().pos, 2);
+    }
+
+    #[test]
+    fn test_capture_avoiding_substitution() {
+        assert_eq!(eval(r"(\x y. x) y").to_string(), r"\y'. y");
+        assert!(eval(r"(\x y. x y) y").alpha_eq(&parse(r"\z. y z")));
+        assert!(!parse(r"\x. y").alpha_eq(&parse(r"\y. y")));
+    }
+
+    #[test]
+    fn test_church_arithmetic() {
+        assert_eq!(unchurch(&church(3)), Some(3));
+        let num = |n: usize| format!("({})", church(n));
+        let sum = eval(&format!("({ADD}) {} {}", num(2), num(3)));
+        assert_eq!(unchurch(&sum), Some(5));
+        let product = eval(&format!("({MUL}) {} {}", num(2), num(3)));
+        assert_eq!(unchurch(&product), Some(6));
+        assert_eq!(unchurch(&eval(&format!("({SUCC}) {}", num(0)))), Some(1));
+    }
+
+    #[test]
+    fn test_normal_order_and_fuel() {
+        let omega = r"(\x. x x) (\x. x x)";
+        assert!(!parse(omega).reduce(100).normal);
+        // Normal order discards the looping argument without evaluating it.
+        assert_eq!(eval(&format!(r"(\x. z) ({omega})")), Term::var("z"));
+    }
+}
@@ -0,0 +1,281 @@
+// LaTeX delimiter matching: `\begin{env}`/`\end{env}` pairs by environment
+// name, plus `{}`, `[]`, inline `$...$`, display `$$...$$`, `\(...\)` and
+// `\[...\]`. Same single stack as the checkers and as `html`:
+//
+// `check_latex(r"\begin{a}$x^{2}$\end{b}")`:
+//   [ ]
+// Read \begin{a} → push "a"         [ a ]
+// Read $         → push "$"         [ a , $ ]
+// Read {, }      → push, pop "{"    [ a , $ ]
+// Read $         → top is "$", pop  [ a ]
+// Read \end{b}   → "b" is not open, "a" is expected
+//
+// Control symbols (`\{`, `\$`, `\%`, `\\`...) are text, `%` comments run to
+// the end of the line, and verbatim bodies (`\verb|...|`, `verbatim`,
+// `lstlisting`, `minted`) are skipped.
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+// Environments whose body is not LaTeX.
+const VERBATIM_ENVIRONMENTS: &[&str] = &["verbatim", "verbatim*", "lstlisting", "minted"];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LatexErrorKind {
+    /// Opened but never closed: still open at the end of input, or skipped
+    /// over by the `\end` of an outer environment.
+    Unclosed,
+    /// A closer that matches nothing open.
+    UnexpectedClose,
+    /// A closer that matches nothing open while `expected` is open.
+    Mismatched {
+        expected: String,
+        expected_pos: usize,
+    },
+}
+
+/// A problem at byte offset `pos`. `name` is the environment name, or the
+/// delimiter as written (`{`, `]`, `$`, `\(`...).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LatexError {
+    pub kind: LatexErrorKind,
+    pub name: String,
+    pub pos: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Role {
+    Open,
+    Close,
+    /// `$` and `$$`: closes if the same delimiter is on top, else opens.
+    Toggle,
+}
+
+// A delimiter; `key` is shared by an opener and its closer.
+struct Delim {
+    role: Role,
+    key: String,
+    name: String,
+    pos: usize,
+}
+
+fn delim(role: Role, key: &str, name: &str, pos: usize) -> Delim {
+    Delim {
+        role,
+        key: key.to_string(),
+        name: name.to_string(),
+        pos,
+    }
+}
+
+// Reads `{name}` after `\begin`/`\end` starting at `i`, skipping spaces.
+// Returns the name and the position after `}`.
+fn environment_name(input: &str, mut i: usize) -> Option<(&str, usize)> {
+    let bytes = input.as_bytes();
+    while bytes.get(i) == Some(&b' ') {
+        i += 1;
+    }
+    if bytes.get(i) != Some(&b'{') {
+        return None;
+    }
+    let close = input[i + 1..].find('}')? + i + 1;
+    Some((&input[i + 1..close], close + 1))
+}
+
+fn delimiters(input: &str) -> Vec<Delim> {
+    let bytes = input.as_bytes();
+    let mut found = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let start = i;
+        i += 1;
+        match bytes[start] {
+            b'%' => i = input[start..].find('\n').map_or(bytes.len(), |n| start + n),
+            b'{' => found.push(delim(Role::Open, "{", "{", start)),
+            b'}' => found.push(delim(Role::Close, "{", "}", start)),
+            b'[' => found.push(delim(Role::Open, "[", "[", start)),
+            b']' => found.push(delim(Role::Close, "[", "]", start)),
+            b'$' if bytes.get(i) == Some(&b'$') => {
+                i += 1;
+                found.push(delim(Role::Toggle, "$$", "$$", start));
+            }
+            b'$' => found.push(delim(Role::Toggle, "$", "$", start)),
+            b'\\' => match bytes.get(i) {
+                Some(b'(') => found.push(delim(Role::Open, "\\(", "\\(", start)),
+                Some(b')') => found.push(delim(Role::Close, "\\(", "\\)", start)),
+                Some(b'[') => found.push(delim(Role::Open, "\\[", "\\[", start)),
+                Some(b']') => found.push(delim(Role::Close, "\\[", "\\]", start)),
+                Some(c) if c.is_ascii_alphabetic() => {
+                    let len = input[i..]
+                        .find(|c: char| !c.is_ascii_alphabetic())
+                        .unwrap_or(bytes.len() - i);
+                    let command = &input[i..i + len];
+                    i += len;
+                    match command {
+                        "begin" | "end" => {
+                            let Some((name, after)) = environment_name(input, i) else {
+                                continue;
+                            };
+                            i = after;
+                            let role = if command == "begin" {
+                                Role::Open
+                            } else {
+                                Role::Close
+                            };
+                            found.push(delim(role, name, name, start));
+                            if role == Role::Open && VERBATIM_ENVIRONMENTS.contains(&name) {
+                                // Jump to the matching \end; the body is not LaTeX.
+                                let end = [r"\end{", name, "}"].concat();
+                                if let Some(offset) = input[i..].find(&end) {
+                                    found.push(delim(Role::Close, name, name, i + offset));
+                                    i += offset + end.len();
+                                } else {
+                                    i = bytes.len();
+                                }
+                            }
+                            continue;
+                        }
+                        "verb" => {}
+                        _ => continue,
+                    }
+                    // \verb<d>...<d>: skip to the second delimiter.
+                    i += (bytes.get(i) == Some(&b'*')) as usize;
+                    if let Some(d) = input[i..].chars().next() {
+                        i += d.len_utf8();
+                        i = input[i..]
+                            .find(d)
+                            .map_or(bytes.len(), |n| i + n + d.len_utf8());
+                    }
+                    continue;
+                }
+                // A control symbol; its character is text.
+                Some(_) => {}
+                None => continue,
+            },
+            _ => continue,
+        }
+        if bytes[start] == b'\\' {
+            // Skip the (possibly multi-byte) character after the backslash.
+            i += input[i..].chars().next().map_or(0, char::len_utf8);
+        }
+    }
+
+    found
+}
+
+/// Checks that every environment and delimiter is closed in order.
+/// Returns every problem found, not just the first.
+/// Example: `\begin{itemize}\item $x$\end{itemize}` → Ok,
+/// `\begin{a}\end{b}` → Err (mismatched "b" while "a" is open)
+pub fn check_latex(input: &str) -> Result<(), Vec<LatexError>> {
+    let mut errors = Vec::new();
+    let mut stack: Vec<Delim> = Vec::new();
+
+    for d in delimiters(input) {
+        let closes_top = stack.last().is_some_and(|top| top.key == d.key);
+        match d.role {
+            Role::Open => stack.push(d),
+            Role::Toggle if !closes_top => stack.push(d),
+            Role::Toggle => {
+                stack.pop();
+            }
+            Role::Close => match stack.iter().rposition(|open| open.key == d.key) {
+                Some(index) => {
+                    // Everything opened after the match was never closed.
+                    for open in stack.drain(index + 1..) {
+                        errors.push(LatexError {
+                            kind: LatexErrorKind::Unclosed,
+                            name: open.name,
+                            pos: open.pos,
+                        });
+                    }
+                    stack.pop();
+                }
+                None => errors.push(LatexError {
+                    kind: match stack.last() {
+                        Some(open) => LatexErrorKind::Mismatched {
+                            expected: open.name.clone(),
+                            expected_pos: open.pos,
+                        },
+                        None => LatexErrorKind::UnexpectedClose,
+                    },
+                    name: d.name,
+                    pos: d.pos,
+                }),
+            },
+        }
+    }
+
+    for open in stack {
+        errors.push(LatexError {
+            kind: LatexErrorKind::Unclosed,
+            name: open.name,
+            pos: open.pos,
+        });
+    }
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balanced_document() {
+        let input = r"\documentclass[a4paper]{article}
+\begin{document}
+\section{Intro} % unbalanced in a comment: {
+Sets \{x\} cost \$5. $a_{i}$ and $$\sum_{k} k$$ or \(x\) and \[y\].
+\begin{itemize}
+  \item \verb|\end{itemize}{|
+\end{itemize}
+\begin{verbatim}
+\begin{document} { [
+\end{verbatim}
+\end{document}";
+        assert_eq!(check_latex(input), Ok(()));
+    }
+
+    #[test]
+    fn test_mismatched_environment_names() {
+        let errors = check_latex(r"\begin{a}\end{b}\end{a}").unwrap_err();
+        assert_eq!(
+            errors,
+            vec![LatexError {
+                kind: LatexErrorKind::Mismatched {
+                    expected: "a".to_string(),
+                    expected_pos: 0
+                },
+                name: "b".to_string(),
+                pos: 9
+            }]
+        );
+    }
+
+    #[test]
+    fn test_unclosed_inner_environment() {
+        let input = r"\begin{itemize}\begin{enumerate}\end{itemize}";
+        let errors = check_latex(input).unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].kind, LatexErrorKind::Unclosed);
+        assert_eq!((errors[0].name.as_str(), errors[0].pos), ("enumerate", 15));
+    }
+
+    #[test]
+    fn test_math_and_brace_errors() {
+        let errors = check_latex("$x + {y$").unwrap_err();
+        let names: Vec<&str> = errors.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["$", "{", "$"]);
+        assert_eq!(
+            check_latex("a}").unwrap_err()[0].kind,
+            LatexErrorKind::UnexpectedClose
+        );
+        assert!(check_latex(r"\(x]").is_err());
+    }
+}
@@ -0,0 +1,163 @@
+// Core of the single-stack language: the balance checkers plus the structural
+// tooling built on top of them.
+//
+// Without the default `std` feature the matcher core (checkers, spans, trees,
+// recovery, repair, traces, tag/indent checks) is `no_std + alloc`: the stack
+// is just a `Vec`. File, stream and rendering helpers need `std`.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+
+extern crate alloc;
+
+use alloc::vec::Vec;
+
+pub mod beaver;
+#[cfg(feature = "std")]
+pub mod complexity;
+pub mod debugger;
+pub mod diff;
+pub mod dyck;
+pub mod fast;
+pub mod html;
+pub mod indent;
+pub mod json;
+pub mod lambda;
+pub mod latex;
+pub mod lines;
+pub mod parallel;
+pub mod pda;
+#[cfg(feature = "parallel")]
+pub mod project;
+pub mod queue;
+#[cfg(feature = "std")]
+pub mod rainbow;
+pub mod recovery;
+pub mod repair;
+pub mod report;
+pub mod sexpr;
+pub mod skip;
+pub mod spans;
+pub mod stats;
+#[cfg(feature = "std")]
+pub mod stream;
+pub mod tm;
+pub mod trace;
+pub mod tree;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+
+/// Delimiter pairs understood by the structural APIs (repair, spans, trees...).
+pub const PAIRS: &[(char, char)] = &[('(', ')'), ('[', ']'), ('{', '}')];
+
+/// Returns the closing delimiter for `open`, if it is an opener in [`PAIRS`].
+pub fn closer_for(open: char) -> Option<char> {
+    PAIRS.iter().find(|&&(o, _)| o == open).map(|&(_, c)| c)
+}
+
+/// Returns the opening delimiter for `close`, if it is a closer in [`PAIRS`].
+pub fn opener_for(close: char) -> Option<char> {
+    PAIRS.iter().find(|&&(_, c)| c == close).map(|&(o, _)| o)
+}
+
+// Checks if every '(' has a matching ')' using a single stack.
+// Returns true if balanced, false otherwise.
+// Example: "((()))" → true, "(()" → false, ")((" → false
+//
+// `check_parentheses("((()))")`:
+// Initial: stack = []
+//   [ ]
+// Step 1: Read '(' → stack.push('(')
+//   [ ( ] ← Push arrow
+// Step 2: Read '(' → stack.push('(')
+//   [ ( , ( ] ← Push arrow
+// Step 3: Read '(' → stack.push('(')
+//   [ ( , ( , ( ] ← Push arrow
+// Step 4: Read ')' → stack.pop() (get '(', matches)
+//   [ ( , ( ] ← Pop arrow
+// Step 5: Read ')' → stack.pop() (get '(', matches)
+//   [ ( ] ← Pop arrow
+// Step 6: Read ')' → stack.pop() (get '(', matches)
+//   [ ] ← Pop arrow
+pub fn check_parentheses(input: &str) -> bool {
+    let mut stack: Vec<char> = Vec::new();
+
+    for c in input.chars() {
+        match c {
+            '(' => stack.push(c),
+            ')' => {
+                // Pop and check if the popped element is '(', or if stack was empty
+                if stack.pop() != Some('(') {
+                    return false;
+                }
+            }
+            _ => continue, // Ignore non-parenthesis characters
+        }
+    }
+
+    // Stack must be empty for balanced parentheses
+    stack.is_empty()
+}
+
+// Checks if every '(' has a matching ')' and every '[' has a matching ']'.
+// Uses a single stack, which correctly identifies mismatches (e.g., "([)]" is invalid
+// due to improper nesting and is rejected because the stack pops '[' when expecting '(').
+// The single stack cannot robustly handle all interleaved cases due to LIFO limitations.
+// Returns true if stack is empty and no mismatches occur, false otherwise.
+// Example: "( [] )" → true, "([)]" → false, "([[" → false
+//
+// `check_parentheses_and_brackets("([)]")` (invalid):
+// Initial: stack = []
+//   [ ]
+// Step 1: Read '(' → stack.push('(')
+//   [ ( ] ← Push arrow
+// Step 2: Read '[' → stack.push('[')
+//   [ ( , [ ] ← Push arrow
+// Step 3: Read ')' → stack.pop() (expect '(', get '[' → mismatch)
+//   [ ( ] ← Pop arrow (popped '[' ≠ '(')
+pub fn check_parentheses_and_brackets(input: &str) -> bool {
+    let mut stack: Vec<char> = Vec::new();
+
+    for c in input.chars() {
+        match c {
+            '(' | '[' => stack.push(c), // Push opening symbols
+            ')' => {
+                // Pop and check for matching '('
+                if stack.pop() != Some('(') {
+                    return false;
+                }
+            }
+            ']' => {
+                // Pop and check for matching '['
+                if stack.pop() != Some('[') {
+                    return false;
+                }
+            }
+            _ => continue,
+        }
+    }
+
+    // Stack must be empty for valid string, but this doesn't guarantee correct nesting
+    stack.is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parentheses() {
+        assert!(check_parentheses("(())"));
+        assert!(check_parentheses("()()"));
+        assert!(!check_parentheses("(()"));
+        assert!(!check_parentheses("())"));
+        assert!(check_parentheses("a(b)c"));
+    }
+
+    #[test]
+    fn test_parentheses_and_brackets() {
+        assert!(check_parentheses_and_brackets("(())[]"));
+        assert!(!check_parentheses_and_brackets("([]]"));
+        assert!(check_parentheses_and_brackets("a(b)[c]"));
+        assert!(!check_parentheses_and_brackets("([)]"));
+    }
+}
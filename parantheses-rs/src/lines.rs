@@ -0,0 +1,99 @@
+// Per-line bracket balance, for editors that keep fold and indent state per
+// line. Each line is summarised on its own, relative to the depth it starts
+// at, so an edit only requires re-summarising the lines it touched; depths
+// of later lines follow from a running sum of `net`.
+//
+// `line_deltas("f(a,\n  b)) {")`:
+//   line 0  "f(a,"     opens 1, closes 0, net +1, min_depth  0
+//   line 1  "  b)) {"  opens 1, closes 2, net -1, min_depth -2
+// Line 1 starts at depth 1 and dips to 1 - 2 = -1: a stray closer.
+
+use alloc::vec::Vec;
+
+use crate::{closer_for, opener_for};
+
+/// Bracket balance of one line, relative to the depth it starts at.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LineBalance {
+    pub opens: usize,
+    pub closes: usize,
+    /// `opens - closes`: the depth change across the line.
+    pub net: isize,
+    /// Lowest depth reached within the line (0 or negative).
+    pub min_depth: isize,
+}
+
+impl LineBalance {
+    /// Summarises a single line.
+    pub fn of(line: &str) -> Self {
+        let mut balance = LineBalance::default();
+        for c in line.chars() {
+            if closer_for(c).is_some() {
+                balance.opens += 1;
+                balance.net += 1;
+            } else if opener_for(c).is_some() {
+                balance.closes += 1;
+                balance.net -= 1;
+                balance.min_depth = balance.min_depth.min(balance.net);
+            }
+        }
+        balance
+    }
+}
+
+/// Summarises every line of `input` (split on `\n`, so a trailing newline
+/// gives a final empty line, as in an editor).
+pub fn line_deltas(input: &str) -> Vec<LineBalance> {
+    input.split('\n').map(LineBalance::of).collect()
+}
+
+/// The depth each line starts at, given its balances.
+/// Example: nets +1, -1, 0 → starting depths 0, 1, 0
+pub fn start_depths(lines: &[LineBalance]) -> Vec<isize> {
+    lines
+        .iter()
+        .scan(0, |depth, line| {
+            let start = *depth;
+            *depth += line.net;
+            Some(start)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_line_deltas() {
+        let lines = line_deltas("f(a,\n  b)) {");
+        assert_eq!(
+            lines,
+            vec![
+                LineBalance {
+                    opens: 1,
+                    closes: 0,
+                    net: 1,
+                    min_depth: 0
+                },
+                LineBalance {
+                    opens: 1,
+                    closes: 2,
+                    net: -1,
+                    min_depth: -2
+                },
+            ]
+        );
+        assert_eq!(line_deltas("a\n").len(), 2);
+    }
+
+    #[test]
+    fn test_incremental_update() {
+        let mut lines = line_deltas("fn f() {\n    g(x);\n}\n");
+        assert_eq!(start_depths(&lines), vec![0, 1, 1, 0]);
+        // Editing line 1 only needs that line re-summarised.
+        lines[1] = LineBalance::of("    if x {");
+        assert_eq!(start_depths(&lines), vec![0, 1, 2, 1]);
+    }
+}
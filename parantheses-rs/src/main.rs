@@ -2,90 +2,139 @@
 // This language can check if parentheses are balanced but cannot reliably check both parentheses
 // and brackets, demonstrating it is not Turing complete.
 
-use std::vec::Vec;
+use std::fs::File;
+use std::io;
+use std::process::ExitCode;
 
-// Checks if every '(' has a matching ')' using a single stack.
-// Returns true if balanced, false otherwise.
-// Example: "((()))" → true, "(()" → false, ")((" → false
-//
-// `check_parentheses("((()))")`:
-// Initial: stack = []
-//   [ ]
-// Step 1: Read '(' → stack.push('(')
-//   [ ( ] ← Push arrow
-// Step 2: Read '(' → stack.push('(')
-//   [ ( , ( ] ← Push arrow
-// Step 3: Read '(' → stack.push('(')
-//   [ ( , ( , ( ] ← Push arrow
-// Step 4: Read ')' → stack.pop() (get '(', matches)
-//   [ ( , ( ] ← Pop arrow
-// Step 5: Read ')' → stack.pop() (get '(', matches)
-//   [ ( ] ← Pop arrow
-// Step 6: Read ')' → stack.pop() (get '(', matches)
-//   [ ] ← Pop arrow
-fn check_parentheses(input: &str) -> bool {
-    let mut stack: Vec<char> = Vec::new();
+use parantheses_rs::json::{JsonMode, validate_reader};
+use parantheses_rs::pda::Halt;
+use parantheses_rs::queue::QueueMachine;
+use parantheses_rs::rainbow::render_ansi;
+use parantheses_rs::stream::{StreamReport, check_reader};
+use parantheses_rs::{check_parentheses, check_parentheses_and_brackets};
 
-    for c in input.chars() {
-        match c {
-            '(' => stack.push(c),
-            ')' => {
-                // Pop and check if the popped element is '(', or if stack was empty
-                if stack.pop() != Some('(') {
-                    return false;
+const USAGE: &str = "usage: parantheses-rs [--stream|--rainbow|--json|--validate-json] <path>|-\n       parantheses-rs --project <root> [glob...]";
+
+// Usage: parantheses-rs <flag> <path>, where <path> may be "-" for stdin.
+//   --stream   stream the input through the checker, reporting throughput
+//   --rainbow  print the input with ANSI rainbow brackets
+//   --json     print the pair structure and first error as JSON (`serde` feature)
+//   --validate-json  stream-validate JSON (ndjson for *.ndjson / *.jsonl paths)
+//   --project  check every file under <root> matching the globs (`parallel` feature)
+// Without arguments, runs the demo below.
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.as_slice() {
+        [] => {
+            demo();
+            ExitCode::SUCCESS
+        }
+        #[cfg(feature = "parallel")]
+        [flag, root, globs @ ..] if flag == "--project" => {
+            let globs: Vec<&str> = globs.iter().map(String::as_str).collect();
+            match parantheses_rs::project::check_project(root, &globs) {
+                Ok(report) => {
+                    print!("{}", report.table());
+                    status(report.is_balanced())
+                }
+                Err(e) => {
+                    eprintln!("error: {root}: {e}");
+                    ExitCode::from(2)
                 }
             }
-            _ => continue, // Ignore non-parenthesis characters
         }
+        [flag, path] => run(flag, path).unwrap_or_else(|e| {
+            eprintln!("error: {path}: {e}");
+            ExitCode::from(2)
+        }),
+        _ => usage(),
     }
+}
 
-    // Stack must be empty for balanced parentheses
-    stack.is_empty()
+fn usage() -> ExitCode {
+    eprintln!("{USAGE}");
+    ExitCode::from(2)
 }
 
-// Checks if every '(' has a matching ')' and every '[' has a matching ']'.
-// Uses a single stack, which correctly identifies mismatches (e.g., "([)]" is invalid
-// due to improper nesting and is rejected because the stack pops '[' when expecting '(').
-// The single stack cannot robustly handle all interleaved cases due to LIFO limitations.
-// Returns true if stack is empty and no mismatches occur, false otherwise.
-// Example: "( [] )" → true, "([)]" → false, "([[" → false
-//
-// `check_parentheses_and_brackets("([)]")` (invalid):
-// Initial: stack = []
-//   [ ]
-// Step 1: Read '(' → stack.push('(')
-//   [ ( ] ← Push arrow
-// Step 2: Read '[' → stack.push('[')
-//   [ ( , [ ] ← Push arrow
-// Step 3: Read ')' → stack.pop() (expect '(', get '[' → mismatch)
-//   [ ( ] ← Pop arrow (popped '[' ≠ '(')
-fn check_parentheses_and_brackets(input: &str) -> bool {
-    let mut stack: Vec<char> = Vec::new();
+// Exit status follows the checker: success only for balanced input.
+fn status(balanced: bool) -> ExitCode {
+    if balanced {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
 
-    for c in input.chars() {
-        match c {
-            '(' | '[' => stack.push(c), // Push opening symbols
-            ')' => {
-                // Pop and check for matching '('
-                if stack.pop() != Some('(') {
-                    return false;
-                }
-            }
-            ']' => {
-                // Pop and check for matching '['
-                if stack.pop() != Some('[') {
-                    return false;
-                }
+fn run(flag: &str, path: &str) -> io::Result<ExitCode> {
+    match flag {
+        "--stream" => {
+            let report = stream(path)?;
+            println!(
+                "Balanced: {} ({} bytes in {:.3}s, {:.2} GB/s)",
+                report.balanced,
+                report.bytes,
+                report.elapsed.as_secs_f64(),
+                report.throughput_gbps()
+            );
+            Ok(status(report.balanced))
+        }
+        "--rainbow" => {
+            print!("{}", render_ansi(&read_input(path)?));
+            Ok(ExitCode::SUCCESS)
+        }
+        #[cfg(feature = "serde")]
+        "--json" => {
+            let input = read_input(path)?;
+            let tree = parantheses_rs::tree::parse_tree(&input);
+            let report = serde_json::json!({
+                "balanced": tree.is_ok(),
+                "error": tree.as_ref().err(),
+                "tree": tree.as_ref().ok(),
+                "structure": parantheses_rs::recovery::recover(&input),
+            });
+            println!("{report}");
+            Ok(status(tree.is_ok()))
+        }
+        "--validate-json" => {
+            let mode = if path.ends_with(".ndjson") || path.ends_with(".jsonl") {
+                JsonMode::Lines
+            } else {
+                JsonMode::Single
+            };
+            let result = if path == "-" {
+                validate_reader(io::stdin().lock(), mode)?
+            } else {
+                validate_reader(File::open(path)?, mode)?
+            };
+            match result {
+                Ok(()) => println!("Valid JSON"),
+                Err(e) => println!("Invalid JSON at byte {}: {:?}", e.pos, e.kind),
             }
-            _ => continue,
+            Ok(status(result.is_ok()))
         }
+        _ => Ok(usage()),
     }
+}
+
+// Streams a file (or stdin for "-") through the checker without loading it.
+fn stream(path: &str) -> io::Result<StreamReport> {
+    if path == "-" {
+        check_reader(io::stdin().lock())
+    } else {
+        check_reader(File::open(path)?)
+    }
+}
 
-    // Stack must be empty for valid string, but this doesn't guarantee correct nesting
-    stack.is_empty()
+// Reads a whole file (or stdin for "-") as text.
+fn read_input(path: &str) -> io::Result<String> {
+    if path == "-" {
+        io::read_to_string(io::stdin())
+    } else {
+        std::fs::read_to_string(path)
+    }
 }
 
-fn main() {
+fn demo() {
     let tests_parentheses = vec!["(())", "()()", "(()", "())", "a(b)c"];
 
     println!("Testing parentheses-only checker:");
@@ -93,9 +142,7 @@ fn main() {
         println!("Input: {} -> Balanced: {}", test, check_parentheses(test));
     }
 
-    let tests_both = vec![
-        "([)]", "(())[]", "([]]", "[(])", "a(b)[c]",
-    ];
+    let tests_both = vec!["([)]", "(())[]", "([]]", "[(])", "a(b)[c]"];
 
     println!("\nTesting parentheses and brackets checker (limited by single stack):");
     for test in tests_both {
@@ -119,26 +166,11 @@ fn main() {
     println!(
         "- Since this language is limited to one stack, it cannot compute all Turing-computable functions."
     );
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_parentheses() {
-        assert_eq!(check_parentheses("(())"), true);
-        assert_eq!(check_parentheses("()()"), true);
-        assert_eq!(check_parentheses("(()"), false);
-        assert_eq!(check_parentheses("())"), false);
-        assert_eq!(check_parentheses("a(b)c"), true);
-    }
 
-    #[test]
-    fn test_parentheses_and_brackets() {
-        assert_eq!(check_parentheses_and_brackets("(())[]"), true);
-        assert_eq!(check_parentheses_and_brackets("([]]"), false);
-        assert_eq!(check_parentheses_and_brackets("a(b)[c]"), true);
-        assert_eq!(check_parentheses_and_brackets("([)]"), false);
+    println!("\nCounterpoint: a single queue is Turing complete (a^n b^n c^n, beyond any PDA):");
+    let machine = QueueMachine::anbncn();
+    for test in ["aabbcc", "aabbc", "abcabc"] {
+        let accepted = machine.run(test, 10_000).halt == Halt::Accepted;
+        println!("Input: {} -> Accepted: {}", test, accepted);
     }
 }
@@ -0,0 +1,158 @@
+// Parallel bracket matching by monoid reduction.
+//
+// Any chunk of input reduces to the closers it could not match (a prefix like
+// ")]") followed by the openers it left open (a suffix like "{(").
+// Two adjacent summaries merge by matching the left's open openers against the
+// right's unmatched closers, innermost first:
+//
+//   left = ")" + "[("     right = ")]" + "{"
+//   "(" meets ")" → ok, "[" meets "]" → ok
+//   merged = ")" + "{"
+//
+// The merge is associative, so chunks can be reduced in any grouping (here by
+// rayon) and the whole input is balanced iff the final summary is empty.
+// The rayon entry points need the `parallel` feature; `Summary` itself is
+// always available (the streaming checker folds it block by block).
+
+use alloc::vec::Vec;
+
+use crate::{PAIRS, closer_for};
+
+// CLOSER_OF[opener] and OPENER_OF[closer]; 0 for every other byte.
+const CLOSER_OF: [u8; 256] = partner_table(false);
+const OPENER_OF: [u8; 256] = partner_table(true);
+
+const fn partner_table(by_closer: bool) -> [u8; 256] {
+    let mut table = [0u8; 256];
+    let mut i = 0;
+    while i < PAIRS.len() {
+        let (open, close) = (PAIRS[i].0 as u8, PAIRS[i].1 as u8);
+        if by_closer {
+            table[close as usize] = open;
+        } else {
+            table[open as usize] = close;
+        }
+        i += 1;
+    }
+    table
+}
+
+/// Unmatched delimiters of a chunk, or a mismatch found inside it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Summary {
+    /// Closers with no opener in the chunk, in input order.
+    pub closers: Vec<u8>,
+    /// Openers with no closer in the chunk, in input order (top of stack last).
+    pub openers: Vec<u8>,
+    /// Set once an opener met a closer of a different kind.
+    pub mismatched: bool,
+}
+
+impl Summary {
+    /// Reduces one chunk. Delimiters are ASCII, so chunks may split UTF-8
+    /// sequences anywhere without affecting the result.
+    pub fn of(bytes: &[u8]) -> Self {
+        let mut summary = Summary::default();
+        for &b in bytes {
+            if CLOSER_OF[b as usize] != 0 {
+                summary.openers.push(b);
+            } else if OPENER_OF[b as usize] != 0 {
+                match summary.openers.pop() {
+                    Some(top) if top == OPENER_OF[b as usize] => {}
+                    Some(_) => summary.mismatched = true,
+                    None => summary.closers.push(b),
+                }
+            }
+        }
+        summary
+    }
+
+    /// Combines the summaries of two adjacent chunks, `self` first.
+    pub fn merge(mut self, mut right: Summary) -> Self {
+        if self.mismatched || right.mismatched {
+            self.mismatched = true;
+            return self;
+        }
+
+        let matched = self.openers.len().min(right.closers.len());
+        let pairs = self.openers.iter().rev().zip(&right.closers);
+        for (&open, &close) in pairs.take(matched) {
+            if closer_for(open as char) != Some(close as char) {
+                self.mismatched = true;
+                return self;
+            }
+        }
+
+        self.openers.truncate(self.openers.len() - matched);
+        self.closers.extend_from_slice(&right.closers[matched..]);
+        self.openers.append(&mut right.openers);
+        self
+    }
+
+    /// True when the summarized input is balanced on its own.
+    pub fn is_balanced(&self) -> bool {
+        !self.mismatched && self.closers.is_empty() && self.openers.is_empty()
+    }
+}
+
+/// Chunk size used by [`check_parallel`].
+#[cfg(feature = "parallel")]
+pub const CHUNK_SIZE: usize = 1 << 20;
+
+#[cfg(feature = "parallel")]
+/// Checks every pair in [`PAIRS`](crate::PAIRS) across all cores.
+/// Example: `check_parallel(b"([]{})")` → true, `check_parallel(b"([)]")` → false
+pub fn check_parallel(input: &[u8]) -> bool {
+    check_parallel_chunked(input, CHUNK_SIZE)
+}
+
+/// [`check_parallel`] with an explicit chunk size.
+#[cfg(feature = "parallel")]
+pub fn check_parallel_chunked(input: &[u8], chunk_size: usize) -> bool {
+    use rayon::prelude::*;
+
+    input
+        .par_chunks(chunk_size.max(1))
+        .map(Summary::of)
+        .reduce(Summary::default, Summary::merge)
+        .is_balanced()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_matches_across_chunks() {
+        let left = Summary::of(b")[(");
+        let right = Summary::of(b")]{");
+        let merged = left.merge(right);
+        assert_eq!(merged.closers, b")");
+        assert_eq!(merged.openers, b"{");
+        assert!(!merged.mismatched);
+    }
+
+    #[test]
+    fn test_merge_is_associative() {
+        let (a, b, c) = (Summary::of(b"(["), Summary::of(b"]x{"), Summary::of(b"})"));
+        let left_first = a.clone().merge(b.clone()).merge(c.clone());
+        let right_first = a.merge(b.merge(c));
+        assert_eq!(left_first, right_first);
+        assert!(left_first.is_balanced());
+    }
+
+    #[test]
+    #[cfg(feature = "parallel")]
+    fn test_every_chunk_size_agrees() {
+        for input in ["([]{})", "([)]", "(()", "a(b)[c]{d}", "))((", ""] {
+            let expected = Summary::of(input.as_bytes()).is_balanced();
+            for chunk_size in 1..=input.len().max(1) {
+                assert_eq!(
+                    check_parallel_chunked(input.as_bytes(), chunk_size),
+                    expected,
+                    "{input} / {chunk_size}"
+                );
+            }
+        }
+    }
+}
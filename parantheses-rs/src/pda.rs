@@ -0,0 +1,209 @@
+// Deterministic pushdown automata: the machine model behind the checkers.
+// A machine is a set of transitions
+//
+//   (state, input symbol or ε, stack top or anything) → (state, symbols to push)
+//
+// tried in definition order, so the first matching transition wins. A run
+// accepts when the input is consumed, no transition applies, and the machine
+// sits in an accepting state.
+//
+// `Pda::balanced_parentheses()` is `check_parentheses` as a machine:
+//   q --'(' / push '('--> q
+//   q --')' / pop '('---> q
+//   q --ε / top '$'-----> accept      ('$' marks the bottom of the stack)
+
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// One transition rule.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    pub from: String,
+    /// Input symbol consumed, or `None` for an ε-move.
+    pub read: Option<char>,
+    /// Stack top popped, or `None` to apply whatever is on top (nothing popped).
+    pub pop: Option<char>,
+    pub to: String,
+    /// Symbols pushed after the pop, last one ends on top.
+    pub push: Vec<char>,
+}
+
+/// A deterministic pushdown automaton.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pda {
+    pub start: String,
+    pub accepting: Vec<String>,
+    /// Symbol placed on the stack before the run, if any.
+    pub bottom: Option<char>,
+    pub transitions: Vec<Transition>,
+}
+
+/// An instantaneous description: state, input position (in chars) and stack.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub state: String,
+    pub pos: usize,
+    pub stack: Vec<char>,
+}
+
+/// How a run ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Halt {
+    Accepted,
+    Rejected,
+    /// The fuel ran out (e.g. an ε-loop that keeps pushing).
+    OutOfFuel,
+}
+
+/// A finished run: every configuration visited, in order, and the outcome.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Run {
+    pub configs: Vec<Config>,
+    pub halt: Halt,
+}
+
+impl Pda {
+    /// A machine with no transitions, starting (and not accepting) in `start`.
+    pub fn new(start: &str) -> Self {
+        Pda {
+            start: start.to_string(),
+            accepting: Vec::new(),
+            bottom: None,
+            transitions: Vec::new(),
+        }
+    }
+
+    /// Marks `state` as accepting.
+    pub fn accept(mut self, state: &str) -> Self {
+        self.accepting.push(state.to_string());
+        self
+    }
+
+    /// Starts every run with `symbol` on the stack.
+    pub fn bottom(mut self, symbol: char) -> Self {
+        self.bottom = Some(symbol);
+        self
+    }
+
+    /// Adds a transition; see [`Transition`] for the meaning of each part.
+    pub fn transition(
+        mut self,
+        from: &str,
+        read: Option<char>,
+        pop: Option<char>,
+        to: &str,
+        push: &[char],
+    ) -> Self {
+        self.transitions.push(Transition {
+            from: from.to_string(),
+            read,
+            pop,
+            to: to.to_string(),
+            push: push.to_vec(),
+        });
+        self
+    }
+
+    /// The balanced-parentheses machine described at the top of this module.
+    pub fn balanced_parentheses() -> Self {
+        Pda::new("q")
+            .accept("accept")
+            .bottom('$')
+            .transition("q", Some('('), None, "q", &['('])
+            .transition("q", Some(')'), Some('('), "q", &[])
+            .transition("q", None, Some('$'), "accept", &[])
+    }
+
+    /// The configuration a run over any input starts in.
+    pub fn initial(&self) -> Config {
+        Config {
+            state: self.start.clone(),
+            pos: 0,
+            stack: self.bottom.into_iter().collect(),
+        }
+    }
+
+    /// The first transition applicable in `config`, if any.
+    pub fn applicable(&self, input: &[char], config: &Config) -> Option<&Transition> {
+        let next = input.get(config.pos).copied();
+        let top = config.stack.last().copied();
+        self.transitions.iter().find(|t| {
+            t.from == config.state
+                && (t.read.is_none() || t.read == next)
+                && (t.pop.is_none() || t.pop == top)
+        })
+    }
+
+    /// Applies one transition, or returns `None` when the machine halts.
+    pub fn step(&self, input: &[char], config: &Config) -> Option<Config> {
+        let t = self.applicable(input, config)?;
+        let mut next = config.clone();
+        if t.read.is_some() {
+            next.pos += 1;
+        }
+        if t.pop.is_some() {
+            next.stack.pop();
+        }
+        next.stack.extend_from_slice(&t.push);
+        next.state = t.to.clone();
+        Some(next)
+    }
+
+    /// Whether a halted `config` accepts.
+    pub fn accepts(&self, input: &[char], config: &Config) -> bool {
+        config.pos == input.len() && self.accepting.contains(&config.state)
+    }
+
+    /// Runs to completion, taking at most `fuel` steps.
+    pub fn run(&self, input: &str, fuel: usize) -> Run {
+        let input: Vec<char> = input.chars().collect();
+        let mut configs = vec![self.initial()];
+        for _ in 0..fuel {
+            match self.step(&input, configs.last().unwrap()) {
+                Some(next) => configs.push(next),
+                None => {
+                    let halt = if self.accepts(&input, configs.last().unwrap()) {
+                        Halt::Accepted
+                    } else {
+                        Halt::Rejected
+                    };
+                    return Run { configs, halt };
+                }
+            }
+        }
+        Run {
+            configs,
+            halt: Halt::OutOfFuel,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_parentheses;
+
+    #[test]
+    fn test_balanced_parentheses_machine() {
+        let pda = Pda::balanced_parentheses();
+        for input in ["", "(())", "()()", "(()", "())", ")("] {
+            let accepted = pda.run(input, 100).halt == Halt::Accepted;
+            assert_eq!(accepted, check_parentheses(input), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_run_records_configs() {
+        let run = Pda::balanced_parentheses().run("()", 100);
+        let stacks: Vec<&[char]> = run.configs.iter().map(|c| c.stack.as_slice()).collect();
+        assert_eq!(stacks, vec![&['$'][..], &['$', '('], &['$'], &[]]);
+        assert_eq!(run.configs.last().unwrap().state, "accept");
+    }
+
+    #[test]
+    fn test_fuel_limit() {
+        let looping = Pda::new("q").transition("q", None, None, "q", &['x']);
+        assert_eq!(looping.run("", 10).halt, Halt::OutOfFuel);
+    }
+}
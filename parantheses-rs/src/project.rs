@@ -0,0 +1,379 @@
+// Repository-wide checking: walk a directory, pick the files matching a set
+// of globs, and check each one in parallel with the skipping rule for its
+// language, so a whole tree can be gated on balanced delimiters.
+//
+//   check_project("src", &["**/*.rs"])
+//   ├─ src/lib.rs       c-like  ok
+//   └─ src/broken.rs    c-like  line 3: unclosed '(' at byte 41
+//
+// Languages are chosen by extension (see `Language::for_path`); each one is a
+// `skip::check_with` callback. Hidden entries and `target` directories are
+// not descended into.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use rayon::prelude::*;
+
+use crate::skip::{check_with, hash, line_comment, string};
+use crate::spans::{MismatchError, MismatchKind};
+
+/// Comment and string conventions used to skip non-code regions.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Language {
+    /// `//` and `/* */` comments, `"..."` strings and short `'x'` literals.
+    CLike,
+    /// `#` comments with `'...'` and `"..."` strings (Python, shell, TOML...).
+    Hash,
+    /// `;` comments and `"..."` strings.
+    Lisp,
+    /// Every delimiter is structural.
+    Plain,
+}
+
+impl Language {
+    /// Picks the language from the file extension; unknown ones are `Plain`.
+    pub fn for_path(path: &Path) -> Language {
+        let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("");
+        match extension {
+            "rs" | "c" | "h" | "cc" | "cpp" | "hpp" | "java" | "js" | "ts" | "go" | "cs" | "kt"
+            | "swift" | "css" | "json" => Language::CLike,
+            "py" | "sh" | "rb" | "toml" | "yaml" | "yml" | "pl" | "r" => Language::Hash,
+            "lisp" | "el" | "clj" | "scm" | "rkt" => Language::Lisp,
+            _ => Language::Plain,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Language::CLike => "c-like",
+            Language::Hash => "hash",
+            Language::Lisp => "lisp",
+            Language::Plain => "plain",
+        }
+    }
+
+    /// Checks `input` with this language's skipping rule.
+    pub fn check(self, input: &str) -> Result<(), MismatchError> {
+        match self {
+            Language::CLike => check_with(input, c_like),
+            Language::Hash => check_with(input, hash),
+            Language::Lisp => check_with(input, |input, pos| {
+                line_comment(input, pos, ";").or_else(|| string(input, pos, &['"']))
+            }),
+            Language::Plain => check_with(input, |_, _| None),
+        }
+    }
+}
+
+// C-like rule. A `'` only starts a char literal if it is `'x'` or a short
+// escape like `'\n'`, so Rust lifetimes (`<'a>`) stay code.
+fn c_like(input: &str, pos: usize) -> Option<usize> {
+    let rest = &input[pos..];
+    if let Some(len) = line_comment(input, pos, "//") {
+        return Some(len);
+    }
+    if let Some(body) = rest.strip_prefix("/*") {
+        return Some(body.find("*/").map_or(rest.len(), |n| n + 4));
+    }
+    if let Some(len) = raw_string(input, pos) {
+        return Some(len);
+    }
+    if let Some(body) = rest.strip_prefix('\'') {
+        if body.starts_with('\\') {
+            let len = string(input, pos, &['\''])?;
+            return (len <= 12 && !rest[..len].contains('\n')).then_some(len);
+        }
+        let c = body.chars().next()?;
+        return body[c.len_utf8()..]
+            .starts_with('\'')
+            .then_some(c.len_utf8() + 2);
+    }
+    string(input, pos, &['"'])
+}
+
+// A Rust raw string, `r"..."` or `r#"..."#` (optionally `b`-prefixed), not
+// part of a longer identifier.
+fn raw_string(input: &str, pos: usize) -> Option<usize> {
+    let ident = |c: char| c.is_alphanumeric() || c == '_';
+    if input[..pos].chars().next_back().is_some_and(ident) {
+        return None;
+    }
+    let rest = &input[pos..];
+    let after_r = rest.strip_prefix("br").or_else(|| rest.strip_prefix('r'))?;
+    let hashes = after_r.len() - after_r.trim_start_matches('#').len();
+    let body = after_r[hashes..].strip_prefix('"')?;
+    let terminator = format!("\"{}", "#".repeat(hashes));
+    let prefix = rest.len() - body.len();
+    Some(
+        body.find(&terminator)
+            .map_or(rest.len(), |n| prefix + n + terminator.len()),
+    )
+}
+
+/// Outcome of checking one file.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "kebab-case"))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileStatus {
+    Balanced,
+    /// The first error, with its 1-based line.
+    Mismatch {
+        error: MismatchError,
+        line: usize,
+    },
+    /// The file could not be read as UTF-8 text.
+    Unreadable(String),
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileReport {
+    /// Relative to the project root.
+    pub path: PathBuf,
+    pub language: Language,
+    pub bytes: usize,
+    pub status: FileStatus,
+}
+
+/// Every checked file, sorted by path.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProjectReport {
+    pub files: Vec<FileReport>,
+}
+
+impl ProjectReport {
+    /// True if every file was read and is balanced.
+    pub fn is_balanced(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    pub fn failures(&self) -> impl Iterator<Item = &FileReport> {
+        self.files
+            .iter()
+            .filter(|f| f.status != FileStatus::Balanced)
+    }
+
+    /// One aligned row per file plus a totals line.
+    pub fn table(&self) -> String {
+        let width = self
+            .files
+            .iter()
+            .map(|f| f.path.display().to_string().len())
+            .max()
+            .unwrap_or(0)
+            .max("path".len());
+        let mut table = format!(
+            "{:width$}  {:8}  {:>9}  status\n",
+            "path", "language", "bytes"
+        );
+        for file in &self.files {
+            let status = match &file.status {
+                FileStatus::Balanced => "ok".to_string(),
+                FileStatus::Mismatch { error, line } => {
+                    let what = match error.kind {
+                        MismatchKind::UnexpectedClose => "unexpected",
+                        MismatchKind::Mismatched { .. } => "mismatched",
+                        MismatchKind::Unclosed => "unclosed",
+                    };
+                    format!(
+                        "line {line}: {what} '{}' at byte {}",
+                        error.found, error.pos
+                    )
+                }
+                FileStatus::Unreadable(reason) => format!("unreadable: {reason}"),
+            };
+            let _ = writeln!(
+                table,
+                "{:width$}  {:8}  {:>9}  {status}",
+                file.path.display(),
+                file.language.name(),
+                file.bytes
+            );
+        }
+        let _ = writeln!(
+            table,
+            "{} files, {} failing",
+            self.files.len(),
+            self.failures().count()
+        );
+        table
+    }
+
+    /// The report as JSON.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("report serializes")
+    }
+}
+
+/// Matches `path` (with `/` separators) against a glob: `*` and `?` stay
+/// within one path component, `**/` matches any number of directories.
+/// A pattern without `/` is matched against the file name only.
+/// Example: `glob_match("src/**/*.rs", "src/a/b.rs")` → true
+pub fn glob_match(pattern: &str, path: &str) -> bool {
+    if !pattern.contains('/') {
+        let name = path.rsplit('/').next().unwrap_or(path);
+        return matches(pattern.as_bytes(), name.as_bytes());
+    }
+    matches(pattern.as_bytes(), path.as_bytes())
+}
+
+fn matches(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => text.is_empty(),
+        [b'*', b'*', b'/', rest @ ..] => {
+            matches(rest, text)
+                || text
+                    .iter()
+                    .enumerate()
+                    .any(|(i, &b)| b == b'/' && matches(rest, &text[i + 1..]))
+        }
+        [b'*', rest @ ..] => (0..=text.len())
+            .take_while(|&i| i == 0 || text[i - 1] != b'/')
+            .any(|i| matches(rest, &text[i..])),
+        [b'?', rest @ ..] => {
+            matches!(text, [c, ..] if *c != b'/') && matches(rest, utf8_tail(text))
+        }
+        [p, rest @ ..] => matches!(text, [c, tail @ ..] if c == p && matches(rest, tail)),
+    }
+}
+
+// `text` without its first (possibly multi-byte) char.
+fn utf8_tail(text: &[u8]) -> &[u8] {
+    let len = text[1..].iter().take_while(|&&b| b & 0xC0 == 0x80).count();
+    &text[1 + len..]
+}
+
+// Every file below `dir`, skipping hidden entries and `target`.
+fn walk(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        let name = name.to_string_lossy();
+        if name.starts_with('.') {
+            continue;
+        }
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            if name != "target" {
+                walk(&path, files)?;
+            }
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+fn check_file(root: &Path, path: PathBuf) -> FileReport {
+    let language = Language::for_path(&path);
+    let relative = path.strip_prefix(root).unwrap_or(&path).to_path_buf();
+    let (bytes, status) = match fs::read_to_string(&path) {
+        Ok(text) => {
+            let status = match language.check(&text) {
+                Ok(()) => FileStatus::Balanced,
+                Err(error) => FileStatus::Mismatch {
+                    error,
+                    line: text[..error.pos].matches('\n').count() + 1,
+                },
+            };
+            (text.len(), status)
+        }
+        Err(e) => (0, FileStatus::Unreadable(e.to_string())),
+    };
+    FileReport {
+        path: relative,
+        language,
+        bytes,
+        status,
+    }
+}
+
+/// Checks every file under `root` whose relative path matches one of
+/// `globs` (all files if `globs` is empty), in parallel.
+pub fn check_project(root: impl AsRef<Path>, globs: &[&str]) -> io::Result<ProjectReport> {
+    let root = root.as_ref();
+    let mut paths = Vec::new();
+    walk(root, &mut paths)?;
+    paths.retain(|path| {
+        let relative = path.strip_prefix(root).unwrap_or(path);
+        let relative = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        globs.is_empty() || globs.iter().any(|glob| glob_match(glob, &relative))
+    });
+    let mut files: Vec<FileReport> = paths
+        .into_par_iter()
+        .map(|path| check_file(root, path))
+        .collect();
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(ProjectReport { files })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*.rs", "src/deep/lib.rs"));
+        assert!(glob_match("src/**/*.rs", "src/lib.rs"));
+        assert!(glob_match("src/**/*.rs", "src/a/b/c.rs"));
+        assert!(!glob_match("src/*.rs", "src/a/b.rs"));
+        assert!(glob_match("data/?.json", "data/é.json"));
+        assert!(!glob_match("*.rs", "lib.rs.bak"));
+    }
+
+    #[test]
+    fn test_language_rules() {
+        let rust = Language::for_path(Path::new("lib.rs"));
+        assert_eq!(
+            rust.check("fn f<'a>(s: &'a str) { g(')', \"]\") } // )"),
+            Ok(())
+        );
+        assert_eq!(rust.check("/* ( */ x['['] + y['\\''] "), Ok(()));
+        assert_eq!(rust.check("f(r#\"(\"]\"#, br\")\")"), Ok(()));
+        assert!(rust.check("for_r(\")\")").is_ok() && rust.check("r(]").is_err());
+        let python = Language::for_path(Path::new("tool.py"));
+        assert_eq!(python.check("print('(')  # )"), Ok(()));
+        let lisp = Language::for_path(Path::new("init.el"));
+        assert_eq!(lisp.check("(a \")\") ; ("), Ok(()));
+        assert!(Language::Plain.check("print('(')").is_err());
+    }
+
+    #[test]
+    fn test_check_project() -> io::Result<()> {
+        let root = std::env::temp_dir().join(format!("parantheses-project-{}", std::process::id()));
+        fs::create_dir_all(root.join("src/nested"))?;
+        fs::create_dir_all(root.join("target"))?;
+        fs::write(root.join("src/lib.rs"), "fn f() { g(\"(\") }\n")?;
+        fs::write(root.join("src/nested/bad.rs"), "fn f() {\n    g(\n")?;
+        fs::write(root.join("src/notes.txt"), "(")?;
+        fs::write(root.join("target/out.rs"), "(")?;
+
+        let report = check_project(&root, &["**/*.rs"])?;
+        fs::remove_dir_all(&root)?;
+        let paths: Vec<&Path> = report.files.iter().map(|f| f.path.as_path()).collect();
+        assert_eq!(
+            paths,
+            [Path::new("src/lib.rs"), Path::new("src/nested/bad.rs")]
+        );
+        assert!(!report.is_balanced());
+        assert!(matches!(
+            report.files[1].status,
+            FileStatus::Mismatch { line: 2, .. }
+        ));
+        let table = report.table();
+        assert!(table.contains("line 2: unclosed '(' at byte 14"), "{table}");
+        assert!(table.ends_with("2 files, 1 failing\n"));
+        Ok(())
+    }
+}
@@ -0,0 +1,182 @@
+// Queue automata: the same finite control as a PDA, but memory is one FIFO
+// queue instead of one stack. The input starts in the queue followed by a
+// '#' end marker; each step dequeues one symbol and enqueues a string.
+//
+// Unlike a single stack, a single queue is Turing complete: by rotating the
+// whole content once per pass the machine can reach any cell, so it can
+// simulate a Turing machine's tape (the queue is the tape read from the head
+// around to just before it).
+//
+// `QueueMachine::anbncn()` decides { aⁿbⁿcⁿ }, which no PDA can. Each pass
+// rotates the queue, dropping the first a, b and c it meets:
+//   "aabbcc#" → "abc#" → "#" → accept
+
+use alloc::collections::VecDeque;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::pda::Halt;
+
+/// End marker enqueued after the input.
+pub const END: char = '#';
+
+/// (state, dequeued symbol) → (state, symbols to enqueue).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueTransition {
+    pub from: String,
+    pub read: char,
+    pub to: String,
+    pub write: Vec<char>,
+}
+
+/// A deterministic queue automaton.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueMachine {
+    pub start: String,
+    pub accepting: Vec<String>,
+    pub transitions: Vec<QueueTransition>,
+}
+
+/// Machine state plus queue content (front first).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueConfig {
+    pub state: String,
+    pub queue: VecDeque<char>,
+}
+
+/// A finished run: every configuration visited, in order, and the outcome.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueueRun {
+    pub configs: Vec<QueueConfig>,
+    pub halt: Halt,
+}
+
+impl QueueMachine {
+    /// A machine with no transitions, starting in `start`.
+    pub fn new(start: &str) -> Self {
+        QueueMachine {
+            start: start.to_string(),
+            accepting: Vec::new(),
+            transitions: Vec::new(),
+        }
+    }
+
+    /// Marks `state` as accepting; entering it halts the run.
+    pub fn accept(mut self, state: &str) -> Self {
+        self.accepting.push(state.to_string());
+        self
+    }
+
+    /// Adds a transition that dequeues `read` and enqueues `write`.
+    pub fn transition(mut self, from: &str, read: char, to: &str, write: &[char]) -> Self {
+        self.transitions.push(QueueTransition {
+            from: from.to_string(),
+            read,
+            to: to.to_string(),
+            write: write.to_vec(),
+        });
+        self
+    }
+
+    /// The { aⁿbⁿcⁿ } decider described at the top of this module.
+    pub fn anbncn() -> Self {
+        QueueMachine::new("pass")
+            .accept("accept")
+            .transition("pass", END, "accept", &[])
+            .transition("pass", 'a', "a", &[])
+            .transition("a", 'a', "a", &['a'])
+            .transition("a", 'b', "b", &[])
+            .transition("b", 'b', "b", &['b'])
+            .transition("b", 'c', "c", &[])
+            .transition("c", 'c', "c", &['c'])
+            .transition("c", END, "pass", &[END])
+    }
+
+    /// The configuration a run over `input` starts in.
+    pub fn initial(&self, input: &str) -> QueueConfig {
+        QueueConfig {
+            state: self.start.clone(),
+            queue: input.chars().chain([END]).collect(),
+        }
+    }
+
+    /// Applies one transition, or returns `None` when the machine halts.
+    pub fn step(&self, config: &QueueConfig) -> Option<QueueConfig> {
+        if self.accepting.contains(&config.state) {
+            return None;
+        }
+        let &front = config.queue.front()?;
+        let t = self
+            .transitions
+            .iter()
+            .find(|t| t.from == config.state && t.read == front)?;
+        let mut next = config.clone();
+        next.queue.pop_front();
+        next.queue.extend(t.write.iter().copied());
+        next.state = t.to.clone();
+        Some(next)
+    }
+
+    /// Runs to completion, taking at most `fuel` steps.
+    pub fn run(&self, input: &str, fuel: usize) -> QueueRun {
+        let mut configs = Vec::from([self.initial(input)]);
+        for _ in 0..fuel {
+            match self.step(configs.last().unwrap()) {
+                Some(next) => configs.push(next),
+                None => {
+                    let accepted = self.accepting.contains(&configs.last().unwrap().state);
+                    let halt = if accepted {
+                        Halt::Accepted
+                    } else {
+                        Halt::Rejected
+                    };
+                    return QueueRun { configs, halt };
+                }
+            }
+        }
+        QueueRun {
+            configs,
+            halt: Halt::OutOfFuel,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_anbncn() {
+        let machine = QueueMachine::anbncn();
+        for (input, expected) in [
+            ("", true),
+            ("abc", true),
+            ("aaabbbccc", true),
+            ("aabbc", false),
+            ("abcabc", false),
+            ("aabcc", false),
+            ("cba", false),
+        ] {
+            let accepted = machine.run(input, 1000).halt == Halt::Accepted;
+            assert_eq!(accepted, expected, "{input}");
+        }
+    }
+
+    #[test]
+    fn test_passes_rotate_the_queue() {
+        let run = QueueMachine::anbncn().run("aabbcc", 1000);
+        let pass_starts: Vec<String> = run
+            .configs
+            .iter()
+            .filter(|c| c.state == "pass")
+            .map(|c| c.queue.iter().collect())
+            .collect();
+        assert_eq!(pass_starts, vec!["aabbcc#", "abc#", "#"]);
+    }
+
+    #[test]
+    fn test_fuel_limit() {
+        let looping = QueueMachine::new("q").transition("q", END, "q", &[END]);
+        assert_eq!(looping.run("", 10).halt, Halt::OutOfFuel);
+    }
+}
@@ -0,0 +1,117 @@
+// Rainbow-bracket rendering: re-emits the input with every delimiter
+// coloured by its nesting depth, using the error-recovering pairing to know
+// which delimiters matched. Only the delimiters that pair with nothing are
+// marked as errors, so everything after a mismatch is still coloured.
+
+use std::collections::HashMap;
+
+use crate::recovery::recover;
+use crate::{closer_for, opener_for};
+
+/// ANSI foreground colours cycled through by depth.
+const ANSI_COLORS: &[&str] = &["31", "33", "32", "36", "34", "35"];
+const ANSI_ERROR: &str = "1;41";
+const ANSI_RESET: &str = "\x1b[0m";
+
+// Depth of every matched delimiter, keyed by byte offset.
+fn delimiter_depths(input: &str) -> HashMap<usize, usize> {
+    let mut depths = HashMap::new();
+    for span in recover(input).pairs {
+        depths.insert(span.open, span.depth);
+        depths.insert(span.close, span.depth);
+    }
+    depths
+}
+
+fn is_delimiter(c: char) -> bool {
+    closer_for(c).is_some() || opener_for(c).is_some()
+}
+
+/// Colours delimiters with ANSI escapes; unmatched ones get a red background.
+/// Example: `render_ansi("(a)")` → "\x1b[31m(\x1b[0ma\x1b[31m)\x1b[0m"
+pub fn render_ansi(input: &str) -> String {
+    let depths = delimiter_depths(input);
+    let mut output = String::with_capacity(input.len() * 2);
+
+    for (pos, c) in input.char_indices() {
+        if !is_delimiter(c) {
+            output.push(c);
+            continue;
+        }
+        let color = match depths.get(&pos) {
+            Some(&depth) => ANSI_COLORS[depth % ANSI_COLORS.len()],
+            None => ANSI_ERROR,
+        };
+        output.push_str(&format!("\x1b[{color}m{c}{ANSI_RESET}"));
+    }
+
+    output
+}
+
+/// Wraps delimiters in `<span class="depth-N">` (cycling N through 0..6),
+/// or `<span class="unmatched">`, and HTML-escapes everything else.
+/// Example: `render_html("(<)")` → `<span class="depth-0">(</span>&lt;<span class="depth-0">)</span>`
+pub fn render_html(input: &str) -> String {
+    let depths = delimiter_depths(input);
+    let mut output = String::with_capacity(input.len() * 4);
+
+    for (pos, c) in input.char_indices() {
+        match c {
+            '&' => output.push_str("&amp;"),
+            '<' => output.push_str("&lt;"),
+            '>' => output.push_str("&gt;"),
+            '"' => output.push_str("&quot;"),
+            c if is_delimiter(c) => match depths.get(&pos) {
+                Some(depth) => output.push_str(&format!(
+                    "<span class=\"depth-{}\">{c}</span>",
+                    depth % ANSI_COLORS.len()
+                )),
+                None => output.push_str(&format!("<span class=\"unmatched\">{c}</span>")),
+            },
+            c => output.push(c),
+        }
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ansi_by_depth() {
+        assert_eq!(
+            render_ansi("([])"),
+            "\x1b[31m(\x1b[0m\x1b[33m[\x1b[0m\x1b[33m]\x1b[0m\x1b[31m)\x1b[0m"
+        );
+    }
+
+    #[test]
+    fn test_unmatched_marked() {
+        assert_eq!(render_ansi("x)"), "x\x1b[1;41m)\x1b[0m");
+    }
+
+    #[test]
+    fn test_pairs_after_a_mismatch() {
+        assert_eq!(
+            render_ansi("]()"),
+            "\x1b[1;41m]\x1b[0m\x1b[31m(\x1b[0m\x1b[31m)\x1b[0m"
+        );
+        assert_eq!(
+            render_html("(]){}"),
+            "<span class=\"depth-0\">(</span><span class=\"unmatched\">]</span>\
+             <span class=\"depth-0\">)</span><span class=\"depth-0\">{</span>\
+             <span class=\"depth-0\">}</span>"
+        );
+    }
+
+    #[test]
+    fn test_html_escapes_and_depth_classes() {
+        assert_eq!(
+            render_html("(a<b)]"),
+            "<span class=\"depth-0\">(</span>a&lt;b<span class=\"depth-0\">)</span>\
+             <span class=\"unmatched\">]</span>"
+        );
+    }
+}
@@ -0,0 +1,127 @@
+// Error-recovering structure: instead of stopping at the first mismatch like
+// `pair_spans`, keep matching and collect the delimiters that could not be
+// paired as orphans.
+//
+// A closer that matches an opener deeper in the stack closes it, orphaning
+// the openers above it; a closer that matches nothing is itself an orphan.
+//
+// `recover("([)]")`:
+//   [ ]
+// Read '(' → push                 [ ( ]
+// Read '[' → push                 [ ( , [ ]
+// Read ')' → '(' is deeper        [ ]      → pair 0..=2, '[' at 1 orphaned
+// Read ']' → nothing to match     [ ]      → ']' at 3 orphaned
+
+use alloc::vec::Vec;
+
+use crate::spans::PairSpan;
+use crate::{closer_for, opener_for};
+
+/// A delimiter that could not be paired.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Orphan {
+    pub pos: usize,
+    pub ch: char,
+}
+
+impl Orphan {
+    /// True for an unclosed opener, false for a stray closer.
+    pub fn is_opener(&self) -> bool {
+        closer_for(self.ch).is_some()
+    }
+}
+
+/// Best-effort nesting structure of possibly unbalanced input.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PartialStructure {
+    /// Matched pairs, ordered by opening position.
+    pub pairs: Vec<PairSpan>,
+    /// Unpaired delimiters, ordered by position.
+    pub orphans: Vec<Orphan>,
+}
+
+impl PartialStructure {
+    /// True when nothing was orphaned.
+    pub fn is_balanced(&self) -> bool {
+        self.orphans.is_empty()
+    }
+}
+
+/// Matches as much of `input` as possible and reports the rest as orphans.
+/// A pair's depth counts every opener still open around it, including ones
+/// orphaned later because nothing closes them.
+/// Example: `recover("(a]")` → no pairs, orphans '(' at 0 and ']' at 2
+pub fn recover(input: &str) -> PartialStructure {
+    let mut stack: Vec<(usize, char)> = Vec::new();
+    let mut structure = PartialStructure::default();
+
+    for (pos, c) in input.char_indices() {
+        if closer_for(c).is_some() {
+            stack.push((pos, c));
+        } else if let Some(open_char) = opener_for(c) {
+            match stack.iter().rposition(|&(_, top)| top == open_char) {
+                Some(index) => {
+                    for (pos, ch) in stack.drain(index + 1..) {
+                        structure.orphans.push(Orphan { pos, ch });
+                    }
+                    let (open, _) = stack.pop().unwrap();
+                    structure.pairs.push(PairSpan {
+                        open,
+                        close: pos,
+                        open_char,
+                        close_char: c,
+                        depth: stack.len(),
+                    });
+                }
+                None => structure.orphans.push(Orphan { pos, ch: c }),
+            }
+        }
+    }
+
+    structure
+        .orphans
+        .extend(stack.into_iter().map(|(pos, ch)| Orphan { pos, ch }));
+    structure.pairs.sort_by_key(|p| p.open);
+    structure.orphans.sort_by_key(|o| o.pos);
+    structure
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn positions(structure: &PartialStructure) -> (Vec<(usize, usize)>, Vec<usize>) {
+        (
+            structure.pairs.iter().map(|p| (p.open, p.close)).collect(),
+            structure.orphans.iter().map(|o| o.pos).collect(),
+        )
+    }
+
+    #[test]
+    fn test_balanced_has_no_orphans() {
+        let structure = recover("(a[b]){}");
+        assert!(structure.is_balanced());
+        assert_eq!(positions(&structure).0, vec![(0, 5), (2, 4), (6, 7)]);
+    }
+
+    #[test]
+    fn test_interleaved() {
+        let structure = recover("([)]");
+        assert_eq!(positions(&structure), (vec![(0, 2)], vec![1, 3]));
+        assert!(structure.orphans[0].is_opener());
+        assert!(!structure.orphans[1].is_opener());
+    }
+
+    #[test]
+    fn test_everything_matched_except_two() {
+        let structure = recover("f(x, [1, 2) { g(]) }");
+        assert_eq!(structure.orphans.len(), 2);
+        assert_eq!(structure.pairs.len(), 3);
+        // "{ g(]) }": '(' is inside '{', and the stray ']' does not count.
+        assert_eq!(structure.pairs[2].depth, 1);
+        // The unclosed '(' around "(a)" still counts.
+        assert_eq!(recover("((a)").pairs[0].depth, 1);
+    }
+}
@@ -0,0 +1,213 @@
+// Minimum-edit repair of unbalanced input.
+//
+// Interval DP over the delimiter characters only: `cost[i][j]` is the fewest
+// edits that balance delimiters `i..j`. Either delimiter `i` is fixed on its own
+// (one edit), or it is an opener matched with some closer `k` in the range,
+// splitting the problem into `i+1..k` and `k+1..j`. Non-delimiter characters
+// never cost anything and are left untouched.
+//
+// This is O(n^3) time and O(n^2) memory in the number of delimiters, so it is
+// meant for source-line or file-sized inputs, not multi-megabyte dumps.
+
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{closer_for, opener_for};
+
+/// A single edit against the original input. Positions are byte offsets.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Edit {
+    /// Insert `ch` before the byte at `at` (or at the end when `at == input.len()`).
+    Insert { at: usize, ch: char },
+    /// Delete the delimiter `ch` starting at byte `at`.
+    Delete { at: usize, ch: char },
+}
+
+impl Edit {
+    /// Byte offset the edit applies to.
+    pub fn position(&self) -> usize {
+        match *self {
+            Edit::Insert { at, .. } | Edit::Delete { at, .. } => at,
+        }
+    }
+}
+
+/// A minimal list of edits that balances an input, ordered by position.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RepairScript {
+    pub edits: Vec<Edit>,
+}
+
+impl RepairScript {
+    /// Number of edits, which is the minimal repair cost.
+    pub fn cost(&self) -> usize {
+        self.edits.len()
+    }
+
+    /// True when the input was already balanced.
+    pub fn is_empty(&self) -> bool {
+        self.edits.is_empty()
+    }
+
+    /// Applies the script to the input it was computed for.
+    pub fn apply(&self, input: &str) -> String {
+        let mut output = String::with_capacity(input.len() + self.edits.len());
+        let mut edits = self.edits.iter().peekable();
+
+        for (pos, ch) in input.char_indices() {
+            let mut deleted = false;
+            while let Some(edit) = edits.next_if(|e| e.position() == pos) {
+                match *edit {
+                    Edit::Insert { ch, .. } => output.push(ch),
+                    Edit::Delete { .. } => deleted = true,
+                }
+            }
+            if !deleted {
+                output.push(ch);
+            }
+        }
+        for edit in edits {
+            if let Edit::Insert { ch, .. } = *edit {
+                output.push(ch);
+            }
+        }
+
+        output
+    }
+}
+
+// Marks a range whose first delimiter is repaired on its own.
+const ORPHAN: u32 = u32::MAX;
+
+/// Computes the provably minimal set of insertions/deletions that balances
+/// every pair in [`PAIRS`](crate::PAIRS).
+///
+/// Unclosed openers get their closer inserted at the end of the enclosing
+/// region (so `foo(bar` becomes `foo(bar)`), and stray closers are deleted.
+/// Example: `min_repair("([)]").cost()` → 2
+pub fn min_repair(input: &str) -> RepairScript {
+    let tokens: Vec<(usize, char)> = input
+        .char_indices()
+        .filter(|&(_, c)| closer_for(c).is_some() || opener_for(c).is_some())
+        .collect();
+    let n = tokens.len();
+    let width = n + 1;
+
+    // cost[i * width + j] and choice[i * width + j] describe tokens[i..j].
+    let mut cost = vec![0u32; width * width];
+    let mut choice = vec![ORPHAN; width * width];
+
+    for i in (0..n).rev() {
+        let closer = closer_for(tokens[i].1);
+        for j in i + 1..=n {
+            let mut best = 1 + cost[(i + 1) * width + j];
+            let mut best_k = ORPHAN;
+            if let Some(closer) = closer {
+                for k in i + 1..j {
+                    if tokens[k].1 == closer {
+                        let c = cost[(i + 1) * width + k] + cost[(k + 1) * width + j];
+                        if c < best {
+                            best = c;
+                            best_k = k as u32;
+                        }
+                    }
+                }
+            }
+            cost[i * width + j] = best;
+            choice[i * width + j] = best_k;
+        }
+    }
+
+    let mut edits = Vec::with_capacity(cost[n] as usize);
+    collect_edits(&tokens, &choice, width, 0, n, input.len(), &mut edits);
+    // Stable sort: edits sharing a position were pushed innermost first.
+    edits.sort_by_key(Edit::position);
+
+    RepairScript { edits }
+}
+
+// Walks the choice table for tokens[i..j]; `end` is where that region stops.
+fn collect_edits(
+    tokens: &[(usize, char)],
+    choice: &[u32],
+    width: usize,
+    i: usize,
+    j: usize,
+    end: usize,
+    edits: &mut Vec<Edit>,
+) {
+    if i >= j {
+        return;
+    }
+
+    let (at, ch) = tokens[i];
+    match choice[i * width + j] {
+        ORPHAN => {
+            collect_edits(tokens, choice, width, i + 1, j, end, edits);
+            match closer_for(ch) {
+                Some(closer) => edits.push(Edit::Insert {
+                    at: end,
+                    ch: closer,
+                }),
+                None => edits.push(Edit::Delete { at, ch }),
+            }
+        }
+        k => {
+            let k = k as usize;
+            collect_edits(tokens, choice, width, i + 1, k, tokens[k].0, edits);
+            collect_edits(tokens, choice, width, k + 1, j, end, edits);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_parentheses_and_brackets;
+
+    #[test]
+    fn test_balanced_needs_no_edits() {
+        assert!(min_repair("a(b)[c]").is_empty());
+        assert!(min_repair("").is_empty());
+    }
+
+    #[test]
+    fn test_unclosed_opener_closed_at_region_end() {
+        let script = min_repair("foo(bar");
+        assert_eq!(script.edits, vec![Edit::Insert { at: 7, ch: ')' }]);
+        assert_eq!(script.apply("foo(bar"), "foo(bar)");
+    }
+
+    #[test]
+    fn test_stray_closer_deleted() {
+        let script = min_repair("(a))");
+        assert_eq!(script.edits, vec![Edit::Delete { at: 3, ch: ')' }]);
+        assert_eq!(script.apply("(a))"), "(a)");
+    }
+
+    #[test]
+    fn test_minimal_cost_and_valid_result() {
+        for (input, cost) in [
+            ("([)]", 2),
+            ("(()", 1),
+            ("([", 2),
+            ("][", 2),
+            ("((a)[b]", 1),
+        ] {
+            let script = min_repair(input);
+            assert_eq!(script.cost(), cost, "{input}");
+            assert!(
+                check_parentheses_and_brackets(&script.apply(input)),
+                "{input}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_nested_inserts_at_same_position() {
+        assert_eq!(min_repair("([").apply("(["), "([])");
+    }
+}
@@ -0,0 +1,179 @@
+// One pass, one result: `analyze` walks the input once with the recovering
+// stack from `recovery` and fills in everything the separate APIs report:
+// validity and the first error (`pair_spans`), orphans and pairs
+// (`recover`), depth and pair counts (`stats`), and optionally the spans and
+// the step trace (`trace`).
+//
+// Until the first mismatch the recovering stack and the strict one agree, so
+// the first error and the trace are exactly what the strict checkers see.
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::recovery::Orphan;
+use crate::spans::{MismatchError, MismatchKind, PairSpan};
+use crate::trace::{Action, Step, Trace};
+use crate::{PAIRS, closer_for, opener_for};
+
+/// Which of the larger parts of a [`BalanceReport`] to collect.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct AnalyzeOptions {
+    /// Every matched pair, ordered by opening position.
+    pub spans: bool,
+    /// The step-by-step trace, up to the first error.
+    pub trace: bool,
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BalanceReport {
+    pub balanced: bool,
+    /// The first mismatch, as [`pair_spans`](crate::spans::pair_spans) reports it.
+    pub error: Option<MismatchError>,
+    /// Every delimiter that could not be paired, ordered by position.
+    pub orphans: Vec<Orphan>,
+    /// Deepest nesting of matched pairs.
+    pub max_depth: usize,
+    /// Matched pairs of each kind, in [`PAIRS`] order.
+    pub pair_counts: Vec<usize>,
+    pub spans: Option<Vec<PairSpan>>,
+    pub trace: Option<Trace>,
+}
+
+/// Analyzes `input` in a single pass.
+/// Example: `analyze("([)]", AnalyzeOptions::default())` → not balanced, error
+/// at 2, orphans '[' at 1 and ']' at 3, one `()` pair
+pub fn analyze(input: &str, options: AnalyzeOptions) -> BalanceReport {
+    let mut stack: Vec<(usize, char)> = Vec::new();
+    let mut error: Option<MismatchError> = None;
+    let mut orphans = Vec::new();
+    let mut spans = Vec::new();
+    let mut steps = Vec::new();
+    let mut max_depth = 0;
+    let mut pair_counts = vec![0; PAIRS.len()];
+    let snapshot = |stack: &[(usize, char)]| stack.iter().map(|&(_, c)| c).collect();
+
+    for (pos, c) in input.char_indices() {
+        let action = if closer_for(c).is_some() {
+            stack.push((pos, c));
+            Action::Push
+        } else if let Some(open_char) = opener_for(c) {
+            let action = match stack.last() {
+                Some(&(_, top)) if top == open_char => Action::Pop,
+                top => {
+                    if error.is_none() {
+                        let kind = match top {
+                            Some(&(open, top)) => MismatchKind::Mismatched {
+                                open,
+                                expected: closer_for(top).unwrap(),
+                            },
+                            None => MismatchKind::UnexpectedClose,
+                        };
+                        error = Some(MismatchError {
+                            pos,
+                            found: c,
+                            kind,
+                        });
+                        if options.trace {
+                            steps.push(Step {
+                                pos,
+                                ch: c,
+                                action: Action::Mismatch,
+                                stack: snapshot(&stack),
+                            });
+                        }
+                    }
+                    Action::Mismatch
+                }
+            };
+            match stack.iter().rposition(|&(_, top)| top == open_char) {
+                Some(index) => {
+                    for (pos, ch) in stack.drain(index + 1..) {
+                        orphans.push(Orphan { pos, ch });
+                    }
+                    let (open, _) = stack.pop().unwrap();
+                    max_depth = max_depth.max(stack.len() + 1);
+                    pair_counts[PAIRS.iter().position(|&(o, _)| o == open_char).unwrap()] += 1;
+                    if options.spans {
+                        spans.push(PairSpan {
+                            open,
+                            close: pos,
+                            open_char,
+                            close_char: c,
+                            depth: stack.len(),
+                        });
+                    }
+                }
+                None => orphans.push(Orphan { pos, ch: c }),
+            }
+            action
+        } else {
+            continue;
+        };
+        if options.trace && error.is_none() {
+            steps.push(Step {
+                pos,
+                ch: c,
+                action,
+                stack: snapshot(&stack),
+            });
+        }
+    }
+
+    if error.is_none() {
+        error = stack.last().map(|&(pos, found)| MismatchError {
+            pos,
+            found,
+            kind: MismatchKind::Unclosed,
+        });
+    }
+    orphans.extend(stack.into_iter().map(|(pos, ch)| Orphan { pos, ch }));
+    orphans.sort_by_key(|o| o.pos);
+    spans.sort_by_key(|s| s.open);
+
+    BalanceReport {
+        balanced: error.is_none(),
+        error,
+        orphans,
+        max_depth,
+        pair_counts,
+        spans: options.spans.then_some(spans),
+        trace: options.trace.then_some(Trace { steps, error }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::recovery::recover;
+    use crate::spans::pair_spans;
+    use crate::trace::trace;
+
+    const ALL: AnalyzeOptions = AnalyzeOptions {
+        spans: true,
+        trace: true,
+    };
+
+    #[test]
+    fn test_agrees_with_separate_passes() {
+        for input in ["f(a[0], {b})", "([)]", "(()", "a)b(", "{[()()]}", ""] {
+            let report = analyze(input, ALL);
+            let first_error = pair_spans(input).find_map(Result::err);
+            assert_eq!(report.error, first_error, "{input}");
+            assert_eq!(report.balanced, first_error.is_none(), "{input}");
+            let structure = recover(input);
+            assert_eq!(report.orphans, structure.orphans, "{input}");
+            assert_eq!(report.spans.as_ref(), Some(&structure.pairs), "{input}");
+            assert_eq!(report.trace, Some(trace(input)), "{input}");
+        }
+    }
+
+    #[test]
+    fn test_depth_and_counts() {
+        let report = analyze("f(a[0], {b}) ([x])", AnalyzeOptions::default());
+        assert!(report.balanced);
+        assert_eq!(report.max_depth, 2);
+        assert_eq!(report.pair_counts, vec![2, 2, 1]);
+        assert_eq!((report.spans, report.trace), (None, None));
+    }
+}
@@ -0,0 +1,338 @@
+// S-expressions on top of `pair_spans`: a tiny Lisp reader.
+//
+// Reading happens in two passes. First string literals and `;` comments are
+// blanked out (byte offsets stay the same) and the rest goes through
+// `pair_spans`, so every list's extent, and any bracket error, comes from the
+// same single-stack matcher as the checkers. Then each list is filled in from
+// the original text between its delimiters:
+//
+//   (define (sq x) "(x²)")
+//   └─ pair 0..=22 ─ atom define, pair 8..=13, string "(x²)"
+//
+// `(`, `[` and `{` all open lists; `'x` reads as `(quote x)`.
+
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::spans::{MismatchError, pair_spans};
+use crate::{closer_for, opener_for};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SExpr {
+    Atom(String),
+    /// A string literal, escapes resolved.
+    Str(String),
+    List(Vec<SExpr>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SExprErrorKind {
+    Brackets(MismatchError),
+    /// A `"` without its closing quote.
+    UnterminatedString,
+    /// A backslash escape other than `\" \\ \n \t \r`.
+    InvalidEscape(char),
+    /// A `'` with no expression after it.
+    DanglingQuote,
+    /// No expression at all.
+    Empty,
+    /// More than one top-level expression where one was expected.
+    TrailingInput,
+}
+
+/// A read error at byte offset `pos`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SExprError {
+    pub pos: usize,
+    pub kind: SExprErrorKind,
+}
+
+// Copy of `input` with strings and comments replaced by spaces.
+fn mask(input: &str) -> Result<String, SExprError> {
+    let mut masked = String::with_capacity(input.len());
+    let mut chars = input.char_indices();
+    let blank =
+        |masked: &mut String, c: char| masked.extend(core::iter::repeat_n(' ', c.len_utf8()));
+
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            ';' => {
+                blank(&mut masked, c);
+                for (_, c) in chars.by_ref() {
+                    if c == '\n' {
+                        masked.push(c);
+                        break;
+                    }
+                    blank(&mut masked, c);
+                }
+            }
+            '"' => {
+                blank(&mut masked, c);
+                let mut escaped = false;
+                loop {
+                    let Some((_, c)) = chars.next() else {
+                        return Err(SExprError {
+                            pos,
+                            kind: SExprErrorKind::UnterminatedString,
+                        });
+                    };
+                    blank(&mut masked, c);
+                    match c {
+                        _ if escaped => escaped = false,
+                        '\\' => escaped = true,
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            _ => masked.push(c),
+        }
+    }
+    Ok(masked)
+}
+
+struct Reader<'a> {
+    input: &'a str,
+    // Opener offset → closer offset, for every list.
+    lists: BTreeMap<usize, usize>,
+}
+
+fn is_atom_char(c: char) -> bool {
+    !(c.is_whitespace()
+        || matches!(c, '"' | ';' | '\'')
+        || closer_for(c).is_some()
+        || opener_for(c).is_some())
+}
+
+impl Reader<'_> {
+    // Reads every expression in `start..end`.
+    fn read_all(&self, start: usize, end: usize) -> Result<Vec<SExpr>, SExprError> {
+        let mut exprs = Vec::new();
+        let mut pos = start;
+        while let Some((_, expr, next)) = self.read(pos, end)? {
+            exprs.push(expr);
+            pos = next;
+        }
+        Ok(exprs)
+    }
+
+    // Reads the next expression at or after `pos`, returning where it starts,
+    // the expression and the offset after it, or `None` if only whitespace
+    // and comments remain.
+    fn read(
+        &self,
+        mut pos: usize,
+        end: usize,
+    ) -> Result<Option<(usize, SExpr, usize)>, SExprError> {
+        while let Some(c) = self.input[pos..end].chars().next() {
+            match c {
+                c if c.is_whitespace() => pos += c.len_utf8(),
+                ';' => pos = self.input[pos..end].find('\n').map_or(end, |n| pos + n),
+                '\'' => {
+                    return match self.read(pos + 1, end)? {
+                        Some((_, quoted, next)) => Ok(Some((
+                            pos,
+                            SExpr::List(vec![SExpr::Atom("quote".to_string()), quoted]),
+                            next,
+                        ))),
+                        None => Err(SExprError {
+                            pos,
+                            kind: SExprErrorKind::DanglingQuote,
+                        }),
+                    };
+                }
+                '"' => {
+                    return self
+                        .read_string(pos)
+                        .map(|(expr, next)| Some((pos, expr, next)));
+                }
+                _ if self.lists.contains_key(&pos) => {
+                    let close = self.lists[&pos];
+                    let items = self.read_all(pos + c.len_utf8(), close)?;
+                    return Ok(Some((pos, SExpr::List(items), close + 1)));
+                }
+                _ => {
+                    let len = self.input[pos..end]
+                        .find(|c| !is_atom_char(c))
+                        .unwrap_or(end - pos);
+                    let atom = self.input[pos..pos + len].to_string();
+                    return Ok(Some((pos, SExpr::Atom(atom), pos + len)));
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    // Reads the string literal whose opening quote is at `start`; `mask`
+    // has already checked that it terminates.
+    fn read_string(&self, start: usize) -> Result<(SExpr, usize), SExprError> {
+        let mut text = String::new();
+        let mut chars = self.input[start + 1..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => return Ok((SExpr::Str(text), start + 1 + i + 1)),
+                '\\' => {
+                    let (_, escaped) = chars.next().unwrap();
+                    text.push(match escaped {
+                        '"' | '\\' => escaped,
+                        'n' => '\n',
+                        't' => '\t',
+                        'r' => '\r',
+                        _ => {
+                            return Err(SExprError {
+                                pos: start + 1 + i,
+                                kind: SExprErrorKind::InvalidEscape(escaped),
+                            });
+                        }
+                    });
+                }
+                c => text.push(c),
+            }
+        }
+        unreachable!("mask() rejects unterminated strings")
+    }
+}
+
+// First pass: finds every list with the pair matcher.
+fn reader(input: &str) -> Result<Reader<'_>, SExprError> {
+    let masked = mask(input)?;
+    let mut lists = BTreeMap::new();
+    for span in pair_spans(&masked) {
+        let span = span.map_err(|e| SExprError {
+            pos: e.pos,
+            kind: SExprErrorKind::Brackets(e),
+        })?;
+        lists.insert(span.open, span.close);
+    }
+    Ok(Reader { input, lists })
+}
+
+/// Reads every top-level expression, e.g. the forms of a program.
+pub fn parse_sexprs(input: &str) -> Result<Vec<SExpr>, SExprError> {
+    reader(input)?.read_all(0, input.len())
+}
+
+/// Reads exactly one expression.
+/// Example: `parse_sexpr("(+ 1 (f \"a)\"))")` is
+/// `List[Atom("+"), Atom("1"), List[Atom("f"), Str("a)")]]`
+pub fn parse_sexpr(input: &str) -> Result<SExpr, SExprError> {
+    let reader = reader(input)?;
+    let Some((_, expr, next)) = reader.read(0, input.len())? else {
+        return Err(SExprError {
+            pos: input.len(),
+            kind: SExprErrorKind::Empty,
+        });
+    };
+    match reader.read(next, input.len())? {
+        None => Ok(expr),
+        Some((pos, ..)) => Err(SExprError {
+            pos,
+            kind: SExprErrorKind::TrailingInput,
+        }),
+    }
+}
+
+/// Prints in the syntax `parse_sexpr` reads back.
+impl fmt::Display for SExpr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SExpr::Atom(atom) => write!(f, "{atom}"),
+            SExpr::Str(text) => {
+                f.write_str("\"")?;
+                for c in text.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\t' => f.write_str("\\t")?,
+                        '\r' => f.write_str("\\r")?,
+                        c => write!(f, "{c}")?,
+                    }
+                }
+                f.write_str("\"")
+            }
+            SExpr::List(items) => {
+                f.write_str("(")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" ")?;
+                    }
+                    write!(f, "{item}")?;
+                }
+                f.write_str(")")
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::spans::MismatchKind;
+
+    fn atom(s: &str) -> SExpr {
+        SExpr::Atom(s.to_string())
+    }
+
+    #[test]
+    fn test_nested_lists_strings_and_comments() {
+        let expr = parse_sexpr("(define (f x) ; doubles )\n  [* 2 \"x)\\\"\" x])").unwrap();
+        assert_eq!(
+            expr,
+            SExpr::List(vec![
+                atom("define"),
+                SExpr::List(vec![atom("f"), atom("x")]),
+                SExpr::List(vec![
+                    atom("*"),
+                    atom("2"),
+                    SExpr::Str("x)\"".to_string()),
+                    atom("x")
+                ]),
+            ])
+        );
+        assert_eq!(expr.to_string(), "(define (f x) (* 2 \"x)\\\"\" x))");
+    }
+
+    #[test]
+    fn test_quote_and_programs() {
+        let forms = parse_sexprs("'(a b) 'c\n(d)").unwrap();
+        let printed: Vec<String> = forms.iter().map(|f| f.to_string()).collect();
+        assert_eq!(printed, ["(quote (a b))", "(quote c)", "(d)"]);
+        assert_eq!(parse_sexprs("; nothing\n"), Ok(vec![]));
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = parse_sexpr("(a (b]").unwrap_err();
+        assert_eq!(err.pos, 5);
+        assert!(matches!(
+            err.kind,
+            SExprErrorKind::Brackets(MismatchError {
+                kind: MismatchKind::Mismatched { open: 3, .. },
+                ..
+            })
+        ));
+        let kind = |input| parse_sexpr(input).unwrap_err();
+        assert_eq!(
+            kind("(a \"b)"),
+            SExprError {
+                pos: 3,
+                kind: SExprErrorKind::UnterminatedString
+            }
+        );
+        assert_eq!(kind(r#""a\qb""#).kind, SExprErrorKind::InvalidEscape('q'));
+        assert_eq!(kind(r#""a\qb""#).pos, 2);
+        assert_eq!(kind("(a) '").kind, SExprErrorKind::DanglingQuote);
+        assert_eq!(kind("  ").kind, SExprErrorKind::Empty);
+        assert_eq!(
+            kind("(a) b"),
+            SExprError {
+                pos: 4,
+                kind: SExprErrorKind::TrailingInput
+            }
+        );
+    }
+}
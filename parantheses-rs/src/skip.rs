@@ -0,0 +1,224 @@
+// Comment and string skipping for C-like source, so delimiters inside
+// `// ...`, `/* ... */`, "..." and '...' are not mistaken for structure.
+// Same state machine as the comment-stripping crate, but it reports byte
+// positions of the code characters instead of rebuilding the text.
+//
+// For other languages, `check_with` takes the skipping rule as a callback:
+// at every position the checker asks it whether a region starts there and,
+// if so, how many bytes long it is.
+
+use alloc::vec::Vec;
+
+use crate::spans::{MismatchError, MismatchKind};
+use crate::{closer_for, opener_for};
+
+/// States of the skipping state machine.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Code,              // Normal code
+    SingleLineComment, // Inside // comment
+    MultiLineComment,  // Inside /* */ comment
+    String(char),      // Inside string literal, with quote type
+}
+
+/// Iterator over the `(byte offset, char)` pairs of `input` that are code,
+/// i.e. outside comments and string/char literals. Quote characters themselves
+/// are skipped too, newlines ending `//` comments are kept.
+pub struct CodeChars<'a> {
+    chars: core::iter::Peekable<core::str::CharIndices<'a>>,
+    state: State,
+    escaped: bool,
+}
+
+/// Returns the code characters of C-like `input`.
+/// Example: `code_chars("f(\")\") // )")` yields `f`, `(`, `)` and ` `.
+pub fn code_chars(input: &str) -> CodeChars<'_> {
+    CodeChars {
+        chars: input.char_indices().peekable(),
+        state: State::Code,
+        escaped: false,
+    }
+}
+
+impl Iterator for CodeChars<'_> {
+    type Item = (usize, char);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((pos, ch)) = self.chars.next() {
+            match self.state {
+                State::Code => match ch {
+                    '/' => match self.chars.peek() {
+                        Some(&(_, '/')) => {
+                            self.chars.next(); // Consume '/'
+                            self.state = State::SingleLineComment;
+                        }
+                        Some(&(_, '*')) => {
+                            self.chars.next(); // Consume '*'
+                            self.state = State::MultiLineComment;
+                        }
+                        _ => return Some((pos, ch)),
+                    },
+                    '"' | '\'' => self.state = State::String(ch),
+                    _ => return Some((pos, ch)),
+                },
+                State::SingleLineComment => {
+                    if ch == '\n' {
+                        self.state = State::Code;
+                        return Some((pos, ch));
+                    }
+                }
+                State::MultiLineComment => {
+                    if ch == '*' && self.chars.next_if(|&(_, c)| c == '/').is_some() {
+                        self.state = State::Code;
+                    }
+                }
+                State::String(quote) => {
+                    if self.escaped {
+                        self.escaped = false;
+                    } else if ch == '\\' {
+                        self.escaped = true;
+                    } else if ch == quote {
+                        self.state = State::Code;
+                    }
+                }
+            }
+        }
+        None
+    }
+}
+
+/// Checks the pairs in [`PAIRS`](crate::PAIRS), skipping regions chosen by
+/// `skip`: it is called with the input and a byte offset and returns the
+/// length in bytes of an ignorable region starting there, if any.
+/// Example: skipping `#` comments, `check_with("f(x) # )", ...)` is Ok
+pub fn check_with(
+    input: &str,
+    skip: impl Fn(&str, usize) -> Option<usize>,
+) -> Result<(), MismatchError> {
+    let mut stack: Vec<(usize, char)> = Vec::new();
+    let mut pos = 0;
+
+    while let Some(c) = input[pos..].chars().next() {
+        if let Some(len) = skip(input, pos).filter(|&len| len > 0) {
+            pos = (pos + len).min(input.len());
+            while !input.is_char_boundary(pos) {
+                pos += 1;
+            }
+            continue;
+        }
+        if closer_for(c).is_some() {
+            stack.push((pos, c));
+        } else if let Some(open_char) = opener_for(c) {
+            let kind = match stack.last() {
+                Some(&(_, top)) if top == open_char => None,
+                Some(&(open, top)) => Some(MismatchKind::Mismatched {
+                    open,
+                    expected: closer_for(top).unwrap(),
+                }),
+                None => Some(MismatchKind::UnexpectedClose),
+            };
+            if let Some(kind) = kind {
+                return Err(MismatchError {
+                    pos,
+                    found: c,
+                    kind,
+                });
+            }
+            stack.pop();
+        }
+        pos += c.len_utf8();
+    }
+
+    match stack.pop() {
+        Some((pos, found)) => Err(MismatchError {
+            pos,
+            found,
+            kind: MismatchKind::Unclosed,
+        }),
+        None => Ok(()),
+    }
+}
+
+// A comment from `marker` up to (not including) the newline.
+pub(crate) fn line_comment(input: &str, pos: usize, marker: &str) -> Option<usize> {
+    let rest = input[pos..].strip_prefix(marker)?;
+    Some(marker.len() + rest.find('\n').unwrap_or(rest.len()))
+}
+
+// A backslash-escaped string literal opened by one of `quotes`; an
+// unterminated one runs to the end of the input.
+pub(crate) fn string(input: &str, pos: usize, quotes: &[char]) -> Option<usize> {
+    let quote = input[pos..].chars().next().filter(|q| quotes.contains(q))?;
+    let mut escaped = false;
+    for (i, c) in input[pos + 1..].char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            c if c == quote => return Some(i + 2),
+            _ => {}
+        }
+    }
+    Some(input.len() - pos)
+}
+
+// The rule for `#` comments and `'...'` or `"..."` strings (Python, shell,
+// TOML...).
+pub(crate) fn hash(input: &str, pos: usize) -> Option<usize> {
+    line_comment(input, pos, "#").or_else(|| string(input, pos, &['"', '\'']))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn code(input: &str) -> String {
+        code_chars(input).map(|(_, c)| c).collect()
+    }
+
+    #[test]
+    fn test_skips_comments_and_strings() {
+        assert_eq!(code("f(\")\") // )\ng('(');"), "f() \ng();");
+        assert_eq!(code("a /* ( \n */ b"), "a  b");
+    }
+
+    #[test]
+    fn test_escaped_quote_and_backslash() {
+        assert_eq!(code(r#"s("\"(", "\\") )"#), "s(, ) )");
+    }
+
+    #[test]
+    fn test_positions_are_byte_offsets() {
+        let positions: Vec<usize> = code_chars("é/*x*/(").map(|(p, _)| p).collect();
+        assert_eq!(positions, vec![0, 7]);
+    }
+
+    // Python-ish: `#` comments and '...' / "..." strings.
+    fn python(input: &str, pos: usize) -> Option<usize> {
+        let rest = &input[pos..];
+        match rest.chars().next()? {
+            '#' => Some(rest.find('\n').unwrap_or(rest.len())),
+            q @ ('\'' | '"') => Some(rest[1..].find(q).map_or(rest.len(), |n| n + 2)),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_check_with_callback() {
+        assert_eq!(check_with("f(x) # )", python), Ok(()));
+        assert_eq!(check_with("print(')', \"[\")", python), Ok(()));
+        let err = check_with("g(']') ]", python).unwrap_err();
+        assert_eq!((err.pos, err.kind), (7, MismatchKind::UnexpectedClose));
+        // Without a rule this is the plain pair check.
+        assert!(check_with("f(')')", |_, _| None).is_err());
+        // Regions may run past the end or into a multi-byte char.
+        assert_eq!(
+            check_with("(é)", |_, pos| (pos == 0).then_some(2)),
+            Err(MismatchError {
+                pos: 3,
+                found: ')',
+                kind: MismatchKind::UnexpectedClose
+            })
+        );
+        assert_eq!(check_with("((", |_, _| Some(100)), Ok(()));
+    }
+}
@@ -0,0 +1,219 @@
+// Pair spans: the positions of every matched delimiter pair, produced with
+// the same single stack as the checkers, but remembering where each opener was.
+//
+// `pair_spans("(a[b])")`:
+//   [ ]
+// Read '(' at 0 → push (0, '(')            [ (0 ]
+// Read '[' at 2 → push (2, '[')            [ (0 , [2 ]
+// Read ']' at 4 → pop [2 → yield 2..=4     [ (0 ]
+// Read ')' at 5 → pop (0 → yield 0..=5     [ ]
+//
+// With `Escapes::BACKSLASH`, `\(` and friends are literal text (regex and
+// LaTeX conventions); `\\` escapes the backslash itself, so in `\\(` the
+// parenthesis is structural again.
+
+use alloc::vec::Vec;
+
+use crate::{closer_for, opener_for};
+
+/// A matched pair. Positions are byte offsets of the delimiters themselves.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PairSpan {
+    pub open: usize,
+    pub close: usize,
+    pub open_char: char,
+    pub close_char: char,
+    /// Number of openers enclosing this one (0 at top level).
+    pub depth: usize,
+}
+
+impl PairSpan {
+    /// Byte range of the contents between the delimiters.
+    pub fn inner(&self) -> core::ops::Range<usize> {
+        self.open + self.open_char.len_utf8()..self.close
+    }
+}
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MismatchKind {
+    /// A closer with nothing open.
+    UnexpectedClose,
+    /// A closer while the opener at `open` (wanting `expected`) is on top.
+    Mismatched { open: usize, expected: char },
+    /// An opener still open at the end of input.
+    Unclosed,
+}
+
+/// The first structural error, at byte offset `pos` where `found` was read.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MismatchError {
+    pub pos: usize,
+    pub found: char,
+    pub kind: MismatchKind,
+}
+
+/// Whether an escape character turns the delimiter after it into text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Escapes {
+    /// Every delimiter is structural.
+    #[default]
+    None,
+    /// The character after this one is literal.
+    Char(char),
+}
+
+impl Escapes {
+    /// `\` escapes, as in regexes and LaTeX.
+    pub const BACKSLASH: Escapes = Escapes::Char('\\');
+}
+
+/// Iterator returned by [`pair_spans`] and [`pair_spans_with`].
+pub struct PairSpans<'a> {
+    chars: core::str::CharIndices<'a>,
+    stack: Vec<(usize, char)>,
+    escapes: Escapes,
+    escaped: bool,
+    done: bool,
+}
+
+/// Yields every pair in [`PAIRS`](crate::PAIRS) as it closes (so inner pairs
+/// come before the pairs enclosing them). The first mismatch is yielded as an
+/// `Err` and ends the iteration.
+/// Example: `pair_spans("()]")` → Ok(0..=1), then Err(UnexpectedClose at 2)
+pub fn pair_spans(input: &str) -> PairSpans<'_> {
+    pair_spans_with(input, Escapes::None)
+}
+
+/// Like [`pair_spans`], skipping delimiters escaped according to `escapes`.
+/// Example: `pair_spans_with(r"(\))", Escapes::BACKSLASH)` → Ok(0..=3)
+pub fn pair_spans_with(input: &str, escapes: Escapes) -> PairSpans<'_> {
+    PairSpans {
+        chars: input.char_indices(),
+        stack: Vec::new(),
+        escapes,
+        escaped: false,
+        done: false,
+    }
+}
+
+/// The first mismatch in `input`, if any, honouring `escapes`.
+pub fn check_escaped(input: &str, escapes: Escapes) -> Result<(), MismatchError> {
+    pair_spans_with(input, escapes)
+        .find_map(Result::err)
+        .map_or(Ok(()), Err)
+}
+
+impl Iterator for PairSpans<'_> {
+    type Item = Result<PairSpan, MismatchError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        for (pos, c) in self.chars.by_ref() {
+            if self.escaped {
+                self.escaped = false;
+                continue;
+            }
+            if self.escapes == Escapes::Char(c) {
+                self.escaped = true;
+            } else if closer_for(c).is_some() {
+                self.stack.push((pos, c));
+            } else if let Some(open_char) = opener_for(c) {
+                let kind = match self.stack.last() {
+                    Some(&(open, top)) if top == open_char => {
+                        self.stack.pop();
+                        return Some(Ok(PairSpan {
+                            open,
+                            close: pos,
+                            open_char,
+                            close_char: c,
+                            depth: self.stack.len(),
+                        }));
+                    }
+                    Some(&(open, top)) => MismatchKind::Mismatched {
+                        open,
+                        expected: closer_for(top).unwrap(),
+                    },
+                    None => MismatchKind::UnexpectedClose,
+                };
+                self.done = true;
+                return Some(Err(MismatchError {
+                    pos,
+                    found: c,
+                    kind,
+                }));
+            }
+        }
+
+        self.done = true;
+        // Report the innermost opener left open.
+        self.stack.pop().map(|(pos, found)| {
+            Err(MismatchError {
+                pos,
+                found,
+                kind: MismatchKind::Unclosed,
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spans_in_closing_order() {
+        let spans: Vec<PairSpan> = pair_spans("(a[b]){}").map(Result::unwrap).collect();
+        let ranges: Vec<(usize, usize, usize)> =
+            spans.iter().map(|s| (s.open, s.close, s.depth)).collect();
+        assert_eq!(ranges, vec![(2, 4, 1), (0, 5, 0), (6, 7, 0)]);
+        assert_eq!(spans[0].inner(), 3..4);
+    }
+
+    #[test]
+    fn test_errors_end_iteration() {
+        let items: Vec<_> = pair_spans("()]()").collect();
+        assert_eq!(items.len(), 2);
+        assert_eq!(
+            items[1],
+            Err(MismatchError {
+                pos: 2,
+                found: ']',
+                kind: MismatchKind::UnexpectedClose
+            })
+        );
+    }
+
+    #[test]
+    fn test_mismatched_and_unclosed() {
+        let last = pair_spans("([)]").last().unwrap();
+        assert_eq!(
+            last.unwrap_err().kind,
+            MismatchKind::Mismatched {
+                open: 1,
+                expected: ']'
+            }
+        );
+        let last = pair_spans("((x)").last().unwrap().unwrap_err();
+        assert_eq!((last.pos, last.kind), (0, MismatchKind::Unclosed));
+    }
+
+    #[test]
+    fn test_escaped_delimiters() {
+        let spans: Vec<(usize, usize)> = pair_spans_with(r"(\)\[)", Escapes::BACKSLASH)
+            .map(|s| s.map(|s| (s.open, s.close)).unwrap())
+            .collect();
+        assert_eq!(spans, vec![(0, 5)]);
+        assert!(check_escaped(r"\(x", Escapes::BACKSLASH).is_ok());
+        assert!(check_escaped(r"\(x", Escapes::None).is_err());
+        // An escaped backslash leaves the next delimiter structural.
+        let err = check_escaped(r"\\(", Escapes::BACKSLASH).unwrap_err();
+        assert_eq!((err.pos, err.kind), (2, MismatchKind::Unclosed));
+        assert!(check_escaped("`{x", Escapes::Char('`')).is_ok());
+    }
+}
@@ -0,0 +1,126 @@
+// Nesting statistics for corpus analysis: how deep pairs sit and which kinds
+// are used, gathered in one pass with the usual stack. Stats of several files
+// merge, so a whole codebase can be summarised file by file.
+//
+// `stats("f(a[0], (b))")`:
+//   depth 0: ( at 1            histogram [1, 2]
+//   depth 1: [ at 3, ( at 8    max 2, mean 0.67, pair_counts [2, 1, 0]
+
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::{PAIRS, closer_for, opener_for};
+
+/// Depth statistics of the matched pairs in some input.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DepthStats {
+    /// `histogram[d]` is the number of pairs with `d` pairs around them.
+    pub histogram: Vec<usize>,
+    /// Deepest nesting, i.e. `histogram.len()`.
+    pub max: usize,
+    /// Mean depth of a pair (0 for top-level pairs).
+    pub mean: f64,
+    /// Matched pairs of each kind, in [`PAIRS`] order.
+    pub pair_counts: Vec<usize>,
+    /// Delimiters left unmatched (stray or mismatched closers, unclosed openers).
+    pub unmatched: usize,
+}
+
+impl DepthStats {
+    /// Total number of matched pairs.
+    pub fn pairs(&self) -> usize {
+        self.histogram.iter().sum()
+    }
+
+    /// Adds the statistics of another input.
+    pub fn merge(&mut self, other: &DepthStats) {
+        if self.histogram.len() < other.histogram.len() {
+            self.histogram.resize(other.histogram.len(), 0);
+        }
+        for (mine, theirs) in self.histogram.iter_mut().zip(&other.histogram) {
+            *mine += theirs;
+        }
+        if self.pair_counts.len() < other.pair_counts.len() {
+            self.pair_counts.resize(other.pair_counts.len(), 0);
+        }
+        for (mine, theirs) in self.pair_counts.iter_mut().zip(&other.pair_counts) {
+            *mine += theirs;
+        }
+        self.unmatched += other.unmatched;
+        self.max = self.histogram.len();
+        self.mean = mean(&self.histogram);
+    }
+}
+
+fn mean(histogram: &[usize]) -> f64 {
+    let pairs: usize = histogram.iter().sum();
+    if pairs == 0 {
+        return 0.0;
+    }
+    let total: usize = histogram.iter().enumerate().map(|(d, n)| d * n).sum();
+    total as f64 / pairs as f64
+}
+
+/// Gathers [`DepthStats`] for `input`. Closers that do not match the open
+/// pair on top are counted as unmatched and otherwise ignored.
+pub fn stats(input: &str) -> DepthStats {
+    let mut stack: Vec<char> = Vec::new();
+    let mut histogram: Vec<usize> = Vec::new();
+    let mut pair_counts = vec![0; PAIRS.len()];
+    let mut unmatched = 0;
+
+    for c in input.chars() {
+        if closer_for(c).is_some() {
+            stack.push(c);
+        } else if let Some(open) = opener_for(c) {
+            if stack.last() != Some(&open) {
+                unmatched += 1;
+                continue;
+            }
+            stack.pop();
+            let depth = stack.len();
+            if histogram.len() <= depth {
+                histogram.resize(depth + 1, 0);
+            }
+            histogram[depth] += 1;
+            pair_counts[PAIRS.iter().position(|&(o, _)| o == open).unwrap()] += 1;
+        }
+    }
+
+    DepthStats {
+        max: histogram.len(),
+        mean: mean(&histogram),
+        histogram,
+        pair_counts,
+        unmatched: unmatched + stack.len(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_stats() {
+        let s = stats("f(a[0], (b))");
+        assert_eq!(s.histogram, vec![1, 2]);
+        assert_eq!(s.max, 2);
+        assert!((s.mean - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(s.pair_counts, vec![2, 1, 0]);
+        assert_eq!(s.unmatched, 0);
+        assert_eq!(stats("").max, 0);
+        assert_eq!(stats("(]{").unmatched, 3);
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut total = stats("{ [x] }");
+        total.merge(&stats("((()))"));
+        assert_eq!(total.histogram, vec![2, 2, 1]);
+        assert_eq!(total.max, 3);
+        assert_eq!(total.pairs(), 5);
+        assert_eq!(total.pair_counts, vec![3, 1, 1]);
+        assert!((total.mean - 4.0 / 5.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,147 @@
+// Streaming checker for inputs larger than memory.
+//
+// The input is read in fixed-size blocks and each block's `Summary` is folded
+// into a running one, so memory use is one block plus the currently open
+// delimiters. Bytes are never decoded, so binary or non-UTF-8 dumps work too.
+//
+// Brackets inside `"` strings do not count, so JSON like {"a": "]"} is
+// balanced. A backslash escapes the next byte, and the string state carries
+// over block boundaries, so an escape or a string may be split anywhere.
+
+use std::io::{self, Read};
+use std::time::{Duration, Instant};
+
+use crate::parallel::Summary;
+
+/// Block size used by [`check_reader`].
+pub const BLOCK_SIZE: usize = 1 << 20;
+
+/// Outcome of streaming an input through the checker.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StreamReport {
+    pub balanced: bool,
+    /// Bytes read before the result was known (an unmatched closer stops early).
+    pub bytes: u64,
+    pub elapsed: Duration,
+}
+
+impl StreamReport {
+    /// Decimal gigabytes per second.
+    pub fn throughput_gbps(&self) -> f64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs == 0.0 {
+            0.0
+        } else {
+            self.bytes as f64 / secs / 1e9
+        }
+    }
+}
+
+/// Checks every pair in [`PAIRS`](crate::PAIRS) over `reader` in
+/// [`BLOCK_SIZE`] blocks, skipping `"` strings.
+pub fn check_reader(reader: impl Read) -> io::Result<StreamReport> {
+    check_reader_blocks(reader, BLOCK_SIZE)
+}
+
+/// [`check_reader`] with an explicit block size.
+pub fn check_reader_blocks(mut reader: impl Read, block_size: usize) -> io::Result<StreamReport> {
+    let start = Instant::now();
+    let mut buffer = vec![0u8; block_size.max(1)];
+    let mut summary = Summary::default();
+    let mut bytes = 0u64;
+    let mut strings = Strings::default();
+
+    loop {
+        let n = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(n) => n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e),
+        };
+        bytes += n as u64;
+        strings.blank(&mut buffer[..n]);
+        summary = summary.merge(Summary::of(&buffer[..n]));
+
+        // A leading unmatched closer can never be matched by later input.
+        if summary.mismatched || !summary.closers.is_empty() {
+            break;
+        }
+    }
+
+    Ok(StreamReport {
+        balanced: summary.is_balanced(),
+        bytes,
+        elapsed: start.elapsed(),
+    })
+}
+
+// Where a block boundary left the `"` string scan.
+#[derive(Default)]
+struct Strings {
+    inside: bool,
+    escaped: bool,
+}
+
+impl Strings {
+    // Overwrites the strings of `block`, quotes included, with spaces.
+    fn blank(&mut self, block: &mut [u8]) {
+        for b in block {
+            if self.inside {
+                if self.escaped {
+                    self.escaped = false;
+                } else if *b == b'\\' {
+                    self.escaped = true;
+                } else if *b == b'"' {
+                    self.inside = false;
+                }
+                *b = b' ';
+            } else if *b == b'"' {
+                self.inside = true;
+                *b = b' ';
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blocks_split_anywhere() {
+        let input = br#"{"a": [1, {"b": [2, 3]}]}"#.repeat(10);
+        for block_size in [1, 3, 7, 64, 4096] {
+            let report = check_reader_blocks(&input[..], block_size).unwrap();
+            assert!(report.balanced);
+            assert_eq!(report.bytes, input.len() as u64);
+        }
+    }
+
+    #[test]
+    fn test_stops_at_unmatched_closer() {
+        let input = b"()]"
+            .iter()
+            .chain(&[b'('; 100])
+            .copied()
+            .collect::<Vec<_>>();
+        let report = check_reader_blocks(&input[..], 4).unwrap();
+        assert!(!report.balanced);
+        assert_eq!(report.bytes, 4);
+    }
+
+    #[test]
+    fn test_brackets_in_strings() {
+        let input = br#"{"a": "]", "b": "\"[", "c\\": ["}"]}"#;
+        for block_size in [1, 2, 5, 4096] {
+            let report = check_reader_blocks(&input[..], block_size).unwrap();
+            assert!(report.balanced);
+        }
+        assert!(!check_reader(&br#"{"a": "\\"]}"#[..]).unwrap().balanced);
+    }
+
+    #[test]
+    fn test_non_utf8_bytes() {
+        let report = check_reader(&[b'(', 0xff, 0xfe, b')'][..]).unwrap();
+        assert!(report.balanced);
+    }
+}
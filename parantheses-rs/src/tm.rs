@@ -0,0 +1,351 @@
+// Single-tape Turing machines, the top of the hierarchy this crate walks up:
+// a PDA (`pda`) decides balanced parentheses, a queue machine (`queue`) or a
+// TM decides anything computable.
+//
+// `TuringMachine::interleaved_brackets()` decides the "interleaved" language
+// the single-stack checker cannot: strings over ( ) [ ] in which parentheses
+// and brackets are each balanced on their own, so "([)]" is accepted. It
+// repeatedly crosses off the leftmost closer together with the nearest open
+// partner to its left, then checks that no opener is left over:
+//   ([)]  →  X[X]  →  XXXX  →  accept
+
+use alloc::collections::VecDeque;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::pda::Halt;
+
+/// Default blank symbol.
+pub const BLANK: char = '_';
+
+/// Head movement after a write.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Move {
+    Left,
+    Right,
+    Stay,
+}
+
+/// (state, symbol under the head) → (write, move, state).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TmTransition {
+    pub from: String,
+    pub read: char,
+    pub write: char,
+    pub movement: Move,
+    pub to: String,
+}
+
+/// A tape that grows with blanks in both directions.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tape {
+    cells: VecDeque<char>,
+    head: usize,
+    // Index in `cells` of the cell the input started at.
+    origin: usize,
+    blank: char,
+}
+
+impl Tape {
+    /// A tape holding `input` with the head on its first symbol.
+    pub fn new(input: &str, blank: char) -> Self {
+        let mut cells: VecDeque<char> = input.chars().collect();
+        if cells.is_empty() {
+            cells.push_back(blank);
+        }
+        Tape {
+            cells,
+            head: 0,
+            origin: 0,
+            blank,
+        }
+    }
+
+    /// The symbol under the head.
+    pub fn read(&self) -> char {
+        self.cells[self.head]
+    }
+
+    /// Overwrites the symbol under the head.
+    pub fn write(&mut self, symbol: char) {
+        self.cells[self.head] = symbol;
+    }
+
+    /// Moves the head, growing the tape with a blank if it falls off an end.
+    pub fn move_head(&mut self, movement: Move) {
+        match movement {
+            Move::Left if self.head == 0 => {
+                self.cells.push_front(self.blank);
+                self.origin += 1;
+            }
+            Move::Left => self.head -= 1,
+            Move::Right => {
+                self.head += 1;
+                if self.head == self.cells.len() {
+                    self.cells.push_back(self.blank);
+                }
+            }
+            Move::Stay => {}
+        }
+    }
+
+    /// Head position relative to where the input started (negative = left).
+    pub fn position(&self) -> isize {
+        self.head as isize - self.origin as isize
+    }
+
+    /// Number of cells visited so far.
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    /// Always false: a tape has at least the cell under the head.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    /// The visited cells, leftmost first.
+    pub fn cells(&self) -> impl Iterator<Item = char> + '_ {
+        self.cells.iter().copied()
+    }
+
+    /// Occurrences of `symbol` on the visited part of the tape.
+    pub fn count(&self, symbol: char) -> usize {
+        self.cells.iter().filter(|&&c| c == symbol).count()
+    }
+
+    /// Two lines: the tape, then a caret under the head.
+    /// Example: "X[X]\n ^"
+    pub fn render(&self) -> String {
+        let mut out: String = self.cells.iter().collect();
+        out.push('\n');
+        out.extend(core::iter::repeat_n(' ', self.head));
+        out.push('^');
+        out
+    }
+}
+
+/// Machine state plus tape.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TmConfig {
+    pub state: String,
+    pub tape: Tape,
+    pub steps: usize,
+}
+
+impl TmConfig {
+    /// The tape snapshot with the state name after the caret.
+    /// Example: "X[X]\n ^ scan"
+    pub fn render(&self) -> String {
+        let mut out = self.tape.render();
+        out.push(' ');
+        out.push_str(&self.state);
+        out
+    }
+}
+
+/// A finished run: the final configuration and the outcome.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TmRun {
+    pub config: TmConfig,
+    pub halt: Halt,
+}
+
+/// A deterministic single-tape Turing machine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TuringMachine {
+    pub start: String,
+    pub accepting: Vec<String>,
+    pub blank: char,
+    pub transitions: Vec<TmTransition>,
+}
+
+impl TuringMachine {
+    /// A machine with no transitions, starting in `start`, blank [`BLANK`].
+    pub fn new(start: &str) -> Self {
+        TuringMachine {
+            start: start.to_string(),
+            accepting: Vec::new(),
+            blank: BLANK,
+            transitions: Vec::new(),
+        }
+    }
+
+    /// Marks `state` as accepting; entering it halts the run.
+    pub fn accept(mut self, state: &str) -> Self {
+        self.accepting.push(state.to_string());
+        self
+    }
+
+    /// Uses `symbol` for unvisited cells.
+    pub fn blank(mut self, symbol: char) -> Self {
+        self.blank = symbol;
+        self
+    }
+
+    /// Adds a transition; see [`TmTransition`].
+    pub fn transition(
+        mut self,
+        from: &str,
+        read: char,
+        write: char,
+        movement: Move,
+        to: &str,
+    ) -> Self {
+        self.transitions.push(TmTransition {
+            from: from.to_string(),
+            read,
+            write,
+            movement,
+            to: to.to_string(),
+        });
+        self
+    }
+
+    /// The interleaved-brackets decider described at the top of this module.
+    pub fn interleaved_brackets() -> Self {
+        use Move::*;
+        let mut tm = TuringMachine::new("scan").accept("accept");
+        // Scan right for the leftmost closer.
+        for c in ['(', '[', 'X'] {
+            tm = tm.transition("scan", c, c, Right, "scan");
+        }
+        tm = tm
+            .transition("scan", ')', 'X', Left, "find (")
+            .transition("scan", ']', 'X', Left, "find [")
+            .transition("scan", BLANK, BLANK, Left, "check");
+        // Walk left to the nearest open partner; every closer to the left is
+        // already crossed off, so only the other opener and X can be skipped.
+        for (state, open, other) in [("find (", '(', '['), ("find [", '[', '(')] {
+            tm = tm
+                .transition(state, 'X', 'X', Left, state)
+                .transition(state, other, other, Left, state)
+                .transition(state, open, 'X', Right, "scan");
+        }
+        // Only X may remain.
+        tm.transition("check", 'X', 'X', Left, "check")
+            .transition("check", BLANK, BLANK, Stay, "accept")
+    }
+
+    /// The configuration a run over `input` starts in.
+    pub fn initial(&self, input: &str) -> TmConfig {
+        TmConfig {
+            state: self.start.clone(),
+            tape: Tape::new(input, self.blank),
+            steps: 0,
+        }
+    }
+
+    /// The transition that applies in `config`, if any.
+    pub fn applicable(&self, config: &TmConfig) -> Option<&TmTransition> {
+        if self.accepting.contains(&config.state) {
+            return None;
+        }
+        let symbol = config.tape.read();
+        self.transitions
+            .iter()
+            .find(|t| t.from == config.state && t.read == symbol)
+    }
+
+    /// Applies one transition in place. Returns false when the machine halts.
+    pub fn step(&self, config: &mut TmConfig) -> bool {
+        let Some(t) = self.applicable(config) else {
+            return false;
+        };
+        config.tape.write(t.write);
+        config.tape.move_head(t.movement);
+        if config.state != t.to {
+            config.state = t.to.clone();
+        }
+        config.steps += 1;
+        true
+    }
+
+    /// How a halted `config` ended.
+    pub fn outcome(&self, config: &TmConfig) -> Halt {
+        if self.accepting.contains(&config.state) {
+            Halt::Accepted
+        } else {
+            Halt::Rejected
+        }
+    }
+
+    /// Runs to completion, taking at most `fuel` steps.
+    pub fn run(&self, input: &str, fuel: usize) -> TmRun {
+        let mut config = self.initial(input);
+        for _ in 0..fuel {
+            if !self.step(&mut config) {
+                let halt = self.outcome(&config);
+                return TmRun { config, halt };
+            }
+        }
+        let halt = if self.applicable(&config).is_some() {
+            Halt::OutOfFuel
+        } else {
+            self.outcome(&config)
+        };
+        TmRun { config, halt }
+    }
+
+    /// Renders every configuration of a run of at most `fuel` steps.
+    pub fn snapshots(&self, input: &str, fuel: usize) -> Vec<String> {
+        let mut config = self.initial(input);
+        let mut out = Vec::from([config.render()]);
+        for _ in 0..fuel {
+            if !self.step(&mut config) {
+                break;
+            }
+            out.push(config.render());
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::check_parentheses_and_brackets;
+    use crate::fast::check_pairs_independently;
+
+    #[test]
+    fn test_interleaved_brackets() {
+        let tm = TuringMachine::interleaved_brackets();
+        for input in [
+            "", "([)]", "[(])", "(())[]", "([]]", "((", ")(", "]", "[[)]](",
+        ] {
+            let accepted = tm.run(input, 10_000).halt == Halt::Accepted;
+            assert_eq!(accepted, check_pairs_independently(input), "{input}");
+        }
+        // Where the single-stack checker and the TM part ways.
+        assert!(!check_parentheses_and_brackets("([)]"));
+        assert_eq!(tm.run("([)]", 10_000).halt, Halt::Accepted);
+    }
+
+    #[test]
+    fn test_tape_grows_both_ways() {
+        let mut tape = Tape::new("ab", BLANK);
+        tape.move_head(Move::Left);
+        tape.write('x');
+        assert_eq!(tape.position(), -1);
+        for _ in 0..3 {
+            tape.move_head(Move::Right);
+        }
+        assert_eq!(tape.position(), 2);
+        assert_eq!(tape.cells().collect::<String>(), "xab_");
+        assert_eq!(tape.render(), "xab_\n   ^");
+    }
+
+    #[test]
+    fn test_snapshots() {
+        let snapshots = TuringMachine::interleaved_brackets().snapshots("()", 100);
+        assert_eq!(snapshots[0], "()\n^ scan");
+        assert_eq!(snapshots[2], "(X\n^ find (");
+        assert_eq!(snapshots.last().unwrap(), "_XX_\n^ accept");
+    }
+
+    #[test]
+    fn test_fuel_limit() {
+        let tm = TuringMachine::new("q").transition("q", BLANK, BLANK, Move::Right, "q");
+        assert_eq!(tm.run("", 50).halt, Halt::OutOfFuel);
+    }
+}
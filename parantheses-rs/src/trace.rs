@@ -0,0 +1,196 @@
+// Step-by-step traces of the stack machine, in the same shape as the
+// walkthroughs in the checkers' comments.
+//
+// `trace("([)]").render()`:
+// Initial: stack = []
+//   [ ]
+// Step 1: Read '(' → stack.push('(')
+//   [ ( ] ← Push arrow
+// Step 2: Read '[' → stack.push('[')
+//   [ ( , [ ] ← Push arrow
+// Step 3: Read ')' → stack.pop() (expect '(', get '[' → mismatch)
+//   [ ( , [ ] ← Mismatch
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::Write;
+
+use crate::spans::{MismatchError, MismatchKind};
+use crate::{closer_for, opener_for};
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Push,
+    Pop,
+    /// The closer did not match the top of the stack (or the stack was empty).
+    Mismatch,
+}
+
+/// One delimiter read by the machine. `stack` is the stack after the step.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Step {
+    pub pos: usize,
+    pub ch: char,
+    pub action: Action,
+    pub stack: Vec<char>,
+}
+
+/// Every step of one run, and how it ended.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trace {
+    pub steps: Vec<Step>,
+    /// `None` when the input was balanced.
+    pub error: Option<MismatchError>,
+}
+
+/// Runs the stack machine over every pair in [`PAIRS`](crate::PAIRS),
+/// recording each push and pop. Stops at the first mismatch.
+pub fn trace(input: &str) -> Trace {
+    let mut stack: Vec<(usize, char)> = Vec::new();
+    let mut steps = Vec::new();
+    let snapshot = |stack: &[(usize, char)]| stack.iter().map(|&(_, c)| c).collect();
+
+    for (pos, c) in input.char_indices() {
+        if closer_for(c).is_some() {
+            stack.push((pos, c));
+            steps.push(Step {
+                pos,
+                ch: c,
+                action: Action::Push,
+                stack: snapshot(&stack),
+            });
+        } else if let Some(open_char) = opener_for(c) {
+            match stack.last() {
+                Some(&(_, top)) if top == open_char => {
+                    stack.pop();
+                    steps.push(Step {
+                        pos,
+                        ch: c,
+                        action: Action::Pop,
+                        stack: snapshot(&stack),
+                    });
+                }
+                top => {
+                    let kind = match top {
+                        Some(&(open, top)) => MismatchKind::Mismatched {
+                            open,
+                            expected: closer_for(top).unwrap(),
+                        },
+                        None => MismatchKind::UnexpectedClose,
+                    };
+                    steps.push(Step {
+                        pos,
+                        ch: c,
+                        action: Action::Mismatch,
+                        stack: snapshot(&stack),
+                    });
+                    return Trace {
+                        steps,
+                        error: Some(MismatchError {
+                            pos,
+                            found: c,
+                            kind,
+                        }),
+                    };
+                }
+            }
+        }
+    }
+
+    let error = stack.last().map(|&(pos, found)| MismatchError {
+        pos,
+        found,
+        kind: MismatchKind::Unclosed,
+    });
+    Trace { steps, error }
+}
+
+// "[ ( , [ ]"
+fn render_stack(stack: &[char]) -> String {
+    if stack.is_empty() {
+        return "[ ]".to_string();
+    }
+    let items: Vec<String> = stack.iter().map(char::to_string).collect();
+    format!("[ {} ]", items.join(" , "))
+}
+
+impl Trace {
+    /// True when the run ended without an error.
+    pub fn is_balanced(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Renders the run as the ASCII walkthrough used in this crate's comments.
+    pub fn render(&self) -> String {
+        let mut out = String::from("Initial: stack = []\n  [ ]\n");
+        for (i, step) in self.steps.iter().enumerate() {
+            let (what, arrow) = match step.action {
+                Action::Push => (format!("stack.push('{}')", step.ch), "Push arrow"),
+                Action::Pop => {
+                    let open = opener_for(step.ch).unwrap();
+                    (format!("stack.pop() (get '{open}', matches)"), "Pop arrow")
+                }
+                Action::Mismatch => match step.stack.last() {
+                    Some(&top) => (
+                        format!(
+                            "stack.pop() (expect '{}', get '{top}' → mismatch)",
+                            opener_for(step.ch).unwrap()
+                        ),
+                        "Mismatch",
+                    ),
+                    None => (
+                        "stack.pop() (stack empty → mismatch)".to_string(),
+                        "Mismatch",
+                    ),
+                },
+            };
+            let _ = writeln!(out, "Step {}: Read '{}' → {what}", i + 1, step.ch);
+            let _ = writeln!(out, "  {} ← {arrow}", render_stack(&step.stack));
+        }
+        if let Some(MismatchError {
+            kind: MismatchKind::Unclosed,
+            ..
+        }) = self.error
+        {
+            out.push_str("End: stack not empty → unbalanced\n");
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_balanced_trace() {
+        let trace = trace("(a[])");
+        let actions: Vec<Action> = trace.steps.iter().map(|s| s.action).collect();
+        assert_eq!(
+            actions,
+            vec![Action::Push, Action::Push, Action::Pop, Action::Pop]
+        );
+        assert_eq!(trace.steps[1].stack, vec!['(', '[']);
+        assert!(trace.is_balanced());
+    }
+
+    #[test]
+    fn test_render_matches_comment_walkthrough() {
+        let expected = "Initial: stack = []\n  [ ]\n\
+            Step 1: Read '(' → stack.push('(')\n  [ ( ] ← Push arrow\n\
+            Step 2: Read '[' → stack.push('[')\n  [ ( , [ ] ← Push arrow\n\
+            Step 3: Read ')' → stack.pop() (expect '(', get '[' → mismatch)\n  [ ( , [ ] ← Mismatch\n";
+        assert_eq!(trace("([)]").render(), expected);
+    }
+
+    #[test]
+    fn test_unclosed_reported() {
+        let trace = trace("((");
+        assert_eq!(trace.error.unwrap().kind, MismatchKind::Unclosed);
+        assert!(trace.render().ends_with("unbalanced\n"));
+    }
+}
@@ -0,0 +1,204 @@
+// Bracket nesting tree: the structure the single stack walks through,
+// materialised. Each matched pair becomes a node whose children are the text
+// runs and pairs between its delimiters.
+//
+// `parse_tree("a(b[c])")`:
+// Root 0..7
+// ├── Text "a"        0..1
+// └── Pair ( ) 1..7
+//     ├── Text "b"    2..3
+//     └── Pair [ ] 3..6
+//         └── Text "c" 4..5
+
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+use crate::spans::{MismatchError, MismatchKind};
+use crate::{closer_for, opener_for};
+
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeKind {
+    /// The whole input.
+    Root,
+    /// A matched delimiter pair.
+    Pair { open: char, close: char },
+    /// A maximal run of non-delimiter text.
+    Text,
+}
+
+/// A tree node. `span` is a byte range of the input; for pairs it includes
+/// both delimiters.
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub span: Range<usize>,
+    pub children: Vec<Node>,
+}
+
+impl Node {
+    /// The input text covered by this node.
+    pub fn text<'a>(&self, input: &'a str) -> &'a str {
+        &input[self.span.clone()]
+    }
+
+    /// Byte range between the delimiters of a pair (the whole span otherwise).
+    pub fn inner(&self) -> Range<usize> {
+        match self.kind {
+            NodeKind::Pair { open, close } => {
+                self.span.start + open.len_utf8()..self.span.end - close.len_utf8()
+            }
+            _ => self.span.clone(),
+        }
+    }
+
+    /// Deepest pair nesting below (and including) this node.
+    pub fn max_depth(&self) -> usize {
+        let own = matches!(self.kind, NodeKind::Pair { .. }) as usize;
+        own + self.children.iter().map(Node::max_depth).max().unwrap_or(0)
+    }
+
+    /// All pair nodes in pre-order (outer pairs before the pairs they contain).
+    pub fn pairs(&self) -> Vec<&Node> {
+        let mut pairs = Vec::new();
+        let mut pending = vec![self];
+        while let Some(node) = pending.pop() {
+            if matches!(node.kind, NodeKind::Pair { .. }) {
+                pairs.push(node);
+            }
+            pending.extend(node.children.iter().rev());
+        }
+        pairs
+    }
+}
+
+// Appends `start..end` as a text leaf, if non-empty.
+fn push_text(children: &mut Vec<Node>, start: usize, end: usize) {
+    if start < end {
+        children.push(Node {
+            kind: NodeKind::Text,
+            span: start..end,
+            children: Vec::new(),
+        });
+    }
+}
+
+/// Builds the nesting tree of every pair in [`PAIRS`](crate::PAIRS), or
+/// returns the first mismatch (the same error [`pair_spans`](crate::spans::pair_spans)
+/// would report).
+pub fn parse_tree(input: &str) -> Result<Node, MismatchError> {
+    // Open pairs: (opener position, opener, children so far). Index 0 is the root.
+    let mut stack: Vec<(usize, char, Vec<Node>)> = vec![(0, '\0', Vec::new())];
+    let mut text_start = 0;
+
+    for (pos, c) in input.char_indices() {
+        if closer_for(c).is_some() {
+            push_text(&mut stack.last_mut().unwrap().2, text_start, pos);
+            stack.push((pos, c, Vec::new()));
+            text_start = pos + c.len_utf8();
+        } else if let Some(open_char) = opener_for(c) {
+            let top = stack.len() - 1;
+            let (open, top_char, _) = stack[top];
+            if top == 0 || top_char != open_char {
+                let kind = if top == 0 {
+                    MismatchKind::UnexpectedClose
+                } else {
+                    MismatchKind::Mismatched {
+                        open,
+                        expected: closer_for(top_char).unwrap(),
+                    }
+                };
+                return Err(MismatchError {
+                    pos,
+                    found: c,
+                    kind,
+                });
+            }
+
+            let (open, _, mut children) = stack.pop().unwrap();
+            push_text(&mut children, text_start, pos);
+            text_start = pos + c.len_utf8();
+            stack.last_mut().unwrap().2.push(Node {
+                kind: NodeKind::Pair {
+                    open: open_char,
+                    close: c,
+                },
+                span: open..text_start,
+                children,
+            });
+        }
+    }
+
+    if stack.len() > 1 {
+        let (pos, found, _) = stack.pop().unwrap();
+        return Err(MismatchError {
+            pos,
+            found,
+            kind: MismatchKind::Unclosed,
+        });
+    }
+
+    let (_, _, mut children) = stack.pop().unwrap();
+    push_text(&mut children, text_start, input.len());
+    Ok(Node {
+        kind: NodeKind::Root,
+        span: 0..input.len(),
+        children,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tree_shape() {
+        let input = "a(b[c])";
+        let root = parse_tree(input).unwrap();
+        assert_eq!(root.kind, NodeKind::Root);
+        assert_eq!(root.children.len(), 2);
+        assert_eq!(root.children[0].text(input), "a");
+
+        let paren = &root.children[1];
+        assert_eq!(
+            paren.kind,
+            NodeKind::Pair {
+                open: '(',
+                close: ')'
+            }
+        );
+        assert_eq!(paren.span, 1..7);
+        assert_eq!(&input[paren.inner()], "b[c]");
+        assert_eq!(paren.children[1].children[0].text(input), "c");
+        assert_eq!(root.max_depth(), 2);
+    }
+
+    #[test]
+    fn test_pairs_in_pre_order() {
+        let input = "(x)[{y}]";
+        let root = parse_tree(input).unwrap();
+        let texts: Vec<&str> = root.pairs().iter().map(|n| n.text(input)).collect();
+        assert_eq!(texts, vec!["(x)", "[{y}]", "{y}"]);
+    }
+
+    #[test]
+    fn test_errors() {
+        assert_eq!(
+            parse_tree("(]").unwrap_err().kind,
+            MismatchKind::Mismatched {
+                open: 0,
+                expected: ')'
+            }
+        );
+        assert_eq!(parse_tree("a)").unwrap_err().pos, 1);
+        assert_eq!(parse_tree("((x)").unwrap_err().kind, MismatchKind::Unclosed);
+    }
+
+    #[test]
+    fn test_empty_and_text_only() {
+        assert!(parse_tree("").unwrap().children.is_empty());
+        assert_eq!(parse_tree("abc").unwrap().children[0].kind, NodeKind::Text);
+    }
+}
@@ -0,0 +1,47 @@
+// wasm-bindgen exports for embedding the checkers and their visualisations in
+// a web page. Structured results cross the boundary as JSON strings, so the
+// page only needs `JSON.parse`.
+//
+// The manifest keeps a plain rlib (a cdylib can't link without std), so
+// request the cdylib when building:
+//
+//   cargo rustc --lib --release --target wasm32-unknown-unknown \
+//       --no-default-features --features wasm --crate-type cdylib
+//   wasm-bindgen --target web --out-dir pkg \
+//       target/wasm32-unknown-unknown/release/parantheses_rs.wasm
+//   python3 -m http.server   (then open www/index.html)
+
+use wasm_bindgen::prelude::*;
+
+use crate::{rainbow, trace, tree};
+
+/// True when every pair in [`PAIRS`](crate::PAIRS) is balanced.
+#[wasm_bindgen]
+pub fn check(input: &str) -> bool {
+    tree::parse_tree(input).is_ok()
+}
+
+/// The [`trace::Trace`] of `input` as JSON.
+#[wasm_bindgen]
+pub fn trace_json(input: &str) -> String {
+    serde_json::to_string(&trace::trace(input)).unwrap()
+}
+
+/// The stack walkthrough of `input` as plain text.
+#[wasm_bindgen]
+pub fn trace_text(input: &str) -> String {
+    trace::trace(input).render()
+}
+
+/// The nesting tree of `input` (or its first mismatch) as JSON:
+/// `{"Ok": node}` or `{"Err": error}`.
+#[wasm_bindgen]
+pub fn tree_json(input: &str) -> String {
+    serde_json::to_string(&tree::parse_tree(input)).unwrap()
+}
+
+/// `input` as HTML with depth-classed rainbow brackets.
+#[wasm_bindgen]
+pub fn rainbow_html(input: &str) -> String {
+    rainbow::render_html(input)
+}
@@ -0,0 +1,1353 @@
+//! A parser for C struct declarations, so a layout can come from the
+//! source it describes instead of a hand-written member list.
+//!
+//! ```
+//! use struct_alignment_and_padding::c;
+//! use struct_alignment_and_padding::target::Target;
+//!
+//! let structs = c::parse("struct Foo { int a; short b; double c; };", Target::X86_64SysV).unwrap();
+//! assert_eq!(structs[0].name, "Foo");
+//! assert_eq!(structs[0].layout().total_size, 16);
+//! ```
+//!
+//! Struct definitions, typedefs, pointers (function pointers included),
+//! arrays and nested structs are understood, with primitive sizes from the
+//! [`Target`]. Array sizes may be integer constant expressions using
+//! object-like `#define`s, enumerators, `sizeof` and `_Alignof`. Everything
+//! else at file scope (functions, variables, prototypes) is skipped.
+//!
+//! An enum is as big as the target's compilers make it: an `int`, unless
+//! its values need more; the smallest integer that holds them if it is
+//! `__attribute__((packed))`; or its underlying type if it has one, as C23's
+//! `enum E : unsigned char` and C++'s `enum class E : uint8_t`.
+//!
+//! Structs are packed as `#pragma pack` says where they are defined: `(N)`,
+//! `()`, `(push[, label][, N])` and `(pop[, label][, N])` are followed, as
+//! are the Windows SDK's `<pshpackN.h>` and `<poppack.h>` includes. GCC's
+//! `__attribute__((packed))` on a struct packs it to 1 regardless.
+//! Over-alignment is read from `_Alignas`/`alignas` (of a constant or a
+//! type), `__attribute__((aligned(N)))` and `__declspec(align(N))`, on
+//! members, on structs (after `struct`, or after the closing brace) and on
+//! typedefs; other attributes are ignored. Bitfields are allocated by the
+//! target's rules, Visual C++'s on the MSVC targets and Itanium's
+//! elsewhere. Unions are not supported and are reported as errors.
+
+use std::collections::HashMap;
+
+use crate::target::{Primitive, Target};
+use crate::{ArrayType, Member, ParseError, StructDef, TypeInfo};
+
+type Result<T> = std::result::Result<T, ParseError>;
+
+fn error(line: usize, message: impl Into<String>) -> ParseError {
+    ParseError {
+        line,
+        message: message.into(),
+    }
+}
+
+/// Every named struct defined in `source`, in order, with its members laid
+/// out for `target`. A struct defined without a tag is named after the
+/// typedef that introduces it.
+pub fn parse(source: &str, target: Target) -> Result<Vec<StructDef>> {
+    let mut lexer = Lexer::default();
+    let mut tokens = Vec::new();
+    lexer.lex(source, 1, &mut tokens)?;
+    let mut parser = Parser {
+        tokens,
+        packs: lexer.packs,
+        pos: 0,
+        target,
+        tags: HashMap::new(),
+        constants: HashMap::new(),
+        typedefs: HashMap::new(),
+        structs: Vec::new(),
+    };
+    while parser.peek().is_some() {
+        parser.external_declaration()?;
+    }
+    Ok(parser.structs)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(u64),
+    Punct(char),
+    /// A string, character or floating-point literal.
+    Literal,
+}
+
+/// Splits the input into tokens, expanding object-like macros and keeping
+/// track of `#pragma pack` as it goes.
+#[derive(Default)]
+struct Lexer {
+    defines: HashMap<String, Vec<Token>>,
+    /// The packing in effect.
+    pack: Option<usize>,
+    /// What `push` saved: its label and the packing then.
+    pack_stack: Vec<(Option<String>, Option<usize>)>,
+    /// Every change of the packing: the index of the first token it
+    /// applies to, and the new packing.
+    packs: Vec<(usize, Option<usize>)>,
+}
+
+impl Lexer {
+    fn lex(&mut self, source: &str, first_line: usize, out: &mut Vec<(Token, usize)>) -> Result<()> {
+        let chars: Vec<char> = source.chars().collect();
+        let (mut i, mut line, mut line_start) = (0, first_line, true);
+        while let Some(&c) = chars.get(i) {
+            let next = chars.get(i + 1).copied();
+            if c == '\n' {
+                line += 1;
+                line_start = true;
+                i += 1;
+            } else if c.is_whitespace() {
+                i += 1;
+            } else if c == '/' && next == Some('/') {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            } else if c == '/' && next == Some('*') {
+                let start = line;
+                i += 2;
+                while !(chars.get(i) == Some(&'*') && chars.get(i + 1) == Some(&'/')) {
+                    match chars.get(i) {
+                        None => return Err(error(start, "unterminated comment")),
+                        Some('\n') => line += 1,
+                        Some(_) => {}
+                    }
+                    i += 1;
+                }
+                i += 2;
+            } else if c == '#' && line_start {
+                // A directive runs to the end of the line, continuations
+                // included; the newline itself is left for the loop.
+                let start = line;
+                let mut text = String::new();
+                i += 1;
+                while let Some(&c) = chars.get(i) {
+                    match c {
+                        '\n' => break,
+                        '\\' if chars.get(i + 1) == Some(&'\n') => {
+                            line += 1;
+                            i += 1;
+                        }
+                        c => text.push(c),
+                    }
+                    i += 1;
+                }
+                self.directive(&text, start, out.len())?;
+            } else {
+                line_start = false;
+                let start = i;
+                if c.is_alphabetic() || c == '_' {
+                    while chars.get(i).is_some_and(|&c| c.is_alphanumeric() || c == '_') {
+                        i += 1;
+                    }
+                    let word: String = chars[start..i].iter().collect();
+                    match self.defines.get(&word) {
+                        Some(body) => out.extend(body.iter().map(|token| (token.clone(), line))),
+                        None => out.push((Token::Ident(word), line)),
+                    }
+                } else if c.is_ascii_digit() {
+                    while chars.get(i).is_some_and(|&c| c.is_alphanumeric() || c == '.') {
+                        i += 1;
+                    }
+                    let text: String = chars[start..i].iter().collect();
+                    out.push((parse_number(&text).map_or(Token::Literal, Token::Number), line));
+                } else if c == '"' || c == '\'' {
+                    i += 1;
+                    while chars.get(i).is_some_and(|&quote| quote != c && quote != '\n') {
+                        i += if chars[i] == '\\' { 2 } else { 1 };
+                    }
+                    if chars.get(i) != Some(&c) {
+                        return Err(error(line, "unterminated literal"));
+                    }
+                    i += 1;
+                    out.push((Token::Literal, line));
+                } else {
+                    out.push((Token::Punct(c), line));
+                    i += 1;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Records `#define NAME body`, `#undef NAME` and the packing
+    /// directives, which apply from token `position` on; other directives
+    /// (includes, conditionals) are ignored, so every branch is read.
+    fn directive(&mut self, text: &str, line: usize, position: usize) -> Result<()> {
+        let text = text.trim_start();
+        let (directive, rest) = text.split_at(text.find(|c: char| !c.is_alphanumeric()).unwrap_or(text.len()));
+        let rest = rest.trim_start();
+        let name_end = rest.find(|c: char| !(c.is_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        let (name, body) = rest.split_at(name_end);
+        match directive {
+            // Function-like macros are not expanded.
+            "define" if !name.is_empty() && !body.starts_with('(') => {
+                let mut tokens = Vec::new();
+                self.lex(body, line, &mut tokens)?;
+                self.defines.insert(name.to_string(), tokens.into_iter().map(|(token, _)| token).collect());
+            }
+            "undef" => {
+                self.defines.remove(name);
+            }
+            "pragma" if name == "pack" => self.pragma_pack(body, line, position)?,
+            "include" => {
+                let header = rest.trim().trim_matches(['<', '>', '"']).to_ascii_lowercase();
+                let arguments = match header.as_str() {
+                    "pshpack1.h" => "(push, 1)",
+                    "pshpack2.h" => "(push, 2)",
+                    "pshpack4.h" => "(push, 4)",
+                    "pshpack8.h" => "(push, 8)",
+                    "poppack.h" => "(pop)",
+                    _ => return Ok(()),
+                };
+                self.pragma_pack(arguments, line, position)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// `#pragma pack` with `body`, its parenthesized arguments.
+    fn pragma_pack(&mut self, body: &str, line: usize, position: usize) -> Result<()> {
+        let Some(arguments) = body.trim().strip_prefix('(').and_then(|body| body.strip_suffix(')')) else {
+            return Err(error(line, "expected `#pragma pack(...)`"));
+        };
+        let arguments: Vec<&str> = arguments.split(',').map(str::trim).filter(|a| !a.is_empty()).collect();
+        let value = |text: &str| {
+            parse_number(text)
+                .map(|n| n as usize)
+                .filter(|&n| n.is_power_of_two() && n <= 16)
+                .ok_or_else(|| error(line, format!("`{text}` is not a packing of 1, 2, 4, 8 or 16")))
+        };
+        // The optional label and packing after `push` or `pop`.
+        let label_and_value = |rest: &[&str]| match *rest {
+            [] => Ok((None, None)),
+            [n] if n.starts_with(|c: char| c.is_ascii_digit()) => Ok((None, Some(value(n)?))),
+            [label] => Ok((Some(label.to_string()), None)),
+            [label, n] => Ok((Some(label.to_string()), Some(value(n)?))),
+            _ => Err(error(line, "too many arguments to `#pragma pack`")),
+        };
+        match arguments.as_slice() {
+            [] => self.pack = None,
+            ["show"] => return Ok(()),
+            ["push", rest @ ..] => {
+                let (label, n) = label_and_value(rest)?;
+                self.pack_stack.push((label, self.pack));
+                self.pack = n.or(self.pack);
+            }
+            ["pop", rest @ ..] => {
+                let (label, n) = label_and_value(rest)?;
+                // Popping a label pops everything pushed after it too.
+                let at = match &label {
+                    Some(label) => self.pack_stack.iter().rposition(|(pushed, _)| pushed.as_ref() == Some(label)),
+                    None => self.pack_stack.len().checked_sub(1),
+                };
+                let Some(at) = at else {
+                    return Err(error(line, "`#pragma pack(pop)` without a matching push"));
+                };
+                self.pack = n.or(self.pack_stack[at].1);
+                self.pack_stack.truncate(at);
+            }
+            [n] => self.pack = Some(value(n)?),
+            _ => return Err(error(line, "unrecognized `#pragma pack`")),
+        }
+        self.packs.push((position, self.pack));
+        Ok(())
+    }
+}
+
+/// An integer literal, with any `u`/`l` suffix; `None` for anything else.
+fn parse_number(text: &str) -> Option<u64> {
+    let digits = text.trim_end_matches(['u', 'U', 'l', 'L']);
+    if let Some(hex) = digits.strip_prefix("0x").or_else(|| digits.strip_prefix("0X")) {
+        u64::from_str_radix(hex, 16).ok()
+    } else if digits.len() > 1 && digits.starts_with('0') {
+        u64::from_str_radix(&digits[1..], 8).ok()
+    } else {
+        digits.parse().ok()
+    }
+}
+
+/// Keywords that qualify a declaration without changing its layout.
+const QUALIFIERS: [&str; 12] = [
+    "const",
+    "volatile",
+    "restrict",
+    "static",
+    "extern",
+    "register",
+    "inline",
+    "auto",
+    "__restrict",
+    "__inline",
+    "__extension__",
+    "__const",
+];
+
+/// The keywords a primitive type is spelled with.
+const BUILTINS: [&str; 12] = [
+    "void", "char", "short", "int", "long", "signed", "unsigned", "float", "double", "_Bool", "bool", "__int128",
+];
+
+/// A type as far as it is known: struct references are looked up when
+/// they are used, since `typedef struct Foo Foo;` usually comes before
+/// the definition.
+#[derive(Debug, Clone)]
+enum Ty {
+    Known(TypeInfo),
+    Struct(String),
+    Array(ArrayType),
+    Void,
+    Function,
+}
+
+/// One step from the base type to the declared one.
+#[derive(Debug, Clone, Copy)]
+enum Derived {
+    Pointer,
+    /// `None` for `[]`.
+    Array(Option<usize>),
+    Function,
+}
+
+/// What the attributes and alignment specifiers of a declaration say about
+/// its layout.
+#[derive(Default)]
+struct Attributes {
+    packed: bool,
+    /// The largest alignment asked for.
+    aligned: Option<usize>,
+}
+
+impl Attributes {
+    fn align(&mut self, n: usize) {
+        self.aligned = self.aligned.max(Some(n));
+    }
+}
+
+struct Declarator {
+    name: String,
+    /// Applied to the base type in order: `int *a[4]` is pointer, then
+    /// array.
+    derived: Vec<Derived>,
+    attributes: Attributes,
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    /// [`Lexer::packs`].
+    packs: Vec<(usize, Option<usize>)>,
+    pos: usize,
+    target: Target,
+    /// Size and alignment of every struct and enum tag defined so far.
+    tags: HashMap<String, TypeInfo>,
+    /// The value of every enumerator defined so far.
+    constants: HashMap<String, i128>,
+    typedefs: HashMap<String, Ty>,
+    structs: Vec<StructDef>,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|(token, _)| token)
+    }
+
+    fn peek_ident(&self) -> Option<&str> {
+        match self.peek() {
+            Some(Token::Ident(word)) => Some(word),
+            _ => None,
+        }
+    }
+
+    fn peek_punct(&self, c: char) -> bool {
+        self.peek() == Some(&Token::Punct(c))
+    }
+
+    fn eat_punct(&mut self, c: char) -> bool {
+        let matched = self.peek_punct(c);
+        self.pos += usize::from(matched);
+        matched
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let matched = self.peek_ident() == Some(keyword);
+        self.pos += usize::from(matched);
+        matched
+    }
+
+    /// The `#pragma pack` in effect at the current token.
+    fn pragma_pack(&self) -> Option<usize> {
+        let changes = self.packs.partition_point(|&(position, _)| position <= self.pos);
+        changes.checked_sub(1).and_then(|i| self.packs[i].1)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens.get(self.pos).or(self.tokens.last()).map_or(1, |(_, line)| *line)
+    }
+
+    fn error(&self, message: impl Into<String>) -> ParseError {
+        error(self.line(), message)
+    }
+
+    /// `expected` and what was found instead.
+    fn unexpected(&self, expected: &str) -> ParseError {
+        let found = match self.peek() {
+            Some(Token::Ident(word)) => format!("`{word}`"),
+            Some(Token::Number(n)) => format!("`{n}`"),
+            Some(Token::Punct(c)) => format!("`{c}`"),
+            Some(Token::Literal) => "a literal".to_string(),
+            None => "the end of the input".to_string(),
+        };
+        self.error(format!("expected {expected}, found {found}"))
+    }
+
+    fn expect_punct(&mut self, c: char) -> Result<()> {
+        if self.eat_punct(c) { Ok(()) } else { Err(self.unexpected(&format!("`{c}`"))) }
+    }
+
+    fn expect_ident(&mut self) -> Result<String> {
+        match self.peek_ident() {
+            Some(word) => {
+                let word = word.to_string();
+                self.pos += 1;
+                Ok(word)
+            }
+            None => Err(self.unexpected("a name")),
+        }
+    }
+
+    /// Skips a bracketed group starting at the current `open`.
+    fn skip_group(&mut self, open: char, close: char) -> Result<()> {
+        self.expect_punct(open)?;
+        let mut depth = 1;
+        while depth > 0 {
+            match self.peek() {
+                None => return Err(self.unexpected(&format!("`{close}`"))),
+                Some(Token::Punct(c)) if *c == open => depth += 1,
+                Some(Token::Punct(c)) if *c == close => depth -= 1,
+                Some(_) => {}
+            }
+            self.pos += 1;
+        }
+        Ok(())
+    }
+
+    fn skip_qualifiers(&mut self) {
+        while self.peek_ident().is_some_and(|word| QUALIFIERS.contains(&word)) {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_attributes(&mut self) -> Result<()> {
+        self.attributes(&mut Attributes::default())
+    }
+
+    /// Reads any `__attribute__((...))`, `__declspec(...)` and
+    /// `_Alignas(...)` into `attributes`.
+    fn attributes(&mut self, attributes: &mut Attributes) -> Result<()> {
+        loop {
+            match self.peek_ident() {
+                Some("__attribute__" | "__attribute") => {
+                    self.pos += 1;
+                    self.expect_punct('(')?;
+                    self.expect_punct('(')?;
+                    while !self.eat_punct(')') {
+                        // Either spelling: `packed` or `__packed__`.
+                        match self.expect_ident()?.trim_matches('_') {
+                            "packed" => attributes.packed = true,
+                            "aligned" if self.peek_punct('(') => {
+                                self.pos += 1;
+                                attributes.align(self.alignment_operand()?);
+                            }
+                            "aligned" => return Err(self.error("`aligned` needs an alignment")),
+                            _ if self.peek_punct('(') => self.skip_group('(', ')')?,
+                            _ => {}
+                        }
+                        self.eat_punct(',');
+                    }
+                    self.expect_punct(')')?;
+                }
+                Some("__declspec") => {
+                    self.pos += 1;
+                    self.expect_punct('(')?;
+                    while !self.eat_punct(')') {
+                        if self.expect_ident()? == "align" {
+                            self.expect_punct('(')?;
+                            attributes.align(self.alignment_operand()?);
+                        } else if self.peek_punct('(') {
+                            self.skip_group('(', ')')?;
+                        }
+                    }
+                }
+                Some("_Alignas" | "alignas") => {
+                    self.pos += 1;
+                    self.expect_punct('(')?;
+                    attributes.align(self.alignment_operand()?);
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// After the opening parenthesis: an alignment, or a type whose
+    /// alignment it is, through the closing one.
+    fn alignment_operand(&mut self) -> Result<usize> {
+        let n = if self.starts_type() { self.type_name()?.alignment } else { self.const_expr()? as usize };
+        self.expect_punct(')')?;
+        if !n.is_power_of_two() {
+            return Err(self.error(format!("alignment {n} is not a power of two")));
+        }
+        Ok(n)
+    }
+
+    /// Whether a type name starts here, rather than an expression.
+    fn starts_type(&self) -> bool {
+        self.peek_ident().is_some_and(|word| {
+            BUILTINS.contains(&word)
+                || QUALIFIERS.contains(&word)
+                || matches!(word, "struct" | "union" | "enum")
+                || self.typedefs.contains_key(word)
+                || Primitive::from_c_name(word).is_some()
+        })
+    }
+
+    /// The size and alignment of a type name, as in `sizeof(int *)`.
+    fn type_name(&mut self) -> Result<TypeInfo> {
+        let mut attributes = Attributes::default();
+        let (base, _) = self.specifiers(&mut attributes)?;
+        let declarator = self.declarator(true)?;
+        let ty = self.derive(&base, &declarator)?;
+        self.resolve(&ty, "the type")
+    }
+
+    /// Skips to the end of a declaration we have no use for: past its `;`,
+    /// or past the body of a function definition.
+    fn skip_declaration(&mut self) -> Result<()> {
+        let mut depth = 0usize;
+        loop {
+            match self.peek() {
+                None => return Err(self.unexpected("`;`")),
+                Some(Token::Punct('{')) => {
+                    self.skip_group('{', '}')?;
+                    if depth == 0 {
+                        self.eat_punct(';');
+                        return Ok(());
+                    }
+                    continue;
+                }
+                Some(Token::Punct('(' | '[')) => depth += 1,
+                Some(Token::Punct(')' | ']')) => depth = depth.saturating_sub(1),
+                Some(Token::Punct(';')) if depth == 0 => {
+                    self.pos += 1;
+                    return Ok(());
+                }
+                Some(_) => {}
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn external_declaration(&mut self) -> Result<()> {
+        self.skip_qualifiers();
+        if self.eat_keyword("typedef") {
+            return self.typedef();
+        }
+        // Outside a typedef only the struct and enum definitions matter.
+        if matches!(self.peek_ident(), Some("struct" | "enum")) {
+            self.specifiers(&mut Attributes::default())?;
+        }
+        self.skip_declaration()
+    }
+
+    fn typedef(&mut self) -> Result<()> {
+        let mut attributes = Attributes::default();
+        let (base, mut anonymous) = self.specifiers(&mut attributes)?;
+        loop {
+            let declarator = self.declarator(false)?;
+            if let Some(mut def) = anonymous.take() {
+                def.name = declarator.name.clone();
+                self.structs.push(def);
+            }
+            let mut ty = self.derive(&base, &declarator)?;
+            // An aligned typedef keeps the size and raises the alignment.
+            if let Some(n) = attributes.aligned.max(declarator.attributes.aligned) {
+                let info = self.resolve(&ty, &declarator.name)?;
+                ty = Ty::Known(TypeInfo {
+                    size: info.size,
+                    alignment: info.alignment.max(n),
+                });
+            }
+            self.typedefs.insert(declarator.name, ty);
+            if !self.eat_punct(',') {
+                break;
+            }
+        }
+        self.expect_punct(';')
+    }
+
+    /// The type a declaration starts with, and the definition of the
+    /// struct it names if that has no tag; attributes among the specifiers
+    /// go to `attributes`.
+    fn specifiers(&mut self, attributes: &mut Attributes) -> Result<(Ty, Option<StructDef>)> {
+        let mut words: Vec<String> = Vec::new();
+        let mut found: Option<(Ty, Option<StructDef>)> = None;
+        while let Some(word) = self.peek_ident() {
+            let word = word.to_string();
+            let bare = words.is_empty() && found.is_none();
+            if QUALIFIERS.contains(&word.as_str()) {
+                self.pos += 1;
+            } else if matches!(word.as_str(), "__attribute__" | "__attribute" | "__declspec" | "_Alignas" | "alignas") {
+                self.attributes(attributes)?;
+            } else if word == "union" {
+                return Err(self.error("unions are not supported"));
+            } else if word == "struct" && bare {
+                self.pos += 1;
+                found = Some(self.struct_specifier()?);
+            } else if word == "enum" && bare {
+                self.pos += 1;
+                found = Some((Ty::Known(self.enum_specifier()?), None));
+            } else if BUILTINS.contains(&word.as_str()) && found.is_none() {
+                words.push(word);
+                self.pos += 1;
+            } else if bare {
+                let ty = match self.typedefs.get(&word) {
+                    Some(ty) => ty.clone(),
+                    None => match Primitive::from_c_name(&word) {
+                        Some(primitive) => Ty::Known(self.primitive(primitive)?),
+                        None => return Err(self.error(format!("unknown type `{word}`"))),
+                    },
+                };
+                self.pos += 1;
+                found = Some((ty, None));
+            } else {
+                break;
+            }
+        }
+        if let Some(found) = found {
+            return Ok(found);
+        }
+        if words.is_empty() {
+            return Err(self.unexpected("a type"));
+        }
+        let name = words.join(" ");
+        if name == "void" {
+            return Ok((Ty::Void, None));
+        }
+        match Primitive::from_c_name(&name) {
+            Some(primitive) => Ok((Ty::Known(self.primitive(primitive)?), None)),
+            None => Err(self.error(format!("`{name}` is not a type"))),
+        }
+    }
+
+    fn primitive(&self, primitive: Primitive) -> Result<TypeInfo> {
+        self.target
+            .type_info(primitive)
+            .ok_or_else(|| self.error(format!("{primitive:?} is not available on {}", self.target)))
+    }
+
+    /// After `struct`: a reference by tag or a definition.
+    fn struct_specifier(&mut self) -> Result<(Ty, Option<StructDef>)> {
+        let mut attributes = Attributes::default();
+        self.attributes(&mut attributes)?;
+        let tag = match self.peek_ident() {
+            Some(_) => Some(self.expect_ident()?),
+            None => None,
+        };
+        let pragma_pack = self.pragma_pack();
+        if !self.eat_punct('{') {
+            return match tag {
+                Some(tag) => Ok((Ty::Struct(tag), None)),
+                None => Err(self.unexpected("a struct tag or `{`")),
+            };
+        }
+        let members = self.members()?;
+        self.attributes(&mut attributes)?;
+        let def = StructDef {
+            name: tag.clone().unwrap_or_default(),
+            members,
+            packing: if attributes.packed { Some(1) } else { pragma_pack },
+            alignment: attributes.aligned,
+            bitfields: self.target.bitfield_rules(),
+        };
+        let Some(layout) = def.try_layout() else {
+            return Err(self.error(match &tag {
+                Some(tag) => format!("struct {tag} is too large"),
+                None => "the struct is too large".to_string(),
+            }));
+        };
+        let info = TypeInfo {
+            size: layout.total_size,
+            alignment: layout.alignment,
+        };
+        let Some(tag) = tag else {
+            return Ok((Ty::Known(info), Some(def)));
+        };
+        if self.tags.insert(tag.clone(), info).is_some() {
+            return Err(self.error(format!("struct {tag} is defined twice")));
+        }
+        self.structs.push(def);
+        Ok((Ty::Known(info), None))
+    }
+
+    /// After `enum`: the size and alignment of the enum, by tag or by
+    /// definition; the enumerators of a definition become constants.
+    fn enum_specifier(&mut self) -> Result<TypeInfo> {
+        let mut attributes = Attributes::default();
+        // C++'s scoped enums are laid out like the others.
+        if matches!(self.peek_ident(), Some("class" | "struct")) {
+            self.pos += 1;
+        }
+        self.attributes(&mut attributes)?;
+        let tag = match self.peek_ident() {
+            Some(_) => Some(self.expect_ident()?),
+            None => None,
+        };
+        let underlying = if self.eat_punct(':') { Some(self.type_name()?) } else { None };
+        if !self.eat_punct('{') {
+            return match (underlying, tag) {
+                (Some(info), _) => Ok(info),
+                (None, Some(tag)) => {
+                    self.tags.get(&tag).copied().ok_or_else(|| self.error(format!("enum {tag} is not defined")))
+                }
+                (None, None) => Err(self.unexpected("an enum tag or `{`")),
+            };
+        }
+        // `next` is `None` after the largest value there is.
+        let (mut min, mut max, mut next) = (0, 0, Some(0));
+        while !self.eat_punct('}') {
+            let name = self.expect_ident()?;
+            self.skip_attributes()?;
+            let value = match next {
+                _ if self.eat_punct('=') => self.binary(0)?,
+                Some(next) => next,
+                None => return Err(self.error(format!("the value of enumerator `{name}` overflows"))),
+            };
+            (min, max, next) = (min.min(value), max.max(value), value.checked_add(1));
+            self.constants.insert(name, value);
+            if !self.eat_punct(',') {
+                self.expect_punct('}')?;
+                break;
+            }
+        }
+        self.attributes(&mut attributes)?;
+        let info = match underlying {
+            Some(info) => info,
+            None => self.target.enum_type(min, max, attributes.packed).ok_or_else(|| {
+                let name = tag.as_deref().unwrap_or("(anonymous)");
+                self.error(format!("the values of enum {name} do not fit in its type on {}", self.target))
+            })?,
+        };
+        if let Some(tag) = tag
+            && self.tags.insert(tag.clone(), info).is_some()
+        {
+            return Err(self.error(format!("enum {tag} is defined twice")));
+        }
+        Ok(info)
+    }
+
+    /// The members of a struct body, through its closing brace.
+    fn members(&mut self) -> Result<Vec<Member>> {
+        let mut members = Vec::new();
+        // A `[]` member, which must be the last.
+        let mut flexible: Option<String> = None;
+        while !self.eat_punct('}') {
+            if let Some(name) = flexible.take() {
+                return Err(self.error(format!("flexible array member `{name}` is not at the end of the struct")));
+            }
+            let mut attributes = Attributes::default();
+            let (base, anonymous) = self.specifiers(&mut attributes)?;
+            if self.eat_punct(';') {
+                // A C11 anonymous struct: its members are laid out as one
+                // nested struct.
+                match (anonymous, base) {
+                    (Some(_), Ty::Known(info)) => members.push(Member::new("(anonymous)", info)),
+                    _ => return Err(self.error("expected a member name")),
+                }
+                continue;
+            }
+            loop {
+                if self.eat_punct(':') {
+                    // An unnamed bitfield, which only pads.
+                    let ty = self.resolve(&base, "bitfield")?;
+                    let width = self.bit_width(ty, "(unnamed)")?;
+                    members.push(Member::bitfield("", ty, width));
+                    if !self.eat_punct(',') {
+                        break;
+                    }
+                    continue;
+                }
+                let declarator = self.declarator(false)?;
+                if self.eat_punct(':') {
+                    let ty = match self.derive(&base, &declarator)? {
+                        Ty::Known(info) if declarator.derived.is_empty() => info,
+                        _ => return Err(self.error(format!("bitfield `{}` must have integer type", declarator.name))),
+                    };
+                    let width = self.bit_width(ty, &declarator.name)?;
+                    if width == 0 {
+                        return Err(self.error(format!("zero-width bitfield `{}` must be unnamed", declarator.name)));
+                    }
+                    members.push(Member::bitfield(declarator.name, ty, width));
+                    if !self.eat_punct(',') {
+                        break;
+                    }
+                    continue;
+                }
+                if let Some(name) = flexible.take() {
+                    return Err(self.error(format!("flexible array member `{name}` is not at the end of the struct")));
+                }
+                if matches!(declarator.derived.last(), Some(Derived::Array(None))) {
+                    flexible = Some(declarator.name.clone());
+                }
+                let mut member = match self.derive(&base, &declarator)? {
+                    Ty::Array(array) => Member::array(declarator.name, array),
+                    ty => {
+                        let ty = self.resolve(&ty, &declarator.name)?;
+                        Member::new(declarator.name, ty)
+                    }
+                };
+                member.align = attributes.aligned.max(declarator.attributes.aligned);
+                members.push(member);
+                if !self.eat_punct(',') {
+                    break;
+                }
+            }
+            self.expect_punct(';')?;
+        }
+        Ok(members)
+    }
+
+    /// The width after the `:` of a bitfield of type `ty` called `name`.
+    fn bit_width(&mut self, ty: TypeInfo, name: &str) -> Result<usize> {
+        let width = self.const_expr()? as usize;
+        if width > ty.size * 8 {
+            return Err(self.error(format!("bitfield `{name}` is wider than its type")));
+        }
+        Ok(width)
+    }
+
+    /// A (possibly `abstract`, nameless) declarator.
+    fn declarator(&mut self, abstract_: bool) -> Result<Declarator> {
+        let mut attributes = Attributes::default();
+        let mut stars = 0;
+        while self.eat_punct('*') {
+            stars += 1;
+            self.skip_qualifiers();
+            self.attributes(&mut attributes)?;
+        }
+        let nested = self.peek_punct('(')
+            && (!abstract_ || matches!(self.tokens.get(self.pos + 1), Some((Token::Punct('*' | '('), _))));
+        let inner = if nested {
+            self.pos += 1;
+            let inner = self.declarator(abstract_)?;
+            self.expect_punct(')')?;
+            Some(inner)
+        } else {
+            None
+        };
+        let name = match &inner {
+            None if !abstract_ || self.peek_ident().is_some() => self.expect_ident()?,
+            _ => String::new(),
+        };
+        self.attributes(&mut attributes)?;
+
+        let mut suffixes = Vec::new();
+        loop {
+            if self.eat_punct('[') {
+                if self.eat_punct(']') {
+                    suffixes.push(Derived::Array(None));
+                } else {
+                    let count = self.const_expr()?;
+                    self.expect_punct(']')?;
+                    suffixes.push(Derived::Array(Some(count as usize)));
+                }
+            } else if self.peek_punct('(') {
+                self.skip_group('(', ')')?;
+                suffixes.push(Derived::Function);
+            } else {
+                break;
+            }
+        }
+        self.attributes(&mut attributes)?;
+        // `a[2][3]` is an array of 2 arrays of 3: the last suffix applies
+        // first.
+        suffixes.reverse();
+
+        let mut derived = vec![Derived::Pointer; stars];
+        derived.extend(suffixes);
+        match inner {
+            // Whatever the parentheses hold applies after what is outside
+            // them: `(*f)(int)` is a pointer to a function.
+            Some(inner) => {
+                derived.extend(inner.derived);
+                if let Some(n) = inner.attributes.aligned {
+                    attributes.align(n);
+                }
+                Ok(Declarator {
+                    name: inner.name,
+                    derived,
+                    attributes,
+                })
+            }
+            None => Ok(Declarator {
+                name,
+                derived,
+                attributes,
+            }),
+        }
+    }
+
+    /// The type `declarator` gives `base`.
+    fn derive(&self, base: &Ty, declarator: &Declarator) -> Result<Ty> {
+        let mut ty = base.clone();
+        for derived in &declarator.derived {
+            ty = match *derived {
+                Derived::Pointer => Ty::Known(self.primitive(Primitive::Pointer)?),
+                Derived::Function => Ty::Function,
+                Derived::Array(count) => {
+                    let element = self.resolve(&ty, &declarator.name)?;
+                    if matches!(&ty, Ty::Array(array) if array.is_flexible()) {
+                        return Err(self.error(format!("`{}` is an array of flexible arrays", declarator.name)));
+                    }
+                    // `[]` is a flexible array member.
+                    let count = count.unwrap_or(0);
+                    let array = match ty {
+                        // `a[2][3]`: the inner array was derived first.
+                        Ty::Array(mut array) => {
+                            array.dims.insert(0, count);
+                            array
+                        }
+                        _ => ArrayType::new(element, [count]),
+                    };
+                    if array.type_info().is_none() {
+                        return Err(self.error(format!("`{}` is too large", declarator.name)));
+                    }
+                    Ty::Array(array)
+                }
+            };
+        }
+        Ok(ty)
+    }
+
+    /// The size and alignment of `ty`, given to `name`.
+    fn resolve(&self, ty: &Ty, name: &str) -> Result<TypeInfo> {
+        match ty {
+            Ty::Known(info) => Ok(*info),
+            Ty::Array(array) => array.type_info().ok_or_else(|| self.error(format!("`{name}` is too large"))),
+            Ty::Struct(tag) => self
+                .tags
+                .get(tag)
+                .copied()
+                .ok_or_else(|| self.error(format!("`{name}` has incomplete type struct {tag}"))),
+            Ty::Void => Err(self.error(format!("`{name}` has type void"))),
+            Ty::Function => Err(self.error(format!("`{name}` is a function"))),
+        }
+    }
+
+    /// An integer constant expression.
+    fn const_expr(&mut self) -> Result<u64> {
+        let value = self.binary(0)?;
+        u64::try_from(value).map_err(|_| self.error(format!("constant expression is {value}, expected a size")))
+    }
+
+    fn binary(&mut self, min_precedence: u8) -> Result<i128> {
+        let mut value = self.unary()?;
+        loop {
+            let second = self.tokens.get(self.pos + 1).map(|(token, _)| token);
+            let (operator, precedence, width) = match (self.peek(), second) {
+                (Some(Token::Punct('|')), _) => ('|', 0, 1),
+                (Some(Token::Punct('^')), _) => ('^', 1, 1),
+                (Some(Token::Punct('&')), _) => ('&', 2, 1),
+                (Some(Token::Punct('<')), Some(Token::Punct('<'))) => ('<', 3, 2),
+                (Some(Token::Punct('>')), Some(Token::Punct('>'))) => ('>', 3, 2),
+                (Some(Token::Punct(c @ ('+' | '-'))), _) => (*c, 4, 1),
+                (Some(Token::Punct(c @ ('*' | '/' | '%'))), _) => (*c, 5, 1),
+                _ => break,
+            };
+            if precedence < min_precedence {
+                break;
+            }
+            self.pos += width;
+            let rhs = self.binary(precedence + 1)?;
+            let result = match operator {
+                '|' => Some(value | rhs),
+                '^' => Some(value ^ rhs),
+                '&' => Some(value & rhs),
+                // Bits shifted out, or into the sign, overflow.
+                '<' => u32::try_from(rhs)
+                    .ok()
+                    .and_then(|rhs| value.checked_shl(rhs).filter(|&shifted| shifted >> rhs == value)),
+                '>' => u32::try_from(rhs).ok().and_then(|rhs| value.checked_shr(rhs)),
+                '+' => value.checked_add(rhs),
+                '-' => value.checked_sub(rhs),
+                '*' => value.checked_mul(rhs),
+                '/' => value.checked_div(rhs),
+                _ => value.checked_rem(rhs),
+            };
+            value = result.ok_or_else(|| self.error("constant expression overflows or divides by zero"))?;
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> Result<i128> {
+        match self.peek().cloned() {
+            Some(Token::Number(n)) => {
+                self.pos += 1;
+                Ok(n.into())
+            }
+            Some(Token::Punct('-')) => {
+                self.pos += 1;
+                Ok(-self.unary()?)
+            }
+            Some(Token::Punct('~')) => {
+                self.pos += 1;
+                Ok(!self.unary()?)
+            }
+            Some(Token::Punct('(')) => {
+                self.pos += 1;
+                let value = self.binary(0)?;
+                self.expect_punct(')')?;
+                Ok(value)
+            }
+            Some(Token::Ident(word)) if matches!(word.as_str(), "sizeof" | "_Alignof" | "alignof" | "__alignof__") => {
+                self.pos += 1;
+                self.expect_punct('(')?;
+                let info = self.type_name()?;
+                self.expect_punct(')')?;
+                Ok(if word == "sizeof" { info.size } else { info.alignment } as i128)
+            }
+            Some(Token::Ident(word)) if self.constants.contains_key(&word) => {
+                self.pos += 1;
+                Ok(self.constants[&word])
+            }
+            _ => Err(self.unexpected("an integer constant")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Name, offset and size of each member.
+    type Members = Vec<(String, usize, usize)>;
+
+    fn layout_of(source: &str, target: Target) -> Vec<(String, Members, usize)> {
+        parse(source, target)
+            .unwrap()
+            .iter()
+            .map(|def| {
+                let layout = def.layout();
+                let members = layout.members.iter().map(|m| (m.name.clone(), m.offset, m.size)).collect();
+                (def.name.clone(), members, layout.total_size)
+            })
+            .collect()
+    }
+
+    fn members(source: &str, target: Target) -> Members {
+        layout_of(source, target).pop().unwrap().1
+    }
+
+    fn strings(items: &[(&str, usize, usize)]) -> Members {
+        items.iter().map(|&(name, offset, size)| (name.to_string(), offset, size)).collect()
+    }
+
+    #[test]
+    fn test_typedefs_pointers_arrays() {
+        let source = "
+            #include <stdint.h>
+            #define NAME_LEN (8 * 2)
+
+            typedef unsigned long ulong;
+            typedef struct Node Node;   /* defined below */
+
+            struct Node {
+                const char *name, tag[NAME_LEN];
+                ulong id;
+                Node *next;
+                int (*compare)(const Node *, const Node *);
+                uint8_t flags[2][3];
+                double matrix[2];
+            };
+        ";
+        let expected = strings(&[
+            ("name", 0, 8),
+            ("tag", 8, 16),
+            ("id", 24, 8),
+            ("next", 32, 8),
+            ("compare", 40, 8),
+            ("flags", 48, 6),
+            ("matrix", 56, 16),
+        ]);
+        assert_eq!(members(source, Target::X86_64SysV), expected);
+        let expected = strings(&[
+            ("name", 0, 4),
+            ("tag", 4, 16),
+            ("id", 20, 4),
+            ("next", 24, 4),
+            ("compare", 28, 4),
+            ("flags", 32, 6),
+            ("matrix", 40, 16),
+        ]);
+        assert_eq!(members(source, Target::MsvcX86), expected);
+    }
+
+    #[test]
+    fn test_array_members() {
+        let source = "
+            typedef int row[3];
+            struct Grid {
+                char tag;
+                row cells[2];
+                short pairs[4][2], *(rows[2]);
+            };
+        ";
+        let def = parse(source, Target::X86_64SysV).unwrap().pop().unwrap();
+        let layout = def.layout();
+        let dims = |name| layout.member(name).and_then(|m| m.array.as_ref()).map(|a| a.dims.clone());
+        assert_eq!(dims("cells"), Some(vec![2, 3]));
+        assert_eq!(dims("pairs"), Some(vec![4, 2]));
+        assert_eq!(dims("rows"), Some(vec![2]));
+        assert_eq!(dims("tag"), None);
+        assert_eq!(layout.element_offset("cells", &[1, 2]), Some(4 + 20));
+        assert_eq!(layout.element_offset("pairs", &[3, 1]), Some(28 + 14));
+        assert_eq!(layout.element_offset("rows", &[1]), Some(48 + 8));
+        assert_eq!(layout.total_size, 64);
+    }
+
+    #[test]
+    fn test_flexible_array_member() {
+        let source = "
+            struct Packet {
+                uint16_t kind;
+                uint32_t length;
+                uint8_t payload[];
+            };
+            struct Rows { int count; short rows[][3]; };
+        ";
+        let structs = parse(source, Target::X86_64SysV).unwrap();
+        let packet = structs[0].layout();
+        assert_eq!((packet.member_offsets[2], packet.total_size), (8, 8));
+        assert_eq!(packet.size_for(5), Some(16));
+        let rows = structs[1].layout();
+        assert_eq!((rows.member_offsets[1], rows.total_size), (4, 4));
+        assert_eq!(rows.size_for(2), Some(16));
+        assert_eq!(rows.element_offset("rows", &[1, 2]), Some(4 + 10));
+    }
+
+    #[test]
+    fn test_bitfields() {
+        let source = "
+            struct Flags {
+                unsigned a : 3;
+                unsigned b : 30;   /* does not fit after a */
+                char c;
+                unsigned short d : 4, : 0, e : 4;
+            };
+        ";
+        let offsets = |target| {
+            let def = parse(source, target).unwrap().pop().unwrap();
+            let layout = def.layout();
+            (layout.member_offsets, layout.total_size)
+        };
+        // d shares the byte after c, and the zero-width bitfield moves e to
+        // the next short.
+        assert_eq!(offsets(Target::X86_64SysV), (vec![0, 4, 8, 8, 10, 10], 12));
+        // Each change of type starts a new storage unit.
+        assert_eq!(offsets(Target::MsvcX64), (vec![0, 4, 8, 10, 12, 12], 16));
+
+        let def = parse(source, Target::X86_64SysV).unwrap().pop().unwrap();
+        let layout = def.layout();
+        let d = layout.member("d").unwrap();
+        assert_eq!((d.size, d.bitfield.map(|b| (b.bit_offset, b.width))), (2, Some((8, 4))));
+        assert_eq!(layout.members[4].name, "");
+    }
+
+    #[test]
+    fn test_enums() {
+        let source = "
+            enum Color { RED, GREEN, BLUE };
+            enum __attribute__((packed)) Small { S_A, S_B = 200 };
+            enum Signed { NEG = -1, POS = 100 } __attribute__((packed));
+            enum class Scoped : uint8_t { X, Y };
+            enum Big { HUGE = (1 << 20) * (1 << 20) };
+            enum Flags { F_A = 1 << 0, F_B = 1 << 1, F_ALL = F_A | F_B };
+            struct S {
+                char c;
+                enum Color color;
+                enum Small small;
+                enum Signed sgn;
+                enum Scoped scoped;
+                enum Big big;
+                char names[F_ALL];
+            };
+        ";
+        let expected = strings(&[
+            ("c", 0, 1),
+            ("color", 4, 4),
+            ("small", 8, 1),
+            ("sgn", 9, 1),
+            ("scoped", 10, 1),
+            ("big", 16, 8),
+            ("names", 24, 3),
+        ]);
+        assert_eq!(members(source, Target::X86_64SysV), expected);
+        // long long is 4-aligned on i686.
+        assert_eq!(layout_of(source, Target::I686)[0].2, 24);
+        assert_eq!(
+            parse(source, Target::MsvcX64).unwrap_err().message,
+            "the values of enum Big do not fit in its type on msvc-x64"
+        );
+    }
+
+    #[test]
+    fn test_nested_and_anonymous_structs() {
+        let source = "
+            typedef struct { char c; double d; } Pair;
+            struct Outer {
+                Pair pairs[2];
+                struct Inner { short s; } inner;
+                struct Inner *more;
+                char tail[sizeof(struct Inner) + _Alignof(Pair)];
+            };
+            int helper(struct Outer *outer) { return outer->inner.s; }
+            static const char *names[] = { \"a\", \"b\" };
+        ";
+        let structs = layout_of(source, Target::X86_64SysV);
+        let names: Vec<&str> = structs.iter().map(|(name, _, _)| name.as_str()).collect();
+        assert_eq!(names, ["Pair", "Inner", "Outer"]);
+        assert_eq!(structs[0].2, 16);
+        assert_eq!(
+            structs[2].1,
+            strings(&[("pairs", 0, 32), ("inner", 32, 2), ("more", 40, 8), ("tail", 48, 10)])
+        );
+        assert_eq!(structs[2].2, 64);
+    }
+
+    #[test]
+    fn test_packed_attribute() {
+        let source = "
+            struct __attribute__((packed)) A { char c; int i; };
+            typedef struct { char c; short s; struct A a; } __attribute__((__packed__, unused)) B;
+            struct C { char c; struct A a; short s; };
+        ";
+        let structs = parse(source, Target::X86_64SysV).unwrap();
+        let packing: Vec<Option<usize>> = structs.iter().map(|def| def.packing).collect();
+        assert_eq!(packing, [Some(1), Some(1), None]);
+        let sizes: Vec<usize> = structs.iter().map(|def| def.layout().total_size).collect();
+        assert_eq!(sizes, [5, 8, 8]);
+    }
+
+    #[test]
+    fn test_over_alignment() {
+        let source = "
+            struct Line { _Alignas(64) char data[8]; int n; };
+            struct Holder { char c; struct Line line; };
+            struct __attribute__((aligned(32))) Small { short s; };
+            struct __declspec(align(16)) Msvc { char c; };
+            typedef int wide_int __attribute__((aligned(8)));
+            struct Wide { char c; wide_int w; };
+            struct Packed { char c; wide_int w; alignas(long) char d; } __attribute__((packed));
+        ";
+        let structs = parse(source, Target::X86_64SysV).unwrap();
+        let layouts: Vec<(Vec<usize>, usize, usize)> = structs
+            .iter()
+            .map(|def| {
+                let layout = def.layout();
+                (layout.member_offsets, layout.total_size, layout.alignment)
+            })
+            .collect();
+        assert_eq!(
+            layouts,
+            [
+                (vec![0, 8], 64, 64),
+                (vec![0, 64], 128, 64),
+                (vec![0], 32, 32),
+                (vec![0], 16, 16),
+                (vec![0, 8], 16, 8),
+                // Packing lowers the typedef's alignment but not the
+                // member's own.
+                (vec![0, 1, 8], 16, 8),
+            ]
+        );
+        let error = parse("struct A { _Alignas(3) int x; };", Target::X86_64SysV).unwrap_err();
+        assert_eq!(error.message, "alignment 3 is not a power of two");
+    }
+
+    #[test]
+    fn test_pragma_pack() {
+        let source = "
+            #pragma pack(push, 1)
+            struct A { char c; int i; };
+            #pragma pack(push, inner, 2)
+            struct B { char c; int i; };
+            #pragma pack(push, 8)
+            #pragma pack(pop, inner)
+            struct C { char c; int i; };
+            #pragma pack(pop)
+            struct D { char c; int i; };
+            #pragma pack(4)
+            struct E { char c; double d; };
+            #pragma pack()
+            #include <pshpack2.h>
+            struct F { char c; int i; };
+            #include <poppack.h>
+            struct G { char c; int i; };
+        ";
+        let structs = parse(source, Target::MsvcX64).unwrap();
+        let packing: Vec<Option<usize>> = structs.iter().map(|def| def.packing).collect();
+        assert_eq!(packing, [Some(1), Some(2), Some(1), None, Some(4), Some(2), None]);
+        let sizes: Vec<usize> = structs.iter().map(|def| def.layout().total_size).collect();
+        assert_eq!(sizes, [5, 6, 5, 8, 12, 6, 8]);
+
+        let error = |source| parse(source, Target::MsvcX64).unwrap_err();
+        assert_eq!(
+            error("struct A { int x; };\n#pragma pack(pop)\n"),
+            ParseError {
+                line: 2,
+                message: "`#pragma pack(pop)` without a matching push".to_string()
+            }
+        );
+        assert_eq!(error("#pragma pack(3)").message, "`3` is not a packing of 1, 2, 4, 8 or 16");
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |source| parse(source, Target::X86_64SysV).unwrap_err();
+        assert_eq!(
+            error("struct A {\n  struct B b;\n};"),
+            ParseError {
+                line: 2,
+                message: "`b` has incomplete type struct B".to_string()
+            }
+        );
+        assert_eq!(error("struct A { foo_t x; };").message, "unknown type `foo_t`");
+        assert_eq!(error("struct A { int x : 33; };").message, "bitfield `x` is wider than its type");
+        assert_eq!(error("struct A { int x : 0; };").message, "zero-width bitfield `x` must be unnamed");
+        assert_eq!(error("struct A { int *x : 3; };").message, "bitfield `x` must have integer type");
+        assert_eq!(error("struct A { int x }").message, "expected `;`, found `}`");
+        assert_eq!(error("struct A { enum E e; };").message, "enum E is not defined");
+        assert_eq!(
+            error("struct A { int n; char d[]; int m; };").message,
+            "flexible array member `d` is not at the end of the struct"
+        );
+        assert_eq!(error("struct A { int n; char d[2][]; };").message, "`d` is an array of flexible arrays");
+        assert_eq!(error("struct A { char c[1 - 2]; };").message, "constant expression is -1, expected a size");
+        assert_eq!(error("struct A { int x; };\nstruct A { int y; };").line, 2);
+        assert_eq!(error("struct A { long c[1 << 62]; };").message, "`c` is too large");
+        assert_eq!(error("struct A { char c[4][1 << 62]; };").message, "`c` is too large");
+        assert_eq!(error("struct A { char c[1 << 62]; };").message, "struct A is too large");
+        assert_eq!(error("struct A { struct { char c[1 << 61]; } b; };").message, "the struct is too large");
+        assert!(parse("struct A { __int128 x; };", Target::MsvcX64).is_err());
+        let huge = "enum E { A = ((1 << 126) - 1) + (1 << 126), B }; struct S { enum E e; };";
+        assert_eq!(error(huge).message, "the value of enumerator `B` overflows");
+        let largest = huge.replace(", B", "");
+        assert_eq!(error(&largest).message, "the values of enum E do not fit in its type on x86_64-sysv");
+        let shifted = "struct A { char c[1 << 127]; };";
+        assert_eq!(error(shifted).message, "constant expression overflows or divides by zero");
+    }
+}
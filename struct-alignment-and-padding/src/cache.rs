@@ -0,0 +1,134 @@
+//! Which cache lines a struct's members fall in.
+//!
+//! ```
+//! use struct_alignment_and_padding::cache::{DEFAULT_LINE_SIZE, occupancy};
+//! use struct_alignment_and_padding::{StructDef, TypeInfo};
+//!
+//! let layout = StructDef::new("S")
+//!     .array_member("hot", TypeInfo { size: 8, alignment: 8 }, [7])
+//!     .member("counter", TypeInfo { size: 16, alignment: 8 })
+//!     .layout();
+//! let report = occupancy(&layout, DEFAULT_LINE_SIZE);
+//! assert_eq!(report.lines[0].members, ["hot", "counter"]);
+//! assert_eq!(report.straddling, ["counter"]);
+//! assert_eq!(report.lines_touched, 2);
+//! ```
+
+use crate::StructLayout;
+
+/// The usual cache line of x86_64 and most aarch64 cores.
+pub const DEFAULT_LINE_SIZE: usize = 64;
+
+/// One cache line of a struct that starts at a line boundary.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CacheLine {
+    /// Offset of the line in the struct.
+    pub offset: usize,
+    /// The members with bytes in it, in order.
+    pub members: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct CacheReport {
+    pub line_size: usize,
+    pub lines: Vec<CacheLine>,
+    /// Members split between two or more lines, so that reading one may
+    /// miss twice.
+    pub straddling: Vec<String>,
+    /// Lines one instance touches when it starts at a line boundary, as
+    /// `alignas(64)` would make it.
+    pub lines_touched: usize,
+    /// Lines one instance touches at worst, starting anywhere its own
+    /// alignment allows.
+    pub worst_lines_touched: usize,
+}
+
+/// How the members of `layout` share `line_size`-byte cache lines. A
+/// bitfield is in the lines its own bits are in; zero-sized members, such
+/// as a flexible array member, are in none.
+pub fn occupancy(layout: &StructLayout, line_size: usize) -> CacheReport {
+    assert!(line_size > 0, "a cache line needs a byte");
+    let mut lines: Vec<CacheLine> = (0..layout.total_size.div_ceil(line_size))
+        .map(|i| CacheLine {
+            offset: i * line_size,
+            members: Vec::new(),
+        })
+        .collect();
+    let mut straddling = Vec::new();
+    for member in &layout.members {
+        let bytes = member.byte_range();
+        if bytes.is_empty() {
+            continue;
+        }
+        let (first, last) = (bytes.start / line_size, (bytes.end - 1) / line_size);
+        for line in &mut lines[first..=last] {
+            line.members.push(member.name.clone());
+        }
+        if first != last {
+            straddling.push(member.name.clone());
+        }
+    }
+
+    // An instance can start at any multiple of its alignment, which is
+    // any multiple of this within a line.
+    let step = gcd(layout.alignment, line_size);
+    let touched = |start: usize| (start + layout.total_size).div_ceil(line_size) - start / line_size;
+    let worst_lines_touched = if layout.total_size == 0 {
+        0
+    } else {
+        (0..line_size).step_by(step).map(touched).max().unwrap_or(0)
+    };
+    CacheReport {
+        line_size,
+        lines_touched: lines.len(),
+        lines,
+        straddling,
+        worst_lines_touched,
+    }
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{StructDef, TypeInfo};
+
+    fn ty(size: usize, alignment: usize) -> TypeInfo {
+        TypeInfo { size, alignment }
+    }
+
+    #[test]
+    fn test_occupancy() {
+        // 4 + 60 bytes fill the first line exactly; a 4-aligned struct
+        // of 72 bytes can touch three lines.
+        let layout = StructDef::new("S")
+            .member("id", ty(4, 4))
+            .array_member("data", ty(4, 4), [15])
+            .member("next", ty(4, 4))
+            .bitfield("a", ty(4, 4), 4)
+            .member("tail", ty(1, 1))
+            .flexible_member("rest", ty(4, 4))
+            .layout();
+        assert_eq!(layout.total_size, 72);
+        let report = occupancy(&layout, 64);
+        let lines: Vec<(usize, Vec<String>)> = report.lines.into_iter().map(|l| (l.offset, l.members)).collect();
+        let names = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        assert_eq!(lines, [(0, names(&["id", "data"])), (64, names(&["next", "a", "tail"]))]);
+        assert!(report.straddling.is_empty());
+        assert_eq!((report.lines_touched, report.worst_lines_touched), (2, 3));
+
+        let report = occupancy(&layout, 32);
+        assert_eq!(report.straddling, ["data"]);
+        assert_eq!(report.lines.len(), 3);
+        assert_eq!(report.lines[1].members, ["data"]);
+
+        // A line-sized, line-aligned struct only ever touches one.
+        let layout = StructDef::new("S").member("x", ty(64, 64)).layout();
+        assert_eq!(occupancy(&layout, 64).worst_lines_touched, 1);
+    }
+}
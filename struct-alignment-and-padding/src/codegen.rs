@@ -0,0 +1,255 @@
+//! `#[repr(C)]` Rust definitions of laid-out structs, for FFI bindings.
+//!
+//! ```
+//! use struct_alignment_and_padding::codegen::rust_struct;
+//! use struct_alignment_and_padding::{StructDef, TypeInfo};
+//!
+//! let def = StructDef::new("Header")
+//!     .member("tag", TypeInfo { size: 1, alignment: 1 })
+//!     .member("length", TypeInfo { size: 8, alignment: 8 });
+//! assert_eq!(
+//!     rust_struct(&def),
+//!     "#[repr(C)]
+//! pub struct Header {
+//!     pub tag: u8,
+//!     _pad1: [u8; 7],
+//!     pub length: u64,
+//! }
+//!
+//! const _: () = assert!(core::mem::size_of::<Header>() == 16);
+//! const _: () = assert!(core::mem::align_of::<Header>() == 8);
+//! "
+//! );
+//! ```
+//!
+//! A [`StructDef`] keeps sizes and alignments rather than C types, so each
+//! member becomes an unsigned integer, or an array of them, of its size and
+//! alignment: an `int` is a `u32`, a `double` a `u64`, a nested struct an
+//! array of its most aligned integer. Members packed below their alignment
+//! get smaller integers, and padding and trailing padding are explicit
+//! `_padN` byte arrays, so the offsets match without `packed`; an
+//! over-aligned struct gets `align(N)`. Rust has no bitfields, so each run
+//! of them becomes a byte array of the bytes their bits are in, with the
+//! bits listed above it. The size and alignment are checked by const
+//! assertions.
+
+use std::fmt::Write;
+
+use crate::{MemberLayout, StructDef, TypeInfo};
+
+/// Rust's strict and reserved keywords, which members called so become
+/// raw identifiers.
+const KEYWORDS: [&str; 48] = [
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn", "else", "enum",
+    "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro", "match", "mod", "move",
+    "mut", "override", "priv", "pub", "ref", "return", "static", "struct", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+/// Keywords that cannot be raw identifiers either, and `_`, which get a
+/// trailing `_` instead.
+const NOT_RAW: [&str; 5] = ["crate", "self", "Self", "super", "_"];
+
+/// A field of the generated struct.
+enum Field<'a> {
+    Member(&'a MemberLayout),
+    /// A run of bitfields, stored in the bytes from `offset`.
+    Bitfields {
+        offset: usize,
+        size: usize,
+        members: Vec<&'a MemberLayout>,
+    },
+}
+
+/// The `#[repr(C)]` Rust struct laid out as `def`, with const assertions
+/// of its size and alignment; see the module documentation.
+pub fn rust_struct(def: &StructDef) -> String {
+    let layout = def.layout();
+    let mut fields = Vec::new();
+    for member in &layout.members {
+        match (member.bitfield, fields.last_mut()) {
+            (None, _) => fields.push(Field::Member(member)),
+            (Some(_), Some(Field::Bitfields { offset, size, members })) => {
+                let bytes = member.byte_range();
+                *size = (*size).max(bytes.end.saturating_sub(*offset));
+                members.push(member);
+            }
+            (Some(_), _) => {
+                let bytes = member.byte_range();
+                fields.push(Field::Bitfields {
+                    offset: bytes.start,
+                    size: bytes.len(),
+                    members: vec![member],
+                });
+            }
+        }
+    }
+
+    let name = identifier(&def.name).unwrap_or_else(|| "Struct".to_string());
+    let mut body = String::new();
+    let mut end = 0;
+    let mut pads = 0;
+    let mut alignment = 1;
+    let mut pad_to = |body: &mut String, end: &mut usize, offset: usize| {
+        if offset > *end {
+            pads += 1;
+            writeln!(body, "    _pad{pads}: [u8; {}],", offset - *end).unwrap();
+            *end = offset;
+        }
+    };
+    for (i, field) in fields.iter().enumerate() {
+        match field {
+            Field::Member(member) => {
+                pad_to(&mut body, &mut end, member.offset);
+                let (ty, field_alignment) = member_type(member);
+                alignment = alignment.max(field_alignment);
+                let field_name = identifier(&member.name).unwrap_or_else(|| format!("field{}", i + 1));
+                writeln!(body, "    pub {field_name}: {ty},").unwrap();
+                end = end.max(member.offset + member.size);
+            }
+            Field::Bitfields { offset, size, members } => {
+                pad_to(&mut body, &mut end, *offset);
+                for member in members {
+                    let bits = member.bit_range();
+                    let (start, stop) = (bits.start - offset * 8, bits.end - offset * 8);
+                    let member_name = if member.name.is_empty() { "(unnamed)" } else { &member.name };
+                    writeln!(body, "    /// `{member_name}`: bits {start}..{stop}").unwrap();
+                }
+                writeln!(body, "    pub bitfields{}: [u8; {size}],", i + 1).unwrap();
+                end = end.max(offset + size);
+            }
+        }
+    }
+    pad_to(&mut body, &mut end, layout.total_size);
+
+    let mut out = String::new();
+    if layout.alignment > alignment {
+        writeln!(out, "#[repr(C, align({}))]", layout.alignment).unwrap();
+    } else {
+        out.push_str("#[repr(C)]\n");
+    }
+    writeln!(out, "pub struct {name} {{\n{body}}}\n").unwrap();
+    writeln!(out, "const _: () = assert!(core::mem::size_of::<{name}>() == {});", layout.total_size).unwrap();
+    writeln!(out, "const _: () = assert!(core::mem::align_of::<{name}>() == {});", layout.alignment).unwrap();
+    out
+}
+
+/// The Rust type of a member, and its alignment.
+fn member_type(member: &MemberLayout) -> (String, usize) {
+    let Some(array) = &member.array else {
+        let ty = TypeInfo {
+            size: member.size,
+            alignment: member.alignment,
+        };
+        return integers(ty, member.alignment);
+    };
+    let (mut ty, alignment) = integers(array.element, member.alignment);
+    for dim in array.dims.iter().rev() {
+        ty = format!("[{ty}; {dim}]");
+    }
+    (ty, alignment)
+}
+
+/// Unsigned integers making up `size` bytes, as aligned as they can be
+/// without exceeding `max_alignment`.
+fn integers(ty: TypeInfo, max_alignment: usize) -> (String, usize) {
+    let mut unit = 16;
+    while unit > 1 && (!ty.size.is_multiple_of(unit) || unit > max_alignment || unit > ty.alignment.max(1)) {
+        unit /= 2;
+    }
+    let integer = format!("u{}", unit * 8);
+    match ty.size / unit {
+        1 => (integer, unit),
+        count => (format!("[{integer}; {count}]"), unit),
+    }
+}
+
+/// `name` as a Rust identifier: raw if it is a keyword, or with a `_`
+/// after it if it cannot be; `None` if it is not an identifier at all, as
+/// `(anonymous)`.
+fn identifier(name: &str) -> Option<String> {
+    let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if !valid {
+        None
+    } else if NOT_RAW.contains(&name) {
+        Some(format!("{name}_"))
+    } else if KEYWORDS.contains(&name) {
+        Some(format!("r#{name}"))
+    } else {
+        Some(name.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ty(size: usize, alignment: usize) -> TypeInfo {
+        TypeInfo { size, alignment }
+    }
+
+    #[test]
+    fn test_rust_struct() {
+        let def = StructDef::new("Packet")
+            .member("kind", ty(1, 1))
+            .bitfield("flags", ty(4, 4), 3)
+            .bitfield("", ty(4, 4), 2)
+            .bitfield("ttl", ty(4, 4), 6)
+            .array_member("type", ty(2, 2), [2, 3])
+            .member("(anonymous)", ty(12, 4))
+            .aligned_member("id", ty(8, 8), 16)
+            .flexible_member("payload", ty(1, 1));
+        let expected = "
+#[repr(C, align(16))]
+pub struct Packet {
+    pub kind: u8,
+    /// `flags`: bits 0..3
+    /// `(unnamed)`: bits 3..5
+    /// `ttl`: bits 5..11
+    pub bitfields2: [u8; 2],
+    _pad1: [u8; 1],
+    pub r#type: [[u16; 3]; 2],
+    pub field4: [u32; 3],
+    _pad2: [u8; 4],
+    pub id: u64,
+    pub payload: [u8; 0],
+    _pad3: [u8; 8],
+}
+
+const _: () = assert!(core::mem::size_of::<Packet>() == 48);
+const _: () = assert!(core::mem::align_of::<Packet>() == 16);
+";
+        assert_eq!(rust_struct(&def), expected[1..]);
+    }
+
+    #[test]
+    fn test_identifiers() {
+        let def = StructDef::new("type")
+            .member("self", ty(1, 1))
+            .member("Self", ty(1, 1))
+            .member("super", ty(1, 1))
+            .member("crate", ty(1, 1))
+            .member("_", ty(1, 1))
+            .member("virtual", ty(1, 1))
+            .member("typeof", ty(1, 1))
+            .member("union", ty(1, 1));
+        let code = rust_struct(&def);
+        let fields = "pub self_: u8,\n    pub Self_: u8,\n    pub super_: u8,\n    pub crate_: u8,\n    pub __: u8,\n";
+        assert!(code.contains(fields));
+        assert!(code.contains("pub r#virtual: u8,\n    pub r#typeof: u8,\n    pub union: u8,\n}"));
+        assert!(code.starts_with("#[repr(C)]\npub struct r#type {\n"));
+        assert!(code.contains("size_of::<r#type>() == 8"));
+        assert_eq!(rust_struct(&StructDef::new("")).lines().nth(1), Some("pub struct Struct {"));
+    }
+
+    #[test]
+    fn test_packed() {
+        // #pragma pack(2): struct { char c; int i; double d; }
+        let def = StructDef::new("P").member("c", ty(1, 1)).member("i", ty(4, 4)).member("d", ty(8, 8)).packed(2);
+        let code = rust_struct(&def);
+        assert!(code.starts_with("#[repr(C)]\n"));
+        assert!(code.contains("    _pad1: [u8; 1],\n    pub i: [u16; 2],\n    pub d: [u16; 4],\n}"));
+        assert!(code.contains("size_of::<P>() == 14"));
+    }
+}
@@ -0,0 +1,785 @@
+//! an impl of the structure alignment and padding rules
+//! defined here: https://elric.pl/blog/struct-padding
+//!
+//! ```
+//! use struct_alignment_and_padding::{StructDef, TypeInfo};
+//!
+//! let def = StructDef::new("Header")
+//!     .member("tag", TypeInfo { size: 1, alignment: 1 })
+//!     .member("length", TypeInfo { size: 8, alignment: 8 });
+//! let layout = def.layout();
+//! assert_eq!(layout.members[1].name, "length");
+//! assert_eq!(layout.members[1].offset, 8);
+//! assert_eq!(layout.total_size, 16);
+//! ```
+
+use std::fmt;
+use std::ops::Range;
+
+pub mod c;
+pub mod cache;
+pub mod codegen;
+pub mod render;
+pub mod reorder;
+#[cfg(feature = "rust")]
+pub mod rust;
+pub mod target;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct TypeInfo {
+    pub size: usize,
+    pub alignment: usize,
+}
+
+/// An array: `dims` (outermost first, so `int a[2][3]` is `[2, 3]`) of
+/// `element`, stored row by row. An outermost dimension of 0 is a flexible
+/// array member, `T fam[]` (or the older `T fam[0]`): it adds nothing to
+/// the size of the struct, but may be indexed past its end.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ArrayType {
+    pub element: TypeInfo,
+    pub dims: Vec<usize>,
+}
+
+impl ArrayType {
+    pub fn new(element: TypeInfo, dims: impl Into<Vec<usize>>) -> Self {
+        Self {
+            element,
+            dims: dims.into(),
+        }
+    }
+
+    /// Whether the outermost dimension is empty, as in `T fam[]`.
+    pub fn is_flexible(&self) -> bool {
+        self.dims.first() == Some(&0)
+    }
+
+    /// Number of elements.
+    pub fn count(&self) -> usize {
+        self.dims.iter().product()
+    }
+
+    /// The size and alignment of the whole array; `None` if its size
+    /// overflows `usize`.
+    pub fn type_info(&self) -> Option<TypeInfo> {
+        let size = self.dims.iter().try_fold(self.element.size, |size, &dim| size.checked_mul(dim))?;
+        Some(TypeInfo {
+            size,
+            alignment: self.element.alignment,
+        })
+    }
+
+    /// Offset of the element at `indices` from the start of the array, or
+    /// with fewer indices than dimensions, of that sub-array; `None` if an
+    /// index is out of bounds or there are too many. The first index of a
+    /// flexible array is not bounded.
+    pub fn element_offset(&self, indices: &[usize]) -> Option<usize> {
+        if indices.len() > self.dims.len() {
+            return None;
+        }
+        let mut offset = 0;
+        for (k, (&index, &dim)) in indices.iter().zip(&self.dims).enumerate() {
+            if index >= dim && !(k == 0 && self.is_flexible()) {
+                return None;
+            }
+            let stride: usize = self.element.size * self.dims[k + 1..].iter().product::<usize>();
+            offset += index * stride;
+        }
+        Some(offset)
+    }
+}
+
+/// A named member of a struct.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Member {
+    pub name: String,
+    /// The whole member: for an array, all of it.
+    pub ty: TypeInfo,
+    /// `alignas(N)` on the member: it is aligned to at least N, even in a
+    /// packed struct.
+    pub align: Option<usize>,
+    /// The elements, if the member is an array.
+    pub array: Option<ArrayType>,
+    /// The width in bits, if the member is a bitfield of type `ty`.
+    pub bits: Option<usize>,
+}
+
+impl Member {
+    pub fn new(name: impl Into<String>, ty: TypeInfo) -> Self {
+        Self {
+            name: name.into(),
+            ty,
+            align: None,
+            array: None,
+            bits: None,
+        }
+    }
+
+    /// A bitfield `bits` wide of the integer type `ty`. An unnamed one, as
+    /// `int : 3;`, has an empty name.
+    pub fn bitfield(name: impl Into<String>, ty: TypeInfo, bits: usize) -> Self {
+        Self {
+            bits: Some(bits),
+            ..Self::new(name, ty)
+        }
+    }
+
+    /// An array member. Panics if the size of the array overflows `usize`.
+    pub fn array(name: impl Into<String>, array: ArrayType) -> Self {
+        let ty = array.type_info().expect("array size overflows usize");
+        Self {
+            array: Some(array),
+            ..Self::new(name, ty)
+        }
+    }
+
+    /// Aligns the member to at least `n`, a power of two.
+    pub fn aligned(mut self, n: usize) -> Self {
+        self.align = Some(n);
+        self
+    }
+}
+
+/// A struct: its name and its members in declaration order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructDef {
+    pub name: String,
+    pub members: Vec<Member>,
+    /// `#pragma pack(N)` or `#[repr(packed(N))]`: no member is aligned to
+    /// more than N.
+    pub packing: Option<usize>,
+    /// `alignas(N)` on the struct or `#[repr(align(N))]`: the struct is
+    /// aligned to at least N.
+    pub alignment: Option<usize>,
+    /// How bitfields are allocated.
+    pub bitfields: BitfieldRules,
+}
+
+impl StructDef {
+    /// A struct with no members yet.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            members: Vec::new(),
+            packing: None,
+            alignment: None,
+            bitfields: BitfieldRules::Itanium,
+        }
+    }
+
+    /// Appends a member.
+    pub fn member(mut self, name: impl Into<String>, ty: TypeInfo) -> Self {
+        self.members.push(Member::new(name, ty));
+        self
+    }
+
+    /// Appends an array member of `dims` (outermost first) of `element`.
+    pub fn array_member(mut self, name: impl Into<String>, element: TypeInfo, dims: impl Into<Vec<usize>>) -> Self {
+        self.members.push(Member::array(name, ArrayType::new(element, dims)));
+        self
+    }
+
+    /// Appends a bitfield `bits` wide; see [`Member::bitfield`].
+    pub fn bitfield(mut self, name: impl Into<String>, ty: TypeInfo, bits: usize) -> Self {
+        self.members.push(Member::bitfield(name, ty, bits));
+        self
+    }
+
+    /// Appends a flexible array member of `element`, as `T fam[]`, which
+    /// belongs at the end.
+    pub fn flexible_member(mut self, name: impl Into<String>, element: TypeInfo) -> Self {
+        self.members.push(Member::array(name, ArrayType::new(element, [0])));
+        self
+    }
+
+    /// Appends a member aligned to at least `n`.
+    pub fn aligned_member(mut self, name: impl Into<String>, ty: TypeInfo, n: usize) -> Self {
+        self.members.push(Member::new(name, ty).aligned(n));
+        self
+    }
+
+    /// Caps the alignment of every member at `n`, a power of two.
+    pub fn packed(mut self, n: usize) -> Self {
+        self.packing = Some(n);
+        self
+    }
+
+    /// Aligns the struct to at least `n`, a power of two.
+    pub fn aligned(mut self, n: usize) -> Self {
+        self.alignment = Some(n);
+        self
+    }
+
+    /// Allocates bitfields by `rules`.
+    pub fn bitfield_rules(mut self, rules: BitfieldRules) -> Self {
+        self.bitfields = rules;
+        self
+    }
+
+    /// Panics if the struct is too big to lay out; see
+    /// [`StructDef::try_layout`].
+    pub fn layout(&self) -> StructLayout {
+        self.try_layout().expect("struct size overflows usize")
+    }
+
+    /// The layout, or `None` if the struct is too big for its size in bits
+    /// to fit in a `usize`.
+    pub fn try_layout(&self) -> Option<StructLayout> {
+        let layout = StructLayout::try_compute_with(&self.members, self.packing, self.bitfields)?;
+        match self.alignment {
+            Some(n) if n > layout.alignment => {
+                checked_pad(layout.total_size, n)?;
+                Some(layout.align_to(n))
+            }
+            _ => Some(layout),
+        }
+    }
+}
+
+/// What [`StructLayout::compute`] lays out: named members, or bare types,
+/// which are named by position (`member1`, `member2`, ...).
+pub trait Field {
+    fn name(&self) -> Option<&str>;
+    fn ty(&self) -> TypeInfo;
+
+    /// An explicit minimum alignment, which packing does not lower.
+    fn min_alignment(&self) -> Option<usize> {
+        None
+    }
+
+    /// The elements, for an array.
+    fn array(&self) -> Option<&ArrayType> {
+        None
+    }
+
+    /// The width in bits, for a bitfield.
+    fn bits(&self) -> Option<usize> {
+        None
+    }
+}
+
+impl Field for TypeInfo {
+    fn name(&self) -> Option<&str> {
+        None
+    }
+
+    fn ty(&self) -> TypeInfo {
+        *self
+    }
+}
+
+impl Field for Member {
+    fn name(&self) -> Option<&str> {
+        Some(&self.name)
+    }
+
+    fn ty(&self) -> TypeInfo {
+        self.ty
+    }
+
+    fn min_alignment(&self) -> Option<usize> {
+        self.align
+    }
+
+    fn array(&self) -> Option<&ArrayType> {
+        self.array.as_ref()
+    }
+
+    fn bits(&self) -> Option<usize> {
+        self.bits
+    }
+}
+
+/// How bitfields are allocated, which is where compilers differ most.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BitfieldRules {
+    /// GCC and Clang everywhere but Windows: a bitfield goes at the next
+    /// free bit unless it would cross a boundary of its type's alignment
+    /// (packed to 1, not even then), so bitfields of different types share
+    /// bytes and a bitfield may start in the bytes of an ordinary member.
+    /// Zero-width and unnamed bitfields do not raise the struct's
+    /// alignment.
+    #[default]
+    Itanium,
+    /// Visual C++: adjacent bitfields share a storage unit of their type
+    /// only while they fit and the type has the same size; otherwise the
+    /// rest of the unit is padding and a new one starts. A zero-width
+    /// bitfield only ends the unit after a bitfield. Every named or unnamed
+    /// bitfield raises the struct's alignment to its type's.
+    Msvc,
+}
+
+/// Where a bitfield's bits are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Bitfield {
+    /// First bit, counted from the least significant bit at the member's
+    /// offset.
+    pub bit_offset: usize,
+    pub width: usize,
+}
+
+/// Where one member ended up.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct MemberLayout {
+    pub name: String,
+    pub offset: usize,
+    pub size: usize,
+    /// After packing and any explicit alignment.
+    pub alignment: usize,
+    /// Padding between this member and the next, or the end of the struct.
+    pub padding_after: usize,
+    pub array: Option<ArrayType>,
+    /// For a bitfield, which bits of the storage unit at `offset`, `size`
+    /// bytes of its type, it has.
+    pub bitfield: Option<Bitfield>,
+}
+
+impl MemberLayout {
+    /// Offset in the struct of the array element at `indices`; see
+    /// [`ArrayType::element_offset`]. `None` if this is not an array.
+    pub fn element_offset(&self, indices: &[usize]) -> Option<usize> {
+        Some(self.offset + self.array.as_ref()?.element_offset(indices)?)
+    }
+
+    /// The bits the member's data is in, counted from the start of the
+    /// struct: for a bitfield only its own, not the rest of its storage
+    /// unit.
+    pub fn bit_range(&self) -> Range<usize> {
+        match self.bitfield {
+            Some(bits) => {
+                let start = self.offset * 8 + bits.bit_offset;
+                start..start + bits.width
+            }
+            None => self.offset * 8..(self.offset + self.size) * 8,
+        }
+    }
+
+    /// The bytes [`MemberLayout::bit_range`] touches.
+    pub fn byte_range(&self) -> Range<usize> {
+        let bits = self.bit_range();
+        bits.start / 8..bits.end.div_ceil(8)
+    }
+}
+
+#[derive(Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct StructLayout {
+    pub members: Vec<MemberLayout>,
+    pub member_offsets: Vec<usize>,
+    pub paddings: Vec<usize>,
+    pub total_size: usize,
+    pub alignment: usize,
+}
+
+/// Why C or Rust source could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// 1-based line of the input.
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Rounds `x` up to the next multiple of `alignment`.
+pub fn pad(x: usize, alignment: usize) -> usize {
+    x.div_ceil(alignment) * alignment
+}
+
+/// [`pad`], or `None` on overflow.
+fn checked_pad(x: usize, alignment: usize) -> Option<usize> {
+    x.div_ceil(alignment).checked_mul(alignment)
+}
+
+impl StructLayout {
+    /// Lays `members` out in order, each at the next multiple of its
+    /// alignment, or of `packing` if that is smaller, or of its explicit
+    /// minimum alignment if that is larger. The struct is aligned to its
+    /// most aligned member and padded to a multiple of that, so packing
+    /// also shrinks the trailing padding. Bitfields are allocated by the
+    /// Itanium rules.
+    pub fn compute<F: Field>(members: &[F], packing: Option<usize>) -> Self {
+        Self::compute_with(members, packing, BitfieldRules::Itanium)
+    }
+
+    /// [`StructLayout::compute`], allocating bitfields by `rules`.
+    ///
+    /// Paddings count the whole bytes between the bits of one member and
+    /// the next: bitfields sharing a byte have none between them. Panics
+    /// if the struct is too big for its size in bits to fit in a `usize`.
+    pub fn compute_with<F: Field>(members: &[F], packing: Option<usize>, rules: BitfieldRules) -> Self {
+        Self::try_compute_with(members, packing, rules).expect("struct size overflows usize")
+    }
+
+    /// [`StructLayout::compute_with`], or `None` if the struct is too big.
+    pub fn try_compute_with<F: Field>(members: &[F], packing: Option<usize>, rules: BitfieldRules) -> Option<Self> {
+        assert!(packing.is_none_or(usize::is_power_of_two), "packing must be a power of two");
+        if members.is_empty() {
+            return Some(StructLayout {
+                members: vec![],
+                member_offsets: vec![],
+                paddings: vec![],
+                total_size: 0,
+                alignment: 1,
+            });
+        }
+
+        /// A member placed, as byte offset and size and, in bits, where its
+        /// data is.
+        struct Placed {
+            offset: usize,
+            ty: TypeInfo,
+            bitfield: Option<Bitfield>,
+            data: (usize, usize),
+        }
+
+        let mut placed = Vec::with_capacity(members.len());
+        // The next free bit, and the Visual C++ storage unit being filled,
+        // as its first bit and size in bytes.
+        let mut pos = 0;
+        let mut unit: Option<(usize, usize)> = None;
+        let mut struct_alignment = 1;
+        for member in members {
+            let ty = member.ty();
+            let alignment = packing.map_or(ty.alignment, |n| ty.alignment.min(n));
+            let Some(width) = member.bits() else {
+                let min_alignment = member.min_alignment().unwrap_or(1);
+                assert!(min_alignment.is_power_of_two(), "alignment must be a power of two");
+                let alignment = alignment.max(min_alignment);
+                if let Some((start, size)) = unit.take() {
+                    pos = start.checked_add(size * 8)?;
+                }
+                let offset = checked_pad(pos.div_ceil(8), alignment)?;
+                pos = offset.checked_add(ty.size)?.checked_mul(8)?;
+                struct_alignment = struct_alignment.max(alignment);
+                placed.push(Placed {
+                    offset,
+                    ty: TypeInfo {
+                        size: ty.size,
+                        alignment,
+                    },
+                    bitfield: None,
+                    data: (offset * 8, pos),
+                });
+                continue;
+            };
+            assert!(width <= ty.size * 8, "a bitfield cannot be wider than its type");
+            let (offset, bit_offset) = match rules {
+                BitfieldRules::Itanium => {
+                    let boundary = alignment * 8;
+                    // A zero-width bitfield, or one that would straddle a
+                    // boundary, starts at the next one.
+                    let straddles = packing != Some(1) && pos % boundary + width > ty.size * 8;
+                    if width == 0 || straddles {
+                        pos = checked_pad(pos, boundary)?;
+                    }
+                    if width > 0 && member.name().is_none_or(|name| !name.is_empty()) {
+                        struct_alignment = struct_alignment.max(alignment);
+                    }
+                    // The unit of its type that holds it, or when packed,
+                    // the byte it starts in.
+                    let start = if packing == Some(1) { pos / 8 } else { pos / boundary * alignment };
+                    (start, pos - start * 8)
+                }
+                BitfieldRules::Msvc => {
+                    match unit {
+                        Some((start, size)) if size == ty.size && pos + width <= start + size * 8 && width > 0 => {}
+                        _ if width == 0 && unit.is_none() => {}
+                        _ => {
+                            if let Some((start, size)) = unit.take() {
+                                pos = start.checked_add(size * 8)?;
+                            }
+                            let offset = checked_pad(pos.div_ceil(8), alignment)?;
+                            pos = offset.checked_mul(8)?;
+                            if width > 0 {
+                                unit = Some((pos, ty.size));
+                            }
+                        }
+                    }
+                    if width > 0 {
+                        struct_alignment = struct_alignment.max(alignment);
+                    }
+                    let start = unit.map_or(pos, |(start, _)| start);
+                    (start / 8, pos - start)
+                }
+            };
+            let size = if width == 0 { 0 } else { ty.size.max((bit_offset + width).div_ceil(8)) };
+            placed.push(Placed {
+                offset,
+                ty: TypeInfo { size, alignment },
+                bitfield: Some(Bitfield { bit_offset, width }),
+                data: (pos, pos.checked_add(width)?),
+            });
+            pos += width;
+        }
+        if let Some((start, size)) = unit {
+            pos = start.checked_add(size * 8)?;
+        }
+
+        let end = pos.div_ceil(8);
+        let total_size = checked_pad(end, struct_alignment)?;
+        let paddings: Vec<usize> =
+            placed.windows(2).map(|pair| (pair[1].data.0 / 8).saturating_sub(pair[0].data.1.div_ceil(8))).collect();
+        let last_padding = total_size - placed.last().unwrap().data.1.div_ceil(8);
+
+        let layouts = members
+            .iter()
+            .zip(&placed)
+            .enumerate()
+            .map(|(i, (member, placed))| MemberLayout {
+                name: member.name().map_or_else(|| format!("member{}", i + 1), str::to_string),
+                offset: placed.offset,
+                size: placed.ty.size,
+                alignment: placed.ty.alignment,
+                padding_after: paddings.get(i).copied().unwrap_or(last_padding),
+                array: member.array().cloned(),
+                bitfield: placed.bitfield,
+            })
+            .collect();
+
+        Some(StructLayout {
+            members: layouts,
+            member_offsets: placed.iter().map(|placed| placed.offset).collect(),
+            paddings,
+            total_size,
+            alignment: struct_alignment,
+        })
+    }
+
+    /// Raises the alignment of the struct to at least `n`, a power of two,
+    /// as `alignas(N)` on the struct or `#[repr(align(N))]` does; the size
+    /// grows to a multiple of it by trailing padding.
+    pub fn align_to(mut self, n: usize) -> Self {
+        assert!(n.is_power_of_two(), "alignment must be a power of two");
+        if n <= self.alignment {
+            return self;
+        }
+        let total_size = pad(self.total_size, n);
+        if let Some(last) = self.members.last_mut() {
+            last.padding_after += total_size - self.total_size;
+        }
+        self.total_size = total_size;
+        self.alignment = n;
+        self
+    }
+
+    /// Bytes to allocate for the struct with `n` elements in its trailing
+    /// flexible array member: up to the end of the last element, at least
+    /// the size of the struct, and padded to its alignment so an array of
+    /// such structs stays aligned. `None` if the last member is not a
+    /// flexible array, or if the size overflows `usize`.
+    pub fn size_for(&self, n: usize) -> Option<usize> {
+        let last = self.members.last()?;
+        let array = last.array.as_ref().filter(|array| array.is_flexible())?;
+        let row: usize = array.dims[1..].iter().product();
+        let end = n.checked_mul(row)?.checked_mul(array.element.size)?.checked_add(last.offset)?;
+        Some(checked_pad(end, self.alignment)?.max(self.total_size))
+    }
+
+    /// The member called `name`.
+    pub fn member(&self, name: &str) -> Option<&MemberLayout> {
+        self.members.iter().find(|member| member.name == name)
+    }
+
+    /// Offset in the struct of element `indices` of array member `name`,
+    /// as for `&s.name[i][j]`.
+    pub fn element_offset(&self, name: &str, indices: &[usize]) -> Option<usize> {
+        self.member(name)?.element_offset(indices)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ty(size: usize, alignment: usize) -> TypeInfo {
+        TypeInfo { size, alignment }
+    }
+
+    #[test]
+    fn test_named_layout() {
+        let layout = StructDef::new("S")
+            .member("a", ty(4, 4))
+            .member("b", ty(2, 2))
+            .member("c", ty(8, 8))
+            .member("d", ty(1, 1))
+            .layout();
+        let members: Vec<(&str, usize, usize)> =
+            layout.members.iter().map(|m| (m.name.as_str(), m.offset, m.padding_after)).collect();
+        assert_eq!(members, [("a", 0, 0), ("b", 4, 2), ("c", 8, 0), ("d", 16, 7)]);
+        assert_eq!(layout.member_offsets, [0, 4, 8, 16]);
+        assert_eq!(layout.paddings, [0, 2, 0]);
+        assert_eq!((layout.total_size, layout.alignment), (24, 8));
+    }
+
+    #[test]
+    fn test_bare_types_are_named_by_position() {
+        let layout = StructLayout::compute(&[ty(1, 1), ty(4, 4)], None);
+        assert_eq!(layout.members[1].name, "member2");
+        assert_eq!(layout.members[1].offset, 4);
+        assert_eq!(StructLayout::compute::<TypeInfo>(&[], None).total_size, 0);
+    }
+
+    #[test]
+    fn test_packing() {
+        // char, double, short, as under #pragma pack(1), (2) and (4)
+        let packed = |n| {
+            let layout = StructDef::new("P")
+                .member("c", ty(1, 1))
+                .member("d", ty(8, 8))
+                .member("s", ty(2, 2))
+                .packed(n)
+                .layout();
+            (layout.member_offsets, layout.total_size, layout.alignment)
+        };
+        assert_eq!(packed(1), (vec![0, 1, 9], 11, 1));
+        assert_eq!(packed(2), (vec![0, 2, 10], 12, 2));
+        assert_eq!(packed(4), (vec![0, 4, 12], 16, 4));
+        // Packing above the natural alignment changes nothing.
+        assert_eq!(packed(16), (vec![0, 8, 16], 24, 8));
+    }
+
+    #[test]
+    fn test_array_members() {
+        // struct { char tag; int grid[2][3]; short tail[4]; }
+        let layout = StructDef::new("S")
+            .member("tag", ty(1, 1))
+            .array_member("grid", ty(4, 4), [2, 3])
+            .array_member("tail", ty(2, 2), [4])
+            .layout();
+        assert_eq!(layout.member_offsets, [0, 4, 28]);
+        assert_eq!((layout.total_size, layout.alignment), (36, 4));
+        let grid = layout.member("grid").unwrap();
+        assert_eq!((grid.size, grid.alignment), (24, 4));
+        assert_eq!(grid.array.as_ref().map(ArrayType::count), Some(6));
+        assert_eq!(layout.element_offset("grid", &[0, 0]), Some(4));
+        assert_eq!(layout.element_offset("grid", &[1, 2]), Some(4 + 20));
+        // A row of the grid.
+        assert_eq!(layout.element_offset("grid", &[1]), Some(4 + 12));
+        assert_eq!(layout.element_offset("grid", &[2, 0]), None);
+        assert_eq!(layout.element_offset("grid", &[0, 0, 0]), None);
+        assert_eq!(layout.element_offset("tail", &[3]), Some(34));
+        assert_eq!(layout.element_offset("tag", &[0]), None);
+    }
+
+    #[test]
+    fn test_overflow() {
+        assert_eq!(ArrayType::new(ty(8, 8), [1 << 62]).type_info(), None);
+        assert_eq!(ArrayType::new(ty(1, 1), [4, 1 << 62]).type_info(), None);
+        // 2^62 bytes fit, 2^65 bits do not.
+        let def = StructDef::new("S").array_member("c", ty(1, 1), [1 << 62]);
+        assert!(def.try_layout().is_none());
+        let def = StructDef::new("S").member("a", ty(1, 1)).member("b", ty(usize::MAX / 8, 8));
+        assert!(def.try_layout().is_none());
+        let def = StructDef::new("S").member("a", ty(1 << 60, 1)).aligned(1 << 62);
+        assert_eq!(def.try_layout().map(|layout| layout.total_size), Some(1 << 62));
+    }
+
+    #[test]
+    fn test_flexible_array_member() {
+        // struct { int len; char kind; double data[]; }
+        let layout = StructDef::new("S")
+            .member("len", ty(4, 4))
+            .member("kind", ty(1, 1))
+            .flexible_member("data", ty(8, 8))
+            .layout();
+        assert_eq!(layout.member_offsets, [0, 4, 8]);
+        assert_eq!((layout.total_size, layout.alignment), (8, 8));
+        assert_eq!(layout.size_for(0), Some(8));
+        assert_eq!(layout.size_for(3), Some(32));
+        assert_eq!(layout.size_for(usize::MAX / 8), None);
+        assert_eq!(layout.element_offset("data", &[5]), Some(48));
+
+        // struct { int len; char bytes[]; }: the bytes may use the
+        // trailing padding.
+        let layout = StructDef::new("S").member("len", ty(4, 4)).flexible_member("bytes", ty(1, 1)).layout();
+        assert_eq!(layout.total_size, 4);
+        assert_eq!(layout.size_for(1), Some(8));
+        assert_eq!(layout.size_for(4), Some(8));
+        assert_eq!(layout.size_for(5), Some(12));
+
+        let layout = StructDef::new("S").member("len", ty(4, 4)).layout();
+        assert_eq!(layout.size_for(1), None);
+    }
+
+    #[test]
+    fn test_bitfields() {
+        let layout = |def: StructDef, rules| {
+            let layout = def.bitfield_rules(rules).layout();
+            let bits = layout.members.iter().map(|m| (m.offset, m.bitfield.map(|b| b.bit_offset))).collect();
+            (bits, layout.total_size)
+        };
+        type Bits = Vec<(usize, Option<usize>)>;
+
+        // struct { char c; long long x : 40; }, with i686's 4-aligned long
+        // long: the bitfield fits in the unit at 0 under Itanium.
+        let def = StructDef::new("S").member("c", ty(1, 1)).bitfield("x", ty(8, 4), 40);
+        let expected: (Bits, usize) = (vec![(0, None), (0, Some(8))], 8);
+        assert_eq!(layout(def.clone(), BitfieldRules::Itanium), expected);
+        let expected: (Bits, usize) = (vec![(0, None), (4, Some(0))], 12);
+        assert_eq!(layout(def, BitfieldRules::Msvc), expected);
+
+        // 60 bits would cross the 4-byte boundary at 8 bytes.
+        let def = StructDef::new("S").member("c", ty(1, 1)).bitfield("x", ty(8, 4), 60);
+        let expected: (Bits, usize) = (vec![(0, None), (4, Some(0))], 12);
+        assert_eq!(layout(def, BitfieldRules::Itanium), expected);
+
+        // struct { char c; int : 5; }: the unnamed bitfield does not align
+        // the struct under Itanium.
+        let def = StructDef::new("S").member("c", ty(1, 1)).bitfield("", ty(4, 4), 5);
+        let expected: (Bits, usize) = (vec![(0, None), (0, Some(8))], 2);
+        assert_eq!(layout(def.clone(), BitfieldRules::Itanium), expected);
+        let expected: (Bits, usize) = (vec![(0, None), (4, Some(0))], 8);
+        assert_eq!(layout(def, BitfieldRules::Msvc), expected);
+
+        // Packed, bitfields straddle anything.
+        let layout = StructDef::new("S").bitfield("a", ty(1, 1), 3).bitfield("b", ty(4, 4), 30).packed(1).layout();
+        assert_eq!(layout.members[1].bitfield, Some(Bitfield { bit_offset: 3, width: 30 }));
+        assert_eq!((layout.members[1].offset, layout.members[1].size), (0, 5));
+        assert_eq!((layout.total_size, layout.alignment), (5, 1));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serialize() {
+        let layout = StructDef::new("S").member("a", ty(1, 1)).array_member("b", ty(4, 4), [2]).layout();
+        let json = serde_json::to_value(&layout).unwrap();
+        assert_eq!(json["total_size"], 12);
+        assert_eq!(json["members"][1]["name"], "b");
+        assert_eq!(json["members"][1]["array"]["dims"], serde_json::json!([2]));
+        assert_eq!(json["members"][0]["padding_after"], 3);
+    }
+
+    #[test]
+    fn test_over_alignment() {
+        // struct { char c; alignas(64) char buf[8]; int n; }
+        let def = StructDef::new("S")
+            .member("c", ty(1, 1))
+            .aligned_member("buf", ty(8, 1), 64)
+            .member("n", ty(4, 4));
+        let layout = def.layout();
+        assert_eq!(layout.member_offsets, [0, 64, 72]);
+        assert_eq!((layout.total_size, layout.alignment), (128, 64));
+        assert_eq!(layout.members[2].padding_after, 52);
+        // The explicit alignment survives packing.
+        assert_eq!(def.clone().packed(1).layout().member_offsets, [0, 64, 72]);
+
+        // #[repr(C, align(32))] struct { a: u32, b: u8 }, then nested after a u8
+        let inner = StructDef::new("Inner").member("a", ty(4, 4)).member("b", ty(1, 1)).aligned(32).layout();
+        assert_eq!((inner.total_size, inner.alignment, inner.members[1].padding_after), (32, 32, 27));
+        let nested = StructLayout::compute(&[ty(1, 1), ty(inner.total_size, inner.alignment)], None);
+        assert_eq!((nested.member_offsets[1], nested.total_size), (32, 64));
+        // Alignment below the natural one changes nothing.
+        assert_eq!(StructDef::new("T").member("a", ty(8, 8)).aligned(4).layout().alignment, 8);
+    }
+}
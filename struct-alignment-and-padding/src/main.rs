@@ -1,81 +1,268 @@
-/// an impl of the structure alignment and padding rules
-/// defined here: https://elric.pl/blog/struct-padding
-#[derive(Debug, Clone)]
-struct TypeInfo {
-    size: usize,
-    alignment: usize,
+use std::path::PathBuf;
+use std::process::exit;
+
+use struct_alignment_and_padding::c;
+use struct_alignment_and_padding::cache::{DEFAULT_LINE_SIZE, occupancy};
+use struct_alignment_and_padding::codegen::rust_struct;
+use struct_alignment_and_padding::render::{byte_map, svg};
+use struct_alignment_and_padding::reorder::optimize;
+use struct_alignment_and_padding::target::{Primitive, Target};
+use struct_alignment_and_padding::{ParseError, StructDef, StructLayout, TypeInfo};
+
+const USAGE: &str = "\
+usage: struct-alignment-and-padding [--target TARGET] [--format FORMAT]
+           [--map WIDTH] [--svg DIR] [--cache] [--line-size BYTES] [FILE]
+
+Prints the layout of every struct defined in the C source FILE, or of a
+built-in example without one. A FILE ending in `.rs` is read as Rust, and
+its `#[repr(C)]` structs are laid out (needs a build with `--features
+rust`).
+
+  --target TARGET  the ABI to lay out for, one of x86_64-sysv, i686,
+                   aarch64, aarch64-apple, msvc-x64, msvc-x86, wasm32
+                   (default: the host)
+  --format FORMAT  text (the default); json for other tools to read (needs
+                   a build with `--features serde`); or rust for
+                   `#[repr(C)]` Rust definitions of the same layouts
+  --map WIDTH      also draw each struct as a map of its bytes, WIDTH (such
+                   as 8 or 16) to a row
+  --svg DIR        also write each struct as an SVG image, with its cache
+                   lines marked, to DIR/NAME.svg
+  --cache          also report which members share each cache line and
+                   which straddle two
+  --line-size BYTES
+                   the cache line size (default: 64)";
+
+enum Format {
+    Text,
+    Json,
+    Rust,
 }
 
-#[derive(Debug)]
-struct StructLayout {
-    member_offsets: Vec<usize>,
-    paddings: Vec<usize>,
-    total_size: usize,
-    alignment: usize,
+/// How and what to print besides the layouts.
+struct Options {
+    format: Format,
+    /// Bytes per row of a byte map.
+    map: Option<usize>,
+    /// Where to write SVG images.
+    svg: Option<PathBuf>,
+    cache: bool,
+    line_size: usize,
 }
 
-fn pad(x: usize, alignment: usize) -> usize {
-    ((x + alignment - 1) / alignment) * alignment
+impl Default for Options {
+    fn default() -> Self {
+        Self {
+            format: Format::Text,
+            map: None,
+            svg: None,
+            cache: false,
+            line_size: DEFAULT_LINE_SIZE,
+        }
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("error: {message}");
+    exit(1)
 }
 
-impl StructLayout {
-    fn compute(members: &[TypeInfo]) -> Self {
-        if members.is_empty() {
-            return StructLayout {
-                member_offsets: vec![],
-                paddings: vec![],
-                total_size: 0,
-                alignment: 1,
-            };
+fn main() {
+    let mut target = Target::host().unwrap_or(Target::X86_64SysV);
+    let mut path = None;
+    let mut options = Options::default();
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{USAGE}");
+                return;
+            }
+            "--target" => {
+                let name = args.next().unwrap_or_else(|| fail("--target needs a value"));
+                target = name.parse().unwrap_or_else(|e: String| fail(&e));
+            }
+            "--format" => match args.next().as_deref() {
+                Some("text") => options.format = Format::Text,
+                Some("json") => options.format = Format::Json,
+                Some("rust") => options.format = Format::Rust,
+                Some(other) => fail(&format!("unknown format `{other}`, expected text, json or rust")),
+                None => fail("--format needs a value"),
+            },
+            "--map" => {
+                let width = args.next().unwrap_or_else(|| fail("--map needs a value"));
+                match width.parse() {
+                    Ok(width) if width > 0 => options.map = Some(width),
+                    _ => fail(&format!("--map needs a positive number of bytes, not `{width}`")),
+                }
+            }
+            "--cache" => options.cache = true,
+            "--line-size" => {
+                let size = args.next().unwrap_or_else(|| fail("--line-size needs a value"));
+                match size.parse() {
+                    Ok(size) if size > 0 => options.line_size = size,
+                    _ => fail(&format!("--line-size needs a positive number of bytes, not `{size}`")),
+                }
+            }
+            "--svg" => options.svg = Some(args.next().unwrap_or_else(|| fail("--svg needs a directory")).into()),
+            _ if path.is_none() && !arg.starts_with('-') => path = Some(arg),
+            _ => fail(&format!("unexpected argument `{arg}`\n\n{USAGE}")),
         }
+    }
 
-        let mut offsets = Vec::with_capacity(members.len());
-        let mut paddings = Vec::with_capacity(members.len() - 1);
-        let _current_offset = 0;
+    let Some(path) = path else {
+        example(target, &options);
+        return;
+    };
+    let source = std::fs::read_to_string(&path).unwrap_or_else(|e| fail(&format!("cannot read {path}: {e}")));
+    let parsed = if path.ends_with(".rs") { parse_rust(&source, target) } else { c::parse(&source, target) };
+    let structs = parsed.unwrap_or_else(|e| fail(&format!("{path}: {e}")));
+    match options.format {
+        Format::Text => {}
+        Format::Json => return print_json(target, &structs, &options),
+        Format::Rust => return print_rust(target, &structs),
+    }
+    println!("Target: {target}");
+    for def in &structs {
+        println!();
+        print_layout(def, &options);
+    }
+}
 
-        // First member starts at offset 0
-        offsets.push(0);
+#[cfg(feature = "rust")]
+fn parse_rust(source: &str, target: Target) -> Result<Vec<StructDef>, ParseError> {
+    struct_alignment_and_padding::rust::parse(source, target)
+}
 
-        // Calculate offsets and paddings between members
-        for (i, member) in members.iter().enumerate().skip(1) {
-            let previous_end = offsets[i - 1] + members[i - 1].size;
-            let aligned_offset = pad(previous_end, member.alignment);
-            
-            paddings.push(aligned_offset - previous_end);
-            offsets.push(aligned_offset);
-        }
+#[cfg(not(feature = "rust"))]
+fn parse_rust(_source: &str, _target: Target) -> Result<Vec<StructDef>, ParseError> {
+    fail("Rust source needs a build with `--features rust`")
+}
 
-        // Calculate total size with final padding
-        let last_member_end = offsets.last().unwrap() + members.last().unwrap().size;
-        let struct_alignment = members.iter().map(|t| t.alignment).max().unwrap();
-        let total_size = pad(last_member_end, struct_alignment);
+/// Prints the layouts as one JSON object, and writes any SVG images.
+#[cfg(feature = "serde")]
+fn print_json(target: Target, structs: &[StructDef], options: &Options) {
+    let structs: Vec<serde_json::Value> = structs
+        .iter()
+        .map(|def| {
+            let layout = def.layout();
+            write_svg(def, &layout, options);
+            let cache = options.cache.then(|| occupancy(&layout, options.line_size));
+            serde_json::json!({
+                "name": def.name,
+                "packing": def.packing,
+                "layout": layout,
+                "cache": cache,
+            })
+        })
+        .collect();
+    let report = serde_json::json!({ "target": target.name(), "structs": structs });
+    println!("{}", serde_json::to_string_pretty(&report).expect("layouts serialize"));
+}
 
-        StructLayout {
-            member_offsets: offsets,
-            paddings,
-            total_size,
-            alignment: struct_alignment,
-        }
+#[cfg(not(feature = "serde"))]
+fn print_json(_target: Target, _structs: &[StructDef], _options: &Options) {
+    fail("`--format json` needs a build with `--features serde`")
+}
+
+/// Prints the structs as Rust definitions.
+fn print_rust(target: Target, structs: &[StructDef]) {
+    println!("// Layouts for {target}.");
+    for def in structs {
+        println!();
+        print!("{}", rust_struct(def));
     }
 }
 
-fn main() {
-    let members = vec![
-        TypeInfo { size: 4, alignment: 4 },  // T1
-        TypeInfo { size: 2, alignment: 2 },  // T2
-        TypeInfo { size: 8, alignment: 8 },  // T3
-    ];
-
-    let layout = StructLayout::compute(&members);
-    
-    println!("Struct Layout:");
+/// Writes the SVG image of `layout` if asked to.
+fn write_svg(def: &StructDef, layout: &StructLayout, options: &Options) {
+    if let Some(dir) = &options.svg {
+        let name = if def.name.is_empty() { "struct" } else { &def.name };
+        let path = dir.join(format!("{name}.svg"));
+        std::fs::write(&path, svg(layout, options.line_size))
+            .unwrap_or_else(|e| fail(&format!("cannot write {}: {e}", path.display())));
+        eprintln!("Wrote {}", path.display());
+    }
+}
+
+fn print_layout(def: &StructDef, options: &Options) {
+    let layout = def.layout();
+
+    println!("Struct Layout of {}:", def.name);
     println!("Alignment: {}", layout.alignment);
     println!("Total size: {}", layout.total_size);
-    
-    for (i, offset) in layout.member_offsets.iter().enumerate() {
-        println!("Member {}: offset={}, size={}", i + 1, offset, members[i].size);
+    if let Some(n) = def.packing {
+        println!("Packing: {n}");
+    }
+
+    for (i, member) in layout.members.iter().enumerate() {
+        let name = if member.name.is_empty() { "unnamed" } else { &member.name };
+        println!("Member {} ({}): offset={}, size={}", i + 1, name, member.offset, member.size);
+        if let Some(bits) = member.bitfield {
+            println!("  Bits: {}..{}", bits.bit_offset, bits.bit_offset + bits.width);
+        }
+        if let Some(array) = &member.array {
+            let dims: String = array.dims.iter().map(|dim| format!("[{dim}]")).collect();
+            if array.is_flexible() {
+                let dims = dims.replacen("[0]", "[]", 1);
+                println!("  Flexible array: {dims} of {}-byte elements", array.element.size);
+            } else {
+                println!("  Array: {dims} of {}-byte elements", array.element.size);
+            }
+        }
         if i < layout.paddings.len() {
             println!("  Padding after: {}", layout.paddings[i]);
         }
     }
-}
\ No newline at end of file
+
+    let (optimized, saved) = optimize(def);
+    if saved > 0 {
+        let order: Vec<&str> = optimized.members.iter().map(|member| member.name.as_str()).collect();
+        println!("Reordering as {} saves {saved} bytes", order.join(", "));
+    }
+    if let Some(width) = options.map {
+        println!();
+        print!("{}", byte_map(&layout, width));
+    }
+    if options.cache {
+        let report = occupancy(&layout, options.line_size);
+        println!();
+        println!(
+            "Cache lines ({} bytes): {} from a line boundary, up to {} otherwise",
+            report.line_size, report.lines_touched, report.worst_lines_touched
+        );
+        for line in &report.lines {
+            println!("  Line at {}: {}", line.offset, line.members.join(", "));
+        }
+        if !report.straddling.is_empty() {
+            println!("  Straddling: {}", report.straddling.join(", "));
+        }
+    }
+    write_svg(def, &layout, options);
+}
+
+fn example(target: Target, options: &Options) {
+    let def = StructDef::new("Example")
+        .member("t1", TypeInfo { size: 4, alignment: 4 })
+        .member("t2", TypeInfo { size: 2, alignment: 2 })
+        .member("t3", TypeInfo { size: 8, alignment: 8 });
+    match options.format {
+        Format::Text => {}
+        Format::Json => return print_json(target, &[def], options),
+        Format::Rust => return print_rust(target, &[def]),
+    }
+    print_layout(&def, options);
+
+    // struct { char c; long l; long double d; } on each target
+    println!();
+    println!("struct {{ char c; long l; long double d; }} per target:");
+    for target in Target::ALL {
+        let of = |primitive| target.type_info(primitive).expect("every target has these");
+        let layout = StructDef::new("Mixed")
+            .member("c", of(Primitive::Char))
+            .member("l", of(Primitive::Long))
+            .member("d", of(Primitive::LongDouble))
+            .layout();
+        println!("  {:<14} size={}, alignment={}", target, layout.total_size, layout.alignment);
+    }
+}
@@ -0,0 +1,240 @@
+//! Drawings of a layout: a byte map for the terminal, and an SVG for
+//! documents.
+//!
+//! ```
+//! use struct_alignment_and_padding::render::byte_map;
+//! use struct_alignment_and_padding::{StructDef, TypeInfo};
+//!
+//! let layout = StructDef::new("S")
+//!     .member("tag", TypeInfo { size: 1, alignment: 1 })
+//!     .member("len", TypeInfo { size: 4, alignment: 4 })
+//!     .layout();
+//! assert_eq!(
+//!     byte_map(&layout, 8),
+//!     "     0 1 2 3 4 5 6 7
+//!  0   a . . . b b b b
+//!
+//! a  tag  1 byte at 0
+//! b  len  4 bytes at 4
+//! .  padding
+//! "
+//! );
+//! ```
+
+use std::fmt::Write;
+use std::ops::Range;
+
+use crate::{MemberLayout, StructLayout};
+
+/// What marks the bytes of the `i`th member.
+fn symbol(i: usize) -> char {
+    const SYMBOLS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+    SYMBOLS[i % SYMBOLS.len()] as char
+}
+
+/// The struct as a grid of `width` bytes a row, each byte marked with the
+/// member it belongs to, `.` if it is padding, or `*` if bitfields share
+/// it, followed by a legend. The bytes of a bitfield are those its bits
+/// are in; the rest of its storage unit is padding.
+pub fn byte_map(layout: &StructLayout, width: usize) -> String {
+    assert!(width > 0, "a row needs a byte");
+    let mut bytes = vec!['.'; layout.total_size];
+    for (i, member) in layout.members.iter().enumerate() {
+        for byte in &mut bytes[member.byte_range()] {
+            *byte = if *byte == '.' { symbol(i) } else { '*' };
+        }
+    }
+
+    let digits = layout.total_size.saturating_sub(1).to_string().len().max(2);
+    let column = (width - 1).to_string().len();
+    let mut out = " ".repeat(digits + 2);
+    for i in 0..width {
+        write!(out, " {i:>column$}").unwrap();
+    }
+    out.push('\n');
+    for (row, chunk) in bytes.chunks(width).enumerate() {
+        write!(out, "{:>digits$}  ", row * width).unwrap();
+        for byte in chunk {
+            write!(out, " {byte:>column$}").unwrap();
+        }
+        out.push('\n');
+    }
+
+    out.push('\n');
+    let name_width = layout.members.iter().map(|member| member.name.len()).max().unwrap_or(0);
+    for (i, member) in layout.members.iter().enumerate() {
+        write!(out, "{}  {:<name_width$}  ", symbol(i), member.name).unwrap();
+        match member.bitfield {
+            Some(bits) => writeln!(out, "{} bits at {}, bit {}", bits.width, member.offset, bits.bit_offset),
+            None if member.size == 1 => writeln!(out, "1 byte at {}", member.offset),
+            None => writeln!(out, "{} bytes at {}", member.size, member.offset),
+        }
+        .unwrap();
+    }
+    if bytes.contains(&'*') {
+        out.push_str("*  shared by bitfields\n");
+    }
+    if bytes.contains(&'.') {
+        out.push_str(".  padding\n");
+    }
+    out
+}
+
+/// Bytes in a row of [`svg`].
+const SVG_ROW: usize = 16;
+/// Pixels per byte, so 4 per bit.
+const SVG_CELL: usize = 32;
+const SVG_ROW_HEIGHT: usize = 40;
+/// Room for the offsets left of the rows.
+const SVG_LEFT: usize = 48;
+const SVG_MARGIN: usize = 8;
+/// Fills of the members, in turn.
+const SVG_COLORS: [&str; 8] = ["#8dd3c7", "#ffffb3", "#bebada", "#fb8072", "#80b1d3", "#fdb462", "#b3de69", "#fccde5"];
+
+/// The struct as an SVG image, 16 bytes a row, to scale down to the bit:
+/// members are colored blocks labelled with their names where those fit,
+/// padding is hatched, and the boundaries of `line_size`-byte cache lines
+/// are dashed red lines.
+pub fn svg(layout: &StructLayout, line_size: usize) -> String {
+    assert!(line_size > 0, "a cache line needs a byte");
+    let rows = layout.total_size.div_ceil(SVG_ROW);
+    let width = SVG_LEFT + SVG_ROW * SVG_CELL + SVG_MARGIN;
+    let height = 2 * SVG_MARGIN + rows * SVG_ROW_HEIGHT;
+    let mut out = String::new();
+    write!(out, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}""#).unwrap();
+    out.push_str(concat!(
+        r#" font-family="monospace" font-size="12">"#,
+        "\n",
+        r#"<defs><pattern id="padding" width="6" height="6" patternUnits="userSpaceOnUse""#,
+        r#" patternTransform="rotate(45)">"#,
+        r##"<line x1="0" y1="0" x2="0" y2="6" stroke="#999" stroke-width="2"/></pattern></defs>"##,
+        "\n",
+    ));
+    for row in 0..rows {
+        let y = SVG_MARGIN + row * SVG_ROW_HEIGHT + SVG_ROW_HEIGHT / 2 + 4;
+        writeln!(out, r#"<text x="{}" y="{y}" text-anchor="end">{}</text>"#, SVG_LEFT - 6, row * SVG_ROW).unwrap();
+    }
+
+    // Whatever no member's bits cover is padding.
+    let mut spans: Vec<Range<usize>> =
+        layout.members.iter().map(MemberLayout::bit_range).filter(|bits| !bits.is_empty()).collect();
+    spans.sort_by_key(|bits| bits.start);
+    let mut end = 0;
+    let mut padding = Vec::new();
+    for bits in spans.iter().chain([&(layout.total_size * 8..layout.total_size * 8)]) {
+        if bits.start > end {
+            padding.push(end..bits.start);
+        }
+        end = end.max(bits.end);
+    }
+    for bits in padding {
+        block(&mut out, bits, r#"fill="url(#padding)""#, "padding", None);
+    }
+    for (i, member) in layout.members.iter().enumerate() {
+        let fill = format!(r#"fill="{}""#, SVG_COLORS[i % SVG_COLORS.len()]);
+        let title = match member.bitfield {
+            Some(bits) => format!("{}: {} bits at {}, bit {}", member.name, bits.width, member.offset, bits.bit_offset),
+            None => format!("{}: {} bytes at {}", member.name, member.size, member.offset),
+        };
+        block(&mut out, member.bit_range(), &fill, &title, Some(&member.name));
+    }
+
+    for boundary in (line_size..layout.total_size).step_by(line_size) {
+        let (row, column) = (boundary / SVG_ROW, boundary % SVG_ROW);
+        let (x, y) = (SVG_LEFT + column * SVG_CELL, SVG_MARGIN + row * SVG_ROW_HEIGHT);
+        let (x2, y2) = if column == 0 { (x + SVG_ROW * SVG_CELL, y) } else { (x, y + SVG_ROW_HEIGHT) };
+        write!(out, r#"<line x1="{x}" y1="{y}" x2="{x2}" y2="{y2}""#).unwrap();
+        writeln!(
+            out,
+            r#" stroke="red" stroke-width="2" stroke-dasharray="6 3"><title>cache line {}</title></line>"#,
+            boundary / line_size
+        )
+        .unwrap();
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+/// Draws `bits` as rectangles, one per row they are on, with a tooltip
+/// and the label in the first rectangle if it fits.
+fn block(out: &mut String, bits: Range<usize>, fill: &str, title: &str, label: Option<&str>) {
+    let row_bits = SVG_ROW * 8;
+    let mut start = bits.start;
+    let mut label = label;
+    while start < bits.end {
+        let row = start / row_bits;
+        let end = bits.end.min((row + 1) * row_bits);
+        let x = SVG_LEFT + (start - row * row_bits) * SVG_CELL / 8;
+        let y = SVG_MARGIN + row * SVG_ROW_HEIGHT;
+        let width = (end - start) * SVG_CELL / 8;
+        write!(out, r#"<rect x="{x}" y="{y}" width="{width}" height="{SVG_ROW_HEIGHT}""#).unwrap();
+        writeln!(out, r#" {fill} stroke="black"><title>{}</title></rect>"#, escape(title)).unwrap();
+        // About 7 pixels a character.
+        if let Some(text) = label.take().filter(|text| text.len() * 7 + 4 <= width) {
+            writeln!(
+                out,
+                r#"<text x="{}" y="{}" text-anchor="middle">{}</text>"#,
+                x + width / 2,
+                y + SVG_ROW_HEIGHT / 2 + 4,
+                escape(text)
+            )
+            .unwrap();
+        }
+        start = end;
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{StructDef, TypeInfo};
+
+    fn ty(size: usize, alignment: usize) -> TypeInfo {
+        TypeInfo { size, alignment }
+    }
+
+    #[test]
+    fn test_byte_map() {
+        let layout = StructDef::new("S")
+            .member("id", ty(2, 2))
+            .bitfield("x", ty(4, 4), 3)
+            .bitfield("y", ty(4, 4), 7)
+            .member("value", ty(8, 8))
+            .layout();
+        let expected = "
+      0  1  2  3  4  5  6  7  8  9 10 11 12 13 14 15
+ 0    a  a  *  c  .  .  .  .  d  d  d  d  d  d  d  d
+
+a  id     2 bytes at 0
+b  x      3 bits at 0, bit 16
+c  y      7 bits at 0, bit 19
+d  value  8 bytes at 8
+*  shared by bitfields
+.  padding
+";
+        assert_eq!(byte_map(&layout, 16), expected[1..]);
+    }
+
+    #[test]
+    fn test_svg() {
+        // 2 bytes, 6 of padding, a 64-byte array across the first cache
+        // line boundary, 1 byte and 7 of trailing padding.
+        let layout = StructDef::new("S")
+            .member("id", ty(2, 2))
+            .array_member("data", ty(8, 8), [8])
+            .member("flag", ty(1, 1))
+            .layout();
+        let svg = svg(&layout, 64);
+        assert!(svg.starts_with("<svg ") && svg.ends_with("</svg>\n"));
+        assert_eq!(svg.matches("<rect").count(), 1 + 5 + 1 + 2);
+        assert_eq!(svg.matches(r#"fill="url(#padding)""#).count(), 2);
+        assert!(svg.contains(r#"<rect x="112" y="8" width="192" height="40" fill="url(#padding)""#));
+        assert!(svg.contains(r#"<line x1="48" y1="168" x2="560" y2="168""#));
+        assert!(svg.contains("<title>data: 64 bytes at 8</title>"));
+        assert!(svg.contains(">flag</text>"));
+    }
+}
@@ -0,0 +1,135 @@
+//! Reordering members to save the padding between them.
+//!
+//! ```
+//! use struct_alignment_and_padding::reorder::optimize;
+//! use struct_alignment_and_padding::{StructDef, TypeInfo};
+//!
+//! let def = StructDef::new("S")
+//!     .member("a", TypeInfo { size: 1, alignment: 1 })
+//!     .member("b", TypeInfo { size: 8, alignment: 8 })
+//!     .member("c", TypeInfo { size: 1, alignment: 1 });
+//! let (optimized, saved) = optimize(&def);
+//! let order: Vec<&str> = optimized.members.iter().map(|m| m.name.as_str()).collect();
+//! assert_eq!(order, ["a", "c", "b"]);
+//! assert_eq!(saved, 8);
+//! ```
+
+use crate::{Member, StructDef};
+
+/// Structs with at most this many movable members are searched for the
+/// best order; bigger ones are sorted.
+const SEARCH_LIMIT: usize = 8;
+
+/// `def` with its members in the order that makes it smallest, and the
+/// bytes that saves.
+///
+/// Small structs are searched exhaustively, and of the smallest orders the
+/// one closest to the original is kept; bigger ones have their members
+/// sorted by alignment, largest first. A run of bitfields moves as one,
+/// since reordering within it would change which bits they get, and a
+/// flexible array member stays last. The result is never bigger than
+/// `def`.
+pub fn optimize(def: &StructDef) -> (StructDef, usize) {
+    let mut blocks: Vec<&[Member]> = def.members.chunk_by(|a, b| a.bits.is_some() && b.bits.is_some()).collect();
+    let flexible = blocks.pop_if(|block| block[0].array.as_ref().is_some_and(|array| array.is_flexible()));
+
+    let rebuild = |order: &[usize]| {
+        let mut members: Vec<Member> = order.iter().flat_map(|&i| blocks[i]).cloned().collect();
+        members.extend(flexible.into_iter().flatten().cloned());
+        StructDef { members, ..def.clone() }
+    };
+    let size = |def: &StructDef| def.layout().total_size;
+
+    let mut order: Vec<usize> = (0..blocks.len()).collect();
+    let mut best = (size(def), order.clone());
+    if blocks.len() <= SEARCH_LIMIT {
+        // In lexicographic order from the original, so the first of the
+        // smallest is the least disturbed.
+        while next_permutation(&mut order) {
+            let size = size(&rebuild(&order));
+            if size < best.0 {
+                best = (size, order.clone());
+            }
+        }
+    } else {
+        let alignment = |block: &[Member]| {
+            block
+                .iter()
+                .map(|member| {
+                    let natural = def.packing.map_or(member.ty.alignment, |n| member.ty.alignment.min(n));
+                    natural.max(member.align.unwrap_or(1))
+                })
+                .max()
+                .unwrap_or(1)
+        };
+        order.sort_by_key(|&i| std::cmp::Reverse(alignment(blocks[i])));
+        let size = size(&rebuild(&order));
+        if size < best.0 {
+            best = (size, order);
+        }
+    }
+    let optimized = rebuild(&best.1);
+    (optimized, size(def) - best.0)
+}
+
+/// Steps `order` to the next permutation in lexicographic order; `false`
+/// once it was the last.
+fn next_permutation(order: &mut [usize]) -> bool {
+    let Some(i) = order.windows(2).rposition(|pair| pair[0] < pair[1]) else {
+        return false;
+    };
+    let j = order.iter().rposition(|&x| x > order[i]).expect("order[i + 1] is greater");
+    order.swap(i, j);
+    order[i + 1..].reverse();
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TypeInfo;
+
+    fn ty(size: usize, alignment: usize) -> TypeInfo {
+        TypeInfo { size, alignment }
+    }
+
+    fn names(def: &StructDef) -> Vec<&str> {
+        def.members.iter().map(|member| member.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_search() {
+        // struct { char a; int b; char c; short d; } is 12 bytes.
+        let def = StructDef::new("S")
+            .member("a", ty(1, 1))
+            .member("b", ty(4, 4))
+            .member("c", ty(1, 1))
+            .member("d", ty(2, 2));
+        let (optimized, saved) = optimize(&def);
+        assert_eq!(names(&optimized), ["a", "c", "d", "b"]);
+        assert_eq!((optimized.layout().total_size, saved), (8, 4));
+
+        // Already as small as it gets: left alone.
+        let (optimized, saved) = optimize(&optimized);
+        assert_eq!(names(&optimized), ["a", "c", "d", "b"]);
+        assert_eq!(saved, 0);
+    }
+
+    #[test]
+    fn test_sort_bitfields_and_flexible_array() {
+        let mut def = StructDef::new("S");
+        for i in 0..SEARCH_LIMIT {
+            def = def.member(format!("c{i}"), ty(1, 1)).member(format!("l{i}"), ty(8, 8));
+        }
+        let def = def.bitfield("x", ty(4, 4), 3).bitfield("y", ty(4, 4), 3).flexible_member("tail", ty(2, 2));
+        // The bitfields sort as one 4-aligned block, and the chars then
+        // use the rest of its storage unit.
+        let (optimized, saved) = optimize(&def);
+        assert_eq!((def.layout().total_size, optimized.layout().total_size, saved), (136, 80, 56));
+        assert_eq!(optimized.layout().member("c0").map(|c0| c0.offset), Some(65));
+        let names = names(&optimized);
+        assert_eq!(names[..9], ["l0", "l1", "l2", "l3", "l4", "l5", "l6", "l7", "x"]);
+        assert_eq!(names[9..11], ["y", "c0"]);
+        assert_eq!(names.last(), Some(&"tail"));
+    }
+}
@@ -0,0 +1,678 @@
+//! Layouts of `#[repr(C)]` Rust structs (the `rust` feature), parsed from
+//! source with syn.
+//!
+//! ```
+//! use struct_alignment_and_padding::rust;
+//! use struct_alignment_and_padding::target::Target;
+//!
+//! let source = "
+//!     #[repr(C)]
+//!     struct Packet { kind: u8, header: Header }
+//!     #[repr(C)]
+//!     struct Header { id: u32, next: *const Packet }
+//! ";
+//! let structs = rust::parse(source, Target::I686).unwrap();
+//! assert_eq!(structs[0].name, "Packet");
+//! assert_eq!(structs[0].layout().total_size, 12);
+//! ```
+//!
+//! Every `#[repr(C)]` or `#[repr(transparent)]` struct without generic
+//! parameters is laid out, those in inline modules included, packed if it
+//! is also `packed` or `packed(N)` and over-aligned if `align(N)`. Fields
+//! may be of:
+//!
+//! - primitive types and the `core::ffi` (or `libc`) C types, with sizes
+//!   from the [`Target`];
+//! - raw pointers, references, `Box`, `NonNull`, function pointers, and
+//!   `Option`s of them; pointers to slices, `str` and trait objects are two
+//!   words;
+//! - arrays, whose length may use arithmetic, `size_of`/`align_of` and the
+//!   `const`s of the file; a trailing `[T; 0]` is a flexible array member;
+//! - other structs and type aliases of the file, declared in any order;
+//! - `PhantomData`, atomics, `NonZero` integers, and `MaybeUninit`,
+//!   `ManuallyDrop`, `Cell`, `UnsafeCell` or `Wrapping` of any of these.
+//!
+//! - fieldless enums: of their `#[repr(u8)]` (or other integer) type, as
+//!   big as a C enum of their discriminants if `#[repr(C)]`, and otherwise
+//!   of the smallest integer that holds them, as rustc does.
+//!
+//! A struct without a repr has no defined layout and is skipped; so are
+//! unions and enums with fields. A `#[repr(C)]` struct using one is an
+//! error.
+
+use std::collections::{HashMap, HashSet};
+
+use proc_macro2::Span;
+use syn::spanned::Spanned;
+use syn::{
+    BinOp, Expr, Fields, GenericArgument, Item, ItemEnum, ItemStruct, Lit, Path, PathArguments, Type, TypeArray, UnOp,
+};
+
+use crate::target::{Primitive, Target};
+use crate::{ArrayType, Member, ParseError, StructDef, TypeInfo};
+
+type Result<T> = std::result::Result<T, ParseError>;
+
+fn error(span: Span, message: impl Into<String>) -> ParseError {
+    ParseError {
+        line: span.start().line,
+        message: message.into(),
+    }
+}
+
+const ZERO_SIZED: TypeInfo = TypeInfo { size: 0, alignment: 1 };
+
+/// The integer types an enum can have as its repr.
+const INTEGERS: [&str; 12] = ["u8", "i8", "u16", "i16", "u32", "i32", "u64", "i64", "u128", "i128", "usize", "isize"];
+
+/// Every `#[repr(C)]` struct of `source`, in order, with its fields laid
+/// out for `target`.
+pub fn parse(source: &str, target: Target) -> Result<Vec<StructDef>> {
+    let file = syn::parse_file(source).map_err(|e| error(e.span(), e.to_string()))?;
+    let mut items = Items::default();
+    items.collect(&file.items);
+    let mut resolver = Resolver {
+        target,
+        items: &items,
+        layouts: HashMap::new(),
+        resolving: HashSet::new(),
+    };
+    let mut structs = Vec::new();
+    for item in &items.structs {
+        if let Some(repr) = c_repr(item)? {
+            structs.push(resolver.struct_def(item, &repr)?);
+        }
+    }
+    Ok(structs)
+}
+
+/// The named items of a file, inline modules flattened into it.
+#[derive(Default)]
+struct Items<'a> {
+    /// In source order.
+    structs: Vec<&'a ItemStruct>,
+    aliases: HashMap<String, &'a Type>,
+    consts: HashMap<String, &'a Expr>,
+    enums: HashMap<String, &'a ItemEnum>,
+    /// Unions, by name, to what they are.
+    others: HashMap<String, &'static str>,
+}
+
+impl<'a> Items<'a> {
+    fn collect(&mut self, items: &'a [Item]) {
+        for item in items {
+            match item {
+                Item::Struct(item) => self.structs.push(item),
+                Item::Type(item) if item.generics.params.is_empty() => {
+                    self.aliases.insert(item.ident.to_string(), &item.ty);
+                }
+                Item::Const(item) => {
+                    self.consts.insert(item.ident.to_string(), &item.expr);
+                }
+                Item::Enum(item) => {
+                    self.enums.insert(item.ident.to_string(), item);
+                }
+                Item::Union(item) => {
+                    self.others.insert(item.ident.to_string(), "a union");
+                }
+                Item::Mod(item) => {
+                    if let Some((_, items)) = &item.content {
+                        self.collect(items);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn find_struct(&self, name: &str) -> Option<&'a ItemStruct> {
+        self.structs.iter().copied().find(|item| item.ident == name)
+    }
+}
+
+/// What the repr of a struct with a defined layout says.
+#[derive(Debug, Default)]
+struct Repr {
+    /// `packed(N)`; `packed` alone is `packed(1)`.
+    packing: Option<usize>,
+    /// `align(N)`.
+    alignment: Option<usize>,
+}
+
+/// The repr of `item` if it is non-generic and `#[repr(C)]` or
+/// `#[repr(transparent)]`, the reprs whose layout is defined.
+fn c_repr(item: &ItemStruct) -> Result<Option<Repr>> {
+    let mut c = false;
+    let mut repr = Repr::default();
+    for attr in item.attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("C") || meta.path.is_ident("transparent") {
+                c = true;
+            } else if meta.path.is_ident("packed") {
+                let mut n = 1;
+                if meta.input.peek(syn::token::Paren) {
+                    n = power_of_two(&meta)?;
+                }
+                repr.packing = Some(n);
+            } else if meta.path.is_ident("align") {
+                repr.alignment = Some(power_of_two(&meta)?);
+            }
+            Ok(())
+        })
+        .map_err(|e| error(e.span(), e.to_string()))?;
+    }
+    if repr.packing.is_some() && repr.alignment.is_some() {
+        return Err(error(item.ident.span(), "a struct cannot be both `packed` and `align`"));
+    }
+    Ok((c && item.generics.params.is_empty()).then_some(repr))
+}
+
+/// The `(N)` of `packed(N)` or `align(N)`.
+fn power_of_two(meta: &syn::meta::ParseNestedMeta) -> syn::Result<usize> {
+    let content;
+    syn::parenthesized!(content in meta.input);
+    let n: usize = content.parse::<syn::LitInt>()?.base10_parse()?;
+    if !n.is_power_of_two() {
+        return Err(meta.error(format!("{n} is not a power of two")));
+    }
+    Ok(n)
+}
+
+struct Resolver<'a> {
+    target: Target,
+    items: &'a Items<'a>,
+    /// Size and alignment of every struct laid out so far.
+    layouts: HashMap<String, TypeInfo>,
+    /// The structs, aliases and consts being resolved, to catch cycles.
+    resolving: HashSet<String>,
+}
+
+impl Resolver<'_> {
+    fn struct_def(&mut self, item: &ItemStruct, repr: &Repr) -> Result<StructDef> {
+        let fields = match &item.fields {
+            Fields::Named(fields) => fields.named.iter().collect(),
+            Fields::Unnamed(fields) => fields.unnamed.iter().collect(),
+            Fields::Unit => Vec::new(),
+        };
+        let mut def = StructDef::new(item.ident.to_string());
+        def.packing = repr.packing;
+        def.alignment = repr.alignment;
+        for (i, field) in fields.into_iter().enumerate() {
+            let name = field.ident.as_ref().map_or_else(|| i.to_string(), ToString::to_string);
+            let member = match &field.ty {
+                Type::Array(array) => Member::array(name, self.array(array)?),
+                ty => Member::new(name, self.type_info(ty)?),
+            };
+            def.members.push(member);
+        }
+        if def.try_layout().is_none() {
+            return Err(error(item.ident.span(), format!("`{}` is too large", item.ident)));
+        }
+        Ok(def)
+    }
+
+    /// `[[T; 3]; 2]` is a `[2][3]` array of `T`.
+    fn array(&mut self, array: &TypeArray) -> Result<ArrayType> {
+        let span = array.len.span();
+        let count = self.eval(&array.len)?;
+        let mut array = match &*array.elem {
+            Type::Array(inner) => self.array(inner)?,
+            elem => ArrayType::new(self.type_info(elem)?, []),
+        };
+        array.dims.insert(0, count);
+        if array.type_info().is_none() {
+            return Err(error(span, "the array is too large"));
+        }
+        Ok(array)
+    }
+
+    fn primitive(&self, primitive: Primitive, span: Span) -> Result<TypeInfo> {
+        self.target
+            .type_info(primitive)
+            .ok_or_else(|| error(span, format!("{primitive:?} is not available on {}", self.target)))
+    }
+
+    fn word(&self) -> TypeInfo {
+        let width = self.target.pointer_width();
+        TypeInfo {
+            size: width,
+            alignment: width,
+        }
+    }
+
+    /// Marks `key` as being resolved for the duration of `resolve`.
+    fn guard<T>(&mut self, key: String, span: Span, resolve: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if !self.resolving.insert(key.clone()) {
+            return Err(error(span, format!("`{key}` is defined in terms of itself")));
+        }
+        let result = resolve(self);
+        self.resolving.remove(&key);
+        result
+    }
+
+    fn type_info(&mut self, ty: &Type) -> Result<TypeInfo> {
+        match ty {
+            Type::Array(array) => Ok(self.array(array)?.type_info().expect("array() checks the size")),
+            Type::Ptr(pointer) => Ok(self.pointer(&pointer.elem)),
+            Type::Reference(reference) => Ok(self.pointer(&reference.elem)),
+            Type::FnPtr(_) => Ok(self.word()),
+            Type::Paren(paren) => self.type_info(&paren.elem),
+            Type::Group(group) => self.type_info(&group.elem),
+            Type::Tuple(tuple) if tuple.elems.is_empty() => Ok(ZERO_SIZED),
+            Type::Tuple(tuple) => Err(error(tuple.span(), "tuples have no defined layout")),
+            Type::Path(path) if path.qself.is_none() => self.path(&path.path),
+            _ => Err(error(ty.span(), "this type has no defined layout")),
+        }
+    }
+
+    /// A pointer to `pointee`: two words if that is unsized.
+    fn pointer(&self, pointee: &Type) -> TypeInfo {
+        let unsized_ = match pointee {
+            Type::Slice(_) | Type::TraitObject(_) => true,
+            Type::Path(path) => path.path.is_ident("str"),
+            _ => false,
+        };
+        let word = self.word();
+        TypeInfo {
+            size: if unsized_ { 2 * word.size } else { word.size },
+            alignment: word.alignment,
+        }
+    }
+
+    fn path(&mut self, path: &Path) -> Result<TypeInfo> {
+        let span = path.span();
+        let segment = path.segments.last().expect("paths have a segment");
+        let name = segment.ident.to_string();
+        let argument = match &segment.arguments {
+            PathArguments::AngleBracketed(arguments) => arguments.args.iter().find_map(|argument| match argument {
+                GenericArgument::Type(ty) => Some(ty),
+                _ => None,
+            }),
+            _ => None,
+        };
+        let argument = || argument.ok_or_else(|| error(span, format!("`{name}` needs a type argument")));
+
+        let primitive = match name.as_str() {
+            "u8" | "i8" | "bool" | "c_char" | "c_schar" | "c_uchar" => Some(Primitive::Char),
+            "u16" | "i16" | "c_short" | "c_ushort" => Some(Primitive::Short),
+            "u32" | "i32" | "char" | "c_int" | "c_uint" => Some(Primitive::Int),
+            "c_long" | "c_ulong" => Some(Primitive::Long),
+            "u64" | "i64" | "c_longlong" | "c_ulonglong" => Some(Primitive::LongLong),
+            "f32" | "c_float" => Some(Primitive::Float),
+            "f64" | "c_double" => Some(Primitive::Double),
+            "usize" | "isize" | "size_t" | "ssize_t" | "ptrdiff_t" | "intptr_t" | "uintptr_t" | "c_size_t" => {
+                Some(Primitive::Pointer)
+            }
+            _ => None,
+        };
+        if let Some(primitive) = primitive {
+            return self.primitive(primitive, span);
+        }
+        match name.as_str() {
+            // Rust has 128-bit integers where C does not, 16-aligned.
+            "u128" | "i128" => Ok(self
+                .target
+                .type_info(Primitive::Int128)
+                .unwrap_or(TypeInfo { size: 16, alignment: 16 })),
+            "Box" | "NonNull" => Ok(self.pointer(argument()?)),
+            "Option" => {
+                let inner = argument()?;
+                if nullable(inner) {
+                    self.type_info(inner)
+                } else {
+                    Err(error(span, "only an `Option` of a pointer or `NonZero` has a defined layout"))
+                }
+            }
+            "NonZero" | "MaybeUninit" | "ManuallyDrop" | "Cell" | "UnsafeCell" | "Wrapping" | "Saturating" => {
+                self.type_info(argument()?)
+            }
+            "PhantomData" | "PhantomPinned" => Ok(ZERO_SIZED),
+            "AtomicPtr" | "AtomicUsize" | "AtomicIsize" => Ok(self.word()),
+            // Atomics are aligned to their size, even where the plain
+            // integer is not (u64 on i686).
+            atomic if atomic.starts_with("Atomic") => {
+                let size = match &atomic["Atomic".len()..] {
+                    "Bool" | "U8" | "I8" => 1,
+                    "U16" | "I16" => 2,
+                    "U32" | "I32" => 4,
+                    "U64" | "I64" => 8,
+                    _ => return Err(error(span, format!("unknown atomic `{atomic}`"))),
+                };
+                Ok(TypeInfo { size, alignment: size })
+            }
+            non_zero if non_zero.starts_with("NonZero") => {
+                let integer = non_zero["NonZero".len()..].to_lowercase();
+                self.path(&syn::Ident::new(&integer, span).into())
+            }
+            _ => self.user_type(&name, span),
+        }
+    }
+
+    /// The integer type behind a fieldless enum.
+    fn enum_type(&mut self, item: &ItemEnum) -> Result<TypeInfo> {
+        let span = item.ident.span();
+        if item.variants.iter().any(|variant| !matches!(variant.fields, Fields::Unit)) {
+            return Err(error(span, format!("`{}` is an enum with fields, which is not supported", item.ident)));
+        }
+        let (mut c, mut integer) = (false, None);
+        for attr in item.attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("C") {
+                    c = true;
+                } else if let Some(ident) = meta.path.get_ident()
+                    && INTEGERS.iter().any(|int| ident == int)
+                {
+                    integer = Some(ident.clone());
+                }
+                Ok(())
+            })
+            .map_err(|e| error(e.span(), e.to_string()))?;
+        }
+        if let Some(integer) = integer {
+            return self.path(&integer.into());
+        }
+        if item.variants.is_empty() && !c {
+            return Ok(ZERO_SIZED);
+        }
+        // `next` is `None` after the largest value there is.
+        let (mut min, mut max, mut next) = (0, 0, Some(0));
+        for variant in &item.variants {
+            let value = match (&variant.discriminant, next) {
+                (Some((_, expr)), _) => self.discriminant(expr)?,
+                (None, Some(next)) => next,
+                (None, None) => {
+                    let message = format!("the discriminant of `{}` overflows", variant.ident);
+                    return Err(error(variant.ident.span(), message));
+                }
+            };
+            (min, max, next) = (min.min(value), max.max(value), value.checked_add(1));
+        }
+        self.target.enum_type(min, max, !c).ok_or_else(|| {
+            error(span, format!("the discriminants of `{}` do not fit in a C enum on {}", item.ident, self.target))
+        })
+    }
+
+    /// An explicit discriminant, which unlike an array length may be
+    /// negative.
+    fn discriminant(&mut self, expr: &Expr) -> Result<i128> {
+        match expr {
+            Expr::Unary(unary) if matches!(unary.op, UnOp::Neg(_)) => Ok(-self.discriminant(&unary.expr)?),
+            Expr::Lit(literal) => match &literal.lit {
+                Lit::Int(int) => int.base10_parse().map_err(|e| error(expr.span(), e.to_string())),
+                _ => Err(error(expr.span(), "a discriminant must be an integer")),
+            },
+            expr => Ok(self.eval(expr)? as i128),
+        }
+    }
+
+    /// A struct, enum or type alias of the file.
+    fn user_type(&mut self, name: &str, span: Span) -> Result<TypeInfo> {
+        if let Some(&info) = self.layouts.get(name) {
+            return Ok(info);
+        }
+        if let Some(ty) = self.items.aliases.get(name).copied() {
+            return self.guard(name.to_string(), span, |resolver| resolver.type_info(ty));
+        }
+        if let Some(item) = self.items.find_struct(name) {
+            let Some(repr) = c_repr(item)? else {
+                return Err(error(span, format!("`{name}` has no defined layout: it is generic or not #[repr(C)]")));
+            };
+            let def = self.guard(name.to_string(), span, |resolver| resolver.struct_def(item, &repr))?;
+            let layout = def.layout();
+            let info = TypeInfo {
+                size: layout.total_size,
+                alignment: layout.alignment,
+            };
+            self.layouts.insert(name.to_string(), info);
+            return Ok(info);
+        }
+        if let Some(item) = self.items.enums.get(name).copied() {
+            let info = self.enum_type(item)?;
+            self.layouts.insert(name.to_string(), info);
+            return Ok(info);
+        }
+        match self.items.others.get(name) {
+            Some(what) => Err(error(span, format!("`{name}` is {what}, which is not supported"))),
+            None => Err(error(span, format!("`{name}` is not defined in this file"))),
+        }
+    }
+
+    /// An array length.
+    fn eval(&mut self, expr: &Expr) -> Result<usize> {
+        let span = expr.span();
+        match expr {
+            Expr::Lit(literal) => match &literal.lit {
+                Lit::Int(int) => int.base10_parse().map_err(|e| error(span, e.to_string())),
+                _ => Err(error(span, "an array length must be an integer")),
+            },
+            Expr::Paren(paren) => self.eval(&paren.expr),
+            Expr::Group(group) => self.eval(&group.expr),
+            Expr::Binary(binary) => {
+                let (left, right) = (self.eval(&binary.left)?, self.eval(&binary.right)?);
+                let value = match binary.op {
+                    BinOp::Add(_) => left.checked_add(right),
+                    BinOp::Sub(_) => left.checked_sub(right),
+                    BinOp::Mul(_) => left.checked_mul(right),
+                    BinOp::Div(_) => left.checked_div(right),
+                    BinOp::Rem(_) => left.checked_rem(right),
+                    // Bits shifted out overflow.
+                    BinOp::Shl(_) => u32::try_from(right)
+                        .ok()
+                        .and_then(|right| left.checked_shl(right).filter(|&shifted| shifted >> right == left)),
+                    BinOp::Shr(_) => u32::try_from(right).ok().and_then(|right| left.checked_shr(right)),
+                    _ => return Err(error(span, "unsupported operator in an array length")),
+                };
+                value.ok_or_else(|| error(span, "array length overflows or divides by zero"))
+            }
+            Expr::Path(path) if path.qself.is_none() => {
+                let name = path.path.segments.last().expect("paths have a segment").ident.to_string();
+                let Some(value) = self.items.consts.get(&name).copied() else {
+                    return Err(error(span, format!("`{name}` is not a const of this file")));
+                };
+                self.guard(format!("const {name}"), span, |resolver| resolver.eval(value))
+            }
+            // size_of::<T>() and align_of::<T>()
+            Expr::Call(call) if call.args.is_empty() => {
+                let Expr::Path(function) = &*call.func else {
+                    return Err(error(span, "an array length must be a constant expression"));
+                };
+                let segment = function.path.segments.last().expect("paths have a segment");
+                let ty = match &segment.arguments {
+                    PathArguments::AngleBracketed(arguments) => match arguments.args.first() {
+                        Some(GenericArgument::Type(ty)) => ty,
+                        _ => return Err(error(span, "expected a type argument")),
+                    },
+                    _ => return Err(error(span, "expected a type argument")),
+                };
+                let info = self.type_info(ty)?;
+                match segment.ident.to_string().as_str() {
+                    "size_of" => Ok(info.size),
+                    "align_of" => Ok(info.alignment),
+                    other => Err(error(span, format!("`{other}` cannot be evaluated"))),
+                }
+            }
+            _ => Err(error(span, "an array length must be a constant expression")),
+        }
+    }
+}
+
+/// Whether `None` of `ty` can be the null value, which is when an
+/// `Option<ty>` is laid out as `ty`.
+fn nullable(ty: &Type) -> bool {
+    match ty {
+        Type::Reference(_) | Type::FnPtr(_) => true,
+        Type::Path(path) => path.path.segments.last().is_some_and(|segment| {
+            let name = segment.ident.to_string();
+            matches!(name.as_str(), "Box" | "NonNull") || name.starts_with("NonZero")
+        }),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn offsets(source: &str, target: Target) -> Vec<(String, Vec<usize>, usize)> {
+        parse(source, target)
+            .unwrap()
+            .iter()
+            .map(|def| {
+                let layout = def.layout();
+                (def.name.clone(), layout.member_offsets, layout.total_size)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_layouts_per_target() {
+        let source = "
+            use std::ffi::{c_char, c_long};
+
+            const NAME: usize = 4 * 2;
+
+            #[derive(Clone, Copy)]
+            #[repr(C)]
+            pub struct Record {
+                kind: u8,
+                size: c_long,
+                name: [c_char; NAME + 1],
+                tag: Option<&'static str>,
+                callback: Option<extern \"C\" fn(*mut Record)>,
+                stamp: u64,
+                counter: std::sync::atomic::AtomicU64,
+                marker: PhantomData<Record>,
+            }
+
+            struct Unlaid { x: u8 }
+        ";
+        assert_eq!(
+            offsets(source, Target::X86_64SysV),
+            [("Record".to_string(), vec![0, 8, 16, 32, 48, 56, 64, 72], 72)]
+        );
+        assert_eq!(
+            offsets(source, Target::I686),
+            [("Record".to_string(), vec![0, 4, 8, 20, 28, 32, 40, 48], 48)]
+        );
+    }
+
+    #[test]
+    fn test_nested_types_in_any_order() {
+        let source = "
+            #[repr(C)]
+            struct Outer(Inner, [Word; size_of::<Inner>() / 4], Option<Box<Outer>>);
+
+            type Word = core::num::NonZeroU32;
+
+            mod inner {
+                #[repr(C)]
+                pub struct Inner { a: u16, b: f64 }
+            }
+        ";
+        assert_eq!(
+            offsets(source, Target::X86_64SysV),
+            [
+                ("Outer".to_string(), vec![0, 16, 32], 40),
+                ("Inner".to_string(), vec![0, 8], 16)
+            ]
+        );
+        assert_eq!(parse(source, Target::X86_64SysV).unwrap()[0].members[1].name, "1");
+    }
+
+    #[test]
+    fn test_arrays() {
+        let source = "
+            const N: usize = 3;
+            #[repr(C)]
+            struct Grid { tag: u8, cells: [[u16; N]; 2], flat: [u64; 2] }
+        ";
+        let def = parse(source, Target::X86_64SysV).unwrap().pop().unwrap();
+        let layout = def.layout();
+        let cells = layout.member("cells").and_then(|m| m.array.as_ref()).unwrap();
+        assert_eq!(cells.dims, [2, 3]);
+        assert_eq!(layout.element_offset("cells", &[1, 1]), Some(2 + 8));
+        assert_eq!(layout.element_offset("flat", &[1]), Some(24));
+        assert_eq!(layout.total_size, 32);
+    }
+
+    #[test]
+    fn test_enums() {
+        let source = "
+            #[repr(u16)]
+            enum Kind { A, B }
+            #[repr(C)]
+            enum Color { Red, Green, Blue }
+            enum Small { A = -1, B = 100 }
+            enum Wide { A = 0, B = 256 }
+            enum Never {}
+            #[repr(C)]
+            struct S { kind: Kind, color: Color, small: Small, wide: Wide, never: Never }
+        ";
+        assert_eq!(offsets(source, Target::X86_64SysV), [("S".to_string(), vec![0, 4, 8, 10, 12], 12)]);
+    }
+
+    #[test]
+    fn test_packed() {
+        let source = "
+            #[repr(C, packed)]
+            struct One { a: u8, b: u32 }
+            #[repr(C, packed(2))]
+            struct Two { a: u8, b: u32, one: One }
+            #[repr(C)]
+            struct Holder { a: u8, two: Two }
+        ";
+        assert_eq!(
+            offsets(source, Target::X86_64SysV),
+            [
+                ("One".to_string(), vec![0, 1], 5),
+                ("Two".to_string(), vec![0, 2, 6], 12),
+                ("Holder".to_string(), vec![0, 2], 14)
+            ]
+        );
+        assert!(parse("#[repr(C, packed(3))] struct A { a: u8 }", Target::X86_64SysV).is_err());
+    }
+
+    #[test]
+    fn test_align() {
+        let source = "
+            #[repr(C, align(16))]
+            struct Aligned { a: u8 }
+            #[repr(C)]
+            struct Holder { x: u8, aligned: Aligned, y: u8 }
+        ";
+        assert_eq!(
+            offsets(source, Target::X86_64SysV),
+            [("Aligned".to_string(), vec![0], 16), ("Holder".to_string(), vec![0, 16, 32], 48)]
+        );
+        let error = parse("#[repr(C, packed, align(8))]\nstruct A { a: u8 }", Target::X86_64SysV).unwrap_err();
+        assert_eq!((error.line, error.message.as_str()), (2, "a struct cannot be both `packed` and `align`"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let message = |source| parse(source, Target::X86_64SysV).unwrap_err().message;
+        assert_eq!(
+            message("#[repr(C)] struct A { b: B }\nstruct B { x: u8 }"),
+            "`B` has no defined layout: it is generic or not #[repr(C)]"
+        );
+        assert_eq!(message("#[repr(C)] struct A { b: A }"), "`A` is defined in terms of itself");
+        assert_eq!(
+            message("#[repr(C)] struct A { e: E }\nenum E { X(u8) }"),
+            "`E` is an enum with fields, which is not supported"
+        );
+        assert_eq!(
+            message("#[repr(C)] struct A { u: U }\nunion U { x: u8 }"),
+            "`U` is a union, which is not supported"
+        );
+        assert_eq!(message("#[repr(C)] struct A { v: Vec<u8> }"), "`Vec` is not defined in this file");
+        assert_eq!(message("#[repr(C)] struct A { c: [u64; 1 << 62] }"), "the array is too large");
+        assert_eq!(message("#[repr(C)] struct A { c: [u8; 1 << 62] }"), "`A` is too large");
+        assert_eq!(message("#[repr(C)] struct A { c: [u8; 3 << 63] }"), "array length overflows or divides by zero");
+        let huge = "#[repr(C)] struct A { e: E }\n#[repr(C)] enum E { X = 170141183460469231731687303715884105727, Y }";
+        assert_eq!(message(huge), "the discriminant of `Y` overflows");
+        let largest = huge.replace(", Y", "");
+        assert_eq!(message(&largest), "the discriminants of `E` do not fit in a C enum on x86_64-sysv");
+        let error = parse("\n#[repr(C)]\nstruct A {\n  t: (u8, u8),\n}", Target::X86_64SysV).unwrap_err();
+        assert_eq!(error.line, 4);
+    }
+}
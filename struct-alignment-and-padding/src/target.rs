@@ -0,0 +1,287 @@
+//! Sizes and alignments of the C primitive types per target ABI, so a
+//! layout can be computed for a platform instead of from hand-entered
+//! numbers.
+//!
+//! The ABIs disagree in a few well-known places: `long` is 4 bytes on
+//! Windows and on 32-bit targets but 8 on 64-bit Unix; `long double` is the
+//! 80-bit x87 format (padded to 12 or 16 bytes) on x86 Unix, a 128-bit
+//! quad on aarch64 Linux and wasm32, and plain `double` on MSVC and Apple
+//! arm64; i686 System V aligns 8-byte `double` and `long long` members to 4
+//! only; and pointers are 4 bytes on i686, 32-bit MSVC and wasm32.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{BitfieldRules, TypeInfo};
+
+/// A C primitive type. Signedness does not change the layout, so `int`
+/// and `unsigned int` are both [`Primitive::Int`]; the fixed-width types
+/// are the ones of the same size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Primitive {
+    Bool,
+    Char,
+    Short,
+    Int,
+    Long,
+    LongLong,
+    /// `__int128`, where the target has it.
+    Int128,
+    Float,
+    Double,
+    LongDouble,
+    /// Any data or function pointer, and `size_t`, `ptrdiff_t`,
+    /// `intptr_t`.
+    Pointer,
+}
+
+impl Primitive {
+    /// The primitive a C type name stands for (`unsigned long`, `int32_t`,
+    /// `size_t`, ...), or `None` if it is not one.
+    pub fn from_c_name(name: &str) -> Option<Self> {
+        let words: Vec<&str> = name.split_whitespace().collect();
+        // Signedness and `int` after a size keyword do not matter.
+        let core: Vec<&str> = words
+            .iter()
+            .copied()
+            .filter(|word| !matches!(*word, "signed" | "unsigned"))
+            .collect();
+        let core = match core.as_slice() {
+            [size @ .., "int"] if !size.is_empty() => size,
+            core => core,
+        };
+        Some(match core {
+            // Bare `signed` and `unsigned` mean int.
+            [] if !words.is_empty() => Primitive::Int,
+            ["_Bool"] | ["bool"] => Primitive::Bool,
+            ["char"] | ["int8_t"] | ["uint8_t"] => Primitive::Char,
+            ["short"] | ["int16_t"] | ["uint16_t"] => Primitive::Short,
+            ["int"] | ["int32_t"] | ["uint32_t"] => Primitive::Int,
+            ["long"] => Primitive::Long,
+            ["long", "long"] | ["int64_t"] | ["uint64_t"] => Primitive::LongLong,
+            ["__int128"] | ["__int128_t"] | ["__uint128_t"] => Primitive::Int128,
+            ["float"] => Primitive::Float,
+            ["double"] => Primitive::Double,
+            ["long", "double"] => Primitive::LongDouble,
+            ["size_t"] | ["ssize_t"] | ["ptrdiff_t"] | ["intptr_t"] | ["uintptr_t"] => Primitive::Pointer,
+            _ => return None,
+        })
+    }
+}
+
+/// A target ABI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    /// x86_64 Linux, BSD and macOS (System V).
+    X86_64SysV,
+    /// 32-bit x86 Linux (System V i386).
+    I686,
+    /// aarch64 Linux (AAPCS64).
+    Aarch64,
+    /// aarch64 macOS and iOS.
+    Aarch64Apple,
+    /// 64-bit Windows.
+    MsvcX64,
+    /// 32-bit Windows.
+    MsvcX86,
+    Wasm32,
+}
+
+impl Target {
+    pub const ALL: [Target; 7] = [
+        Target::X86_64SysV,
+        Target::I686,
+        Target::Aarch64,
+        Target::Aarch64Apple,
+        Target::MsvcX64,
+        Target::MsvcX86,
+        Target::Wasm32,
+    ];
+
+    /// The target this crate was compiled for, if it is one of these.
+    pub fn host() -> Option<Self> {
+        if cfg!(all(target_arch = "x86_64", target_env = "msvc")) {
+            Some(Target::MsvcX64)
+        } else if cfg!(all(target_arch = "x86", target_env = "msvc")) {
+            Some(Target::MsvcX86)
+        } else if cfg!(target_arch = "x86_64") {
+            Some(Target::X86_64SysV)
+        } else if cfg!(target_arch = "x86") {
+            Some(Target::I686)
+        } else if cfg!(all(target_arch = "aarch64", target_vendor = "apple")) {
+            Some(Target::Aarch64Apple)
+        } else if cfg!(target_arch = "aarch64") {
+            Some(Target::Aarch64)
+        } else if cfg!(target_arch = "wasm32") {
+            Some(Target::Wasm32)
+        } else {
+            None
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Target::X86_64SysV => "x86_64-sysv",
+            Target::I686 => "i686",
+            Target::Aarch64 => "aarch64",
+            Target::Aarch64Apple => "aarch64-apple",
+            Target::MsvcX64 => "msvc-x64",
+            Target::MsvcX86 => "msvc-x86",
+            Target::Wasm32 => "wasm32",
+        }
+    }
+
+    /// Bytes in a pointer.
+    pub fn pointer_width(self) -> usize {
+        match self {
+            Target::I686 | Target::MsvcX86 | Target::Wasm32 => 4,
+            _ => 8,
+        }
+    }
+
+    /// How its C compilers allocate bitfields.
+    pub fn bitfield_rules(self) -> BitfieldRules {
+        match self {
+            Target::MsvcX64 | Target::MsvcX86 => BitfieldRules::Msvc,
+            _ => BitfieldRules::Itanium,
+        }
+    }
+
+    /// The integer type behind an enum whose values are `min..=max`;
+    /// `None` if none holds them all.
+    ///
+    /// A C enum is an `int`, or on GCC and Clang the first of `unsigned
+    /// int` and `long long` that fits values which `int` does not; Visual
+    /// C++ has no such fallback. With `smallest`, as for GCC's packed
+    /// enums and Rust's fieldless enums without a repr, it is the smallest
+    /// integer that fits instead.
+    pub fn enum_type(self, min: i128, max: i128, smallest: bool) -> Option<TypeInfo> {
+        let fits = |primitive| {
+            self.type_info(primitive).filter(|info| {
+                let bits = 8 * info.size as u32;
+                if min < 0 {
+                    min >= i128::MIN >> (128 - bits) && max <= i128::MAX >> (128 - bits)
+                } else {
+                    max >> (bits - 1) >> 1 == 0
+                }
+            })
+        };
+        let candidates: &[Primitive] = if smallest {
+            &[Primitive::Char, Primitive::Short, Primitive::Int, Primitive::LongLong, Primitive::Int128]
+        } else if matches!(self, Target::MsvcX64 | Target::MsvcX86) {
+            &[Primitive::Int]
+        } else {
+            &[Primitive::Int, Primitive::LongLong]
+        };
+        candidates.iter().find_map(|&primitive| fits(primitive))
+    }
+
+    /// Size and alignment of `primitive` as a struct member; `None` if the
+    /// target does not have it.
+    pub fn type_info(self, primitive: Primitive) -> Option<TypeInfo> {
+        let info = |size, alignment| Some(TypeInfo { size, alignment });
+        let sysv_i386 = self == Target::I686;
+        match primitive {
+            Primitive::Bool | Primitive::Char => info(1, 1),
+            Primitive::Short => info(2, 2),
+            Primitive::Int | Primitive::Float => info(4, 4),
+            Primitive::Long if self.pointer_width() == 4 || matches!(self, Target::MsvcX64) => info(4, 4),
+            Primitive::Long => info(8, 8),
+            Primitive::LongLong | Primitive::Double if sysv_i386 => info(8, 4),
+            Primitive::LongLong | Primitive::Double => info(8, 8),
+            Primitive::Int128 => match self {
+                Target::X86_64SysV | Target::Aarch64 | Target::Aarch64Apple | Target::Wasm32 => info(16, 16),
+                _ => None,
+            },
+            Primitive::LongDouble => match self {
+                Target::X86_64SysV | Target::Aarch64 | Target::Wasm32 => info(16, 16),
+                Target::I686 => info(12, 4),
+                Target::Aarch64Apple | Target::MsvcX64 | Target::MsvcX86 => info(8, 8),
+            },
+            Primitive::Pointer => info(self.pointer_width(), self.pointer_width()),
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.pad(self.name())
+    }
+}
+
+impl FromStr for Target {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Target::ALL.into_iter().find(|target| target.name() == s).ok_or_else(|| {
+            let names: Vec<&str> = Target::ALL.iter().map(|target| target.name()).collect();
+            format!("unknown target `{s}`, expected one of {}", names.join(", "))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::StructDef;
+
+    #[test]
+    fn test_from_c_name() {
+        assert_eq!(Primitive::from_c_name("unsigned long"), Some(Primitive::Long));
+        assert_eq!(Primitive::from_c_name("long long int"), Some(Primitive::LongLong));
+        assert_eq!(Primitive::from_c_name("unsigned"), Some(Primitive::Int));
+        assert_eq!(Primitive::from_c_name("long  double"), Some(Primitive::LongDouble));
+        assert_eq!(Primitive::from_c_name("uint16_t"), Some(Primitive::Short));
+        assert_eq!(Primitive::from_c_name("size_t"), Some(Primitive::Pointer));
+        assert_eq!(Primitive::from_c_name("struct foo"), None);
+        assert_eq!(Primitive::from_c_name(""), None);
+    }
+
+    #[test]
+    fn test_abi_differences() {
+        let of = |target: Target, primitive| target.type_info(primitive).unwrap();
+        assert_eq!(of(Target::X86_64SysV, Primitive::Long).size, 8);
+        assert_eq!(of(Target::MsvcX64, Primitive::Long).size, 4);
+        assert_eq!(of(Target::I686, Primitive::Double), TypeInfo { size: 8, alignment: 4 });
+        assert_eq!(of(Target::I686, Primitive::LongDouble).size, 12);
+        assert_eq!(of(Target::Aarch64Apple, Primitive::LongDouble).size, 8);
+        assert_eq!(of(Target::Wasm32, Primitive::Pointer).size, 4);
+        assert_eq!(Target::MsvcX64.type_info(Primitive::Int128), None);
+
+        // struct { char c; double d; }
+        let size = |target: Target| {
+            StructDef::new("s")
+                .member("c", of(target, Primitive::Char))
+                .member("d", of(target, Primitive::Double))
+                .layout()
+                .total_size
+        };
+        assert_eq!(size(Target::X86_64SysV), 16);
+        assert_eq!(size(Target::I686), 12);
+    }
+
+    #[test]
+    fn test_enum_type() {
+        let size = |target: Target, min, max, smallest| target.enum_type(min, max, smallest).map(|info| info.size);
+        assert_eq!(size(Target::X86_64SysV, 0, 3, false), Some(4));
+        assert_eq!(size(Target::X86_64SysV, 0, 0xffff_ffff, false), Some(4));
+        assert_eq!(size(Target::X86_64SysV, -1, 0xffff_ffff, false), Some(8));
+        assert_eq!(size(Target::MsvcX64, -1, 0xffff_ffff, false), None);
+        assert_eq!(size(Target::X86_64SysV, 0, 255, true), Some(1));
+        assert_eq!(size(Target::X86_64SysV, -1, 255, true), Some(2));
+        assert_eq!(size(Target::X86_64SysV, -128, 127, true), Some(1));
+        assert_eq!(Target::I686.enum_type(0, 1 << 40, false), Some(TypeInfo { size: 8, alignment: 4 }));
+        assert_eq!(size(Target::X86_64SysV, -1, 1 << 70, true), Some(16));
+        assert_eq!(size(Target::X86_64SysV, i128::MIN, i128::MAX, true), Some(16));
+        assert_eq!(size(Target::X86_64SysV, -1, 1 << 70, false), None);
+    }
+
+    #[test]
+    fn test_names_round_trip() {
+        for target in Target::ALL {
+            assert_eq!(target.to_string().parse::<Target>(), Ok(target));
+        }
+        assert!("sparc".parse::<Target>().is_err());
+        assert!(Target::host().is_some());
+    }
+}